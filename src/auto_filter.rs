@@ -0,0 +1,181 @@
+//! AutoFilter of a list of a sheet, the filter buttons of its header row, the criteria of its columns and its sort order
+use std::collections::BTreeMap;
+use range::{self, Range};
+
+/// Comparison of a value of the column with the value of a condition
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    GreaterThan,
+    LessThan,
+    GreaterThanOrEqual,
+    LessThanOrEqual,
+}
+
+const COMPARISONS: [(Comparison, &str, &str); 6] = [
+    (Comparison::Equal, "equal", "="),
+    (Comparison::NotEqual, "notEqual", "!="),
+    (Comparison::GreaterThan, "greaterThan", ">"),
+    (Comparison::LessThan, "lessThan", "<"),
+    (Comparison::GreaterThanOrEqual, "greaterThanOrEqual", ">="),
+    (Comparison::LessThanOrEqual, "lessThanOrEqual", "<="),
+];
+
+impl Comparison {
+    /// Name of the operator of a xlsx customFilter like "greaterThan"
+    pub(crate) fn name(&self) -> &'static str {
+        COMPARISONS.iter().find(|&&(comparison, _, _)| comparison == *self).map(|&(_, name, _)| name).unwrap_or("equal")
+    }
+
+    pub(crate) fn from_name(name: &str) -> Option<Comparison> {
+        COMPARISONS.iter().find(|&&(_, n, _)| n == name).map(|&(comparison, _, _)| comparison)
+    }
+
+    /// Operator of an ods filter condition like ">"
+    pub(crate) fn symbol(&self) -> &'static str {
+        COMPARISONS.iter().find(|&&(comparison, _, _)| comparison == *self).map(|&(_, _, symbol)| symbol).unwrap_or("=")
+    }
+
+    pub(crate) fn from_symbol(symbol: &str) -> Option<Comparison> {
+        COMPARISONS.iter().find(|&&(_, _, s)| s == symbol).map(|&(comparison, _, _)| comparison)
+    }
+}
+
+/// Criteria of the values of a column shown by the filter
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Filter {
+    /// The values shown as they are displayed, `blank` shows the empty cells too
+    Values { values: Vec<String>, blank: bool },
+    /// One or two comparisons with a number or a text, a text may have the wildcards "*" and "?".
+    /// `and` shows the values matching all the conditions, otherwise one of them is enough.
+    Custom { conditions: Vec<(Comparison, String)>, and: bool },
+    /// The highest or the `bottom` values, `count` is a percent of the values with `percent`
+    Top { count: f64, bottom: bool, percent: bool },
+}
+
+/// Sort of the rows of the list by a column
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SortKey {
+    column: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    descending: bool,
+}
+
+impl SortKey {
+    pub fn new(column: usize, descending: bool) -> SortKey {
+        SortKey {
+            column,
+            descending,
+        }
+    }
+
+    /// Column of the range, 0 for its first column
+    pub fn get_column(&self) -> usize {
+        self.column
+    }
+
+    pub fn is_descending(&self) -> bool {
+        self.descending
+    }
+}
+
+/// AutoFilter is the filter buttons on the header row of a list, the first row of its range.
+/// The criteria and the sort keys are only kept, the rows aren't hidden or sorted by them.
+///
+/// ```
+/// use spsheet::Sheet;
+/// use spsheet::auto_filter::{AutoFilter, Comparison, Filter, SortKey};
+/// use spsheet::range::Range;
+///
+/// let mut filter = AutoFilter::new(Range::new("orders", 0, 0, 20, 3));
+/// filter.set_filter(1, Filter::Values { values: vec![String::from("Tokyo")], blank: false });
+/// filter.set_filter(3, Filter::Custom { conditions: vec![(Comparison::GreaterThan, String::from("100"))], and: true });
+/// filter.add_sort_key(SortKey::new(3, true));
+/// let mut sheet = Sheet::new("orders");
+/// sheet.set_auto_filter(Some(filter));
+/// assert!(sheet.get_auto_filter().unwrap().get_filter(1).is_some());
+/// assert!(sheet.get_auto_filter().unwrap().get_filter(2).is_none());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AutoFilter {
+    range: Range,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "BTreeMap::is_empty"))]
+    filters: BTreeMap<usize, Filter>,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Vec::is_empty"))]
+    sort_keys: Vec<SortKey>,
+}
+
+impl AutoFilter {
+    pub fn new(range: Range) -> AutoFilter {
+        AutoFilter {
+            range,
+            filters: BTreeMap::new(),
+            sort_keys: Vec::new(),
+        }
+    }
+
+    /// Cells of the list with its header row
+    pub fn get_range(&self) -> &Range {
+        &self.range
+    }
+
+    pub fn set_range(&mut self, range: Range) {
+        self.range = range;
+    }
+
+    /// Filters by the column of the range, 0 for its first column
+    pub fn get_filters(&self) -> &BTreeMap<usize, Filter> {
+        &self.filters
+    }
+
+    pub fn get_filter(&self, column: usize) -> Option<&Filter> {
+        self.filters.get(&column)
+    }
+
+    /// Filter a column of the range replacing its filter
+    pub fn set_filter(&mut self, column: usize, filter: Filter) {
+        self.filters.insert(column, filter);
+    }
+
+    pub fn remove_filter(&mut self, column: usize) -> Option<Filter> {
+        self.filters.remove(&column)
+    }
+
+    /// Keys of the last sort, the first key sorts first
+    pub fn get_sort_keys(&self) -> &Vec<SortKey> {
+        &self.sort_keys
+    }
+
+    pub fn set_sort_keys(&mut self, sort_keys: Vec<SortKey>) {
+        self.sort_keys = sort_keys;
+    }
+
+    pub fn add_sort_key(&mut self, sort_key: SortKey) {
+        self.sort_keys.push(sort_key);
+    }
+
+    /// Move the range like the rows inserted at `row_index`
+    pub(crate) fn insert_rows(&mut self, row_index: usize, count: usize) {
+        let mut ranges = vec![self.range.clone()];
+        range::insert_rows(&mut ranges, row_index, count);
+        self.range = ranges.remove(0);
+    }
+
+    /// Move the range like the rows removed at `row_index`, false when all the rows of the range are removed
+    pub(crate) fn remove_rows(&mut self, row_index: usize, count: usize) -> bool {
+        let mut ranges = vec![self.range.clone()];
+        range::remove_rows(&mut ranges, row_index, count);
+        match ranges.pop() {
+            Some(range) => {
+                self.range = range;
+                true
+            },
+            None => false,
+        }
+    }
+}
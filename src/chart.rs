@@ -0,0 +1,233 @@
+//! Charts of sheets drawn from the values of cell ranges
+use image::Anchor;
+use range::Range;
+
+/// Kind of a chart
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ChartType {
+    /// Vertical bars of the values by category
+    Bar,
+    /// Lines through the values by category
+    Line,
+    /// Slices of the values of the first series
+    Pie,
+    /// Points at the categories as x values and the values as y values
+    Scatter,
+}
+
+/// Side of the chart the legend is shown on
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum LegendPosition {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+/// Series is the values of a chart with the cell of its name
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Series {
+    values: Range,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    name: Option<Range>,
+}
+
+impl Series {
+    pub fn new(values: Range) -> Series {
+        Series {
+            values,
+            name: None,
+        }
+    }
+
+    pub fn get_values(&self) -> &Range {
+        &self.values
+    }
+
+    pub fn set_values(&mut self, values: Range) {
+        self.values = values;
+    }
+
+    /// Cell with the name shown in the legend
+    pub fn get_name(&self) -> Option<&Range> {
+        self.name.as_ref()
+    }
+
+    pub fn set_name(&mut self, name: Option<Range>) {
+        self.name = name;
+    }
+}
+
+/// Axis of a chart with its title and the bounds of its values, the bounds are automatic with None
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Axis {
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    title: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    min: Option<f64>,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    max: Option<f64>,
+}
+
+impl Axis {
+    pub fn new() -> Axis {
+        Axis::default()
+    }
+
+    pub fn get_title(&self) -> Option<&String> {
+        self.title.as_ref()
+    }
+
+    pub fn set_title(&mut self, title: Option<String>) {
+        self.title = title;
+    }
+
+    pub fn get_min(&self) -> Option<f64> {
+        self.min
+    }
+
+    pub fn set_min(&mut self, min: Option<f64>) {
+        self.min = min;
+    }
+
+    pub fn get_max(&self) -> Option<f64> {
+        self.max
+    }
+
+    pub fn set_max(&mut self, max: Option<f64>) {
+        self.max = max;
+    }
+}
+
+/// Chart is drawn on a sheet from the cells of its series, so it follows the values edited later.
+/// The categories are the labels of the x axis, a pie chart has no axes.
+///
+/// ```
+/// use spsheet::Sheet;
+/// use spsheet::chart::{Chart, ChartType, LegendPosition, Series};
+/// use spsheet::image::{Anchor, Marker};
+/// use spsheet::range::Range;
+///
+/// let mut chart = Chart::new(ChartType::Bar, Anchor::TwoCell(Marker::new(1, 3, 0.0, 0.0), Marker::new(15, 9, 0.0, 0.0)));
+/// chart.set_title(Some(String::from("Sales")));
+/// chart.set_categories(Range::parse("report!A2:A5"));
+/// let mut series = Series::new(Range::parse("report!B2:B5").unwrap());
+/// series.set_name(Range::parse("report!B1"));
+/// chart.add_series(series);
+/// chart.set_legend(Some(LegendPosition::Bottom));
+/// let mut sheet = Sheet::new("report");
+/// sheet.add_chart(chart);
+/// assert_eq!(1, sheet.get_charts()[0].get_series().len());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Chart {
+    chart_type: ChartType,
+    anchor: Anchor,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    title: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    categories: Option<Range>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    series: Vec<Series>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    x_axis: Axis,
+    #[cfg_attr(feature = "serde", serde(default))]
+    y_axis: Axis,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    legend: Option<LegendPosition>,
+}
+
+impl Chart {
+    /// A chart without series, the legend is on the right
+    pub fn new(chart_type: ChartType, anchor: Anchor) -> Chart {
+        Chart {
+            chart_type,
+            anchor,
+            title: None,
+            categories: None,
+            series: Vec::new(),
+            x_axis: Axis::new(),
+            y_axis: Axis::new(),
+            legend: Some(LegendPosition::Right),
+        }
+    }
+
+    pub fn get_chart_type(&self) -> ChartType {
+        self.chart_type
+    }
+
+    pub fn set_chart_type(&mut self, chart_type: ChartType) {
+        self.chart_type = chart_type;
+    }
+
+    pub fn get_anchor(&self) -> &Anchor {
+        &self.anchor
+    }
+
+    pub fn set_anchor(&mut self, anchor: Anchor) {
+        self.anchor = anchor;
+    }
+
+    pub(crate) fn get_anchor_mut(&mut self) -> &mut Anchor {
+        &mut self.anchor
+    }
+
+    pub fn get_title(&self) -> Option<&String> {
+        self.title.as_ref()
+    }
+
+    pub fn set_title(&mut self, title: Option<String>) {
+        self.title = title;
+    }
+
+    /// Labels of the categories, the x values of a scatter chart
+    pub fn get_categories(&self) -> Option<&Range> {
+        self.categories.as_ref()
+    }
+
+    pub fn set_categories(&mut self, categories: Option<Range>) {
+        self.categories = categories;
+    }
+
+    pub fn get_series(&self) -> &Vec<Series> {
+        &self.series
+    }
+
+    pub fn add_series(&mut self, series: Series) {
+        self.series.push(series);
+    }
+
+    pub fn remove_series(&mut self, index: usize) -> Series {
+        self.series.remove(index)
+    }
+
+    pub fn get_x_axis(&self) -> &Axis {
+        &self.x_axis
+    }
+
+    pub fn set_x_axis(&mut self, axis: Axis) {
+        self.x_axis = axis;
+    }
+
+    pub fn get_y_axis(&self) -> &Axis {
+        &self.y_axis
+    }
+
+    pub fn set_y_axis(&mut self, axis: Axis) {
+        self.y_axis = axis;
+    }
+
+    /// Position of the legend, None hides it
+    pub fn get_legend(&self) -> Option<LegendPosition> {
+        self.legend
+    }
+
+    pub fn set_legend(&mut self, legend: Option<LegendPosition>) {
+        self.legend = legend;
+    }
+}
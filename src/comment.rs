@@ -0,0 +1,84 @@
+//! Comments of cells, the notes of xlsx and the annotations of ods
+use std::borrow::Cow;
+
+/// Comment is a note on a cell with its author.
+/// The size is in points, None is the default size of the application.
+///
+/// ```
+/// use spsheet::Cell;
+/// use spsheet::comment::Comment;
+///
+/// let mut comment = Comment::new("Reviewer", "must be positive");
+/// comment.set_size(Some(144.0), Some(72.0));
+/// comment.set_visible(true);
+/// let mut cell = Cell::float(-1.0, "");
+/// cell.set_comment(Some(comment));
+/// assert_eq!("must be positive", cell.get_comment().unwrap().get_text());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Comment {
+    author: String,
+    text: String,
+    width: Option<f64>,
+    height: Option<f64>,
+    visible: bool,
+}
+
+impl Comment {
+    pub fn new<'a, S>(author: S, text: S) -> Comment
+        where S: Into<Cow<'a, str>>
+    {
+        Comment {
+            author: author.into().into_owned(),
+            text: text.into().into_owned(),
+            width: None,
+            height: None,
+            visible: false,
+        }
+    }
+
+    pub fn get_author(&self) -> &String {
+        &self.author
+    }
+
+    pub fn set_author<'a, S>(&mut self, author: S)
+        where S: Into<Cow<'a, str>>
+    {
+        self.author = author.into().into_owned();
+    }
+
+    /// Text of the comment, the lines are separated by "\n"
+    pub fn get_text(&self) -> &String {
+        &self.text
+    }
+
+    pub fn set_text<'a, S>(&mut self, text: S)
+        where S: Into<Cow<'a, str>>
+    {
+        self.text = text.into().into_owned();
+    }
+
+    pub fn get_width(&self) -> Option<f64> {
+        self.width
+    }
+
+    pub fn get_height(&self) -> Option<f64> {
+        self.height
+    }
+
+    /// Width and height in points
+    pub fn set_size(&mut self, width: Option<f64>, height: Option<f64>) {
+        self.width = width;
+        self.height = height;
+    }
+
+    /// Shown all the time, not only while the pointer is on the cell
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+}
@@ -0,0 +1,254 @@
+//! Conditional formats of cells, the styles, color scales, data bars and icons shown by the values of the cells
+use range::{self, Range};
+use rich_text::Font;
+use validation::Operator;
+use Book;
+
+/// Style a rule applies over the style of the cell, only the attributes set are changed
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DifferentialStyle {
+    font: Font,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    fill_color: Option<String>,
+}
+
+impl DifferentialStyle {
+    pub fn new() -> DifferentialStyle {
+        DifferentialStyle::default()
+    }
+
+    pub fn get_font(&self) -> &Font {
+        &self.font
+    }
+
+    pub fn set_font(&mut self, font: Font) {
+        self.font = font;
+    }
+
+    /// Background color like "FFC7CE"
+    pub fn get_fill_color(&self) -> Option<&String> {
+        self.fill_color.as_ref()
+    }
+
+    pub fn set_fill_color(&mut self, fill_color: Option<String>) {
+        self.fill_color = fill_color;
+    }
+}
+
+/// Value of the cells of the ranges where a color scale, a data bar or an icon starts
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Threshold {
+    /// Lowest value of the cells
+    Min,
+    /// Highest value of the cells
+    Max,
+    Number(f64),
+    /// Percent of the range from the lowest to the highest value
+    Percent(f64),
+    /// Percent of the values below it
+    Percentile(f64),
+    /// Formula without "=" like "$B$1"
+    Formula(String),
+}
+
+/// Icons of an icon set, the number is the number of the icons
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum IconStyle {
+    Arrows3,
+    ArrowsGray3,
+    Flags3,
+    TrafficLights3,
+    TrafficLightsRimmed3,
+    Signs3,
+    Symbols3,
+    SymbolsUncircled3,
+    Arrows4,
+    ArrowsGray4,
+    RedToBlack4,
+    Rating4,
+    TrafficLights4,
+    Arrows5,
+    ArrowsGray5,
+    Rating5,
+    Quarters5,
+}
+
+const ICON_STYLES: [(IconStyle, &str); 17] = [
+    (IconStyle::Arrows3, "3Arrows"),
+    (IconStyle::ArrowsGray3, "3ArrowsGray"),
+    (IconStyle::Flags3, "3Flags"),
+    (IconStyle::TrafficLights3, "3TrafficLights1"),
+    (IconStyle::TrafficLightsRimmed3, "3TrafficLights2"),
+    (IconStyle::Signs3, "3Signs"),
+    (IconStyle::Symbols3, "3Symbols"),
+    (IconStyle::SymbolsUncircled3, "3Symbols2"),
+    (IconStyle::Arrows4, "4Arrows"),
+    (IconStyle::ArrowsGray4, "4ArrowsGray"),
+    (IconStyle::RedToBlack4, "4RedToBlack"),
+    (IconStyle::Rating4, "4Rating"),
+    (IconStyle::TrafficLights4, "4TrafficLights"),
+    (IconStyle::Arrows5, "5Arrows"),
+    (IconStyle::ArrowsGray5, "5ArrowsGray"),
+    (IconStyle::Rating5, "5Rating"),
+    (IconStyle::Quarters5, "5Quarters"),
+];
+
+impl IconStyle {
+    pub fn get_icon_count(&self) -> usize {
+        match self.name().chars().next() {
+            Some('4') => 4,
+            Some('5') => 5,
+            _ => 3,
+        }
+    }
+
+    /// Name of the icon set in xlsx and ods like "3TrafficLights1"
+    pub(crate) fn name(&self) -> &'static str {
+        ICON_STYLES.iter().find(|&&(style, _)| style == *self).map(|&(_, name)| name).unwrap_or("3TrafficLights1")
+    }
+
+    pub(crate) fn from_name(name: &str) -> Option<IconStyle> {
+        ICON_STYLES.iter().find(|&&(_, n)| n == name).map(|&(style, _)| style)
+    }
+}
+
+/// Condition of the cells to format and its format
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Rule {
+    /// Value of the cell compared with formulas without "=" like "100" or "\"done\""
+    CellIs(Operator<String>, DifferentialStyle),
+    /// Formula without "=" which is true for the cells to format like "$C2=\"late\"",
+    /// the references are relative to the first cell of the first range
+    Expression(String, DifferentialStyle),
+    /// Colors like "F8696B" of two or three thresholds from the lowest, the cells between them have the colors between
+    ColorScale(Vec<(Threshold, String)>),
+    /// Bar of the value between the thresholds
+    DataBar {
+        min: Threshold,
+        max: Threshold,
+        color: String,
+    },
+    /// Icon of the highest threshold the value reaches, a threshold for each icon from the lowest,
+    /// the first is usually `Percent(0.0)`
+    IconSet {
+        icons: IconStyle,
+        thresholds: Vec<Threshold>,
+    },
+    /// The `rank` highest values, or the lowest for `bottom`, or the `rank` percent of the values for `percent`
+    Top {
+        rank: u32,
+        bottom: bool,
+        percent: bool,
+        style: DifferentialStyle,
+    },
+    /// Values found in other cells of the ranges
+    Duplicate(DifferentialStyle),
+    /// Values not found in other cells of the ranges
+    Unique(DifferentialStyle),
+}
+
+impl Rule {
+    /// Style of the cells, None for a color scale, a data bar or an icon set
+    pub fn get_style(&self) -> Option<&DifferentialStyle> {
+        match *self {
+            Rule::CellIs(_, ref style) | Rule::Expression(_, ref style)
+            | Rule::Top { ref style, .. } | Rule::Duplicate(ref style) | Rule::Unique(ref style) => Some(style),
+            Rule::ColorScale(_) | Rule::DataBar { .. } | Rule::IconSet { .. } => None,
+        }
+    }
+}
+
+/// ConditionalFormat is the rules of the cells of its ranges, the ranges are in the sheet of the format.
+/// The rules come first by their order in the sheet, the first rule of the first format is applied first.
+///
+/// ```
+/// use spsheet::Sheet;
+/// use spsheet::range::Range;
+/// use spsheet::rich_text::Font;
+/// use spsheet::validation::Operator;
+/// use spsheet::conditional_format::{ConditionalFormat, DifferentialStyle, Rule, Threshold};
+///
+/// let mut bad = DifferentialStyle::new();
+/// let mut font = Font::new();
+/// font.set_color(Some(String::from("9C0006")));
+/// bad.set_font(font);
+/// bad.set_fill_color(Some(String::from("FFC7CE")));
+/// let mut format = ConditionalFormat::new(Range::new("kpi", 1, 1, 10, 1), Rule::CellIs(Operator::LessThan(String::from("0")), bad));
+/// format.add_rule(Rule::ColorScale(vec![(Threshold::Min, String::from("F8696B")), (Threshold::Max, String::from("63BE7B"))]));
+/// let mut sheet = Sheet::new("kpi");
+/// sheet.add_conditional_format(format);
+/// assert_eq!(1, sheet.get_conditional_formats().len());
+/// assert!(sheet.get_conditional_formats()[0].contains(5, 1));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ConditionalFormat {
+    ranges: Vec<Range>,
+    rules: Vec<Rule>,
+}
+
+impl ConditionalFormat {
+    pub fn new(range: Range, rule: Rule) -> ConditionalFormat {
+        ConditionalFormat {
+            ranges: vec![range],
+            rules: vec![rule],
+        }
+    }
+
+    pub fn get_ranges(&self) -> &Vec<Range> {
+        &self.ranges
+    }
+
+    pub fn set_ranges(&mut self, ranges: Vec<Range>) {
+        self.ranges = ranges;
+    }
+
+    pub fn add_range(&mut self, range: Range) {
+        self.ranges.push(range);
+    }
+
+    pub fn get_rules(&self) -> &Vec<Rule> {
+        &self.rules
+    }
+
+    pub fn set_rules(&mut self, rules: Vec<Rule>) {
+        self.rules = rules;
+    }
+
+    pub fn add_rule(&mut self, rule: Rule) {
+        self.rules.push(rule);
+    }
+
+    pub fn contains(&self, row: usize, column: usize) -> bool {
+        self.ranges.iter().any(|range| range.contains(row, column))
+    }
+
+    /// Move the ranges like the rows inserted at `row_index`
+    pub(crate) fn insert_rows(&mut self, row_index: usize, count: usize) {
+        range::insert_rows(&mut self.ranges, row_index, count);
+    }
+
+    /// Move the ranges like the rows removed at `row_index`, the ranges only on the removed rows are dropped
+    pub(crate) fn remove_rows(&mut self, row_index: usize, count: usize) {
+        range::remove_rows(&mut self.ranges, row_index, count);
+    }
+}
+
+/// Styles of the rules of the book without duplicates, in the order of the sheets and the rules
+pub(crate) fn differential_styles(book: &Book) -> Vec<&DifferentialStyle> {
+    let mut styles: Vec<&DifferentialStyle> = Vec::new();
+    for sheet in book.get_sheet_vec() {
+        for format in sheet.get_conditional_formats() {
+            for style in format.get_rules().iter().filter_map(|rule| rule.get_style()) {
+                if !styles.contains(&style) {
+                    styles.push(style);
+                }
+            }
+        }
+    }
+    styles
+}
@@ -0,0 +1,359 @@
+//! CSV and TSV import and export
+extern crate csv;
+extern crate encoding_rs;
+
+use chrono::prelude::*;
+use std::io;
+use std::io::{Read, Write};
+use std::result;
+use std::string::FromUtf8Error;
+use super::{Cell, Sheet, Value};
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+#[derive(Debug)]
+pub enum CsvError {
+    Io(io::Error),
+    Csv(csv::Error),
+    Utf8(FromUtf8Error),
+    /// Text could not be decoded from or encoded to the named encoding
+    Encoding(&'static str),
+}
+
+impl From<io::Error> for CsvError {
+    fn from(err: io::Error) -> CsvError {
+        CsvError::Io(err)
+    }
+}
+
+impl From<csv::Error> for CsvError {
+    fn from(err: csv::Error) -> CsvError {
+        CsvError::Csv(err)
+    }
+}
+
+impl From<FromUtf8Error> for CsvError {
+    fn from(err: FromUtf8Error) -> CsvError {
+        CsvError::Utf8(err)
+    }
+}
+
+type Result<T> = result::Result<T, CsvError>;
+
+/// Character encoding of the csv bytes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    /// UTF-8, a leading BOM is skipped on read and not written
+    Utf8,
+    /// UTF-8, a leading BOM is skipped on read and written on export
+    Utf8Bom,
+    /// Shift_JIS as exported by Japanese Excel
+    ShiftJis,
+}
+
+/// How the first record is handled.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Header {
+    /// No header, every record is data
+    None,
+    /// The first record is kept as string cells without type inference
+    Keep,
+    /// The first record is dropped on import, export writes every row
+    Skip,
+}
+
+/// When fields are quoted on export.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuoteStyle {
+    /// Only fields containing a delimiter, quote or line break
+    Necessary,
+    /// Every field
+    Always,
+    /// Every field which is not a number
+    NonNumeric,
+    /// Never, fields are written as is
+    Never,
+}
+
+/// Options for `Sheet::from_csv` and `Sheet::to_csv`.
+///
+/// ```
+/// use spsheet::csv::{CsvOptions, Encoding};
+/// let mut options = CsvOptions::tsv();
+/// options.set_encoding(Encoding::ShiftJis);
+/// assert_eq!(b'\t', options.get_delimiter());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvOptions {
+    delimiter: u8,
+    quote: u8,
+    quote_style: QuoteStyle,
+    header: Header,
+    encoding: Encoding,
+    infer_types: bool,
+    sheet_name: String,
+}
+
+impl Default for CsvOptions {
+    fn default() -> CsvOptions {
+        CsvOptions::new()
+    }
+}
+
+impl CsvOptions {
+    pub fn new() -> CsvOptions {
+        CsvOptions {
+            delimiter: b',',
+            quote: b'"',
+            quote_style: QuoteStyle::Necessary,
+            header: Header::None,
+            encoding: Encoding::Utf8,
+            infer_types: true,
+            sheet_name: String::from("Sheet1"),
+        }
+    }
+
+    /// Tab separated values
+    pub fn tsv() -> CsvOptions {
+        let mut options = CsvOptions::new();
+        options.set_delimiter(b'\t');
+        options
+    }
+
+    pub fn set_delimiter(&mut self, delimiter: u8) {
+        self.delimiter = delimiter;
+    }
+
+    pub fn get_delimiter(&self) -> u8 {
+        self.delimiter
+    }
+
+    pub fn set_quote(&mut self, quote: u8) {
+        self.quote = quote;
+    }
+
+    pub fn get_quote(&self) -> u8 {
+        self.quote
+    }
+
+    pub fn set_quote_style(&mut self, quote_style: QuoteStyle) {
+        self.quote_style = quote_style;
+    }
+
+    pub fn get_quote_style(&self) -> QuoteStyle {
+        self.quote_style
+    }
+
+    pub fn set_header(&mut self, header: Header) {
+        self.header = header;
+    }
+
+    pub fn get_header(&self) -> Header {
+        self.header
+    }
+
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        self.encoding = encoding;
+    }
+
+    pub fn get_encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Convert numbers to `Value::Float` and dates to `Value::Date` on import
+    pub fn set_infer_types(&mut self, infer_types: bool) {
+        self.infer_types = infer_types;
+    }
+
+    pub fn get_infer_types(&self) -> bool {
+        self.infer_types
+    }
+
+    /// Name of the sheet made by `Sheet::from_csv`
+    pub fn set_sheet_name<S: Into<String>>(&mut self, sheet_name: S) {
+        self.sheet_name = sheet_name.into();
+    }
+
+    pub fn get_sheet_name(&self) -> &String {
+        &self.sheet_name
+    }
+}
+
+impl Sheet {
+    /// Read csv records into a new sheet.
+    ///
+    /// ```
+    /// use spsheet::{Sheet, Value};
+    /// use spsheet::csv::CsvOptions;
+    /// let data = "name,price\napple,120\n";
+    /// let sheet = Sheet::from_csv(data.as_bytes(), &CsvOptions::new()).unwrap();
+    /// assert_eq!(&Value::Float(120.0), sheet.get_cell(1, 1).unwrap().get_value());
+    /// ```
+    pub fn from_csv<R: Read>(mut reader: R, options: &CsvOptions) -> Result<Sheet> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let text = decode(bytes, options.encoding)?;
+
+        let mut csv_reader = csv::ReaderBuilder::new()
+            .delimiter(options.delimiter)
+            .quote(options.quote)
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(text.as_bytes());
+
+        let mut sheet = Sheet::new(options.sheet_name.as_str());
+        let mut row_index = 0;
+        for (record_index, record) in csv_reader.records().enumerate() {
+            let record = record?;
+            let header_flag = record_index == 0 && options.header != Header::None;
+            if header_flag && options.header == Header::Skip {
+                continue;
+            }
+            for (column_index, field) in record.iter().enumerate() {
+                if field.is_empty() {
+                    continue;
+                }
+                let cell = if options.infer_types && !header_flag {
+                    infer_cell(field)
+                } else {
+                    Cell::str(field, "")
+                };
+                sheet.add_cell(cell, row_index, column_index);
+            }
+            row_index += 1;
+        }
+        Ok(sheet)
+    }
+
+    /// Write the sheet as csv records, dates are rendered through the cell format.
+    ///
+    /// ```
+    /// use spsheet::{Sheet, Cell};
+    /// use spsheet::csv::CsvOptions;
+    /// let mut sheet = Sheet::new("sheet1");
+    /// sheet.add_cell(Cell::str("a,b", ""), 0, 0);
+    /// sheet.add_cell(Cell::float(1.5, ""), 0, 1);
+    /// sheet.add_cell(Cell::date("2017-12-02", "YYYY/MM/DD"), 1, 1);
+    /// let mut out = Vec::new();
+    /// sheet.to_csv(&mut out, &CsvOptions::new()).unwrap();
+    /// assert_eq!("\"a,b\",1.5\n,2017/12/02\n", String::from_utf8(out).unwrap());
+    /// ```
+    pub fn to_csv<W: Write>(&self, mut writer: W, options: &CsvOptions) -> Result<()> {
+        let quote_style = match options.quote_style {
+            QuoteStyle::Necessary => csv::QuoteStyle::Necessary,
+            QuoteStyle::Always => csv::QuoteStyle::Always,
+            QuoteStyle::NonNumeric => csv::QuoteStyle::NonNumeric,
+            QuoteStyle::Never => csv::QuoteStyle::Never,
+        };
+        let mut csv_writer = csv::WriterBuilder::new()
+            .delimiter(options.delimiter)
+            .quote(options.quote)
+            .quote_style(quote_style)
+            .from_writer(Vec::new());
+
+        if let Some((max_row_index, max_column_index)) = self.get_max_index() {
+            for row_index in 0..max_row_index + 1 {
+                let mut record = vec![String::from(""); max_column_index + 1];
+                if let Some(columns) = self.get_rows().get(&row_index) {
+                    for (column_index, cell) in columns {
+                        record[*column_index] = cell.get_text();
+                    }
+                }
+                csv_writer.write_record(&record)?;
+            }
+        }
+
+        let bytes = match csv_writer.into_inner() {
+            Ok(bytes) => bytes,
+            Err(err) => return Err(CsvError::Io(io::Error::new(err.error().kind(), err.to_string()))),
+        };
+        writer.write_all(encode(String::from_utf8(bytes)?, options.encoding)?.as_slice())?;
+        Ok(())
+    }
+}
+
+fn decode(mut bytes: Vec<u8>, encoding: Encoding) -> Result<String> {
+    match encoding {
+        Encoding::Utf8 | Encoding::Utf8Bom => {
+            if bytes.starts_with(UTF8_BOM) {
+                bytes.drain(0..UTF8_BOM.len());
+            }
+            Ok(String::from_utf8(bytes)?)
+        },
+        Encoding::ShiftJis => {
+            let (text, had_errors) = encoding_rs::SHIFT_JIS.decode_without_bom_handling(&bytes);
+            if had_errors {
+                return Err(CsvError::Encoding("Shift_JIS"));
+            }
+            Ok(text.into_owned())
+        },
+    }
+}
+
+fn encode(text: String, encoding: Encoding) -> Result<Vec<u8>> {
+    match encoding {
+        Encoding::Utf8 => Ok(text.into_bytes()),
+        Encoding::Utf8Bom => {
+            let mut bytes = UTF8_BOM.to_vec();
+            bytes.extend_from_slice(text.as_bytes());
+            Ok(bytes)
+        },
+        Encoding::ShiftJis => {
+            let (bytes, _, had_errors) = encoding_rs::SHIFT_JIS.encode(text.as_str());
+            if had_errors {
+                return Err(CsvError::Encoding("Shift_JIS"));
+            }
+            Ok(bytes.into_owned())
+        },
+    }
+}
+
+
+// (chrono pattern, Excel format) pairs tried in order
+const DATE_PATTERNS: [(&str, &str); 3] = [
+    ("%Y-%m-%d", "YYYY\\-MM\\-DD"),
+    ("%Y/%m/%d", "YYYY/MM/DD"),
+    ("%Y年%m月%d日", "YYYY\\年MM\\月DD\\日"),
+];
+
+const DATETIME_PATTERNS: [(&str, &str); 6] = [
+    ("%Y-%m-%dT%H:%M:%S", "YYYY\\-MM\\-DD\\ HH:MM:SS"),
+    ("%Y-%m-%d %H:%M:%S", "YYYY\\-MM\\-DD\\ HH:MM:SS"),
+    ("%Y/%m/%d %H:%M:%S", "YYYY/MM/DD\\ HH:MM:SS"),
+    ("%Y-%m-%dT%H:%M", "YYYY\\-MM\\-DD\\ HH:MM"),
+    ("%Y-%m-%d %H:%M", "YYYY\\-MM\\-DD\\ HH:MM"),
+    ("%Y/%m/%d %H:%M", "YYYY/MM/DD\\ HH:MM"),
+];
+
+fn infer_cell(field: &str) -> Cell {
+    if is_number(field) {
+        if let Ok(value) = field.parse::<f64>() {
+            return Cell::float(value, "");
+        }
+    }
+    for &(pattern, format) in DATETIME_PATTERNS.iter() {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(field, pattern) {
+            return Cell::new(Value::Date(DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc)), format);
+        }
+    }
+    for &(pattern, format) in DATE_PATTERNS.iter() {
+        if let Ok(date) = NaiveDate::parse_from_str(field, pattern) {
+            let dt = date.and_hms_opt(0, 0, 0).unwrap();
+            return Cell::new(Value::Date(DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc)), format);
+        }
+    }
+    Cell::str(field, "")
+}
+
+// Plain decimal notation only, codes with leading zeros like "007" stay strings
+fn is_number(field: &str) -> bool {
+    let digits = field.trim_start_matches(['-', '+']);
+    if digits.is_empty() {
+        return false;
+    }
+    if digits.len() > 1 && digits.starts_with('0') && !digits.starts_with("0.") {
+        return false;
+    }
+    digits.chars().all(|c| c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E' || c == '-' || c == '+')
+        && digits.chars().next().is_some_and(|c| c.is_ascii_digit() || c == '.')
+}
@@ -0,0 +1,100 @@
+//! Currency amounts with their ISO 4217 code
+use std::borrow::Cow;
+
+// code, symbol, Excel locale id, decimal places, language, country
+const CURRENCIES: [(&str, &str, &str, usize, &str, &str); 8] = [
+    ("JPY", "¥", "411", 0, "ja", "JP"),
+    ("USD", "$", "409", 2, "en", "US"),
+    ("EUR", "€", "407", 2, "de", "DE"),
+    ("GBP", "£", "809", 2, "en", "GB"),
+    ("CNY", "¥", "804", 2, "zh", "CN"),
+    ("KRW", "₩", "412", 0, "ko", "KR"),
+    ("CHF", "CHF", "807", 2, "de", "CH"),
+    ("INR", "₹", "4009", 2, "en", "IN"),
+];
+
+/// Currency is an amount of money and its ISO 4217 code like "JPY".
+///
+/// ```
+/// use spsheet::currency::Currency;
+///
+/// let yen = Currency::new(1000.0, "JPY");
+/// assert_eq!("¥", yen.get_symbol());
+/// assert_eq!("[$¥-411]#,##0", yen.get_format_code());
+/// assert_eq!(Some(String::from("JPY")), Currency::code_from_format("[$￥-411]#,##0;[RED]\\-[$￥-411]#,##0"));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Currency {
+    amount: f64,
+    code: String,
+}
+
+impl Currency {
+    pub fn new<'a, S>(amount: f64, code: S) -> Currency
+        where S: Into<Cow<'a, str>>
+    {
+        Currency {
+            amount,
+            code: code.into().into_owned(),
+        }
+    }
+
+    pub fn get_amount(&self) -> f64 {
+        self.amount
+    }
+
+    /// ISO 4217 code
+    pub fn get_code(&self) -> &String {
+        &self.code
+    }
+
+    /// Symbol of the code, the code itself for a currency without known symbol
+    pub fn get_symbol(&self) -> &str {
+        match CURRENCIES.iter().find(|c| c.0 == self.code) {
+            Some(c) => c.1,
+            None => self.code.as_str(),
+        }
+    }
+
+    /// Excel format code of the currency like "[$¥-411]#,##0"
+    pub fn get_format_code(&self) -> String {
+        match CURRENCIES.iter().find(|c| c.0 == self.code) {
+            Some(&(_, symbol, locale, 0, _, _)) => format!("[${}-{}]#,##0", symbol, locale),
+            Some(&(_, symbol, locale, _, _, _)) => format!("[${}-{}]#,##0.00", symbol, locale),
+            None => format!("[${}]\\ #,##0.00", self.code),
+        }
+    }
+
+    /// Code of the first `[$symbol-locale]` or `[$code]` in an Excel format code
+    pub fn code_from_format(format: &str) -> Option<String> {
+        let start = format.find("[$")?;
+        let rest = &format[start + 2..];
+        let end = rest.find(']')?;
+        let mut parts = rest[..end].splitn(2, '-');
+        let symbol = parts.next().unwrap_or("").replace('￥', "¥");
+        let locale = parts.next().map(|locale| locale.to_uppercase());
+        if let Some(ref locale) = locale {
+            if let Some(c) = CURRENCIES.iter().find(|c| c.1 == symbol && c.2 == locale.as_str()) {
+                return Some(String::from(c.0));
+            }
+        }
+        if let Some(c) = CURRENCIES.iter().find(|c| c.1 == symbol) {
+            return Some(String::from(c.0));
+        }
+        if symbol.len() == 3 && symbol.chars().all(|c| c.is_ascii_uppercase()) {
+            return Some(symbol);
+        }
+        locale.and_then(|locale| CURRENCIES.iter().find(|c| c.2 == locale.as_str()).map(|c| String::from(c.0)))
+    }
+
+    /// Language and country of an Excel locale id like ("ja", "JP") of "411"
+    pub fn language_of_locale(locale: &str) -> Option<(&'static str, &'static str)> {
+        CURRENCIES.iter().find(|c| c.2.eq_ignore_ascii_case(locale)).map(|c| (c.4, c.5))
+    }
+
+    /// Excel locale id of a language and country of an ods currency symbol like "411"
+    pub fn locale_of_language(language: &str, country: &str) -> Option<&'static str> {
+        CURRENCIES.iter().find(|c| c.4 == language && c.5 == country).map(|c| c.2)
+    }
+}
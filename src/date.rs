@@ -0,0 +1,373 @@
+//! Serial numbers of dates, the days from the epoch of the workbook
+//!
+//! ```
+//! extern crate chrono;
+//! extern crate spsheet;
+//! use chrono::prelude::*;
+//! use spsheet::date::{self, DateSystem};
+//!
+//! # fn main() {
+//! let dt = NaiveDate::from_ymd_opt(2017, 12, 2).unwrap().and_hms_opt(13, 30, 0).unwrap();
+//! assert_eq!(43071.5625, date::datetime_to_serial(&dt, DateSystem::Date1900));
+//! assert_eq!(41609.5625, date::datetime_to_serial(&dt, DateSystem::Date1904));
+//! assert_eq!(Some(dt), date::serial_to_datetime(43071.5625, DateSystem::Date1900));
+//! # }
+//! ```
+use chrono::prelude::*;
+use chrono::Duration;
+
+const MILLISECONDS_PER_DAY: i64 = 86_400_000;
+
+/// Date system of a workbook
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DateSystem {
+    /// Serial 1 is 1900-01-01 and 60 is the leap day 1900-02-29 which doesn't exist
+    #[default]
+    Date1900,
+    /// Serial 0 is 1904-01-01, xlsx with `date1904`
+    Date1904,
+}
+
+/// The day of serial 0, the serials before 1900-03-01 count from a day later
+/// because of the leap day 1900-02-29
+fn epoch(date_system: DateSystem, before_march_1900: bool) -> NaiveDate {
+    match date_system {
+        DateSystem::Date1904 => NaiveDate::from_ymd_opt(1904, 1, 1).unwrap(),
+        DateSystem::Date1900 if before_march_1900 => NaiveDate::from_ymd_opt(1899, 12, 31).unwrap(),
+        DateSystem::Date1900 => NaiveDate::from_ymd_opt(1899, 12, 30).unwrap(),
+    }
+}
+
+/// Date and time of a serial rounded to milliseconds.
+/// The serial 60 of the 1900 system is 1900-03-01 like 61,
+/// negative serials are the days before 1899-12-31 like -1 is 1899-12-30.
+pub fn serial_to_datetime(serial: f64, date_system: DateSystem) -> Option<NaiveDateTime> {
+    if !serial.is_finite() {
+        return None;
+    }
+    let milliseconds = (serial * MILLISECONDS_PER_DAY as f64).round();
+    if milliseconds.abs() > i64::MAX as f64 {
+        return None;
+    }
+    let milliseconds = milliseconds as i64;
+    let days = milliseconds.div_euclid(MILLISECONDS_PER_DAY);
+    let time = milliseconds.rem_euclid(MILLISECONDS_PER_DAY);
+    let date = epoch(date_system, days < 61).checked_add_signed(Duration::days(days))?;
+    let time = NaiveTime::from_num_seconds_from_midnight_opt(
+        (time / 1000) as u32,
+        (time % 1000) as u32 * 1_000_000)?;
+    Some(date.and_time(time))
+}
+
+/// Serial of a date and time rounded to milliseconds, the inverse of `serial_to_datetime`
+pub fn datetime_to_serial(datetime: &NaiveDateTime, date_system: DateSystem) -> f64 {
+    let date = datetime.date();
+    let before_march_1900 = date < NaiveDate::from_ymd_opt(1900, 3, 1).unwrap();
+    let days = date.signed_duration_since(epoch(date_system, before_march_1900)).num_days();
+    let time = datetime.time();
+    let milliseconds = time.num_seconds_from_midnight() as i64 * 1000
+        + ((time.nanosecond() as i64 + 500_000) / 1_000_000);
+    (days * MILLISECONDS_PER_DAY + milliseconds) as f64 / MILLISECONDS_PER_DAY as f64
+}
+
+/// Fraction of a day of a time, the serial of the time on day 0
+pub fn time_to_serial(time: &NaiveTime) -> f64 {
+    let milliseconds = time.num_seconds_from_midnight() as i64 * 1000
+        + ((time.nanosecond() as i64 + 500_000) / 1_000_000);
+    milliseconds as f64 / MILLISECONDS_PER_DAY as f64
+}
+
+/// Time of day of a serial, the days are ignored
+pub fn serial_to_time(serial: f64) -> Option<NaiveTime> {
+    serial_to_datetime(serial, DateSystem::Date1904).map(|dt| dt.time())
+}
+
+/// Days of a duration, a duration of 36 hours is 1.5
+pub fn duration_to_serial(duration: &Duration) -> f64 {
+    duration.num_milliseconds() as f64 / MILLISECONDS_PER_DAY as f64
+}
+
+/// Duration of a serial rounded to milliseconds
+pub fn serial_to_duration(serial: f64) -> Option<Duration> {
+    let milliseconds = (serial * MILLISECONDS_PER_DAY as f64).round();
+    if !milliseconds.is_finite() || milliseconds.abs() > i64::MAX as f64 {
+        return None;
+    }
+    Some(Duration::milliseconds(milliseconds as i64))
+}
+
+/// ISO 8601 duration like "PT37H30M00S", the value of an ods time cell
+///
+/// ```
+/// extern crate chrono;
+/// extern crate spsheet;
+/// use chrono::Duration;
+/// use spsheet::date;
+///
+/// # fn main() {
+/// let duration = Duration::hours(37) + Duration::minutes(30);
+/// assert_eq!("PT37H30M00S", date::duration_to_iso8601(&duration));
+/// assert_eq!(Some(duration), date::iso8601_to_duration("PT37H30M00S"));
+/// assert_eq!(Some(duration), date::iso8601_to_duration("P1DT13H30M"));
+/// # }
+/// ```
+pub fn duration_to_iso8601(duration: &Duration) -> String {
+    let milliseconds = duration.num_milliseconds();
+    let sign = if milliseconds < 0 { "-" } else { "" };
+    let milliseconds = milliseconds.abs();
+    let seconds = milliseconds / 1000;
+    let fraction = match milliseconds % 1000 {
+        0 => String::new(),
+        n => format!(".{:03}", n),
+    };
+    format!("{}PT{:02}H{:02}M{:02}{}S", sign, seconds / 3600, seconds / 60 % 60, seconds % 60, fraction)
+}
+
+/// Duration of ISO 8601 with days, hours, minutes and seconds,
+/// years and months are not a fixed length and give None
+pub fn iso8601_to_duration(src: &str) -> Option<Duration> {
+    let src = src.trim();
+    let (negative, src) = match src.strip_prefix('-') {
+        Some(src) => (true, src),
+        None => (false, src),
+    };
+    if !src.starts_with('P') {
+        return None;
+    }
+    let mut milliseconds = 0f64;
+    let mut number = String::new();
+    let mut time_flag = false;
+    for c in src[1..].chars() {
+        let unit = match c {
+            'T' => {
+                time_flag = true;
+                continue;
+            },
+            '0'..='9' | '.' | ',' => {
+                number.push(if c == ',' { '.' } else { c });
+                continue;
+            },
+            'W' if !time_flag => 7.0 * MILLISECONDS_PER_DAY as f64,
+            'D' if !time_flag => MILLISECONDS_PER_DAY as f64,
+            'H' if time_flag => 3_600_000.0,
+            'M' if time_flag => 60_000.0,
+            'S' if time_flag => 1000.0,
+            _ => return None,
+        };
+        milliseconds += number.parse::<f64>().ok()? * unit;
+        number.clear();
+    }
+    if !number.is_empty() {
+        return None;
+    }
+    let milliseconds = milliseconds.round() as i64;
+    Some(Duration::milliseconds(if negative { -milliseconds } else { milliseconds }))
+}
+
+/// ISO 8601 text of a `Value::Duration` for serde
+#[cfg(feature = "serde")]
+pub(crate) mod iso8601 {
+    use chrono::Duration;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde::de::Error;
+
+    pub fn serialize<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        serializer.serialize_str(&super::duration_to_iso8601(duration))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+        where D: Deserializer<'de>
+    {
+        let text = String::deserialize(deserializer)?;
+        super::iso8601_to_duration(&text)
+            .ok_or_else(|| D::Error::custom(format!("invalid duration: {}", text)))
+    }
+}
+
+/// Seconds east of UTC of the timezone of a `Book` for serde
+#[cfg(feature = "serde")]
+pub(crate) mod utc_offset {
+    use chrono::FixedOffset;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde::de::Error;
+
+    pub fn default() -> FixedOffset {
+        FixedOffset::east_opt(0).unwrap()
+    }
+
+    pub fn serialize<S>(timezone: &FixedOffset, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        serializer.serialize_i32(timezone.local_minus_utc())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<FixedOffset, D::Error>
+        where D: Deserializer<'de>
+    {
+        let seconds = i32::deserialize(deserializer)?;
+        FixedOffset::east_opt(seconds)
+            .ok_or_else(|| D::Error::custom(format!("invalid offset: {}", seconds)))
+    }
+}
+
+/// Name of the newtype the date fields are serialized in, `ser` writes its text as a date cell
+#[cfg(feature = "serde")]
+pub(crate) const CELL_DATE: &str = "$spsheet::date";
+
+/// Date and time of a date cell text, RFC 3339 like "2017-12-02T13:30:00+09:00" is in UTC
+#[cfg(feature = "serde")]
+fn parse_datetime(text: &str) -> Option<NaiveDateTime> {
+    let text = text.trim();
+    DateTime::parse_from_rfc3339(text).map(|dt| dt.naive_utc())
+        .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f"))
+        .or_else(|_| NaiveDate::parse_from_str(text, "%Y-%m-%d").map(|date| date.and_hms_opt(0, 0, 0).unwrap()))
+        .ok()
+}
+
+/// A `DateTime<Utc>` field of a date cell for `#[serde(with = "spsheet::date::datetime")]`,
+/// a text without the offset is in UTC
+#[cfg(feature = "serde")]
+pub mod datetime {
+    use chrono::{DateTime, TimeZone, Utc};
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde::de::Error;
+
+    pub fn serialize<S>(dt: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        serializer.serialize_newtype_struct(super::CELL_DATE, &dt.to_rfc3339())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+        where D: Deserializer<'de>
+    {
+        let text = String::deserialize(deserializer)?;
+        super::parse_datetime(&text)
+            .map(|dt| Utc.from_utc_datetime(&dt))
+            .ok_or_else(|| D::Error::custom(format!("invalid date and time: {}", text)))
+    }
+}
+
+/// A `NaiveDate` field of a date cell for `#[serde(with = "spsheet::date::naive_date")]`,
+/// the date of a date and time cell is in UTC
+///
+/// ```
+/// extern crate chrono;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate spsheet;
+/// use chrono::NaiveDate;
+/// use spsheet::{Cell, Value};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Member {
+///     #[serde(with = "spsheet::date::naive_date")]
+///     joined: NaiveDate,
+/// }
+///
+/// fn main() {
+///     let members = vec![Member { joined: NaiveDate::from_ymd_opt(2017, 12, 2).unwrap() }];
+///     let mut sheet = spsheet::ser::to_sheet("members", &members).unwrap();
+///     assert_eq!(&Value::DateOnly(NaiveDate::from_ymd_opt(2017, 12, 2).unwrap()), sheet.get_cell(1, 0).unwrap().get_value());
+///     sheet.add_cell(Cell::date("2017-12-03", "YYYY/MM/DD"), 1, 0);
+///     let members: Vec<Member> = spsheet::de::from_sheet(&sheet).unwrap();
+///     assert_eq!(NaiveDate::from_ymd_opt(2017, 12, 3).unwrap(), members[0].joined);
+/// }
+/// ```
+#[cfg(feature = "serde")]
+pub mod naive_date {
+    use chrono::NaiveDate;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde::de::Error;
+
+    pub fn serialize<S>(date: &NaiveDate, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        serializer.serialize_newtype_struct(super::CELL_DATE, &date.format("%Y-%m-%d").to_string())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<NaiveDate, D::Error>
+        where D: Deserializer<'de>
+    {
+        let text = String::deserialize(deserializer)?;
+        NaiveDate::parse_from_str(text.trim(), "%Y-%m-%d").ok()
+            .or_else(|| super::parse_datetime(&text).map(|dt| dt.date()))
+            .ok_or_else(|| D::Error::custom(format!("invalid date: {}", text)))
+    }
+}
+
+/// A `NaiveDateTime` field of a date cell in UTC for `#[serde(with = "spsheet::date::naive_datetime")]`
+#[cfg(feature = "serde")]
+pub mod naive_datetime {
+    use chrono::NaiveDateTime;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde::de::Error;
+
+    pub fn serialize<S>(dt: &NaiveDateTime, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        serializer.serialize_newtype_struct(super::CELL_DATE, &dt.format("%Y-%m-%dT%H:%M:%S%.f").to_string())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<NaiveDateTime, D::Error>
+        where D: Deserializer<'de>
+    {
+        let text = String::deserialize(deserializer)?;
+        super::parse_datetime(&text)
+            .ok_or_else(|| D::Error::custom(format!("invalid date and time: {}", text)))
+    }
+}
+
+/// A `NaiveTime` field of a time cell for `#[serde(with = "spsheet::date::naive_time")]`,
+/// the time of a date and time cell is in UTC
+#[cfg(feature = "serde")]
+pub mod naive_time {
+    use chrono::NaiveTime;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde::de::Error;
+
+    pub fn serialize<S>(time: &NaiveTime, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        serializer.serialize_newtype_struct(super::CELL_DATE, &time.format("%H:%M:%S%.f").to_string())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<NaiveTime, D::Error>
+        where D: Deserializer<'de>
+    {
+        let text = String::deserialize(deserializer)?;
+        NaiveTime::parse_from_str(text.trim(), "%H:%M:%S%.f").ok()
+            .or_else(|| super::parse_datetime(&text).map(|dt| dt.time()))
+            .ok_or_else(|| D::Error::custom(format!("invalid time: {}", text)))
+    }
+}
+
+/// A unix timestamp `i64` field of a date cell for `#[serde(with = "spsheet::date::timestamp")]`,
+/// a number cell is the seconds as they are
+#[cfg(feature = "serde")]
+pub mod timestamp {
+    use chrono::{TimeZone, Utc};
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde::de::Error;
+    use serde::ser;
+
+    pub fn serialize<S>(seconds: &i64, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        let dt = Utc.timestamp_opt(*seconds, 0).single()
+            .ok_or_else(|| <S::Error as ser::Error>::custom(format!("invalid timestamp: {}", seconds)))?;
+        serializer.serialize_newtype_struct(super::CELL_DATE, &dt.to_rfc3339())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<i64, D::Error>
+        where D: Deserializer<'de>
+    {
+        let text = String::deserialize(deserializer)?;
+        text.trim().parse::<i64>().ok()
+            .or_else(|| super::parse_datetime(&text).map(|dt| Utc.from_utc_datetime(&dt).timestamp()))
+            .ok_or_else(|| D::Error::custom(format!("invalid timestamp: {}", text)))
+    }
+}
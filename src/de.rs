@@ -0,0 +1,414 @@
+//! Deserialize sheet rows into structs
+//!
+//! The header row maps column names to struct fields, every following row
+//! becomes one value.
+//! A date cell is the RFC 3339 text of a `DateTime`, a date only cell is like "2017-12-02"
+//! and a time cell is like "13:30:00", the fields of the other chrono types use the helpers of `date`
+//! like `#[serde(with = "spsheet::date::naive_date")]`.
+//!
+//! ```
+//! #[macro_use]
+//! extern crate serde_derive;
+//! extern crate spsheet;
+//! use spsheet::{Sheet, Cell};
+//!
+//! #[derive(Deserialize)]
+//! struct Item {
+//!     name: String,
+//!     price: u32,
+//!     memo: Option<String>,
+//! }
+//!
+//! fn main() {
+//!     let mut sheet = Sheet::new("items");
+//!     sheet.add_cell(Cell::str("name", ""), 0, 0);
+//!     sheet.add_cell(Cell::str("price", ""), 0, 1);
+//!     sheet.add_cell(Cell::str("memo", ""), 0, 2);
+//!     sheet.add_cell(Cell::str("apple", ""), 1, 0);
+//!     sheet.add_cell(Cell::float(120.0, ""), 1, 1);
+//!     let items: Vec<Item> = spsheet::de::from_sheet(&sheet).unwrap();
+//!     assert_eq!("apple", items[0].name);
+//!     assert_eq!(120, items[0].price);
+//!     assert_eq!(None, items[0].memo);
+//! }
+//! ```
+use serde::de;
+use serde::de::{DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::result;
+use super::{Cell, Sheet, Value, index_to_column_and_row};
+use super::rich_text::TextRun;
+use super::date;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeError {
+    message: String,
+    reference: Option<String>,
+}
+
+impl DeError {
+    /// Reference of the failing cell like "B3"
+    pub fn get_reference(&self) -> Option<&String> {
+        self.reference.as_ref()
+    }
+
+    pub fn get_message(&self) -> &String {
+        &self.message
+    }
+
+    fn at(mut self, row_index: usize, column_index: usize) -> DeError {
+        if self.reference.is_none() {
+            self.reference = Some(index_to_column_and_row(column_index, row_index));
+        }
+        self
+    }
+}
+
+impl fmt::Display for DeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.reference {
+            Some(ref reference) => write!(f, "{}: {}", reference, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl error::Error for DeError {
+    fn description(&self) -> &str {
+        &self.message
+    }
+}
+
+impl de::Error for DeError {
+    fn custom<T: fmt::Display>(msg: T) -> DeError {
+        DeError {
+            message: msg.to_string(),
+            reference: None,
+        }
+    }
+}
+
+type Result<T> = result::Result<T, DeError>;
+
+/// Deserialize every row below the first row using it as the header.
+pub fn from_sheet<T: DeserializeOwned>(sheet: &Sheet) -> Result<Vec<T>> {
+    Deserializer::new(sheet).deserialize()
+}
+
+/// Deserializer over the rows of a sheet.
+pub struct Deserializer<'a> {
+    sheet: &'a Sheet,
+    header_row_index: usize,
+}
+
+impl<'a> Deserializer<'a> {
+    pub fn new(sheet: &'a Sheet) -> Deserializer<'a> {
+        Deserializer {
+            sheet,
+            header_row_index: 0,
+        }
+    }
+
+    /// Row which holds the column names, rows above it are ignored
+    pub fn set_header_row_index(&mut self, header_row_index: usize) {
+        self.header_row_index = header_row_index;
+    }
+
+    pub fn deserialize<T: DeserializeOwned>(&self) -> Result<Vec<T>> {
+        let mut headers: Vec<(usize, String)> = Vec::new();
+        if let Some(columns) = self.sheet.get_rows().get(&self.header_row_index) {
+            for (column_index, cell) in columns {
+                if let Value::Str(name) = cell.get_value() {
+                    headers.push((*column_index, name.clone()));
+                }
+            }
+        }
+        headers.sort();
+
+        let mut row_index_vec: Vec<usize> = self.sheet.get_rows().keys()
+            .filter(|row_index| **row_index > self.header_row_index)
+            .cloned()
+            .collect();
+        row_index_vec.sort();
+
+        let mut result = Vec::new();
+        for row_index in row_index_vec {
+            let columns = self.sheet.get_rows().get(&row_index).unwrap();
+            if columns.is_empty() {
+                continue;
+            }
+            let row = RowDeserializer {
+                columns,
+                headers: &headers,
+                row_index,
+            };
+            result.push(T::deserialize(row)?);
+        }
+        Ok(result)
+    }
+}
+
+struct RowDeserializer<'a> {
+    columns: &'a HashMap<usize, Cell>,
+    headers: &'a Vec<(usize, String)>,
+    row_index: usize,
+}
+
+impl<'de, 'a> de::Deserializer<'de> for RowDeserializer<'a> {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_map(RowAccess {
+            row: self,
+            position: 0,
+        })
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_seq(RowAccess {
+            row: self,
+            position: 0,
+        })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, _len: usize, visitor: V) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes
+        byte_buf option unit unit_struct newtype_struct map struct enum
+        identifier ignored_any
+    }
+}
+
+struct RowAccess<'a> {
+    row: RowDeserializer<'a>,
+    position: usize,
+}
+
+impl<'a> RowAccess<'a> {
+    fn next_cell<'de, T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<T::Value> {
+        let column_index = self.row.headers[self.position].0;
+        self.position += 1;
+        let cell = CellDeserializer {
+            cell: self.row.columns.get(&column_index),
+        };
+        seed.deserialize(cell).map_err(|err| err.at(self.row.row_index, column_index))
+    }
+}
+
+impl<'de, 'a> MapAccess<'de> for RowAccess<'a> {
+    type Error = DeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        if self.position >= self.row.headers.len() {
+            return Ok(None);
+        }
+        let name = self.row.headers[self.position].1.as_str();
+        seed.deserialize(name.into_deserializer()).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        self.next_cell(seed)
+    }
+}
+
+impl<'de, 'a> SeqAccess<'de> for RowAccess<'a> {
+    type Error = DeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        if self.position >= self.row.headers.len() {
+            return Ok(None);
+        }
+        self.next_cell(seed).map(Some)
+    }
+}
+
+struct CellDeserializer<'a> {
+    cell: Option<&'a Cell>,
+}
+
+impl<'a> CellDeserializer<'a> {
+    fn get_value(&self) -> Option<&'a Value> {
+        match self.cell {
+            Some(cell) => match cell.get_value() {
+                Value::Str(value) if value.is_empty() => None,
+                &Value::Empty => None,
+                value => Some(value),
+            },
+            None => None,
+        }
+    }
+
+    fn get_float(&self) -> Result<f64> {
+        match self.get_value() {
+            Some(&Value::Float(value)) => Ok(value),
+            Some(Value::Currency(value)) => Ok(value.get_amount()),
+            Some(Value::Str(value)) => {
+                value.trim().parse::<f64>()
+                    .map_err(|_| de::Error::custom(format!("invalid number: {}", value)))
+            },
+            Some(Value::RichText(runs)) => {
+                let value = TextRun::concat(runs);
+                value.trim().parse::<f64>()
+                    .map_err(|_| de::Error::custom(format!("invalid number: {}", value)))
+            },
+            Some(&Value::Bool(value)) => Ok(if value { 1.0 } else { 0.0 }),
+            Some(Value::Error(value)) => Err(de::Error::custom(format!("expected a number, found {}", value))),
+            Some(&Value::Date(_)) | Some(&Value::DateOnly(_)) => Err(de::Error::custom("expected a number, found a date")),
+            Some(&Value::Time(_)) => Err(de::Error::custom("expected a number, found a time")),
+            // seconds of the duration
+            Some(&Value::Duration(value)) => Ok(value.num_milliseconds() as f64 / 1000.0),
+            Some(&Value::Empty) | None => Err(de::Error::custom("expected a number, found an empty cell")),
+        }
+    }
+
+    fn get_integer(&self) -> Result<i64> {
+        if let Some(Value::Str(value)) = self.get_value() {
+            if let Ok(value) = value.trim().parse::<i64>() {
+                return Ok(value);
+            }
+        }
+        let value = self.get_float()?;
+        if value.fract() != 0.0 {
+            return Err(de::Error::custom(format!("expected an integer, found {}", value)));
+        }
+        Ok(value as i64)
+    }
+}
+
+impl<'de, 'a> de::Deserializer<'de> for CellDeserializer<'a> {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.get_value() {
+            Some(Value::Str(value)) => visitor.visit_str(value),
+            Some(&Value::Float(value)) => visitor.visit_f64(value),
+            Some(Value::Currency(value)) => visitor.visit_f64(value.get_amount()),
+            Some(Value::Date(value)) => visitor.visit_string(value.to_rfc3339()),
+            Some(Value::RichText(runs)) => visitor.visit_string(TextRun::concat(runs)),
+            Some(&Value::Bool(value)) => visitor.visit_bool(value),
+            Some(Value::Error(value)) => visitor.visit_str(value),
+            Some(Value::Time(value)) => visitor.visit_string(value.format("%H:%M:%S%.f").to_string()),
+            Some(Value::Duration(value)) => visitor.visit_string(date::duration_to_iso8601(value)),
+            Some(Value::DateOnly(value)) => visitor.visit_string(value.format("%Y-%m-%d").to_string()),
+            Some(&Value::Empty) | None => visitor.visit_none(),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.get_value() {
+            Some(Value::Str(value)) => {
+                match value.trim().to_lowercase().as_str() {
+                    "true" | "1" => visitor.visit_bool(true),
+                    "false" | "0" => visitor.visit_bool(false),
+                    _ => Err(de::Error::custom(format!("invalid bool: {}", value))),
+                }
+            },
+            Some(&Value::Float(value)) => visitor.visit_bool(value != 0.0),
+            Some(&Value::Bool(value)) => visitor.visit_bool(value),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_i8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_i64(self.get_integer()?)
+    }
+
+    fn deserialize_i16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_i64(self.get_integer()?)
+    }
+
+    fn deserialize_i32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_i64(self.get_integer()?)
+    }
+
+    fn deserialize_i64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_i64(self.get_integer()?)
+    }
+
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_i64(self.get_integer()?)
+    }
+
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_i64(self.get_integer()?)
+    }
+
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_i64(self.get_integer()?)
+    }
+
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_i64(self.get_integer()?)
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_f64(self.get_float()?)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_f64(self.get_float()?)
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.get_value() {
+            Some(&Value::Float(value)) => visitor.visit_string(value.to_string()),
+            Some(Value::Currency(value)) => visitor.visit_string(value.get_amount().to_string()),
+            Some(Value::Date(value)) => visitor.visit_string(value.to_rfc3339()),
+            Some(Value::Str(value)) => visitor.visit_str(value),
+            Some(Value::RichText(runs)) => visitor.visit_string(TextRun::concat(runs)),
+            Some(&Value::Bool(value)) => visitor.visit_string(value.to_string()),
+            Some(Value::Error(value)) => visitor.visit_str(value),
+            Some(Value::Time(value)) => visitor.visit_string(value.format("%H:%M:%S%.f").to_string()),
+            Some(Value::Duration(value)) => visitor.visit_string(date::duration_to_iso8601(value)),
+            Some(Value::DateOnly(value)) => visitor.visit_string(value.format("%Y-%m-%d").to_string()),
+            Some(&Value::Empty) | None => visitor.visit_str(""),
+        }
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.get_value() {
+            Some(_) => visitor.visit_some(self),
+            None => visitor.visit_none(),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value> {
+        match self.get_value() {
+            Some(Value::Str(value)) => visitor.visit_enum(value.trim().into_deserializer()),
+            _ => Err(de::Error::custom("expected a string for an enum variant")),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        bytes byte_buf unit_struct seq tuple tuple_struct map struct identifier
+    }
+}
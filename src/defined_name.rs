@@ -0,0 +1,150 @@
+//! Names of cell ranges and constants like "TaxRate", the names of a sheet are only known in it
+use std::borrow::Cow;
+use formula;
+use range::{self, Range};
+
+/// Built-in name of the cells printed from a sheet
+pub const PRINT_AREA: &str = "_xlnm.Print_Area";
+/// Built-in name of the rows or columns printed on each page of a sheet
+pub const PRINT_TITLES: &str = "_xlnm.Print_Titles";
+/// Built-in hidden name of the list of the auto filter of a sheet, xlsx makes it from `Sheet::get_auto_filter`
+pub const FILTER_DATABASE: &str = "_xlnm._FilterDatabase";
+
+/// DefinedName is a name of a formula, the formula is the cells of the name like "Sheet1!$A$1:$B$5",
+/// the areas separated by commas like "Sheet1!$A$1:$B$5,Sheet1!$D$1:$D$5" or a constant like "0.08".
+///
+/// ```
+/// use spsheet::{Book, Sheet};
+/// use spsheet::defined_name::DefinedName;
+/// use spsheet::range::Range;
+///
+/// let mut book = Book::new();
+/// book.add_sheet(Sheet::new("input"));
+/// book.add_defined_name(DefinedName::range("Amounts", &Range::new("input", 1, 1, 10, 1)));
+/// book.add_defined_name(DefinedName::new("TaxRate", "0.08"));
+/// assert_eq!(Some(Range::new("input", 1, 1, 10, 1)), book.get_named_range("amounts", None));
+/// assert_eq!(None, book.get_named_range("TaxRate", None));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DefinedName {
+    name: String,
+    formula: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    hidden: bool,
+}
+
+impl DefinedName {
+    pub fn new<'a, S>(name: S, formula: S) -> DefinedName
+        where S: Into<Cow<'a, str>>
+    {
+        DefinedName {
+            name: name.into().into_owned(),
+            formula: formula.into().into_owned(),
+            hidden: false,
+        }
+    }
+
+    /// A name of the cells of the range
+    pub fn range<'a, S>(name: S, range: &Range) -> DefinedName
+        where S: Into<Cow<'a, str>>
+    {
+        DefinedName::new(name.into(), Cow::Owned(range.to_string()))
+    }
+
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn set_name<'a, S>(&mut self, name: S)
+        where S: Into<Cow<'a, str>>
+    {
+        self.name = name.into().into_owned();
+    }
+
+    /// Formula without the leading "="
+    pub fn get_formula(&self) -> &String {
+        &self.formula
+    }
+
+    pub fn set_formula<'a, S>(&mut self, formula: S)
+        where S: Into<Cow<'a, str>>
+    {
+        self.formula = formula.into().into_owned();
+    }
+
+    /// A hidden name isn't listed by the application
+    pub fn is_hidden(&self) -> bool {
+        self.hidden
+    }
+
+    pub fn set_hidden(&mut self, hidden: bool) {
+        self.hidden = hidden;
+    }
+
+    /// Areas of the formula, empty for a constant or a formula which isn't only references
+    pub fn get_ranges(&self) -> Vec<Range> {
+        let mut ranges = Vec::new();
+        for area in range::split_unquoted(&self.formula, ',') {
+            match Range::parse(area) {
+                Some(range) => ranges.push(range),
+                None => return Vec::new(),
+            }
+        }
+        ranges
+    }
+
+    /// The range of a formula of one area
+    pub fn get_range(&self) -> Option<Range> {
+        let mut ranges = self.get_ranges();
+        if ranges.len() == 1 { ranges.pop() } else { None }
+    }
+
+    /// Rename the sheet of the references to the sheet
+    pub(crate) fn rename_sheet(&mut self, sheet_name: &str, new_name: &str) {
+        self.formula = formula::rename_sheet(&self.formula, sheet_name, new_name);
+    }
+
+    /// Move the references to the sheet like the rows inserted at `row_index`
+    pub(crate) fn insert_rows(&mut self, sheet_name: &str, row_index: usize, count: usize) {
+        self.formula = formula::insert_rows(&self.formula, sheet_name, row_index, count, false);
+    }
+
+    /// Move the references to the sheet like the rows removed at `row_index`, the areas only on the removed rows are dropped.
+    /// False when no area is left, a formula which isn't only references gets "#REF!" like the formulas of the cells.
+    pub(crate) fn remove_rows(&mut self, sheet_name: &str, row_index: usize, count: usize) -> bool {
+        if self.get_ranges().is_empty() {
+            self.formula = formula::remove_rows(&self.formula, sheet_name, row_index, count);
+            return true;
+        }
+        let end = row_index + count;
+        let areas: Vec<String> = range::split_unquoted(&self.formula, ',').into_iter().zip(self.get_ranges())
+            .filter(|(_, range)| {
+                range.get_sheet_name().to_lowercase() != sheet_name.to_lowercase()
+                    || range.get_first_row() < row_index || range.get_last_row() >= end
+            })
+            .map(|(area, _)| formula::remove_rows(area, sheet_name, row_index, count))
+            .collect();
+        self.formula = areas.join(",");
+        !areas.is_empty()
+    }
+}
+
+/// Find a name, the names aren't case sensitive
+pub(crate) fn find<'a>(names: &'a [DefinedName], name: &str) -> Option<&'a DefinedName> {
+    names.iter().find(|n| n.name.to_lowercase() == name.to_lowercase())
+}
+
+/// Add a name replacing the one with the same name
+pub(crate) fn add(names: &mut Vec<DefinedName>, defined_name: DefinedName) {
+    let name = defined_name.name.to_lowercase();
+    match names.iter().position(|n| n.name.to_lowercase() == name) {
+        Some(index) => names[index] = defined_name,
+        None => names.push(defined_name),
+    }
+}
+
+pub(crate) fn remove(names: &mut Vec<DefinedName>, name: &str) -> Option<DefinedName> {
+    let name = name.to_lowercase();
+    names.iter().position(|n| n.name.to_lowercase() == name).map(|index| names.remove(index))
+}
@@ -4,12 +4,13 @@ extern crate walkdir;
 extern crate zip;
 
 use self::quick_xml::events::attributes::Attribute;
-use self::quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
-use self::quick_xml::Writer;
+use self::quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use self::quick_xml::{Reader, Writer};
 use self::tempdir::TempDir;
 use self::walkdir::WalkDir;
 use self::zip::write::FileOptions;
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::fs;
 use std::fs::File;
 use std::io;
@@ -23,6 +24,12 @@ pub fn write_to_file(path: &Path, dir: &TempDir) -> Result<(), io::Error> {
     let options = FileOptions::default()
         .compression_method(zip::CompressionMethod::Deflated)
         .unix_permissions(0o644);
+    // the mimetype of an ods package is its first file without compression
+    let mimetype = dir.path().join("mimetype");
+    if mimetype.is_file() {
+        zip.start_file("mimetype", FileOptions::default().compression_method(zip::CompressionMethod::Stored))?;
+        zip.write_all(&fs::read(&mimetype)?)?;
+    }
     let walkdir = WalkDir::new(dir.path());
     let it = walkdir.into_iter();
 
@@ -34,7 +41,7 @@ pub fn write_to_file(path: &Path, dir: &TempDir) -> Result<(), io::Error> {
             .to_str()
             .unwrap();
 
-        if path.is_file() {
+        if path.is_file() && path != mimetype {
             //println!("adding {:?} as {:?} ...", path, name);
             try!(zip.start_file(name, options));
             let mut f = File::open(path)?;
@@ -73,6 +80,33 @@ pub fn unzip(zip_file: &File, dir: &TempDir) -> Result<(), zip::result::ZipError
     Ok(())
 }
 
+/// Read every file of the zip into memory
+pub fn read_package(zip_file: &File) -> Result<BTreeMap<String, Vec<u8>>, zip::result::ZipError> {
+    let mut zip = zip::ZipArchive::new(zip_file)?;
+    let mut parts = BTreeMap::new();
+    for i in 0..zip.len() {
+        let mut file = zip.by_index(i)?;
+        if file.name().ends_with('/') {
+            continue;
+        }
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)?;
+        parts.insert(String::from(file.name()), buffer);
+    }
+    Ok(parts)
+}
+
+pub fn make_file_from_bytes(temp_dir: &TempDir, path: &str, data: &[u8]) -> Result<(), io::Error> {
+    let file_path = temp_dir.path().join(path);
+    if let Some(parent) = file_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut f = File::create(file_path)?;
+    f.write_all(data)?;
+    f.sync_all()?;
+    Ok(())
+}
+
 pub fn make_static_file(
     temp_dir: &TempDir,
     path: &str,
@@ -127,6 +161,238 @@ where
     let _ = writer.write_event(Event::Text(BytesText::from_plain_str(&data.into())));
 }
 
+/// Write already escaped xml as is
+pub fn write_raw(writer: &mut Writer<Cursor<Vec<u8>>>, data: &[u8]) {
+    let _ = writer.write_event(Event::Text(BytesText::from_escaped(data)));
+}
+
+/// Write the tag keeping the original attributes except the replaced ones
+pub fn write_start_tag_with_replaced_attributes(
+    writer: &mut Writer<Cursor<Vec<u8>>>,
+    e: &BytesStart,
+    replaced: Vec<(&str, &str)>,
+    empty_flag: bool,
+) {
+    let mut elem = BytesStart::owned(e.name().to_vec(), e.name().len());
+    let mut rest = replaced.clone();
+    for attr in e.attributes().with_checks(false).flatten() {
+        match replaced.iter().find(|r| r.0.as_bytes() == attr.key) {
+            Some(attribute) => {
+                elem.push_attribute((attribute.0, attribute.1));
+                rest.retain(|r| r.0 != attribute.0);
+            }
+            None => elem.push_attribute(attr),
+        }
+    }
+    for attribute in &rest {
+        elem.push_attribute((attribute.0, attribute.1));
+    }
+    if empty_flag {
+        let _ = writer.write_event(Event::Empty(elem));
+    } else {
+        let _ = writer.write_event(Event::Start(elem));
+    }
+}
+
+/// Copy of the start tag without the attributes of the keys
+pub fn without_attributes(e: &BytesStart, keys: &[&str]) -> BytesStart<'static> {
+    let mut elem = BytesStart::owned(e.name().to_vec(), e.name().len());
+    for attr in e.attributes().with_checks(false).flatten() {
+        if !keys.iter().any(|key| key.as_bytes() == attr.key) {
+            elem.push_attribute(attr);
+        }
+    }
+    elem
+}
+
+/// Xml element kept as written, used to copy parts of an existing file
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawElement {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+impl RawElement {
+    pub fn new<F>(name: &str, callback: F) -> RawElement
+    where
+        F: FnOnce(&mut Writer<Cursor<Vec<u8>>>),
+    {
+        let mut writer = Writer::new(Cursor::new(Vec::new()));
+        callback(&mut writer);
+        RawElement {
+            name: String::from(name),
+            data: writer.into_inner().into_inner(),
+        }
+    }
+
+    /// Start tag of the element
+    pub fn start_tag(&self) -> Option<BytesStart<'static>> {
+        let mut reader = Reader::from_reader(&self.data[..]);
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event(&mut buf) {
+                Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => return Some(e.clone().into_owned()),
+                Ok(Event::Eof) | Err(_) => return None,
+                _ => {}
+            }
+            buf.clear();
+        }
+    }
+
+    /// Unescaped value of an attribute of the start tag
+    pub fn get_attribute(&self, key: &str) -> Option<String> {
+        let tag = self.start_tag()?;
+        for attr in tag.attributes().with_checks(false).flatten() {
+            if attr.key == key.as_bytes() {
+                return get_attribute_value(&attr)
+                    .ok()
+                    .map(|v| condvert_character_reference(&v));
+            }
+        }
+        None
+    }
+
+    /// Copy of the element with some attributes of the start tag replaced
+    pub fn with_replaced_attributes(&self, replaced: Vec<(&str, &str)>) -> RawElement {
+        let mut reader = Reader::from_reader(&self.data[..]);
+        let mut writer = Writer::new(Cursor::new(Vec::new()));
+        let mut buf = Vec::new();
+        let mut first = true;
+        loop {
+            match reader.read_event(&mut buf) {
+                Ok(Event::Start(ref e)) if first => {
+                    write_start_tag_with_replaced_attributes(&mut writer, e, replaced.clone(), false);
+                    first = false;
+                }
+                Ok(Event::Empty(ref e)) if first => {
+                    write_start_tag_with_replaced_attributes(&mut writer, e, replaced.clone(), true);
+                    first = false;
+                }
+                Ok(Event::Eof) | Err(_) => break,
+                Ok(ref event) => {
+                    let _ = writer.write_event(event);
+                }
+            }
+            buf.clear();
+        }
+        RawElement {
+            name: self.name.clone(),
+            data: writer.into_inner().into_inner(),
+        }
+    }
+}
+
+/// Write a document from the root start tag and its children
+pub fn make_file_from_elements(
+    path: &str,
+    temp_dir: &TempDir,
+    root: &BytesStart,
+    children: &[RawElement],
+) -> Result<(), io::Error> {
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    let _ = writer.write_event(Event::Decl(BytesDecl::new(b"1.0", Some(b"UTF-8"), Some(b"yes"))));
+    write_text_node(&mut writer, "\n");
+    if children.is_empty() {
+        write_start_tag_with_replaced_attributes(&mut writer, root, vec![], true);
+    } else {
+        write_start_tag_with_replaced_attributes(&mut writer, root, vec![], false);
+        for child in children {
+            write_raw(&mut writer, &child.data);
+        }
+        let _ = writer.write_event(Event::End(BytesEnd::borrowed(root.name())));
+    }
+    make_file_from_bytes(temp_dir, path, writer.into_inner().get_ref())
+}
+
+/// Split a document into the root start tag and its child elements.
+/// Text between the children is dropped.
+pub fn read_children(data: &[u8]) -> Result<(Option<BytesStart<'static>>, Vec<RawElement>), quick_xml::Error> {
+    let mut reader = Reader::from_reader(data);
+    reader.trim_text(false);
+    let mut buf = Vec::new();
+    let mut root = None;
+    let mut children = Vec::new();
+    let mut depth = 0;
+    let mut name = String::from("");
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    loop {
+        let event = reader.read_event(&mut buf)?;
+        match event {
+            Event::Eof => break,
+            Event::Decl(_) | Event::PI(_) | Event::DocType(_) if depth == 0 => {}
+            Event::Start(ref e) if depth == 0 => {
+                root = Some(e.clone().into_owned());
+                depth = 1;
+            }
+            Event::Empty(ref e) if depth == 0 => {
+                root = Some(e.clone().into_owned());
+            }
+            Event::End(_) if depth == 1 => {
+                depth = 0;
+            }
+            Event::Text(_) | Event::Comment(_) if depth <= 1 => {}
+            Event::Empty(ref e) if depth == 1 => {
+                let _ = writer.write_event(&event);
+                children.push(RawElement {
+                    name: String::from_utf8_lossy(e.name()).into_owned(),
+                    data: writer.into_inner().into_inner(),
+                });
+                writer = Writer::new(Cursor::new(Vec::new()));
+            }
+            Event::Start(ref e) => {
+                if depth == 1 {
+                    name = String::from_utf8_lossy(e.name()).into_owned();
+                }
+                depth += 1;
+                let _ = writer.write_event(&event);
+            }
+            Event::End(_) => {
+                depth -= 1;
+                let _ = writer.write_event(&event);
+                if depth == 1 {
+                    children.push(RawElement {
+                        name: name.clone(),
+                        data: writer.into_inner().into_inner(),
+                    });
+                    writer = Writer::new(Cursor::new(Vec::new()));
+                }
+            }
+            _ => {
+                let _ = writer.write_event(&event);
+            }
+        }
+        buf.clear();
+    }
+    Ok((root, children))
+}
+
+/// Merge generated elements into the original children keeping the schema order.
+/// Original elements with the same name as a generated one are replaced,
+/// unknown original elements stay next to their preceding sibling.
+pub fn merge_elements(order: &[&str], original: Vec<RawElement>, generated: Vec<RawElement>) -> Vec<RawElement> {
+    let rank = |name: &str| order.iter().position(|n| *n == name);
+    let generated_names: Vec<String> = generated.iter().map(|e| e.name.clone()).collect();
+    let mut pending = generated;
+    pending.sort_by_key(|e| rank(&e.name).unwrap_or(order.len()));
+    pending.reverse();
+    let mut result = Vec::new();
+    let mut last_rank = 0;
+    for element in original {
+        let element_rank = rank(&element.name).unwrap_or(last_rank);
+        last_rank = element_rank;
+        while pending.last().is_some_and(|e| rank(&e.name).unwrap_or(order.len()) <= element_rank) {
+            result.push(pending.pop().unwrap());
+        }
+        if !generated_names.contains(&element.name) {
+            result.push(element);
+        }
+    }
+    while let Some(e) = pending.pop() {
+        result.push(e);
+    }
+    result
+}
+
 pub fn make_file_from_writer(
     path: &str,
     temp_dir: &TempDir,
@@ -159,3 +425,17 @@ pub fn condvert_character_reference(src: &str) -> String {
         .replace("&quot;", "\"")
         .replace("&apos;", "'")
 }
+
+/// "59.25pt", "1in", "2.5cm", "10mm" or "79px" in points
+pub fn length_to_points(src: &str) -> Option<f64> {
+    let src = src.trim();
+    let units: [(&str, f64); 5] = [
+        ("pt", 1.0), ("px", 0.75), ("in", 72.0), ("cm", 72.0 / 2.54), ("mm", 72.0 / 25.4),
+    ];
+    for &(unit, scale) in units.iter() {
+        if let Some(number) = src.strip_suffix(unit) {
+            return number.trim().parse::<f64>().ok().map(|v| v * scale);
+        }
+    }
+    src.parse::<f64>().ok()
+}
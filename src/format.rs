@@ -2,9 +2,18 @@
 use std::borrow::Cow;
 use super::nom::{IResult};
 use chrono::prelude::*;
+use chrono::Duration;
 use super::era_jp;
 
+const DATE_ITEMS: [&str; 18] = [
+    "%Y", "%y", "{{era1}}", "{{era2}}", "{{gengou1}}", "{{gengou2}}", "{{gengou3}}",
+    "%-m", "%m", "%b", "%B", "{{month5}}", "%-d", "%d", "%a", "%A", "{{youbi3}}", "{{youbi4}}",
+];
+const TIME_ITEMS: [&str; 6] = ["%-H", "%H", "%-M", "%M", "%-S", "%S"];
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct Format {
     content: String,
 }
@@ -89,6 +98,451 @@ impl Format {
             _ => None
         }
     }
+
+    /// Elapsed time format which has the hours, minutes or seconds in brackets like "[h]:mm"
+    ///
+    /// ```
+    /// use spsheet::format::Format;
+    /// assert!(Format::new("[h]:mm:ss").is_elapsed());
+    /// assert!(!Format::new("[Red]h:mm").is_elapsed());
+    /// ```
+    pub fn is_elapsed(&self) -> bool {
+        let mut rest = self.content.as_str();
+        while let Some(start) = rest.find('[') {
+            rest = &rest[start + 1..];
+            let end = rest.find(']').unwrap_or(rest.len());
+            if is_elapsed_unit(&rest[..end]) {
+                return true;
+            }
+            rest = &rest[end..];
+        }
+        false
+    }
+
+    /// Format of a date or a time like "yyyy/mm/dd" or "hh:mm", not of a number like "#,##0"
+    ///
+    /// ```
+    /// use spsheet::format::Format;
+    /// assert!(Format::new("M\\月D\\日").is_date());
+    /// assert!(!Format::new("General").is_date());
+    /// assert!(!Format::new("[$¥-411]#,##0").is_date());
+    /// ```
+    pub fn is_date(&self) -> bool {
+        if self.content.eq_ignore_ascii_case("General") {
+            return false;
+        }
+        let content = self.content.replace(['[', ']'], "");
+        match ymdhms(content.as_str()) {
+            IResult::Done(_, output) => {
+                output.iter().flat_map(|item_ary| item_ary.iter())
+                    .any(|item| TIME_ITEMS.contains(item) || DATE_ITEMS.contains(item))
+            },
+            _ => false
+        }
+    }
+
+    /// Format of hours, minutes or seconds without year, month or day like "hh:mm"
+    ///
+    /// ```
+    /// use spsheet::format::Format;
+    /// assert!(Format::new("hh:mm").is_time_only());
+    /// assert!(!Format::new("yyyy/mm/dd hh:mm").is_time_only());
+    /// ```
+    pub fn is_time_only(&self) -> bool {
+        let content = self.content.replace(['[', ']'], "");
+        match ymdhms(content.as_str()) {
+            IResult::Done(_, output) => {
+                let items: Vec<&str> = output.into_iter().flatten().collect();
+                items.iter().any(|item| TIME_ITEMS.contains(item))
+                    && !items.iter().any(|item| DATE_ITEMS.contains(item))
+            },
+            _ => false
+        }
+    }
+
+    /// Text of an elapsed time, the hours, minutes or seconds in brackets don't wrap
+    ///
+    /// ```
+    /// extern crate chrono;
+    /// extern crate spsheet;
+    /// use chrono::Duration;
+    /// use spsheet::format::Format;
+    ///
+    /// # fn main() {
+    /// let duration = Duration::hours(37) + Duration::minutes(30);
+    /// assert_eq!(Some(String::from("37:30:00")), Format::new("[h]:mm:ss").get_formated_duration(&duration));
+    /// assert_eq!(Some(String::from("13:30")), Format::new("hh:mm").get_formated_duration(&duration));
+    /// # }
+    /// ```
+    pub fn get_formated_duration(&self, duration: &Duration) -> Option<String> {
+        if self.content.is_empty() {
+            return None;
+        }
+        let milliseconds = duration.num_milliseconds();
+        let seconds = milliseconds.abs() / 1000;
+        let mut result = String::from(if milliseconds < 0 { "-" } else { "" });
+        let chars: Vec<char> = self.content.chars().collect();
+        let mut index = 0;
+        while index < chars.len() {
+            let c = chars[index];
+            let run = chars[index..].iter().take_while(|x| x.eq_ignore_ascii_case(&c)).count();
+            match c {
+                '[' => {
+                    let unit: String = chars[index + 1..].iter().take_while(|&&x| x != ']').collect();
+                    index = index + unit.chars().count() + 2;
+                    if is_elapsed_unit(&unit) {
+                        let value = match unit.chars().next() {
+                            Some('h') | Some('H') => seconds / 3600,
+                            Some('m') | Some('M') => seconds / 60,
+                            _ => seconds,
+                        };
+                        result.push_str(&format!("{:01$}", value, unit.len()));
+                    }
+                    continue;
+                },
+                '"' => {
+                    let text: String = chars[index + 1..].iter().take_while(|&&x| x != '"').collect();
+                    index = index + text.chars().count() + 2;
+                    result.push_str(&text);
+                    continue;
+                },
+                '\\' => {
+                    if let Some(x) = chars.get(index + 1) {
+                        result.push(*x);
+                    }
+                    index += 2;
+                    continue;
+                },
+                'h' | 'H' => result.push_str(&format!("{:01$}", seconds / 3600 % 24, run.min(2))),
+                'm' | 'M' => result.push_str(&format!("{:01$}", seconds / 60 % 60, run.min(2))),
+                's' | 'S' => {
+                    result.push_str(&format!("{:01$}", seconds % 60, run.min(2)));
+                    let digits = chars[index + run..].iter().skip(1).take_while(|&&x| x == '0').count();
+                    if digits > 0 && chars[index + run] == '.' {
+                        let fraction = format!("{:03}", milliseconds.abs() % 1000);
+                        result.push('.');
+                        result.push_str(&fraction[..digits.min(3)]);
+                        index = index + digits + 1;
+                    }
+                },
+                _ => {
+                    result.push(c);
+                    index += 1;
+                    continue;
+                },
+            }
+            index += run;
+        }
+        Some(result)
+    }
+}
+
+/// Part of a section of a number format code like "[$¥-411]#,##0"
+#[derive(Debug, Clone, PartialEq)]
+pub enum NumberToken {
+    /// "General", the number as it is
+    General,
+    /// Digits like "#,##0.00"
+    Number {
+        min_integer_digits: usize,
+        decimal_places: usize,
+        grouping: bool,
+    },
+    /// "%", the number is shown multiplied by 100
+    Percent,
+    /// "[$¥-411]", the symbol and the locale id
+    Currency {
+        symbol: String,
+        locale: Option<String>,
+    },
+    /// Digits with an exponent like "0.00E+00"
+    Scientific {
+        min_integer_digits: usize,
+        decimal_places: usize,
+        min_exponent_digits: usize,
+    },
+    /// A fraction like "# ?/?", without the integer part like "?/?",
+    /// or with a fixed denominator like "# ?/4"
+    Fraction {
+        min_integer_digits: Option<usize>,
+        min_numerator_digits: usize,
+        min_denominator_digits: usize,
+        denominator: Option<usize>,
+    },
+    /// "[Red]"
+    Color(String),
+    Text(String),
+}
+
+impl Format {
+    /// Sections of a number format code separated by ";",
+    /// for positive, negative, zero and text values
+    ///
+    /// ```
+    /// use spsheet::format::{Format, NumberToken};
+    /// let sections = Format::new("#,##0.00;[Red]-#,##0.00").get_number_sections();
+    /// assert_eq!(2, sections.len());
+    /// assert_eq!(NumberToken::Color(String::from("Red")), sections[1][0]);
+    /// ```
+    pub fn get_number_sections(&self) -> Vec<Vec<NumberToken>> {
+        let chars: Vec<char> = self.content.chars().collect();
+        let mut sections = vec![];
+        let mut tokens: Vec<NumberToken> = vec![];
+        let mut index = 0;
+        while index < chars.len() {
+            let c = chars[index];
+            let token = match c {
+                ';' => {
+                    sections.push(tokens);
+                    tokens = vec![];
+                    index += 1;
+                    continue;
+                },
+                '[' => {
+                    let inner: String = chars[index + 1..].iter().take_while(|&&x| x != ']').collect();
+                    index = index + inner.chars().count() + 2;
+                    if let Some(currency) = inner.strip_prefix('$') {
+                        let mut parts = currency.splitn(2, '-');
+                        NumberToken::Currency {
+                            symbol: String::from(parts.next().unwrap_or("")),
+                            locale: parts.next().map(String::from),
+                        }
+                    } else if COLORS.iter().any(|color| color.eq_ignore_ascii_case(&inner)) {
+                        NumberToken::Color(inner)
+                    } else {
+                        // a condition like [>100] or an elapsed time
+                        continue;
+                    }
+                },
+                '"' => {
+                    let text: String = chars[index + 1..].iter().take_while(|&&x| x != '"').collect();
+                    index = index + text.chars().count() + 2;
+                    NumberToken::Text(text)
+                },
+                '\\' => {
+                    index += 2;
+                    match chars.get(index - 1) {
+                        Some(x) => NumberToken::Text(x.to_string()),
+                        None => continue,
+                    }
+                },
+                // the space as wide as the next character, or the next character to fill the cell
+                '_' | '*' => {
+                    index += 2;
+                    if c == '_' { NumberToken::Text(String::from(" ")) } else { continue }
+                },
+                '%' => {
+                    index += 1;
+                    NumberToken::Percent
+                },
+                '0' | '#' | '?' | '.' | ',' => {
+                    let digits: String = chars[index..].iter().take_while(|&&x| "0#?.,".contains(x)).collect();
+                    index += digits.len();
+                    let mut parts = digits.splitn(2, '.');
+                    let integer = parts.next().unwrap_or("");
+                    let decimal = parts.next().unwrap_or("");
+                    let min_integer_digits = integer.matches('0').count();
+                    let decimal_places = decimal.chars().filter(|&x| x != ',').count();
+                    let exponent = match (chars.get(index), chars.get(index + 1)) {
+                        (Some(&'E'), Some(&x)) | (Some(&'e'), Some(&x)) if x == '+' || x == '-' => {
+                            chars[index + 2..].iter().take_while(|&&x| x == '0' || x == '#').count()
+                        },
+                        _ => 0,
+                    };
+                    if exponent > 0 {
+                        index = index + exponent + 2;
+                        NumberToken::Scientific {
+                            min_integer_digits,
+                            decimal_places,
+                            min_exponent_digits: exponent,
+                        }
+                    } else if chars.get(index) == Some(&'/') {
+                        let denominator: String = chars[index + 1..].iter()
+                            .take_while(|&&x| x.is_ascii_digit() || x == '#' || x == '?').collect();
+                        index = index + denominator.len() + 1;
+                        // the integer part is the digits before a space like "# ?/?"
+                        let integer_part = match (tokens.pop(), tokens.pop()) {
+                            (Some(NumberToken::Text(ref space)), Some(NumberToken::Number { min_integer_digits, .. })) if space == " " => {
+                                Some(min_integer_digits)
+                            },
+                            (last, before) => {
+                                tokens.extend(before);
+                                tokens.extend(last);
+                                None
+                            },
+                        };
+                        NumberToken::Fraction {
+                            min_integer_digits: integer_part,
+                            min_numerator_digits: digits.matches(['0', '?']).count().max(1),
+                            min_denominator_digits: denominator.matches(['0', '?']).count().max(1),
+                            denominator: denominator.parse::<usize>().ok().filter(|&x| x > 0),
+                        }
+                    } else {
+                        NumberToken::Number {
+                            min_integer_digits,
+                            decimal_places,
+                            // a comma at the end scales the number by 1000 instead
+                            grouping: integer.trim_end_matches(',').contains(','),
+                        }
+                    }
+                },
+                _ if chars[index..].iter().take(7).collect::<String>().eq_ignore_ascii_case("General") => {
+                    index += 7;
+                    NumberToken::General
+                },
+                _ => {
+                    index += 1;
+                    NumberToken::Text(c.to_string())
+                },
+            };
+            // the characters in a row are one text
+            match (tokens.last_mut(), token) {
+                (Some(&mut NumberToken::Text(ref mut text)), NumberToken::Text(ref next)) => text.push_str(next),
+                (_, token) => tokens.push(token),
+            }
+        }
+        sections.push(tokens);
+        sections
+    }
+
+    /// Text of a number by the number format code
+    ///
+    /// ```
+    /// use spsheet::format::Format;
+    /// assert_eq!(Some(String::from("¥1,235")), Format::new("[$¥-411]#,##0").get_formated_number(1234.5));
+    /// assert_eq!(Some(String::from("-12.50%")), Format::new("0.00%").get_formated_number(-0.125));
+    /// assert_eq!(Some(String::from("(3.00)")), Format::new("0.00;(0.00)").get_formated_number(-3.0));
+    /// assert_eq!(Some(String::from("1.23E+04")), Format::new("0.00E+00").get_formated_number(12345.0));
+    /// assert_eq!(Some(String::from("1 1/4")), Format::new("# ?/?").get_formated_number(1.25));
+    /// ```
+    pub fn get_formated_number(&self, value: f64) -> Option<String> {
+        if self.content.is_empty() {
+            return None;
+        }
+        let sections = self.get_number_sections();
+        let (tokens, value, sign) = if value < 0.0 && sections.len() > 1 {
+            (&sections[1], -value, "")
+        } else if value == 0.0 && sections.len() > 2 {
+            (&sections[2], value, "")
+        } else {
+            (&sections[0], value.abs(), if value < 0.0 { "-" } else { "" })
+        };
+        let value = if tokens.contains(&NumberToken::Percent) { value * 100.0 } else { value };
+        let mut result = String::from(sign);
+        for token in tokens {
+            match *token {
+                NumberToken::General => result.push_str(&value.to_string()),
+                NumberToken::Number { min_integer_digits, decimal_places, grouping } => {
+                    // half away from zero like Excel
+                    let scale = 10f64.powi(decimal_places as i32);
+                    let text = format!("{:.*}", decimal_places, (value * scale).round() / scale);
+                    let mut parts = text.splitn(2, '.');
+                    let integer = parts.next().unwrap_or("0");
+                    let integer = if integer == "0" && min_integer_digits == 0 { "" } else { integer };
+                    let integer = format!("{:0>1$}", integer, min_integer_digits);
+                    if grouping {
+                        let length = integer.len();
+                        for (i, digit) in integer.chars().enumerate() {
+                            if i > 0 && (length - i) % 3 == 0 {
+                                result.push(',');
+                            }
+                            result.push(digit);
+                        }
+                    } else {
+                        result.push_str(&integer);
+                    }
+                    if let Some(decimal) = parts.next() {
+                        result.push('.');
+                        result.push_str(decimal);
+                    }
+                },
+                NumberToken::Scientific { decimal_places, min_exponent_digits, .. } => {
+                    let text = format!("{:.*e}", decimal_places, value);
+                    let mut parts = text.splitn(2, 'e');
+                    result.push_str(parts.next().unwrap_or("0"));
+                    let exponent = parts.next().and_then(|x| x.parse::<i32>().ok()).unwrap_or(0);
+                    let sign = if exponent < 0 { '-' } else { '+' };
+                    result.push_str(&format!("E{}{:02$}", sign, exponent.abs(), min_exponent_digits));
+                },
+                NumberToken::Fraction { min_integer_digits, min_denominator_digits, denominator, .. } => {
+                    let (integer, fraction) = match min_integer_digits {
+                        Some(_) => (value.trunc(), value.fract()),
+                        None => (0.0, value),
+                    };
+                    let (numerator, denominator) = nearest_fraction(fraction, min_denominator_digits, denominator);
+                    let (integer, numerator) = if numerator == denominator && min_integer_digits.is_some() {
+                        (integer + 1.0, 0)
+                    } else {
+                        (integer, numerator)
+                    };
+                    if let Some(min_integer_digits) = min_integer_digits {
+                        if integer > 0.0 || min_integer_digits > 0 || numerator == 0 {
+                            result.push_str(&format!("{:0>1$}", integer, min_integer_digits));
+                        }
+                        if numerator != 0 && integer > 0.0 {
+                            result.push(' ');
+                        }
+                    }
+                    if numerator != 0 || min_integer_digits.is_none() {
+                        result.push_str(&format!("{}/{}", numerator, denominator));
+                    }
+                },
+                NumberToken::Percent => result.push('%'),
+                NumberToken::Currency { ref symbol, .. } => result.push_str(symbol),
+                NumberToken::Color(_) => {},
+                NumberToken::Text(ref text) => result.push_str(text),
+            }
+        }
+        Some(result)
+    }
+}
+
+const COLORS: [&str; 16] = [
+    "Black", "Blue", "Cyan", "Green", "Magenta", "Red", "White", "Yellow",
+    "黒", "青", "水", "緑", "紫", "赤", "白", "黄",
+];
+
+const COLOR_CODES: [&str; 8] = [
+    "000000", "0000FF", "00FFFF", "00FF00", "FF00FF", "FF0000", "FFFFFF", "FFFF00",
+];
+
+/// RGB like "FF0000" of a color of a number format code like "Red" or "赤"
+pub fn color_to_rgb(color: &str) -> Option<&'static str> {
+    COLORS.iter().position(|x| x.eq_ignore_ascii_case(color)).map(|index| COLOR_CODES[index % 8])
+}
+
+/// Color of a number format code like "Red" of a RGB like "FF0000" or "#ff0000"
+pub fn rgb_to_color(rgb: &str) -> Option<&'static str> {
+    let rgb = rgb.trim_start_matches('#');
+    COLOR_CODES.iter().position(|x| x.eq_ignore_ascii_case(rgb)).map(|index| COLORS[index])
+}
+
+/// Numerator and denominator nearest to a fraction less than 1,
+/// the denominator is fixed or has up to the digits
+fn nearest_fraction(fraction: f64, digits: usize, fixed: Option<usize>) -> (usize, usize) {
+    if let Some(denominator) = fixed {
+        return ((fraction * denominator as f64).round() as usize, denominator);
+    }
+    let max = 10usize.pow(digits.min(4) as u32) - 1;
+    let mut result = (0, 1);
+    let mut error = fraction;
+    for denominator in 1..max + 1 {
+        let numerator = (fraction * denominator as f64).round();
+        let current = (fraction - numerator / denominator as f64).abs();
+        if current < error - 1e-12 {
+            result = (numerator as usize, denominator);
+            error = current;
+        }
+    }
+    result
+}
+
+fn is_elapsed_unit(unit: &str) -> bool {
+    let mut chars = unit.chars();
+    match chars.next() {
+        Some(c) if "hHmMsS".contains(c) => chars.all(|x| x == c),
+        _ => false,
+    }
 }
 
 named!(year4<&str, &str>, 
@@ -0,0 +1,299 @@
+//! Cell references of the formulas of the cells, moved with the rows and the copies of the cells.
+//! Whole rows like "2:5", whole columns and R1C1 references are kept as they are.
+use range::{self, MAX_ROW, MAX_COLUMN};
+use {column_to_index, index_to_column};
+
+/// Cell of a reference like "$B2", an absolute row or column doesn't move with a copy of the formula
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Reference {
+    pub row: usize,
+    pub column: usize,
+    pub absolute_row: bool,
+    pub absolute_column: bool,
+}
+
+impl Reference {
+    fn parse(src: &str) -> Option<Reference> {
+        let absolute_column = src.starts_with('$');
+        let src = src.trim_start_matches('$');
+        let split = src.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(src.len());
+        let (letters, rest) = src.split_at(split);
+        let absolute_row = rest.starts_with('$');
+        let digits = rest.trim_start_matches('$');
+        if letters.is_empty() || letters.len() > 3 || digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let row = digits.parse::<usize>().ok()?.checked_sub(1)?;
+        let column = column_to_index(letters.to_uppercase());
+        if row > MAX_ROW || column > MAX_COLUMN {
+            return None;
+        }
+        Some(Reference { row, column, absolute_row, absolute_column })
+    }
+
+    fn write(&self, result: &mut String) {
+        if self.absolute_column {
+            result.push('$');
+        }
+        result.push_str(&index_to_column(self.column));
+        if self.absolute_row {
+            result.push('$');
+        }
+        result.push_str(&(self.row + 1).to_string());
+    }
+}
+
+/// Move the relative references like the formula copied `rows` down and `columns` right,
+/// a reference moved out of the sheet is "#REF!"
+pub(crate) fn offset(formula: &str, rows: isize, columns: isize) -> String {
+    let move_reference = |reference: Reference| -> Option<Reference> {
+        let row = if reference.absolute_row { reference.row as isize } else { reference.row as isize + rows };
+        let column = if reference.absolute_column { reference.column as isize } else { reference.column as isize + columns };
+        if row < 0 || row > MAX_ROW as isize || column < 0 || column > MAX_COLUMN as isize {
+            return None;
+        }
+        Some(Reference { row: row as usize, column: column as usize, ..reference })
+    };
+    map_references(formula, |_, first, last| {
+        let last = match last {
+            Some(last) => Some(move_reference(last)?),
+            None => None,
+        };
+        Some((move_reference(first)?, last))
+    })
+}
+
+/// Move the references to the sheet like the rows inserted at `row_index`.
+/// With `extend` a range ending just above the rows is extended over them.
+pub(crate) fn insert_rows(formula: &str, sheet_name: &str, row_index: usize, count: usize, extend: bool) -> String {
+    let shift = |row: usize| if row >= row_index { (row + count).min(MAX_ROW) } else { row };
+    map_references(formula, |sheet, first, last| {
+        if !is_sheet(sheet, sheet_name) {
+            return Some((first, last));
+        }
+        let last = last.map(|last| {
+            let row = if extend && last.row + 1 == row_index { last.row + count } else { shift(last.row) };
+            Reference { row, ..last }
+        });
+        Some((Reference { row: shift(first.row), ..first }, last))
+    })
+}
+
+/// Move the references to the sheet like the rows removed at `row_index`,
+/// a reference only to the removed rows is "#REF!" and a range over them is shrunk
+pub(crate) fn remove_rows(formula: &str, sheet_name: &str, row_index: usize, count: usize) -> String {
+    let end = row_index + count;
+    map_references(formula, |sheet, first, last| {
+        if !is_sheet(sheet, sheet_name) {
+            return Some((first, last));
+        }
+        let last_row = last.map_or(first.row, |last| last.row);
+        if first.row >= row_index && last_row < end {
+            return None;
+        }
+        let first_row = match first.row {
+            row if row >= end => row - count,
+            row => row.min(row_index),
+        };
+        let last = last.map(|last| {
+            let row = match last.row {
+                row if row >= end => row - count,
+                row if row >= row_index => row_index - 1,
+                row => row,
+            };
+            Reference { row, ..last }
+        });
+        Some((Reference { row: first_row, ..first }, last))
+    })
+}
+
+/// Rename the sheet of the references to the sheet, whole rows and columns like "Sheet1!$1:$1" too
+pub(crate) fn rename_sheet(formula: &str, name: &str, new_name: &str) -> String {
+    rewrite_references(formula, |_, first, last| Some((first, last)), |sheet| {
+        if sheet.to_lowercase() == name.to_lowercase() { Some(String::from(new_name)) } else { None }
+    })
+}
+
+/// Rename the table of the structured references like "Sales[Amount]"
+pub(crate) fn rename_table(formula: &str, name: &str, new_name: &str) -> String {
+    let chars: Vec<char> = formula.chars().collect();
+    let mut result = String::new();
+    let mut index = 0;
+    while index < chars.len() {
+        let c = chars[index];
+        if c == '"' || c == '\'' || c == '[' {
+            // a text, a quoted sheet name or the part of a structured reference is kept
+            let close = if c == '[' { ']' } else { c };
+            let end = (index + 1..chars.len()).find(|&i| chars[i] == close).map_or(chars.len(), |i| i + 1);
+            result.extend(&chars[index..end]);
+            index = end;
+        } else if is_name_char(c) {
+            let end = (index..chars.len()).find(|&i| !is_name_char(chars[i])).unwrap_or(chars.len());
+            let text: String = chars[index..end].iter().collect();
+            if end < chars.len() && chars[end] == '[' && text.to_lowercase() == name.to_lowercase() {
+                result.push_str(new_name);
+            } else {
+                result.push_str(&text);
+            }
+            index = end;
+        } else {
+            result.push(c);
+            index += 1;
+        }
+    }
+    result
+}
+
+fn is_sheet(sheet: Option<&str>, sheet_name: &str) -> bool {
+    match sheet {
+        Some(sheet) => sheet.to_lowercase() == sheet_name.to_lowercase(),
+        None => true,
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.' || c == '$'
+}
+
+/// Rewrite each reference or range of two references outside of the texts and the structured references of a formula.
+/// The callback has the sheet name of a reference to a sheet, its None is "#REF!".
+fn map_references<F>(formula: &str, callback: F) -> String
+    where F: FnMut(Option<&str>, Reference, Option<Reference>) -> Option<(Reference, Option<Reference>)>
+{
+    rewrite_references(formula, callback, |_| None)
+}
+
+/// Rewrite the references like `map_references`, `rename` gives the new name of the sheet of a reference
+fn rewrite_references<F, G>(formula: &str, mut callback: F, mut rename: G) -> String
+    where F: FnMut(Option<&str>, Reference, Option<Reference>) -> Option<(Reference, Option<Reference>)>,
+          G: FnMut(&str) -> Option<String>
+{
+    let chars: Vec<char> = formula.chars().collect();
+    let mut result = String::new();
+    let mut index = 0;
+    // characters of a name like "SUM" or "B2" from `start`
+    let name_end = |start: usize| (start..chars.len()).find(|&i| !is_name_char(chars[i])).unwrap_or(chars.len());
+    while index < chars.len() {
+        let c = chars[index];
+        match c {
+            '"' => {
+                // "" is a quote in a text
+                result.push(c);
+                index += 1;
+                while index < chars.len() {
+                    result.push(chars[index]);
+                    index += 1;
+                    if chars[index - 1] == '"' {
+                        if index < chars.len() && chars[index] == '"' {
+                            result.push('"');
+                            index += 1;
+                        } else {
+                            break;
+                        }
+                    }
+                }
+            },
+            '[' => {
+                let mut depth = 0;
+                while index < chars.len() {
+                    let c = chars[index];
+                    result.push(c);
+                    index += 1;
+                    match c {
+                        '\'' if index < chars.len() => {
+                            result.push(chars[index]);
+                            index += 1;
+                        },
+                        '[' => depth += 1,
+                        ']' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        },
+                        _ => {},
+                    }
+                }
+            },
+            _ if c == '\'' || is_name_char(c) => {
+                let start = index;
+                let mut sheet = None;
+                if c == '\'' {
+                    // a quoted sheet name, '' is a quote in it
+                    let mut name = String::new();
+                    index += 1;
+                    while index < chars.len() {
+                        if chars[index] == '\'' {
+                            if index + 1 < chars.len() && chars[index + 1] == '\'' {
+                                name.push('\'');
+                                index += 2;
+                                continue;
+                            }
+                            break;
+                        }
+                        name.push(chars[index]);
+                        index += 1;
+                    }
+                    index += 1;
+                    if index < chars.len() && chars[index] == '!' {
+                        sheet = Some(name);
+                        index += 1;
+                    } else {
+                        result.extend(&chars[start..index.min(chars.len())]);
+                        continue;
+                    }
+                } else {
+                    let end = name_end(index);
+                    if end < chars.len() && chars[end] == '!' {
+                        sheet = Some(chars[index..end].iter().collect::<String>());
+                        index = end + 1;
+                    }
+                }
+                let prefix: String = match sheet.as_deref().and_then(&mut rename) {
+                    Some(new_name) => format!("{}!", range::quote_sheet_name(&new_name)),
+                    None => chars[start..index].iter().collect(),
+                };
+                let end = name_end(index);
+                let first_text: String = chars[index..end].iter().collect();
+                let is_function = end < chars.len() && chars[end] == '(';
+                let first = if is_function { None } else { Reference::parse(&first_text) };
+                let first = match first {
+                    Some(first) => first,
+                    None => {
+                        result.push_str(&prefix);
+                        result.push_str(&first_text);
+                        index = end;
+                        continue;
+                    },
+                };
+                index = end;
+                let mut last = None;
+                if index < chars.len() && chars[index] == ':' {
+                    let last_end = name_end(index + 1);
+                    let last_text: String = chars[index + 1..last_end].iter().collect();
+                    let is_sheet = last_end < chars.len() && chars[last_end] == '!';
+                    if let (false, Some(reference)) = (is_sheet, Reference::parse(&last_text)) {
+                        last = Some(reference);
+                        index = last_end;
+                    }
+                }
+                result.push_str(&prefix);
+                match callback(sheet.as_deref(), first, last) {
+                    Some((first, last)) => {
+                        first.write(&mut result);
+                        if let Some(last) = last {
+                            result.push(':');
+                            last.write(&mut result);
+                        }
+                    },
+                    None => result.push_str("#REF!"),
+                }
+            },
+            _ => {
+                result.push(c);
+                index += 1;
+            },
+        }
+    }
+    result
+}
@@ -0,0 +1,62 @@
+//! Hyperlinks of cells to web pages, mail addresses or locations in the book
+use std::borrow::Cow;
+
+/// Hyperlink is the target of a cell with the tip shown on it.
+/// The target is an url like "https://example.com" or "mailto:info@example.com",
+/// or a location in the book like "#Sheet2!A1".
+///
+/// ```
+/// use spsheet::Cell;
+/// use spsheet::hyperlink::Hyperlink;
+///
+/// let mut link = Hyperlink::new("#Detail!A1");
+/// link.set_tooltip(Some(String::from("show the detail")));
+/// let mut cell = Cell::str("detail", "");
+/// cell.set_hyperlink(Some(link));
+/// assert_eq!(Some("Detail!A1"), cell.get_hyperlink().unwrap().get_location());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Hyperlink {
+    target: String,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    tooltip: Option<String>,
+}
+
+impl Hyperlink {
+    pub fn new<'a, S>(target: S) -> Hyperlink
+        where S: Into<Cow<'a, str>>
+    {
+        Hyperlink {
+            target: target.into().into_owned(),
+            tooltip: None,
+        }
+    }
+
+    pub fn get_target(&self) -> &String {
+        &self.target
+    }
+
+    pub fn set_target<'a, S>(&mut self, target: S)
+        where S: Into<Cow<'a, str>>
+    {
+        self.target = target.into().into_owned();
+    }
+
+    /// "Sheet2!A1" of "#Sheet2!A1", None for an url
+    pub fn get_location(&self) -> Option<&str> {
+        if self.target.starts_with('#') {
+            Some(&self.target[1..])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_tooltip(&self) -> Option<&String> {
+        self.tooltip.as_ref()
+    }
+
+    pub fn set_tooltip(&mut self, tooltip: Option<String>) {
+        self.tooltip = tooltip;
+    }
+}
@@ -0,0 +1,150 @@
+//! Pictures of sheets anchored to cells
+use std::borrow::Cow;
+
+/// Corner of a picture at a cell, the offsets from the top left of the cell are in points
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Marker {
+    row: usize,
+    column: usize,
+    offset_x: f64,
+    offset_y: f64,
+}
+
+impl Marker {
+    pub fn new(row: usize, column: usize, offset_x: f64, offset_y: f64) -> Marker {
+        Marker {
+            row,
+            column,
+            offset_x,
+            offset_y,
+        }
+    }
+
+    pub fn get_row(&self) -> usize {
+        self.row
+    }
+
+    pub fn get_column(&self) -> usize {
+        self.column
+    }
+
+    pub fn get_offset_x(&self) -> f64 {
+        self.offset_x
+    }
+
+    pub fn get_offset_y(&self) -> f64 {
+        self.offset_y
+    }
+}
+
+/// Where a picture is on the sheet
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Anchor {
+    /// The top left corner moves with a cell, the width and the height are in points
+    OneCell(Marker, f64, f64),
+    /// The corners move with two cells, the picture is resized with them
+    TwoCell(Marker, Marker),
+}
+
+impl Anchor {
+    /// Cell of the top left corner
+    pub fn get_from(&self) -> &Marker {
+        match *self {
+            Anchor::OneCell(ref from, _, _) => from,
+            Anchor::TwoCell(ref from, _) => from,
+        }
+    }
+
+    /// Move the rows of the corners like the cells
+    pub(crate) fn shift_rows<F>(&mut self, shift: F)
+        where F: Fn(usize) -> usize
+    {
+        match *self {
+            Anchor::OneCell(ref mut from, _, _) => from.row = shift(from.row),
+            Anchor::TwoCell(ref mut from, ref mut to) => {
+                from.row = shift(from.row);
+                to.row = shift(to.row);
+            },
+        }
+    }
+}
+
+/// Image is a PNG or JPEG picture with its name.
+///
+/// ```
+/// use spsheet::Sheet;
+/// use spsheet::image::{Anchor, Image, Marker};
+///
+/// let png = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+/// let mut sheet = Sheet::new("report");
+/// sheet.add_image(Image::new("logo", png, Anchor::OneCell(Marker::new(0, 0, 0.0, 0.0), 120.0, 40.0)));
+/// assert_eq!(Some("png"), sheet.get_images()[0].get_extension());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Image {
+    name: String,
+    data: Vec<u8>,
+    anchor: Anchor,
+}
+
+impl Image {
+    pub fn new<'a, S>(name: S, data: Vec<u8>, anchor: Anchor) -> Image
+        where S: Into<Cow<'a, str>>
+    {
+        Image {
+            name: name.into().into_owned(),
+            data,
+            anchor,
+        }
+    }
+
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn set_name<'a, S>(&mut self, name: S)
+        where S: Into<Cow<'a, str>>
+    {
+        self.name = name.into().into_owned();
+    }
+
+    /// Bytes of the picture file
+    pub fn get_data(&self) -> &Vec<u8> {
+        &self.data
+    }
+
+    pub fn get_anchor(&self) -> &Anchor {
+        &self.anchor
+    }
+
+    pub fn set_anchor(&mut self, anchor: Anchor) {
+        self.anchor = anchor;
+    }
+
+    pub(crate) fn get_anchor_mut(&mut self) -> &mut Anchor {
+        &mut self.anchor
+    }
+
+    /// "png" or "jpeg" by the signature of the data, None for the other formats
+    pub fn get_extension(&self) -> Option<&'static str> {
+        if self.data.starts_with(&[0x89, b'P', b'N', b'G']) {
+            Some("png")
+        } else if self.data.starts_with(&[0xFF, 0xD8]) {
+            Some("jpeg")
+        } else {
+            None
+        }
+    }
+
+    /// "image/png" or "image/jpeg"
+    pub fn get_media_type(&self) -> Option<&'static str> {
+        match self.get_extension() {
+            Some("png") => Some("image/png"),
+            Some("jpeg") => Some("image/jpeg"),
+            _ => None,
+        }
+    }
+}
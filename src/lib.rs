@@ -5,19 +5,79 @@ extern crate era_jp;
 #[macro_use]
 extern crate nom;
 
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+
 use chrono::prelude::*;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::borrow::Cow;
+use std::mem;
+
+pub mod auto_filter;
+use auto_filter::AutoFilter;
+
+pub mod comment;
+use comment::Comment;
+
+pub mod conditional_format;
+use conditional_format::ConditionalFormat;
+
+pub mod currency;
+use currency::Currency;
+
+pub mod date;
+use date::DateSystem;
+
+pub mod defined_name;
+use defined_name::DefinedName;
+
+pub mod hyperlink;
+use hyperlink::Hyperlink;
+
+pub mod image;
+use image::Image;
+
+pub mod chart;
+use chart::Chart;
+
+pub mod range;
+use range::Range;
+
+pub mod sheet_view;
+use sheet_view::SheetView;
+
+pub mod table;
+use table::Table;
+
+pub mod validation;
+use validation::DataValidation;
 
 pub mod format;
 use format::Format;
 
+mod formula;
+
+pub mod rich_text;
+use rich_text::{Phonetic, TextRun};
+
 #[cfg(feature = "ods")]
 pub mod ods;
 
 #[cfg(feature = "xlsx")]
 pub mod xlsx;
 
+#[cfg(feature = "csv")]
+pub mod csv;
+
+#[cfg(feature = "serde")]
+pub mod de;
+
+#[cfg(feature = "serde")]
+pub mod ser;
+
+pub mod template;
+
 #[cfg(any(feature = "ods", feature = "xlsx"))]
 mod file_common;
 
@@ -103,23 +163,116 @@ pub fn column_and_row_to_index<'a, S>(value: S) -> Option<(usize, usize)>
     }
 }
 
+/// Usize index pair to column and row String index
+///
+/// ```
+/// use spsheet::*;
+/// assert_eq!("ZZ12", index_to_column_and_row(701, 11));
+/// ```
+pub fn index_to_column_and_row(column_index: usize, row_index: usize) -> String {
+    format!("{}{}", index_to_column(column_index), row_index + 1)
+}
+
 /// Book has owner of sheets.
 ///
 /// ```
 /// let _ = spsheet::Book::new();
 /// ```
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Book {
     sheets: Vec<Sheet>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    package: Option<Package>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    date_system: DateSystem,
+    #[cfg_attr(feature = "serde", serde(default = "date::utc_offset::default", with = "date::utc_offset"))]
+    timezone: FixedOffset,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Vec::is_empty"))]
+    defined_names: Vec<DefinedName>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    active_sheet: usize,
 }
 
 impl Book {
     pub fn new() -> Book {
         Book {
-            sheets: Vec::new()
+            sheets: Vec::new(),
+            package: None,
+            date_system: DateSystem::Date1900,
+            timezone: FixedOffset::east_opt(0).unwrap(),
+            defined_names: Vec::new(),
+            active_sheet: 0,
         }
     }
 
+    /// Timezone of the dates in the file, a date is written as the wall-clock time of it.
+    /// UTC by default.
+    pub fn get_timezone(&self) -> FixedOffset {
+        self.timezone
+    }
+
+    pub fn set_timezone(&mut self, timezone: FixedOffset) {
+        self.timezone = timezone;
+    }
+
+    /// Take the dates read from a file as the wall-clock times of the timezone
+    /// and write them in it again.
+    ///
+    /// ```
+    /// extern crate chrono;
+    /// extern crate spsheet;
+    /// use chrono::prelude::*;
+    /// use spsheet::{Book, Cell, Sheet};
+    ///
+    /// # fn main() {
+    /// let jst = FixedOffset::east_opt(9 * 3600).unwrap();
+    /// let mut sheet = Sheet::new("s");
+    /// sheet.add_cell(Cell::date("2017-12-02T13:30:00", "YYYY/MM/DD HH:MM"), 0, 0);
+    /// let mut book = Book::new();
+    /// book.add_sheet(sheet);
+    /// book.localize(jst);
+    /// let expected = jst.with_ymd_and_hms(2017, 12, 2, 13, 30, 0).unwrap();
+    /// assert_eq!(Some(&Cell::datetime(expected, "YYYY/MM/DD HH:MM")), book.get_sheet(0).get_cell(0, 0));
+    /// # }
+    /// ```
+    pub fn localize(&mut self, timezone: FixedOffset) {
+        let offset = chrono::Duration::seconds(timezone.local_minus_utc() as i64 - self.timezone.local_minus_utc() as i64);
+        for sheet in self.sheets.iter_mut() {
+            for cells in sheet.rows.values_mut() {
+                for cell in cells.values_mut() {
+                    if let Value::Date(ref mut value) = cell.value {
+                        *value -= offset;
+                    }
+                }
+            }
+        }
+        self.timezone = timezone;
+    }
+
+    /// Date system of the serials in a xlsx
+    pub fn get_date_system(&self) -> DateSystem {
+        self.date_system
+    }
+
+    pub fn set_date_system(&mut self, date_system: DateSystem) {
+        self.date_system = date_system;
+    }
+
+    /// Original file kept by `xlsx::open` or `ods::open`
+    pub fn get_package(&self) -> Option<&Package> {
+        self.package.as_ref()
+    }
+
+    pub fn set_package(&mut self, package: Package) {
+        self.package = Some(package);
+    }
+
+    /// Forget the original file, the next write makes every part from scratch
+    pub fn clear_package(&mut self) {
+        self.package = None;
+    }
+
     pub fn add_sheet(&mut self, sheet: Sheet) {
         self.sheets.push(sheet);
     }
@@ -128,6 +281,75 @@ impl Book {
         &self.sheets[index]
     }
 
+    pub fn get_sheet_mut(&mut self, index: usize) -> &mut Sheet {
+        &mut self.sheets[index]
+    }
+
+    /// The active sheet stays the same sheet
+    pub fn insert_sheet(&mut self, index: usize, sheet: Sheet) {
+        self.sheets.insert(index, sheet);
+        if index <= self.active_sheet && self.sheets.len() > 1 {
+            self.active_sheet += 1;
+        }
+    }
+
+    /// The active sheet stays the same sheet, the next sheet or the last one becomes active for a removed active sheet
+    pub fn remove_sheet(&mut self, index: usize) -> Sheet {
+        let sheet = self.sheets.remove(index);
+        if index < self.active_sheet || (index == self.active_sheet && index == self.sheets.len() && index > 0) {
+            self.active_sheet -= 1;
+        }
+        sheet
+    }
+
+    /// Insert rows into the sheet at `sheet_index` like `Sheet::insert_rows`,
+    /// the names of the workbook to the sheet move with the rows too
+    pub fn insert_rows(&mut self, sheet_index: usize, row_index: usize, count: usize) {
+        let sheet = &mut self.sheets[sheet_index];
+        sheet.insert_rows(row_index, count);
+        for defined_name in self.defined_names.iter_mut() {
+            defined_name.insert_rows(sheet.get_name(), row_index, count);
+        }
+    }
+
+    /// Remove rows of the sheet at `sheet_index` like `Sheet::remove_rows`,
+    /// a name of the workbook only to the removed rows is removed
+    pub fn remove_rows(&mut self, sheet_index: usize, row_index: usize, count: usize) {
+        let sheet = &mut self.sheets[sheet_index];
+        sheet.remove_rows(row_index, count);
+        self.defined_names = self.defined_names.drain(..)
+            .filter_map(|mut defined_name| if defined_name.remove_rows(sheet.get_name(), row_index, count) { Some(defined_name) } else { None })
+            .collect();
+    }
+
+    /// Rename the sheet at `sheet_index` like `Sheet::set_name`,
+    /// the references of the other sheets and the names of the workbook to the sheet move to the name too
+    pub fn rename_sheet<'a, S>(&mut self, sheet_index: usize, name: S)
+        where S: Into<Cow<'a, str>>
+    {
+        let name = name.into().into_owned();
+        let old_name = self.sheets[sheet_index].get_name().clone();
+        for (index, sheet) in self.sheets.iter_mut().enumerate() {
+            if index == sheet_index {
+                sheet.set_name(name.as_str());
+            } else {
+                sheet.rename_references(&old_name, &name);
+            }
+        }
+        for defined_name in self.defined_names.iter_mut() {
+            defined_name.rename_sheet(&old_name, &name);
+        }
+    }
+
+    /// Index of the sheet shown when the file is opened
+    pub fn get_active_sheet(&self) -> usize {
+        self.active_sheet
+    }
+
+    pub fn set_active_sheet(&mut self, index: usize) {
+        self.active_sheet = index;
+    }
+
     pub fn get_sheet_size(&self) -> usize {
         self.sheets.len() as usize
     }
@@ -135,6 +357,122 @@ impl Book {
     pub fn get_sheet_vec(&self) -> &Vec<Sheet> {
         &self.sheets
     }
+
+    /// Names of the workbook, the names of a sheet are in the sheet
+    pub fn get_defined_names(&self) -> &Vec<DefinedName> {
+        &self.defined_names
+    }
+
+    pub fn get_defined_name(&self, name: &str) -> Option<&DefinedName> {
+        defined_name::find(&self.defined_names, name)
+    }
+
+    /// Add a name to the workbook, a name with the same name is replaced
+    pub fn add_defined_name(&mut self, defined_name: DefinedName) {
+        defined_name::add(&mut self.defined_names, defined_name);
+    }
+
+    pub fn remove_defined_name(&mut self, name: &str) -> Option<DefinedName> {
+        defined_name::remove(&mut self.defined_names, name)
+    }
+
+    /// Range of a name of one area, a name of the sheet at `sheet_index` hides the name of the workbook.
+    /// A structured reference to a table like "Sales[Amount]" is the range it refers to.
+    pub fn get_named_range(&self, name: &str, sheet_index: Option<usize>) -> Option<Range> {
+        let local = sheet_index
+            .and_then(|index| self.sheets.get(index))
+            .and_then(|sheet| sheet.get_defined_name(name));
+        match local.or_else(|| self.get_defined_name(name)) {
+            Some(defined_name) => defined_name.get_range(),
+            None => {
+                let table_name = name.split('[').next().unwrap_or("").trim();
+                self.get_table(table_name).and_then(|table| table.get_reference_range(name))
+            },
+        }
+    }
+
+    /// Table of the name regardless of case in any sheet
+    pub fn get_table(&self, name: &str) -> Option<&Table> {
+        self.sheets.iter().filter_map(|sheet| sheet.get_table(name)).next()
+    }
+
+    /// Check a value for a cell by the validation of the cell, the error is the validation the value breaks.
+    /// A list from a range has the values of the cells of the range in the book.
+    pub fn validate_cell(&self, sheet_index: usize, row_index: usize, column_index: usize, value: &Value) -> Result<(), &DataValidation> {
+        let validation = match self.sheets.get(sheet_index).and_then(|sheet| sheet.get_validation(row_index, column_index)) {
+            Some(validation) => validation,
+            None => return Ok(()),
+        };
+        let range_texts = match *validation.get_rule() {
+            validation::Rule::ListRange(ref range) => {
+                let mut texts = Vec::new();
+                if let Some(sheet) = self.sheets.iter().find(|sheet| sheet.get_name() == range.get_sheet_name()) {
+                    sheet.sorted_access(|row, column, cell| {
+                        if range.contains(row, column) {
+                            texts.extend(validation::value_text(cell.get_value()));
+                        }
+                    });
+                }
+                Some(texts)
+            },
+            _ => None,
+        };
+        if validation.check(value, self.timezone, range_texts) {
+            Ok(())
+        } else {
+            Err(validation)
+        }
+    }
+}
+
+/// Package has the files of a xlsx or ods zip by their path.
+///
+/// ```
+/// let mut package = spsheet::Package::new();
+/// package.set_part("mimetype", b"application/vnd.oasis.opendocument.spreadsheet".to_vec());
+/// assert!(package.is_ods());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Package {
+    parts: BTreeMap<String, Vec<u8>>,
+}
+
+impl Default for Package {
+    fn default() -> Package {
+        Package::new()
+    }
+}
+
+impl Package {
+    pub fn new() -> Package {
+        Package {
+            parts: BTreeMap::new()
+        }
+    }
+
+    pub fn get_part(&self, name: &str) -> Option<&Vec<u8>> {
+        self.parts.get(name)
+    }
+
+    pub fn set_part<S: Into<String>>(&mut self, name: S, data: Vec<u8>) {
+        self.parts.insert(name.into(), data);
+    }
+
+    pub fn remove_part(&mut self, name: &str) -> Option<Vec<u8>> {
+        self.parts.remove(name)
+    }
+
+    pub fn get_parts(&self) -> &BTreeMap<String, Vec<u8>> {
+        &self.parts
+    }
+
+    pub fn is_xlsx(&self) -> bool {
+        self.parts.contains_key("[Content_Types].xml")
+    }
+
+    pub fn is_ods(&self) -> bool {
+        self.parts.contains_key("mimetype") || self.parts.contains_key("content.xml")
+    }
 }
 
 /// Sheet has owner of cells.
@@ -143,9 +481,21 @@ impl Book {
 /// let _ = spsheet::Sheet::new("sheet1");
 /// ```
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "SheetRepr", into = "SheetRepr"))]
 pub struct Sheet {
     name: String,
     rows: HashMap<usize, HashMap<usize, Cell>>,
+    part_name: Option<String>,
+    row_sources: BTreeMap<usize, usize>,
+    images: Vec<Image>,
+    charts: Vec<Chart>,
+    defined_names: Vec<DefinedName>,
+    validations: Vec<DataValidation>,
+    conditional_formats: Vec<ConditionalFormat>,
+    auto_filter: Option<AutoFilter>,
+    tables: Vec<Table>,
+    view: SheetView,
 }
 
 impl Sheet {
@@ -154,14 +504,209 @@ impl Sheet {
     {
         Sheet {
             name: name.into().into_owned(),
-            rows: HashMap::new()
+            rows: HashMap::new(),
+            part_name: None,
+            row_sources: BTreeMap::new(),
+            images: Vec::new(),
+            charts: Vec::new(),
+            defined_names: Vec::new(),
+            validations: Vec::new(),
+            conditional_formats: Vec::new(),
+            auto_filter: None,
+            tables: Vec::new(),
+            view: SheetView::new(),
         }
     }
 
+    /// Worksheet part (xlsx) or table name (ods) in the package this sheet was read from
+    pub fn get_part_name(&self) -> Option<&String> {
+        self.part_name.as_ref()
+    }
+
+    pub(crate) fn set_part_name(&mut self, part_name: Option<String>) {
+        self.part_name = part_name;
+    }
+
+    /// Row of the original part whose height and style the row keeps
+    pub fn get_row_source(&self, row_index: usize) -> Option<usize> {
+        self.row_sources.get(&row_index).cloned()
+    }
+
+    pub fn get_row_sources(&self) -> &BTreeMap<usize, usize> {
+        &self.row_sources
+    }
+
+    pub(crate) fn set_row_source(&mut self, row_index: usize, source: usize) {
+        self.row_sources.insert(row_index, source);
+    }
+
+    /// Insert empty rows, the rows from `row_index` move down by `count`.
+    /// The references of the formulas and the names of the sheet to it move with the rows,
+    /// the references of the other sheets and the workbook don't, see `Book::insert_rows`.
+    ///
+    /// ```
+    /// let mut sheet = spsheet::Sheet::new("sheet1");
+    /// sheet.add_cell(spsheet::Cell::str("a", ""), 1, 0);
+    /// sheet.insert_rows(0, 2);
+    /// assert_eq!(Some(&spsheet::Cell::str("a", "")), sheet.get_cell(3, 0));
+    /// ```
+    pub fn insert_rows(&mut self, row_index: usize, count: usize) {
+        self.insert_rows_extending(row_index, count, false);
+    }
+
+    /// Insert rows like `insert_rows`, with `extend` the ranges of the formulas ending just above the rows
+    /// are extended over them like a total of the rows of a list
+    pub(crate) fn insert_rows_extending(&mut self, row_index: usize, count: usize, extend: bool) {
+        let name = self.name.clone();
+        self.map_formulas(|formula| formula::insert_rows(formula, &name, row_index, count, extend));
+        let shift = |index: usize| if index >= row_index { index + count } else { index };
+        self.rows = self.rows.drain().map(|(index, row)| (shift(index), row)).collect();
+        self.row_sources = self.row_sources.iter().map(|(index, source)| (shift(*index), *source)).collect();
+        for image in self.images.iter_mut() {
+            image.get_anchor_mut().shift_rows(shift);
+        }
+        for chart in self.charts.iter_mut() {
+            chart.get_anchor_mut().shift_rows(shift);
+        }
+        for defined_name in self.defined_names.iter_mut() {
+            defined_name.insert_rows(&name, row_index, count);
+        }
+        for validation in self.validations.iter_mut() {
+            validation.insert_rows(row_index, count);
+        }
+        for format in self.conditional_formats.iter_mut() {
+            format.insert_rows(row_index, count);
+        }
+        if let Some(ref mut auto_filter) = self.auto_filter {
+            auto_filter.insert_rows(row_index, count);
+        }
+        for table in self.tables.iter_mut() {
+            table.insert_rows(row_index, count);
+        }
+    }
+
+    /// Remove rows, the rows below move up by `count`.
+    /// A reference of a formula only to the removed rows is "#REF!",
+    /// a name of the sheet only to the removed rows is removed.
+    pub fn remove_rows(&mut self, row_index: usize, count: usize) {
+        let name = self.name.clone();
+        self.map_formulas(|formula| formula::remove_rows(formula, &name, row_index, count));
+        let end = row_index + count;
+        let shift = |index: usize| if index >= end { index - count } else { index };
+        self.rows = self.rows.drain()
+            .filter(|&(index, _)| index < row_index || index >= end)
+            .map(|(index, row)| (shift(index), row))
+            .collect();
+        self.row_sources = self.row_sources.iter()
+            .filter(|&(index, _)| *index < row_index || *index >= end)
+            .map(|(index, source)| (shift(*index), *source))
+            .collect();
+        // a picture or a chart on the removed rows moves to the first row below them
+        let shift_anchor = |index: usize| if index >= end { index - count } else { index.min(row_index) };
+        for image in self.images.iter_mut() {
+            image.get_anchor_mut().shift_rows(shift_anchor);
+        }
+        for chart in self.charts.iter_mut() {
+            chart.get_anchor_mut().shift_rows(shift_anchor);
+        }
+        self.defined_names = self.defined_names.drain(..)
+            .filter_map(|mut defined_name| if defined_name.remove_rows(&name, row_index, count) { Some(defined_name) } else { None })
+            .collect();
+        for validation in self.validations.iter_mut() {
+            validation.remove_rows(row_index, count);
+        }
+        self.validations.retain(|validation| !validation.get_ranges().is_empty());
+        for format in self.conditional_formats.iter_mut() {
+            format.remove_rows(row_index, count);
+        }
+        self.conditional_formats.retain(|format| !format.get_ranges().is_empty());
+        if let Some(auto_filter) = self.auto_filter.as_mut() {
+            if !auto_filter.remove_rows(row_index, count) {
+                self.auto_filter = None;
+            }
+        }
+        self.tables = self.tables.drain(..)
+            .filter_map(|mut table| if table.remove_rows(row_index, count) { Some(table) } else { None })
+            .collect();
+    }
+
+    /// Copy the cells and the row style of a row, cells already in the destination are replaced.
+    /// The relative references of the formulas move with the copy.
+    pub fn copy_row(&mut self, from_row_index: usize, to_row_index: usize) {
+        match self.rows.get(&from_row_index).cloned() {
+            Some(mut row) => {
+                let rows = to_row_index as isize - from_row_index as isize;
+                for cell in row.values_mut() {
+                    if let Some(formula) = cell.formula.take() {
+                        cell.formula = Some(formula::offset(&formula, rows, 0));
+                    }
+                }
+                self.rows.insert(to_row_index, row);
+            },
+            None => {
+                self.rows.remove(&to_row_index);
+            },
+        }
+        match self.get_row_source(from_row_index) {
+            Some(source) => {
+                self.row_sources.insert(to_row_index, source);
+            },
+            None => {
+                self.row_sources.remove(&to_row_index);
+            },
+        }
+    }
+
+    fn map_formulas<F>(&mut self, callback: F)
+        where F: Fn(&str) -> String
+    {
+        for cell in self.rows.values_mut().flat_map(|row| row.values_mut()) {
+            if let Some(formula) = cell.formula.take() {
+                cell.formula = Some(callback(&formula));
+            }
+        }
+    }
+
+    /// Rename the sheet, the ranges of its tables, auto filter, validations and conditional formats,
+    /// the references of its formulas and its names to it move to the name.
+    /// The references of the other sheets and the workbook don't, see `Book::rename_sheet`.
     pub fn set_name<'a, S>(&mut self, name: S)
         where S: Into<Cow<'a, str>>
     {
-        self.name = name.into().into_owned();
+        let name = name.into().into_owned();
+        let old_name = mem::replace(&mut self.name, name.clone());
+        self.rename_references(&old_name, &name);
+        let rename = |range: &Range| {
+            let mut range = range.clone();
+            if *range.get_sheet_name() == old_name {
+                range.set_sheet_name(name.as_str());
+            }
+            range
+        };
+        for table in self.tables.iter_mut() {
+            let range = rename(table.get_range());
+            table.set_range(range);
+        }
+        if let Some(ref mut auto_filter) = self.auto_filter {
+            let range = rename(auto_filter.get_range());
+            auto_filter.set_range(range);
+        }
+        for validation in self.validations.iter_mut() {
+            let ranges = validation.get_ranges().iter().map(&rename).collect();
+            validation.set_ranges(ranges);
+        }
+        for format in self.conditional_formats.iter_mut() {
+            let ranges = format.get_ranges().iter().map(&rename).collect();
+            format.set_ranges(ranges);
+        }
+    }
+
+    /// Rename the sheet of the references of the formulas and the names of the sheet
+    pub(crate) fn rename_references(&mut self, sheet_name: &str, new_name: &str) {
+        self.map_formulas(|formula| formula::rename_sheet(formula, sheet_name, new_name));
+        for defined_name in self.defined_names.iter_mut() {
+            defined_name.rename_sheet(sheet_name, new_name);
+        }
     }
 
     pub fn get_name(&self) -> &String {
@@ -190,6 +735,147 @@ impl Sheet {
         &self.rows
     }
 
+    /// Pictures on the sheet in the order they are drawn
+    pub fn get_images(&self) -> &Vec<Image> {
+        &self.images
+    }
+
+    pub fn add_image(&mut self, image: Image) {
+        self.images.push(image);
+    }
+
+    pub fn remove_image(&mut self, index: usize) -> Image {
+        self.images.remove(index)
+    }
+
+    /// Charts added to the sheet, the charts of a read file aren't read
+    pub fn get_charts(&self) -> &Vec<Chart> {
+        &self.charts
+    }
+
+    pub fn add_chart(&mut self, chart: Chart) {
+        self.charts.push(chart);
+    }
+
+    pub fn remove_chart(&mut self, index: usize) -> Chart {
+        self.charts.remove(index)
+    }
+
+    /// Names only known in the sheet like its print area `defined_name::PRINT_AREA`
+    pub fn get_defined_names(&self) -> &Vec<DefinedName> {
+        &self.defined_names
+    }
+
+    pub fn get_defined_name(&self, name: &str) -> Option<&DefinedName> {
+        defined_name::find(&self.defined_names, name)
+    }
+
+    /// Add a name to the sheet, a name with the same name is replaced
+    pub fn add_defined_name(&mut self, defined_name: DefinedName) {
+        defined_name::add(&mut self.defined_names, defined_name);
+    }
+
+    pub fn remove_defined_name(&mut self, name: &str) -> Option<DefinedName> {
+        defined_name::remove(&mut self.defined_names, name)
+    }
+
+    pub fn get_validations(&self) -> &Vec<DataValidation> {
+        &self.validations
+    }
+
+    /// Validation of a cell, the first one of the validations having the cell in their ranges
+    pub fn get_validation(&self, row_index: usize, column_index: usize) -> Option<&DataValidation> {
+        self.validations.iter().find(|validation| validation.contains(row_index, column_index))
+    }
+
+    pub fn add_validation(&mut self, validation: DataValidation) {
+        self.validations.push(validation);
+    }
+
+    pub fn remove_validation(&mut self, index: usize) -> DataValidation {
+        self.validations.remove(index)
+    }
+
+    pub fn get_conditional_formats(&self) -> &Vec<ConditionalFormat> {
+        &self.conditional_formats
+    }
+
+    /// Add a format after the others, its rules are applied after theirs
+    pub fn add_conditional_format(&mut self, format: ConditionalFormat) {
+        self.conditional_formats.push(format);
+    }
+
+    pub fn remove_conditional_format(&mut self, index: usize) -> ConditionalFormat {
+        self.conditional_formats.remove(index)
+    }
+
+    /// Filter buttons of the list of the sheet, a sheet has one of them at most
+    pub fn get_auto_filter(&self) -> Option<&AutoFilter> {
+        self.auto_filter.as_ref()
+    }
+
+    pub fn get_auto_filter_mut(&mut self) -> Option<&mut AutoFilter> {
+        self.auto_filter.as_mut()
+    }
+
+    pub fn set_auto_filter(&mut self, auto_filter: Option<AutoFilter>) {
+        self.auto_filter = auto_filter;
+    }
+
+    /// Tables of the sheet, only xlsx keeps them
+    pub fn get_tables(&self) -> &Vec<Table> {
+        &self.tables
+    }
+
+    /// Table of the name regardless of case
+    pub fn get_table(&self, name: &str) -> Option<&Table> {
+        let name = name.to_lowercase();
+        self.tables.iter().find(|table| table.get_name().to_lowercase() == name)
+    }
+
+    pub fn get_table_mut(&mut self, name: &str) -> Option<&mut Table> {
+        let name = name.to_lowercase();
+        self.tables.iter_mut().find(|table| table.get_name().to_lowercase() == name)
+    }
+
+    /// Add a table to the sheet, a table with the same name is replaced
+    pub fn add_table(&mut self, table: Table) {
+        let name = table.get_name().to_lowercase();
+        match self.tables.iter().position(|t| t.get_name().to_lowercase() == name) {
+            Some(index) => self.tables[index] = table,
+            None => self.tables.push(table),
+        }
+    }
+
+    /// Rename a table, the structured references like "Sales[Amount]" of the formulas of the sheet follow it.
+    /// False when the sheet has no table of the name.
+    pub fn rename_table(&mut self, name: &str, new_name: &str) -> bool {
+        match self.get_table_mut(name) {
+            Some(table) => table.set_name(new_name),
+            None => return false,
+        }
+        self.map_formulas(|formula| formula::rename_table(formula, name, new_name));
+        true
+    }
+
+    pub fn remove_table(&mut self, name: &str) -> Option<Table> {
+        let name = name.to_lowercase();
+        let index = self.tables.iter().position(|table| table.get_name().to_lowercase() == name)?;
+        Some(self.tables.remove(index))
+    }
+
+    pub fn get_view(&self) -> &SheetView {
+        &self.view
+    }
+
+    pub fn get_view_mut(&mut self) -> &mut SheetView {
+        &mut self.view
+    }
+
+    pub fn set_view(&mut self, view: SheetView) {
+        self.view = view;
+    }
+
     pub fn sorted_access<F>(&self, mut callback: F) 
         where F : FnMut(usize, usize, &Cell) -> () 
     {
@@ -248,9 +934,20 @@ impl Sheet {
 /// let _ = spsheet::Cell::str("value", "");
 /// ```
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Cell {
     value: Value,
     format: Format,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    style_id: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    phonetic: Option<Phonetic>,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    comment: Option<Comment>,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    hyperlink: Option<Hyperlink>,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    formula: Option<String>,
 }
 
 impl Cell {
@@ -260,6 +957,11 @@ impl Cell {
         Cell {
             value: value,
             format: Format::new(content),
+            style_id: None,
+            phonetic: None,
+            comment: None,
+            hyperlink: None,
+            formula: None,
         }
     }
 
@@ -281,19 +983,70 @@ impl Cell {
         )
     }
 
+    /// Date of a text like "2017-12-02" or "2017-12-02T13:30:00" in UTC,
+    /// panics if the text isn't a date, see `parse_date`
     pub fn date<'a, S>(value: S, format: S) -> Cell 
         where S: Into<Cow<'a, str>>
     {
-        let mut str_value = value.into().into_owned();
-        let postfix = match str_value.find('T') {
-            Some(_) => "Z",
-            None => "T00:00:00Z"
-        };
-        str_value.push_str(postfix);
-        Cell::new(
-            Value::Date(str_value.parse::<DateTime<Utc>>().unwrap()),
-            format
-        )
+        Cell::parse_date(value, format).unwrap()
+    }
+
+    /// Date of a text like "2017-12-02", "2017-12-02T13:30:00" in UTC
+    /// or "2017-12-02T13:30:00+09:00" with the offset
+    ///
+    /// ```
+    /// use spsheet::Cell;
+    /// assert_eq!(Cell::date("2017-12-02T04:30:00", ""), Cell::parse_date("2017-12-02T13:30:00+09:00", "").unwrap());
+    /// assert!(Cell::parse_date("2017/12/02", "").is_err());
+    /// ```
+    pub fn parse_date<'a, S>(value: S, format: S) -> Result<Cell, chrono::ParseError>
+        where S: Into<Cow<'a, str>>
+    {
+        let value = value.into();
+        let value = value.trim();
+        let dt = DateTime::parse_from_rfc3339(value)
+            .map(|dt| dt.with_timezone(&Utc))
+            .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f").map(|dt| DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc)))
+            .or_else(|_| NaiveDate::parse_from_str(value, "%Y-%m-%d").map(|d| DateTime::<Utc>::from_naive_utc_and_offset(d.and_hms_opt(0, 0, 0).unwrap(), Utc)))?;
+        Ok(Cell::new(Value::Date(dt), format))
+    }
+
+    /// Date and time with any timezone, kept as the same instant in UTC
+    pub fn datetime<'a, Tz, S>(value: DateTime<Tz>, format: S) -> Cell
+        where Tz: TimeZone, S: Into<Cow<'a, str>>
+    {
+        Cell::new(Value::Date(value.with_timezone(&Utc)), format)
+    }
+
+    /// Wall-clock time in the timezone, None if the time doesn't exist
+    /// or is ambiguous in the timezone
+    pub fn naive_datetime<'a, Tz, S>(value: NaiveDateTime, timezone: &Tz, format: S) -> Option<Cell>
+        where Tz: TimeZone, S: Into<Cow<'a, str>>
+    {
+        timezone.from_local_datetime(&value).single().map(|dt| Cell::datetime(dt, format))
+    }
+
+    /// Currency with its format, an empty format is the format of the currency like "[$¥-411]#,##0"
+    ///
+    /// ```
+    /// use spsheet::Cell;
+    /// use spsheet::currency::Currency;
+    /// let cell = Cell::currency(Currency::new(1500.0, "JPY"), "");
+    /// assert_eq!(Some(String::from("¥1,500")), cell.get_formated_value());
+    /// ```
+    pub fn currency<'a, S>(value: Currency, format: S) -> Cell
+        where S: Into<Cow<'a, str>>
+    {
+        let format = format.into();
+        let format = if format.is_empty() { Cow::Owned(value.get_format_code()) } else { format };
+        Cell::new(Value::Currency(value), format)
+    }
+
+    /// Date without time, `Value::DateOnly`
+    pub fn naive_date<'a, S>(value: NaiveDate, format: S) -> Cell
+        where S: Into<Cow<'a, str>>
+    {
+        Cell::new(Value::DateOnly(value), format)
     }
 
     pub fn get_value(&self) -> &Value {
@@ -304,18 +1057,97 @@ impl Cell {
         &self.format
     }
 
+    /// Style of the cell in the package, a cellXfs index (xlsx) or a style name (ods)
+    pub fn get_style_id(&self) -> Option<&String> {
+        self.style_id.as_ref()
+    }
+
+    pub fn set_style_id(&mut self, style_id: Option<String>) {
+        self.style_id = style_id;
+    }
+
+    /// Reading of the text, the furigana of a xlsx
+    pub fn get_phonetic(&self) -> Option<&Phonetic> {
+        self.phonetic.as_ref()
+    }
+
+    pub fn set_phonetic(&mut self, phonetic: Option<Phonetic>) {
+        self.phonetic = phonetic;
+    }
+
+    /// Comment of the cell, a note of xlsx or an annotation of ods
+    pub fn get_comment(&self) -> Option<&Comment> {
+        self.comment.as_ref()
+    }
+
+    pub fn set_comment(&mut self, comment: Option<Comment>) {
+        self.comment = comment;
+    }
+
+    /// Link of the cell to an url or a location in the book
+    pub fn get_hyperlink(&self) -> Option<&Hyperlink> {
+        self.hyperlink.as_ref()
+    }
+
+    pub fn set_hyperlink(&mut self, hyperlink: Option<Hyperlink>) {
+        self.hyperlink = hyperlink;
+    }
+
+    /// Formula of the cell without "=" like "SUM(B2:B5)", the value is its last result.
+    /// Only xlsx reads and writes the formulas.
+    pub fn get_formula(&self) -> Option<&String> {
+        self.formula.as_ref()
+    }
+
+    pub fn set_formula(&mut self, formula: Option<String>) {
+        self.formula = formula;
+    }
+
     pub fn get_formated_value(&self) -> Option<String> {
         match self.value {
             Value::Date(dt) => {
                 self.format.get_formated_date(&dt)
             },
+            Value::DateOnly(date) => {
+                self.format.get_formated_date(&DateTime::<Utc>::from_naive_utc_and_offset(date.and_hms_opt(0, 0, 0).unwrap(), Utc))
+            },
+            // a time is shown on the day 0 of the serials
+            Value::Time(time) => {
+                self.format.get_formated_date(&DateTime::<Utc>::from_naive_utc_and_offset(NaiveDate::from_ymd_opt(1899, 12, 31).unwrap().and_time(time), Utc))
+            },
+            Value::Duration(duration) => {
+                self.format.get_formated_duration(&duration)
+            },
+            Value::Currency(ref currency) => {
+                self.format.get_formated_number(currency.get_amount())
+            },
+            Value::Float(value) if !self.format.is_date() => {
+                self.format.get_formated_number(value)
+            },
             _ => None,
         }
     }
+
+    /// Text of the cell like a csv field or a placeholder, the dates, the times, the durations and the currencies
+    /// with the format of the cell and the other values like `Value::get_text`.
+    ///
+    /// ```
+    /// use spsheet::Cell;
+    /// assert_eq!("2017/12/02", Cell::date("2017-12-02", "YYYY/MM/DD").get_text());
+    /// assert_eq!("1.5", Cell::float(1.5, "0.00").get_text());
+    /// ```
+    pub fn get_text(&self) -> String {
+        match self.value {
+            Value::Float(_) => self.value.get_text(),
+            _ => self.get_formated_value().unwrap_or_else(|| self.value.get_text()),
+        }
+    }
 }
 
 /// Value has Str, Float, Data value.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value", rename_all = "snake_case"))]
 pub enum Value {
     /// String Value
     Str(String),
@@ -323,6 +1155,122 @@ pub enum Value {
     Float(f64),
     /// Data Value
     Date(DateTime<Utc>),
-    /// Currency Value
-    Currency(f64),
-}
\ No newline at end of file
+    /// Currency Value, an amount and its ISO 4217 code
+    Currency(Currency),
+    /// Empty Value, a cell which only has a style
+    Empty,
+    /// Rich Text Value, runs with their own font
+    RichText(Vec<TextRun>),
+    /// Boolean Value
+    Bool(bool),
+    /// Error Value, a formula result like "#DIV/0!"
+    Error(String),
+    /// Time of day Value without date
+    Time(NaiveTime),
+    /// Elapsed time Value like "[h]:mm", the hours go over 24
+    #[cfg_attr(feature = "serde", serde(with = "date::iso8601"))]
+    Duration(chrono::Duration),
+    /// Date Value without time.
+    /// xlsx has no date only cells, it is read back as a `Value::Date` at midnight in UTC
+    /// like the other dates of a date format without the time.
+    DateOnly(NaiveDate),
+}
+
+impl Value {
+    /// Text of the value without a format, a date is "2017-12-02" or "2017-12-02 13:30:00",
+    /// a time "13:30:00", a duration "37:30:00" and a bool "TRUE" or "FALSE"
+    pub fn get_text(&self) -> String {
+        match *self {
+            Value::Str(ref value) | Value::Error(ref value) => value.clone(),
+            Value::Float(value) => value.to_string(),
+            Value::Date(ref value) if value.num_seconds_from_midnight() == 0 => value.format("%Y-%m-%d").to_string(),
+            Value::Date(ref value) => value.format("%Y-%m-%d %H:%M:%S").to_string(),
+            Value::Currency(ref value) => value.get_amount().to_string(),
+            Value::Empty => String::new(),
+            Value::RichText(ref runs) => TextRun::concat(runs),
+            Value::Bool(value) => String::from(if value { "TRUE" } else { "FALSE" }),
+            Value::Time(ref value) => value.format("%H:%M:%S").to_string(),
+            Value::Duration(ref value) => Format::new("[h]:mm:ss").get_formated_duration(value).unwrap_or_default(),
+            Value::DateOnly(ref value) => value.format("%Y-%m-%d").to_string(),
+        }
+    }
+}
+
+// Serialized form of a sheet, cells are listed in row and column order
+// so the same sheet always gives the same output.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct SheetRepr {
+    name: String,
+    cells: Vec<CellRepr>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    images: Vec<Image>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    charts: Vec<Chart>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    defined_names: Vec<DefinedName>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    validations: Vec<DataValidation>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    conditional_formats: Vec<ConditionalFormat>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    auto_filter: Option<AutoFilter>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tables: Vec<Table>,
+    #[serde(default, skip_serializing_if = "SheetView::is_default")]
+    view: SheetView,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct CellRepr {
+    row: usize,
+    column: usize,
+    #[serde(flatten)]
+    cell: Cell,
+}
+
+#[cfg(feature = "serde")]
+impl From<Sheet> for SheetRepr {
+    fn from(sheet: Sheet) -> SheetRepr {
+        let mut cells = Vec::new();
+        sheet.sorted_access(|row_index, column_index, cell| {
+            cells.push(CellRepr {
+                row: row_index,
+                column: column_index,
+                cell: cell.clone(),
+            });
+        });
+        SheetRepr {
+            name: sheet.name,
+            cells,
+            images: sheet.images,
+            charts: sheet.charts,
+            defined_names: sheet.defined_names,
+            validations: sheet.validations,
+            conditional_formats: sheet.conditional_formats,
+            auto_filter: sheet.auto_filter,
+            tables: sheet.tables,
+            view: sheet.view,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<SheetRepr> for Sheet {
+    fn from(repr: SheetRepr) -> Sheet {
+        let mut sheet = Sheet::new(repr.name);
+        for cell in repr.cells {
+            sheet.add_cell(cell.cell, cell.row, cell.column);
+        }
+        sheet.images = repr.images;
+        sheet.charts = repr.charts;
+        sheet.defined_names = repr.defined_names;
+        sheet.validations = repr.validations;
+        sheet.conditional_formats = repr.conditional_formats;
+        sheet.auto_filter = repr.auto_filter;
+        sheet.tables = repr.tables;
+        sheet.view = repr.view;
+        sheet
+    }
+}
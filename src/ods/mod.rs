@@ -6,8 +6,13 @@ extern crate zip;
 use self::quick_xml::events::{BytesStart, Event};
 use self::quick_xml::Reader;
 use self::tempdir::TempDir;
-use super::{Book, Cell, Sheet, Value};
+use super::{Book, Cell, Package, Sheet, Value};
+use currency::Currency;
 use file_common::*;
+use format;
+use index_to_column_and_row;
+use range::{self, Range};
+use std::collections::HashMap;
 use std::fs::File;
 use std::io;
 use std::io::BufReader;
@@ -16,8 +21,13 @@ use std::result;
 use std::string::FromUtf8Error;
 
 mod read_content;
+mod read_settings;
 mod read_style;
+mod write_chart;
 mod write_content;
+mod write_package;
+mod write_frames;
+mod write_settings;
 mod write_style;
 
 const MANIFEST_XML_CONTENT: &'static str = r#"<?xml version="1.0" encoding="UTF-8"?>
@@ -66,7 +76,24 @@ impl From<FromUtf8Error> for OdsError {
 
 type Result<T> = result::Result<T, OdsError>;
 
+/// Error of a value of a part which isn't valid, like a repeat count which isn't a number
+fn invalid_data(message: String) -> OdsError {
+    OdsError::Io(io::Error::new(io::ErrorKind::InvalidData, message))
+}
+
 pub fn read(path: &Path) -> Result<Book> {
+    read_book(path, false)
+}
+
+/// Read a book keeping the original file.
+///
+/// `write` then makes only the tables, the pictures and charts anchored to cells, the styles it needs
+/// and the view settings, every other part like the printer settings or column widths is copied as is.
+pub fn open(path: &Path) -> Result<Book> {
+    read_book(path, true)
+}
+
+fn read_book(path: &Path, keep_package: bool) -> Result<Book> {
     let file = File::open(path)?;
     let dir = TempDir::new("shreadsheet")?;
     match unzip(&file, &dir) {
@@ -77,21 +104,40 @@ pub fn read(path: &Path) -> Result<Book> {
         }
     }
     let style_content = read_style::read(&dir).unwrap();
-    let book = read_content::read(&dir, &style_content);
+    let book = read_content::read(&dir, &style_content, keep_package).and_then(|mut book| {
+        read_settings::read(&dir, &mut book)?;
+        Ok(book)
+    });
     dir.close()?;
-    book
+    let mut book = book?;
+    if keep_package {
+        let mut package = Package::new();
+        for (name, data) in read_package(&File::open(path)?)? {
+            package.set_part(name, data);
+        }
+        book.set_package(package);
+    }
+    Ok(book)
 }
 
 pub fn write(book: &Book, path: &Path) -> result::Result<(), OdsError> {
+    if let Some(package) = book.get_package() {
+        if package.is_ods() {
+            return write_package::write(book, package, path);
+        }
+    }
     let dir = TempDir::new("shreadsheet")?;
-    let _ = write_style::write(book, &dir);
-    let _ = write_content::write(book, &dir);
+    let style_names = write_style::write(book, &dir)?;
+    let (paths, added) = write_frames::write(book, &dir)?;
+    let _ = write_content::write(book, &dir, &paths, &style_names);
+    write_settings::write(book, &dir)?;
     let _ = make_static_file(
         &dir,
         "META-INF/manifest.xml",
         MANIFEST_XML_CONTENT,
         Some("META-INF"),
     )?;
+    write_frames::write_manifest(&dir, MANIFEST_XML_CONTENT.as_bytes(), &added)?;
     write_to_file(path, &dir)?;
     dir.close()?;
     Ok(())
@@ -147,28 +193,220 @@ fn read_number_year(e: &BytesStart) -> result::Result<String, OdsError> {
     ))
 }
 
+/// Name and format of a date, time, currency, percentage or number style,
+/// the hours of a time style with truncate-on-overflow="false" are elapsed like "[HH]:MM"
+/// and the styles which the style applies by style:map are the other sections like "#,##0;[Red]\-#,##0"
+fn read_data_style(
+    e: &BytesStart,
+    reader: &mut Reader<BufReader<File>>,
+    styles: &HashMap<String, String>,
+) -> result::Result<Option<(String, String)>, OdsError> {
+    let mut name = None;
+    let mut elapsed = false;
+    for a in e.attributes().with_checks(false) {
+        match a {
+            Ok(ref attr) if attr.key == b"style:name" => {
+                name = Some(get_attribute_value(attr)?);
+            }
+            Ok(ref attr) if attr.key == b"number:truncate-on-overflow" => {
+                elapsed = get_attribute_value(attr)? == "false";
+            }
+            Ok(_) => {}
+            Err(_) => {}
+        }
+    }
+    let (mut format, maps) = read_number_date_style(reader)?;
+    if elapsed {
+        if let Some(start) = format.find('H') {
+            let end = start + format[start..].chars().take_while(|&c| c == 'H').count();
+            format.insert(end, ']');
+            format.insert(start, '[');
+        }
+    }
+    let mut sections: Vec<&str> = maps.iter().filter_map(|name| styles.get(name)).map(|x| x.as_str()).collect();
+    sections.push(format.as_str());
+    Ok(name.map(|name| (name, sections.join(";"))))
+}
+
+/// Excel locale id of the language and country of a number:currency-symbol
+fn read_currency_locale(e: &BytesStart) -> result::Result<Option<String>, OdsError> {
+    let mut language = String::from("");
+    let mut country = String::from("");
+    for a in e.attributes().with_checks(false) {
+        match a {
+            Ok(ref attr) if attr.key == b"number:language" => {
+                language = get_attribute_value(attr)?;
+            }
+            Ok(ref attr) if attr.key == b"number:country" => {
+                country = get_attribute_value(attr)?;
+            }
+            Ok(_) => {}
+            Err(_) => {}
+        }
+    }
+    Ok(Currency::locale_of_language(&language, &country).map(String::from))
+}
+
+/// Digits of a number:number like "#,##0.00"
+fn read_number_number(e: &BytesStart) -> result::Result<String, OdsError> {
+    let mut decimal_places = 0;
+    let mut min_decimal_places = None;
+    let mut min_integer_digits = 0;
+    let mut grouping = false;
+    for a in e.attributes().with_checks(false) {
+        match a {
+            Ok(ref attr) if attr.key == b"number:decimal-places" => {
+                decimal_places = get_attribute_value(attr)?.parse::<usize>().unwrap_or(0);
+            }
+            Ok(ref attr) if attr.key == b"number:min-decimal-places" => {
+                min_decimal_places = get_attribute_value(attr)?.parse::<usize>().ok();
+            }
+            Ok(ref attr) if attr.key == b"number:min-integer-digits" => {
+                min_integer_digits = get_attribute_value(attr)?.parse::<usize>().unwrap_or(0);
+            }
+            Ok(ref attr) if attr.key == b"number:grouping" => {
+                grouping = get_attribute_value(attr)? == "true";
+            }
+            Ok(_) => {}
+            Err(_) => {}
+        }
+    }
+    let min_decimal_places = min_decimal_places.unwrap_or(decimal_places).min(decimal_places);
+    let mut digits = "0".repeat(min_integer_digits);
+    if grouping {
+        let padded = format!("{:#>4}", digits);
+        let split = padded.len() - 3;
+        digits = format!("{},{}", &padded[..split], &padded[split..]);
+    } else if digits.is_empty() {
+        digits.push('#');
+    }
+    if decimal_places > 0 {
+        digits.push('.');
+        digits.push_str(&"0".repeat(min_decimal_places));
+        digits.push_str(&"#".repeat(decimal_places - min_decimal_places));
+    }
+    Ok(digits)
+}
+
+/// Digits with the exponent of a number:scientific-number like "0.00E+00"
+fn read_scientific_number(e: &BytesStart) -> result::Result<String, OdsError> {
+    let mut min_exponent_digits = 1;
+    for a in e.attributes().with_checks(false) {
+        match a {
+            Ok(ref attr) if attr.key == b"number:min-exponent-digits" => {
+                min_exponent_digits = get_attribute_value(attr)?.parse::<usize>().unwrap_or(1);
+            }
+            Ok(_) => {}
+            Err(_) => {}
+        }
+    }
+    Ok(format!("{}E+{}", read_number_number(e)?, "0".repeat(min_exponent_digits.max(1))))
+}
+
+/// Fraction of a number:fraction like "# ?/?" or "?/4"
+fn read_fraction(e: &BytesStart) -> result::Result<String, OdsError> {
+    let mut min_integer_digits = None;
+    let mut min_numerator_digits = 1;
+    let mut min_denominator_digits = 1;
+    let mut denominator = None;
+    for a in e.attributes().with_checks(false) {
+        match a {
+            Ok(ref attr) if attr.key == b"number:min-integer-digits" => {
+                min_integer_digits = get_attribute_value(attr)?.parse::<usize>().ok();
+            }
+            Ok(ref attr) if attr.key == b"number:min-numerator-digits" => {
+                min_numerator_digits = get_attribute_value(attr)?.parse::<usize>().unwrap_or(1);
+            }
+            Ok(ref attr) if attr.key == b"number:min-denominator-digits" => {
+                min_denominator_digits = get_attribute_value(attr)?.parse::<usize>().unwrap_or(1);
+            }
+            Ok(ref attr) if attr.key == b"number:denominator-value" => {
+                denominator = get_attribute_value(attr)?.parse::<usize>().ok();
+            }
+            Ok(_) => {}
+            Err(_) => {}
+        }
+    }
+    let integer = match min_integer_digits {
+        Some(0) => String::from("# "),
+        Some(digits) => format!("{} ", "0".repeat(digits)),
+        None => String::new(),
+    };
+    let denominator = match denominator {
+        Some(value) => value.to_string(),
+        None => "?".repeat(min_denominator_digits.max(1)),
+    };
+    Ok(format!("{}{}/{}", integer, "?".repeat(min_numerator_digits.max(1)), denominator))
+}
+
+/// Color like "[Red]" of the fo:color of a style:text-properties
+fn read_color(e: &BytesStart) -> result::Result<String, OdsError> {
+    for a in e.attributes().with_checks(false) {
+        match a {
+            Ok(ref attr) if attr.key == b"fo:color" => {
+                if let Some(color) = format::rgb_to_color(&get_attribute_value(attr)?) {
+                    return Ok(format!("[{}]", color));
+                }
+            }
+            Ok(_) => {}
+            Err(_) => {}
+        }
+    }
+    Ok(String::from(""))
+}
+
+/// Style name of a style:map
+fn read_map_style_name(e: &BytesStart) -> result::Result<Option<String>, OdsError> {
+    for a in e.attributes().with_checks(false) {
+        match a {
+            Ok(ref attr) if attr.key == b"style:apply-style-name" => {
+                return Ok(Some(get_attribute_value(attr)?));
+            }
+            Ok(_) => {}
+            Err(_) => {}
+        }
+    }
+    Ok(None)
+}
+
+/// Format of the elements of a data style and the style names of its style:map
 fn read_number_date_style(
     reader: &mut Reader<BufReader<File>>,
-) -> result::Result<String, OdsError> {
+) -> result::Result<(String, Vec<String>), OdsError> {
     let mut buf = Vec::new();
     let mut style_format = String::from("");
+    let mut maps = Vec::new();
     let mut text_empty_flag = true;
+    let mut text_flag = false;
+    let mut currency_locale: Option<Option<String>> = None;
     loop {
         match reader.read_event(&mut buf) {
             Ok(Event::Start(ref e)) => match e.name() {
                 b"number:text" => {
                     text_empty_flag = true;
+                    text_flag = true;
+                }
+                b"number:currency-symbol" => {
+                    currency_locale = Some(read_currency_locale(e)?);
                 }
                 _ => (),
             },
             Ok(Event::End(ref e)) => match e.name() {
                 b"number:text" => {
+                    text_flag = false;
                     if text_empty_flag {
                         style_format.push_str("\\ ");
                     }
                 }
-                b"number:date-style" => {
-                    return Ok(style_format);
+                b"number:currency-symbol" => {
+                    currency_locale = None;
+                }
+                b"number:date-style"
+                | b"number:time-style"
+                | b"number:currency-style"
+                | b"number:percentage-style"
+                | b"number:number-style" => {
+                    return Ok((style_format, maps));
                 }
                 _ => (),
             },
@@ -181,14 +419,32 @@ fn read_number_date_style(
                     b"number:hours" => read_number_format(e, "HH", "H"),
                     b"number:minutes" => read_number_format(e, "MM", "M"),
                     b"number:seconds" => read_number_format(e, "SS", "S"),
+                    b"number:number" => read_number_number(e),
+                    b"number:scientific-number" => read_scientific_number(e),
+                    b"number:fraction" => read_fraction(e),
+                    b"style:text-properties" => read_color(e),
+                    b"style:map" => {
+                        maps.extend(read_map_style_name(e)?);
+                        Ok(String::from(""))
+                    }
                     _ => Ok(String::from("")),
                 };
                 style_format.push_str(added_string?.as_str());
             }
+            Ok(Event::Text(ref e)) if currency_locale.is_some() && !e.is_empty() => {
+                let symbol = e.unescape_and_decode(reader)?;
+                match currency_locale {
+                    Some(Some(ref locale)) => style_format.push_str(&format!("[${}-{}]", symbol, locale)),
+                    _ => style_format.push_str(&format!("[${}]", symbol)),
+                }
+            }
+            // the reader of content.xml doesn't trim the text
+            Ok(Event::Text(ref e)) if !text_flag || e.is_empty() => (),
             Ok(Event::Text(e)) => {
                 match e.unescape_and_decode(&reader).unwrap().as_str() {
                     "/" => style_format.push_str("/"),
                     ":" => style_format.push_str(":"),
+                    "%" => style_format.push('%'),
                     other => {
                         if other.chars().count() == 1 {
                             style_format.push_str("\\");
@@ -210,5 +466,107 @@ fn read_number_date_style(
         // if we don't keep a borrow elsewhere, we can clear the buffer to keep memory usage low
         buf.clear();
     }
-    Ok(String::from(""))
+    Ok((style_format, maps))
+}
+
+/// "Sheet1.B2" or "'My Sheet'.B2:'My Sheet'.B5", the cell range address of a chart
+fn cell_address(range: &Range) -> String {
+    make_cell_address(range, "")
+}
+
+/// "$Sheet1.$B$2" or "$'My Sheet'.$B$2:$'My Sheet'.$B$5", the cell range address of a name
+fn absolute_cell_address(range: &Range) -> String {
+    make_cell_address(range, "$")
+}
+
+fn make_cell_address(range: &Range, absolute: &str) -> String {
+    let sheet_name = range::quote_sheet_name(range.get_sheet_name());
+    let cell = |row: usize, column: usize| {
+        let reference = index_to_column_and_row(column, row);
+        let split = reference.find(|c: char| c.is_ascii_digit()).unwrap_or(reference.len());
+        format!("{}{}.{}{}{}{}", absolute, sheet_name, absolute, &reference[..split], absolute, &reference[split..])
+    };
+    let first = cell(range.get_first_row(), range.get_first_column());
+    if range.get_first_row() == range.get_last_row() && range.get_first_column() == range.get_last_column() {
+        first
+    } else {
+        format!("{}:{}", first, cell(range.get_last_row(), range.get_last_column()))
+    }
+}
+
+/// Range of "$Sheet1.$A$1:.$B$5" or "'My Sheet'.A1:'My Sheet'.B5", the second cell has the sheet of the first without its own
+fn parse_cell_range_address(src: &str) -> Option<Range> {
+    let (first, last) = match *range::split_unquoted(src.trim(), ':').as_slice() {
+        [first] => (first, first),
+        [first, last] => (first, last),
+        _ => return None,
+    };
+    let (sheet_name, first_cell) = split_sheet_name(first);
+    let sheet_name = sheet_name?;
+    let (last_sheet_name, last_cell) = split_sheet_name(last);
+    if last_sheet_name.as_ref().is_some_and(|name| name != &sheet_name) {
+        return None;
+    }
+    let (first_row, first_column) = range::parse_reference(first_cell, 0, 0)?;
+    let (last_row, last_column) = range::parse_reference(last_cell, range::MAX_ROW, range::MAX_COLUMN)?;
+    Some(Range::new(sheet_name, first_row, first_column, last_row, last_column))
+}
+
+/// Sheet name and cell of "$'My Sheet'.$A$1", the sheet name is None for ".A1" or "A1"
+fn split_sheet_name(src: &str) -> (Option<String>, &str) {
+    let parts = range::split_unquoted(src, '.');
+    if parts.len() < 2 {
+        return (None, src);
+    }
+    let cell = parts[parts.len() - 1];
+    let sheet_name = src[..src.len() - cell.len() - 1].trim_start_matches('$');
+    if sheet_name.is_empty() {
+        (None, cell)
+    } else {
+        (range::unquote_sheet_name(sheet_name), cell)
+    }
+}
+
+/// Formula of an expression like "of:=[$Sheet1.$A$1:.$B$5]~[$Sheet1.$D$1]",
+/// the references in brackets are written as "Sheet1!$A$1:$B$5" and the union operator as a comma.
+/// The functions and the other operators are kept as they are.
+fn expression_to_formula(expression: &str) -> String {
+    let expression = expression.trim_start_matches("of:").trim_start_matches('=');
+    let mut formula = String::new();
+    let mut reference: Option<String> = None;
+    let mut quoted = false;
+    // the text of a string isn't an operator
+    let mut string = false;
+    for c in expression.chars() {
+        if c == '"' && reference.is_none() {
+            string = !string;
+        }
+        if string || c == '"' {
+            formula.push(c);
+            continue;
+        }
+        match reference {
+            Some(ref mut text) if c != ']' || quoted => {
+                if c == '\'' {
+                    quoted = !quoted;
+                }
+                text.push(c);
+                continue;
+            },
+            _ => {},
+        }
+        match c {
+            '[' => reference = Some(String::new()),
+            ']' => {
+                let text = reference.take().unwrap_or_default();
+                match parse_cell_range_address(&text) {
+                    Some(range) => formula.push_str(&range.to_string()),
+                    None => formula.push_str(&format!("[{}]", text)),
+                }
+            },
+            '~' => formula.push(','),
+            _ => formula.push(c),
+        }
+    }
+    formula
 }
@@ -1,21 +1,41 @@
-use super::{Book,Sheet,Cell};
+use super::{Book,Sheet,Cell,Value};
+use column_and_row_to_index;
+use chrono::prelude::*;
+use chrono::Duration;
+use comment::Comment;
+use defined_name::{self, DefinedName};
+use range::{self, Range};
+use hyperlink::Hyperlink;
+use image::{Anchor, Image, Marker};
+use currency::Currency;
+use date;
 use file_common::*;
+use format::Format;
+use rich_text::{Font, TextRun};
+use validation::{DataValidation, ErrorStyle, Message, Operator, Rule};
+use conditional_format::{self, ConditionalFormat, DifferentialStyle, IconStyle, Threshold};
+use auto_filter::{AutoFilter, Comparison, Filter, SortKey};
 use super::quick_xml::Reader;
-use super::quick_xml::events::{Event};
+use super::quick_xml::events::{Event, BytesStart};
 use super::tempdir::TempDir;
-use super::{Result};
+use super::{OdsError, Result, expression_to_formula, invalid_data, parse_cell_range_address};
 use super::read_style::StyleContent;
 use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::mem;
 
 const CONTENT_XML: &'static str = "content.xml";
+// styled empty cells repeated more than this are the rest of the sheet
+const MAX_KEPT_REPEAT: usize = 256;
 
-pub fn read(dir: &TempDir, style_content: &StyleContent) -> Result<Book> {
+pub fn read(dir: &TempDir, style_content: &StyleContent, keep_style: bool) -> Result<Book> {
     let mut date_style_map = HashMap::new();
     let mut style_map_for_date: HashMap<String, String> = HashMap::new();
 
     let path = dir.path().join(CONTENT_XML);
     let mut reader = Reader::from_file(path)?;
-    reader.trim_text(true);
+    reader.trim_text(false);
     let mut book = Book::new();
 
     let mut buf = Vec::new();
@@ -27,26 +47,92 @@ pub fn read(dir: &TempDir, style_content: &StyleContent) -> Result<Book> {
     let mut float_value: f64 = 0.0;
     let mut str_value: String = String::from("");
     let mut date_value: String = String::from("");
+    let mut time_value: String = String::from("");
+    let mut currency_code: String = String::from("");
+    let mut bool_value = false;
+    let mut calc_type: String = String::from("");
     let mut table_style_name: String = String::from("");
+    let mut text_style_map: HashMap<String, Font> = HashMap::new();
+    let mut text_style_name: Option<String> = None;
+    // table styles with style:writing-mode="rl-tb" make the tables right to left
+    let mut table_family_style: Option<String> = None;
+    let mut right_to_left_styles: Vec<String> = Vec::new();
+    let mut runs: Vec<TextRun> = Vec::new();
+    let mut span_fonts: Vec<Option<Font>> = Vec::new();
+    let mut paragraph_flag = false;
+    let mut paragraph_count: usize = 0;
+    // white spaces after a white space or at the start of a paragraph are ignored
+    let mut collapsed = true;
+    let mut rows_repeated: usize = 1;
+    let mut columns_repeated: usize = 1;
+    let mut row_cells: Vec<(usize, Cell)> = Vec::new();
+    let mut comment: Option<Comment> = None;
+    let mut hyperlink: Option<Hyperlink> = None;
+    // frames of table:shapes are anchored to the page
+    let mut shapes_flag = false;
+    // the names inside a table are the names of the sheet
+    let mut table_flag = false;
+    // validations by name, their ranges are the areas of the cells having their names
+    let mut validations: Vec<(String, DataValidation)> = Vec::new();
+    let mut validation_name: Option<String> = None;
+    let mut row_validations: Vec<(String, usize, usize)> = Vec::new();
+    let mut validation_areas: Vec<(String, (usize, usize, usize, usize))> = Vec::new();
 
     loop {
         match reader.read_event(&mut buf) {
             Ok(Event::Start(ref e)) => {
                 match e.name() {
                     b"table:table" => {
+                        table_flag = true;
                         for a in e.attributes().with_checks(false) {
                             match a {
                                 Ok(ref attr) if attr.key == b"table:name" => {
-                                    sheet.set_name(get_attribute_value(attr)?);
+                                    let name = get_attribute_value(attr)?;
+                                    if keep_style {
+                                        sheet.set_part_name(Some(name.clone()));
+                                    }
+                                    sheet.set_name(name);
+                                },
+                                Ok(ref attr) if attr.key == b"table:style-name" => {
+                                    let name = get_attribute_value(attr)?;
+                                    sheet.get_view_mut().set_right_to_left(right_to_left_styles.contains(&name));
+                                },
+                                Ok(ref attr) if attr.key == b"table:print-ranges" => {
+                                    let value = condvert_character_reference(&get_attribute_value(attr)?);
+                                    let areas: Option<Vec<String>> = range::split_unquoted(&value, ' ').into_iter()
+                                        .filter(|address| !address.is_empty())
+                                        .map(|address| parse_cell_range_address(address).map(|range| range.to_string()))
+                                        .collect();
+                                    if let Some(areas) = areas.filter(|areas| !areas.is_empty()) {
+                                        sheet.add_defined_name(DefinedName::new(defined_name::PRINT_AREA, areas.join(",").as_str()));
+                                    }
                                 },
                                 Ok(_) => {},
                                 Err(_) => {},
                             }
                         }
                     },
-                    b"table:table-cell" => {
+                    b"table:table-row" => {
+                        rows_repeated = read_repeated(e, b"table:number-rows-repeated")?;
+                        row_cells = Vec::new();
+                        row_validations = Vec::new();
+                    },
+                    b"table:table-cell" | b"table:covered-table-cell" => {
+                        table_style_name = String::from("");
+                        calc_type = String::from("");
+                        currency_code = String::from("");
+                        columns_repeated = 1;
+                        comment = None;
+                        hyperlink = None;
+                        validation_name = None;
                         for a in e.attributes().with_checks(false) {
                             match a {
+                                Ok(ref attr) if attr.key == b"table:number-columns-repeated" => {
+                                    columns_repeated = get_attribute_value(attr)?.parse::<usize>().unwrap_or(1);
+                                },
+                                Ok(ref attr) if attr.key == b"table:content-validation-name" => {
+                                    validation_name = Some(condvert_character_reference(&get_attribute_value(attr)?));
+                                },
                                 Ok(ref attr) if attr.key == b"table:style-name" => {
                                     table_style_name = get_attribute_value(attr)?;
                                 },
@@ -60,23 +146,93 @@ pub fn read(dir: &TempDir, style_content: &StyleContent) -> Result<Book> {
                                 Ok(ref attr) if attr.key == b"office:date-value" => {
                                     date_value = get_attribute_value(attr)?;
                                 },
+                                Ok(ref attr) if attr.key == b"office:currency" => {
+                                    currency_code = get_attribute_value(attr)?;
+                                },
+                                Ok(ref attr) if attr.key == b"office:time-value" => {
+                                    time_value = get_attribute_value(attr)?;
+                                },
+                                Ok(ref attr) if attr.key == b"office:boolean-value" => {
+                                    bool_value = get_attribute_value(attr)? == "true";
+                                },
+                                Ok(ref attr) if attr.key == b"calcext:value-type" => {
+                                    calc_type = get_attribute_value(attr)?;
+                                },
                                 Ok(_) => {},
                                 Err(_) => {},
                             }
                         }
                     },
-                    b"number:date-style" => {
+                    b"style:style" => {
+                        text_style_name = read_style_name(e, "text")?;
+                        table_family_style = read_style_name(e, "table")?;
+                    },
+                    b"style:table-properties" => {
+                        if let (Some(name), true) = (table_family_style.as_ref(), is_right_to_left(e)) {
+                            right_to_left_styles.push(name.clone());
+                        }
+                    },
+                    b"text:a" if paragraph_flag => hyperlink = Some(read_hyperlink(e)?),
+                    // the paragraphs of the annotation are not the text of the cell
+                    b"office:annotation" => comment = Some(read_annotation(e, &mut reader)?),
+                    b"table:shapes" => shapes_flag = true,
+                    b"table:content-validation" => {
+                        // the messages are read even for a condition which isn't a rule
+                        let validation = read_content_validation(e)?;
+                        let (prompt, error, error_style) = read_validation_messages(&mut reader)?;
+                        if let Some((name, mut validation)) = validation {
+                            validation.set_prompt(prompt);
+                            validation.set_error(error);
+                            validation.set_error_style(error_style);
+                            validations.push((name, validation));
+                        }
+                    },
+                    b"calcext:conditional-format" => {
+                        if let Some(format) = read_conditional_format(e, &mut reader, style_content)? {
+                            sheet.add_conditional_format(format);
+                        }
+                    },
+                    b"table:database-range" => {
+                        let mut auto_filter = read_auto_filter(e)?;
+                        read_database_range(&mut reader, auto_filter.as_mut())?;
+                        set_auto_filter(&mut book, auto_filter);
+                    },
+                    b"draw:frame" => {
+                        let image = read_frame(e, &mut reader, dir, row, column)?;
+                        if let (false, Some(image)) = (shapes_flag, image) {
+                            sheet.add_image(image);
+                        }
+                    },
+                    b"text:p" => {
+                        // the paragraphs of a cell are its lines
+                        if paragraph_count > 0 {
+                            push_text(&mut runs, "\n", None);
+                        }
+                        paragraph_count += 1;
+                        paragraph_flag = true;
+                        collapsed = true;
+                    },
+                    b"text:span" => {
+                        // a span without its own style has the font of the outer span
+                        let mut span_font = span_fonts.last().cloned().unwrap_or(None);
                         for a in e.attributes().with_checks(false) {
                             match a {
-                                Ok(ref attr) if attr.key == b"style:name" => {
-                                    date_style_map.insert(
-                                        get_attribute_value(attr)?,
-                                        super::read_number_date_style(&mut reader)?);
+                                Ok(ref attr) if attr.key == b"text:style-name" => {
+                                    if let Some(font) = text_style_map.get(&get_attribute_value(attr)?) {
+                                        span_font = Some(font.clone());
+                                    }
                                 },
                                 Ok(_) => {},
                                 Err(_) => {},
                             }
                         }
+                        span_fonts.push(span_font);
+                    },
+                    b"number:date-style" | b"number:time-style" | b"number:currency-style"
+                    | b"number:percentage-style" | b"number:number-style" => {
+                        if let Some((name, format)) = super::read_data_style(e, &mut reader, &date_style_map)? {
+                            date_style_map.insert(name, format);
+                        }
                     },
                     _ => (),
                 }
@@ -84,59 +240,206 @@ pub fn read(dir: &TempDir, style_content: &StyleContent) -> Result<Book> {
             Ok(Event::End(ref e)) => {
                 match e.name() {
                     b"table:table" => {
+                        table_flag = false;
+                        for (name, validation) in &validations {
+                            let areas = validation_areas.iter().filter(|&(n, _)| n == name).map(|&(_, area)| area).collect();
+                            let ranges: Vec<Range> = merge_areas(areas).into_iter()
+                                .map(|(first_row, first_column, last_row, last_column)| {
+                                    Range::new(sheet.get_name().as_str(), first_row, first_column, last_row, last_column)
+                                })
+                                .collect();
+                            if !ranges.is_empty() {
+                                let mut validation = validation.clone();
+                                validation.set_ranges(ranges);
+                                sheet.add_validation(validation);
+                            }
+                        }
+                        validation_areas = Vec::new();
                         row = 0;
                         column = 0;
                         book.add_sheet(sheet);
                         sheet = Sheet::new("");
                     },
                     b"table:table-row" => {
-                        row = row + 1;
+                        for (name, first_column, count) in row_validations.drain(..) {
+                            validation_areas.push((name, (row, first_column, row + rows_repeated - 1, first_column + count - 1)));
+                        }
+                        add_rows(&mut sheet, &row_cells, row, rows_repeated, keep_style);
+                        row += rows_repeated;
                         column = 0;
                     },
-                    b"table:table-cell" => {
-                        match cell_type.as_str() {
-                            "string" => {
-                                let cell = Cell::str(str_value.clone(), String::from(""));
-                                sheet.add_cell(cell, row, column);
+                    b"style:style" => {
+                        text_style_name = None;
+                        table_family_style = None;
+                    },
+                    b"table:shapes" => shapes_flag = false,
+                    b"text:p" => {
+                        str_value = TextRun::concat(&runs);
+                        paragraph_flag = false;
+                    },
+                    b"text:span" => {
+                        span_fonts.pop();
+                    },
+                    b"table:table-cell" | b"table:covered-table-cell" => {
+                        let cell = match cell_type.as_str() {
+                            _ if calc_type == "error" => Some(Cell::new(Value::Error(str_value.clone()), "")),
+                            "boolean" => Some(Cell::new(Value::Bool(bool_value), "")),
+                            "string" if runs.iter().any(|run| run.get_font().is_some()) => {
+                                Some(Cell::new(Value::RichText(runs.clone()), ""))
                             },
-                            "float" => {
-                                let cell = Cell::float(float_value, "");
-                                sheet.add_cell(cell, row, column);
+                            "string" => Some(Cell::str(str_value.clone(), String::from(""))),
+                            "float" | "percentage" => {
+                                let format = match style_map_for_date.get(&table_style_name) {
+                                    Some(value) => value.clone(),
+                                    None => String::from(""),
+                                };
+                                Some(Cell::float(float_value, format))
+                            },
+                            "currency" => {
+                                let format = match style_map_for_date.get(&table_style_name) {
+                                    Some(value) => value.clone(),
+                                    None => String::from(""),
+                                };
+                                Some(Cell::new(Value::Currency(Currency::new(float_value, currency_code.clone())), format))
                             },
                             "date" => {
                                 let format = match style_map_for_date.get(&table_style_name) {
                                     Some(value) => value.clone(),
                                     None => String::from(""),
                                 };
-                                let cell = Cell::date(date_value.clone(), format);
-                                sheet.add_cell(cell, row, column);
+                                match NaiveDate::parse_from_str(&date_value, "%Y-%m-%d") {
+                                    Ok(value) => Some(Cell::new(Value::DateOnly(value), format)),
+                                    Err(_) => Some(Cell::date(date_value.clone(), format)),
+                                }
                             },
-                            _ => {},
+                            "time" => {
+                                let format = match style_map_for_date.get(&table_style_name) {
+                                    Some(value) => value.clone(),
+                                    None => String::from(""),
+                                };
+                                date::iso8601_to_duration(&time_value).map(|value| {
+                                    Cell::new(duration_to_value(value, &Format::new(format.as_str())), format)
+                                })
+                            },
+                            _ if keep_style && !table_style_name.is_empty() => Some(Cell::new(Value::Empty, "")),
+                            _ if comment.is_some() || hyperlink.is_some() => Some(Cell::new(Value::Empty, "")),
+                            _ => None,
+                        };
+                        if let Some(mut cell) = cell {
+                            cell.set_comment(comment.take());
+                            cell.set_hyperlink(hyperlink.take());
+                            if keep_style && !table_style_name.is_empty() {
+                                cell.set_style_id(Some(table_style_name.clone()));
+                            }
+                            // a styled empty cell repeated to the end of the row is not kept
+                            if cell.get_value() != &Value::Empty || columns_repeated <= MAX_KEPT_REPEAT {
+                                for index in 0..columns_repeated {
+                                    row_cells.push((column + index, cell.clone()));
+                                }
+                            }
+                        }
+                        if let Some(name) = validation_name.take() {
+                            row_validations.push((name, column, columns_repeated));
                         }
                         cell_type = String::from("");
-                        column = column + 1;
+                        str_value = String::from("");
+                        runs = Vec::new();
+                        paragraph_count = 0;
+                        column += columns_repeated;
                     },
                     _ => (),
                 }
             }
             Ok(Event::Empty(ref e)) => {
                 match e.name() {
-                    b"table:table-cell" => {
-                        if 0 == e.attributes().count() {
-                            column = column + 1;
-                        } else {
-                            for a in e.attributes().with_checks(false) {
-                                match a {
-                                    Ok(ref attr) if attr.key == b"table:number-columns-repeated" => {
-                                        let value = get_attribute_value(attr)?;
-                                        column = column + value.parse::<usize>().unwrap();
-                                    },
-                                    Ok(_) => {},
-                                    Err(_) => {},
-                                }
+                    b"table:database-range" => {
+                        let auto_filter = read_auto_filter(e)?;
+                        set_auto_filter(&mut book, auto_filter);
+                    },
+                    b"table:content-validation" => {
+                        if let Some(validation) = read_content_validation(e)? {
+                            validations.push(validation);
+                        }
+                    },
+                    b"table:named-range" | b"table:named-expression" => {
+                        if let Some(defined_name) = read_named_expression(e)? {
+                            if table_flag {
+                                sheet.add_defined_name(defined_name);
+                            } else {
+                                book.add_defined_name(defined_name);
                             }
                         }
                     },
+                    b"table:table-row" => {
+                        let repeated = read_repeated(e, b"table:number-rows-repeated")?;
+                        add_rows(&mut sheet, &[], row, repeated, keep_style);
+                        row += repeated;
+                    },
+                    b"table:table-cell" | b"table:covered-table-cell" => {
+                        let mut repeated = 1;
+                        let mut style_name = String::from("");
+                        let mut empty_validation_name = None;
+                        for a in e.attributes().with_checks(false) {
+                            match a {
+                                Ok(ref attr) if attr.key == b"table:number-columns-repeated" => {
+                                    let value = get_attribute_value(attr)?;
+                                    repeated = value.parse::<usize>()
+                                        .map_err(|_| invalid_data(format!("invalid number of repeated columns: {}", value)))?;
+                                },
+                                Ok(ref attr) if attr.key == b"table:content-validation-name" => {
+                                    empty_validation_name = Some(condvert_character_reference(&get_attribute_value(attr)?));
+                                },
+                                Ok(ref attr) if attr.key == b"table:style-name" => {
+                                    style_name = get_attribute_value(attr)?;
+                                },
+                                Ok(_) => {},
+                                Err(_) => {},
+                            }
+                        }
+                        // the repeat count may come after the validation name
+                        if let Some(name) = empty_validation_name {
+                            row_validations.push((name, column, repeated));
+                        }
+                        // a cell without value only keeps its style
+                        if keep_style && !style_name.is_empty() && repeated <= MAX_KEPT_REPEAT {
+                            for index in 0..repeated {
+                                let mut cell = Cell::new(Value::Empty, "");
+                                cell.set_style_id(Some(style_name.clone()));
+                                row_cells.push((column + index, cell));
+                            }
+                        }
+                        column += repeated;
+                    },
+                    b"text:p" => {
+                        if paragraph_count > 0 {
+                            push_text(&mut runs, "\n", None);
+                        }
+                        paragraph_count += 1;
+                        str_value = TextRun::concat(&runs);
+                    },
+                    b"text:s" if paragraph_flag => {
+                        let count = read_repeated(e, b"text:c")?;
+                        push_text(&mut runs, &" ".repeat(count), span_fonts.last().cloned().unwrap_or(None));
+                        collapsed = false;
+                    },
+                    b"text:tab" if paragraph_flag => {
+                        push_text(&mut runs, "\t", span_fonts.last().cloned().unwrap_or(None));
+                        collapsed = false;
+                    },
+                    b"text:line-break" if paragraph_flag => {
+                        push_text(&mut runs, "\n", span_fonts.last().cloned().unwrap_or(None));
+                        collapsed = true;
+                    },
+                    b"style:text-properties" => {
+                        if let Some(ref name) = text_style_name {
+                            text_style_map.insert(name.clone(), read_text_properties(e)?);
+                        }
+                    },
+                    b"style:table-properties" => {
+                        if let (Some(name), true) = (table_family_style.as_ref(), is_right_to_left(e)) {
+                            right_to_left_styles.push(name.clone());
+                        }
+                    },
                     b"style:style" => {
                         let mut style_name = String::from("");
                         let mut data_style_name = String::from("");
@@ -163,7 +466,10 @@ pub fn read(dir: &TempDir, style_content: &StyleContent) -> Result<Book> {
                     _ => (),
                 }
             }
-            Ok(Event::Text(e)) => str_value = e.unescape_and_decode(&reader).unwrap(),
+            Ok(Event::Text(e)) if paragraph_flag && !e.is_empty() => {
+                let text = collapse_white_spaces(&e.unescape_and_decode(&reader)?, &mut collapsed);
+                push_text(&mut runs, &text, span_fonts.last().cloned().unwrap_or(None));
+            },
             Ok(Event::Eof) => break,
             Err(e) => panic!("Error at position {}: {:?}", reader.buffer_position(), e),
             _ => (),
@@ -175,3 +481,846 @@ pub fn read(dir: &TempDir, style_content: &StyleContent) -> Result<Book> {
 
     Ok(book)
 }
+
+/// Name of a table:named-range or a table:named-expression, None for an address which isn't a range
+fn read_named_expression(e: &BytesStart) -> Result<Option<DefinedName>> {
+    let mut name = None;
+    let mut formula = None;
+    for a in e.attributes().with_checks(false) {
+        match a {
+            Ok(ref attr) if attr.key == b"table:name" => {
+                name = Some(condvert_character_reference(&get_attribute_value(attr)?));
+            },
+            Ok(ref attr) if attr.key == b"table:cell-range-address" => {
+                let address = condvert_character_reference(&get_attribute_value(attr)?);
+                formula = parse_cell_range_address(&address).map(|range| range.to_string());
+            },
+            Ok(ref attr) if attr.key == b"table:expression" => {
+                formula = Some(expression_to_formula(&condvert_character_reference(&get_attribute_value(attr)?)));
+            },
+            Ok(_) => {},
+            Err(_) => {},
+        }
+    }
+    match (name, formula) {
+        (Some(name), Some(formula)) => Ok(Some(DefinedName::new(name, formula))),
+        _ => Ok(None),
+    }
+}
+
+/// Name and validation of a table:content-validation without its ranges and its messages,
+/// None for a condition which isn't a rule
+fn read_content_validation(e: &BytesStart) -> Result<Option<(String, DataValidation)>> {
+    let mut name = None;
+    let mut rule = None;
+    let mut allow_blank = true;
+    let mut drop_down = true;
+    for a in e.attributes().with_checks(false) {
+        match a {
+            Ok(ref attr) if attr.key == b"table:name" => {
+                name = Some(condvert_character_reference(&get_attribute_value(attr)?));
+            },
+            Ok(ref attr) if attr.key == b"table:condition" => {
+                rule = read_validation_rule(&condvert_character_reference(&get_attribute_value(attr)?));
+            },
+            Ok(ref attr) if attr.key == b"table:allow-empty-cell" => allow_blank = get_attribute_value(attr)? != "false",
+            Ok(ref attr) if attr.key == b"table:display-list" => drop_down = get_attribute_value(attr)? != "none",
+            Ok(_) => {},
+            Err(_) => {},
+        }
+    }
+    match (name, rule) {
+        (Some(name), Some(rule)) => {
+            let mut validation = DataValidation::new(Range::cell("", 0, 0), rule);
+            validation.set_ranges(Vec::new());
+            validation.set_allow_blank(allow_blank);
+            validation.set_drop_down(drop_down);
+            Ok(Some((name, validation)))
+        },
+        _ => Ok(None),
+    }
+}
+
+/// Read the help message, the error message and its style of a table:content-validation until its end tag,
+/// the paragraphs are the lines of a message
+fn read_validation_messages(reader: &mut Reader<BufReader<File>>) -> Result<(Option<Message>, Option<Message>, ErrorStyle)> {
+    let mut prompt = None;
+    let mut error = None;
+    let mut error_style = ErrorStyle::Stop;
+    let mut buf = Vec::new();
+    let mut title = String::new();
+    let mut text = String::new();
+    let mut paragraph_flag = false;
+    let mut paragraph_count: usize = 0;
+    let mut collapsed = true;
+    loop {
+        match reader.read_event(&mut buf) {
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) if e.name() == b"table:help-message" || e.name() == b"table:error-message" => {
+                title = String::new();
+                text = String::new();
+                paragraph_count = 0;
+                for a in e.attributes().with_checks(false) {
+                    match a {
+                        Ok(ref attr) if attr.key == b"table:title" => title = condvert_character_reference(&get_attribute_value(attr)?),
+                        Ok(ref attr) if attr.key == b"table:message-type" => {
+                            error_style = match get_attribute_value(attr)?.as_str() {
+                                "warning" => ErrorStyle::Warning,
+                                "information" => ErrorStyle::Information,
+                                _ => ErrorStyle::Stop,
+                            };
+                        },
+                        Ok(_) => {},
+                        Err(_) => {},
+                    }
+                }
+            },
+            Ok(Event::Start(ref e)) if e.name() == b"text:p" => {
+                if paragraph_count > 0 {
+                    text.push('\n');
+                }
+                paragraph_count += 1;
+                paragraph_flag = true;
+                collapsed = true;
+            },
+            Ok(Event::Empty(ref e)) => {
+                match e.name() {
+                    b"text:p" => {
+                        if paragraph_count > 0 {
+                            text.push('\n');
+                        }
+                        paragraph_count += 1;
+                    },
+                    b"text:s" if paragraph_flag => {
+                        text.push_str(&" ".repeat(read_repeated(e, b"text:c")?));
+                        collapsed = false;
+                    },
+                    b"text:tab" if paragraph_flag => {
+                        text.push('\t');
+                        collapsed = false;
+                    },
+                    b"text:line-break" if paragraph_flag => {
+                        text.push('\n');
+                        collapsed = true;
+                    },
+                    _ => (),
+                }
+            },
+            Ok(Event::Text(e)) if paragraph_flag => {
+                text.push_str(&collapse_white_spaces(&e.unescape_and_decode(reader)?, &mut collapsed));
+            },
+            Ok(Event::End(ref e)) => {
+                match e.name() {
+                    b"text:p" => paragraph_flag = false,
+                    b"table:help-message" => prompt = Some(Message::new(title.as_str(), text.as_str())),
+                    b"table:error-message" if !title.is_empty() || !text.is_empty() => {
+                        error = Some(Message::new(title.as_str(), text.as_str()));
+                    },
+                    b"table:content-validation" => break,
+                    _ => (),
+                }
+            },
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(OdsError::Xml(e)),
+            _ => (),
+        }
+        buf.clear();
+    }
+    Ok((prompt, error, error_style))
+}
+
+/// Rule of a condition like "of:cell-content-is-whole-number() and cell-content-is-between(1;10)",
+/// a date is its serial
+fn read_validation_rule(condition: &str) -> Option<Rule> {
+    let condition = condition.trim();
+    let condition = condition.strip_prefix("of:")
+        .or_else(|| condition.strip_prefix("ooow:"))
+        .unwrap_or(condition);
+    if let Some(arguments) = function_arguments(condition, "cell-content-is-in-list") {
+        let items = split_arguments(arguments, &[';']);
+        if items.len() == 1 && !items[0].starts_with('"') {
+            return parse_cell_range_address(items[0].trim_start_matches('[').trim_end_matches(']')).map(Rule::ListRange);
+        }
+        return Some(Rule::List(items.iter().map(|item| {
+            if item.len() >= 2 && item.starts_with('"') && item.ends_with('"') {
+                item[1..item.len() - 1].replace("\"\"", "\"")
+            } else {
+                item.to_string()
+            }
+        }).collect()));
+    }
+    if let Some(formula) = function_arguments(condition, "is-true-formula") {
+        return Some(Rule::Custom(expression_to_formula(formula)));
+    }
+    if condition.starts_with("cell-content-text-length") {
+        return read_condition_operator(condition, "cell-content-text-length", |value| value.parse::<usize>().ok()).map(Rule::TextLength);
+    }
+    let parse_number = |value: &str| value.parse::<f64>().ok();
+    let types = ["cell-content-is-whole-number()", "cell-content-is-decimal-number()", "cell-content-is-date()"];
+    let index = types.iter().position(|t| condition.starts_with(t))?;
+    let operator = condition[types[index].len()..].trim_start().trim_start_matches("and").trim();
+    match index {
+        0 => read_condition_operator(operator, "cell-content", |value| parse_number(value).map(|number| number as i64)).map(Rule::WholeNumber),
+        1 => read_condition_operator(operator, "cell-content", parse_number).map(Rule::Decimal),
+        _ => read_condition_operator(operator, "cell-content", |value| {
+            parse_number(value).and_then(|serial| date::serial_to_datetime(serial, date::DateSystem::Date1900)).map(|dt| dt.date())
+        }).map(Rule::Date),
+    }
+}
+
+/// Operator of a condition like "cell-content-is-between(1;10)" or "cell-content()>=5"
+fn read_condition_operator<T, F>(condition: &str, function: &str, parse: F) -> Option<Operator<T>>
+    where F: Fn(&str) -> Option<T>
+{
+    for &(suffix, name) in [("-is-between", "between"), ("-is-not-between", "notBetween")].iter() {
+        if let Some(arguments) = function_arguments(condition, &format!("{}{}", function, suffix)) {
+            let arguments = split_arguments(arguments, &[';']);
+            if arguments.len() != 2 {
+                return None;
+            }
+            return Operator::from_name(name, parse(&arguments[0])?, Some(parse(&arguments[1])?));
+        }
+    }
+    let comparison = condition.trim_start_matches(function).trim_start();
+    if !comparison.starts_with("()") {
+        return None;
+    }
+    let comparison = comparison[2..].trim_start();
+    let symbols = [
+        (">=", "greaterThanOrEqual"), ("<=", "lessThanOrEqual"), ("!=", "notEqual"), ("<>", "notEqual"),
+        ("=", "equal"), (">", "greaterThan"), ("<", "lessThan"),
+    ];
+    let &(symbol, name) = symbols.iter().find(|&&(symbol, _)| comparison.starts_with(symbol))?;
+    Operator::from_name(name, parse(comparison[symbol.len()..].trim())?, None)
+}
+
+/// Arguments of "name(arguments)"
+fn function_arguments<'a>(src: &'a str, name: &str) -> Option<&'a str> {
+    let src = src.trim();
+    if src.starts_with(name) && src[name.len()..].trim_start().starts_with('(') && src.ends_with(')') {
+        let start = src.find('(')?;
+        Some(&src[start + 1..src.len() - 1])
+    } else {
+        None
+    }
+}
+
+/// Arguments separated by the separators outside of the strings and the references, trimmed
+fn split_arguments(src: &str, separators: &[char]) -> Vec<String> {
+    let mut arguments = Vec::new();
+    let mut argument = String::new();
+    let mut string = false;
+    let mut depth = 0;
+    for c in src.chars() {
+        match c {
+            '"' => string = !string,
+            '[' | '(' if !string => depth += 1,
+            ']' | ')' if !string => depth -= 1,
+            c if separators.contains(&c) && !string && depth == 0 => {
+                arguments.push(argument.trim().to_string());
+                argument = String::new();
+                continue;
+            },
+            _ => {},
+        }
+        argument.push(c);
+    }
+    arguments.push(argument.trim().to_string());
+    arguments
+}
+
+/// Read a calcext:conditional-format until its end tag, the ranges are its target ranges.
+/// A condition which isn't a rule like "begins-with" is dropped and a style not in styles.xml is an empty style.
+fn read_conditional_format(e: &BytesStart, reader: &mut Reader<BufReader<File>>, style_content: &StyleContent) -> Result<Option<ConditionalFormat>> {
+    use conditional_format::Rule;
+    let mut ranges: Vec<Range> = Vec::new();
+    for a in e.attributes().with_checks(false) {
+        match a {
+            Ok(ref attr) if attr.key == b"calcext:target-range-address" => {
+                let addresses = condvert_character_reference(&get_attribute_value(attr)?);
+                ranges = range::split_unquoted(&addresses, ' ').iter()
+                    .filter(|address| !address.is_empty())
+                    .filter_map(|address| parse_cell_range_address(address))
+                    .collect();
+            },
+            Ok(_) => {},
+            Err(_) => {},
+        }
+    }
+    let mut rules: Vec<Rule> = Vec::new();
+    // thresholds and colors of the color scale, data bar or icon set being read
+    let mut thresholds: Vec<Threshold> = Vec::new();
+    let mut colors: Vec<String> = Vec::new();
+    let mut icons: Option<IconStyle> = None;
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event(&mut buf) {
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => {
+                let attributes = read_attributes(e)?;
+                let attribute = |key: &str| attributes.get(key).cloned().unwrap_or_default();
+                match e.name() {
+                    b"calcext:condition" => {
+                        let style = style_content.cell_styles.get(&attribute("calcext:apply-style-name")).cloned().unwrap_or_default();
+                        rules.extend(read_conditional_rule(&attribute("calcext:value"), style));
+                    },
+                    b"calcext:color-scale" | b"calcext:data-bar" | b"calcext:icon-set" => {
+                        thresholds = Vec::new();
+                        colors = Vec::new();
+                        colors.extend(attributes.get("calcext:positive-color").map(|color| color.trim_start_matches('#').to_uppercase()));
+                        icons = attributes.get("calcext:icon-set-type").and_then(|name| IconStyle::from_name(name));
+                    },
+                    b"calcext:color-scale-entry" | b"calcext:formatting-entry" => {
+                        let value = attribute("calcext:value");
+                        let number = value.trim().parse::<f64>().ok();
+                        let threshold = match attribute("calcext:type").as_str() {
+                            "minimum" | "auto-minimum" => Some(Threshold::Min),
+                            "maximum" | "auto-maximum" => Some(Threshold::Max),
+                            "number" => number.map(Threshold::Number),
+                            "percent" => number.map(Threshold::Percent),
+                            "percentile" => number.map(Threshold::Percentile),
+                            "formula" => Some(Threshold::Formula(expression_to_formula(&value))),
+                            _ => None,
+                        };
+                        thresholds.extend(threshold);
+                        colors.extend(attributes.get("calcext:color").map(|color| color.trim_start_matches('#').to_uppercase()));
+                    },
+                    _ => {},
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                match e.name() {
+                    b"calcext:color-scale" if thresholds.len() >= 2 && thresholds.len() == colors.len() => {
+                        rules.push(Rule::ColorScale(thresholds.drain(..).zip(colors.drain(..)).collect()));
+                    },
+                    b"calcext:data-bar" if thresholds.len() == 2 && !colors.is_empty() => {
+                        rules.push(Rule::DataBar {
+                            min: thresholds[0].clone(),
+                            max: thresholds[1].clone(),
+                            color: colors[0].clone(),
+                        });
+                    },
+                    b"calcext:icon-set" => {
+                        if let Some(icons) = icons.take() {
+                            rules.push(Rule::IconSet {
+                                icons,
+                                thresholds: mem::take(&mut thresholds),
+                            });
+                        }
+                    },
+                    b"calcext:conditional-format" => break,
+                    _ => {},
+                }
+            },
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(OdsError::Xml(e)),
+            _ => {},
+        }
+        buf.clear();
+    }
+    let mut ranges = ranges.into_iter();
+    let mut rules = rules.into_iter();
+    let (range, rule) = match (ranges.next(), rules.next()) {
+        (Some(range), Some(rule)) => (range, rule),
+        _ => return Ok(None),
+    };
+    let mut format = ConditionalFormat::new(range, rule);
+    for range in ranges {
+        format.add_range(range);
+    }
+    for rule in rules {
+        format.add_rule(rule);
+    }
+    Ok(Some(format))
+}
+
+/// Auto filter of the target range of a table:database-range with filter buttons
+fn read_auto_filter(e: &BytesStart) -> Result<Option<AutoFilter>> {
+    let attributes = read_attributes(e)?;
+    if attributes.get("table:display-filter-buttons").map(|value| value.as_str()) != Some("true") {
+        return Ok(None);
+    }
+    Ok(attributes.get("table:target-range-address").and_then(|address| parse_cell_range_address(address)).map(AutoFilter::new))
+}
+
+/// The database ranges are after the tables, so the auto filter is set to the sheet of its range
+fn set_auto_filter(book: &mut Book, auto_filter: Option<AutoFilter>) {
+    if let Some(auto_filter) = auto_filter {
+        let index = book.get_sheet_vec().iter().position(|sheet| sheet.get_name() == auto_filter.get_range().get_sheet_name());
+        if let Some(index) = index {
+            book.get_sheet_mut(index).set_auto_filter(Some(auto_filter));
+        }
+    }
+}
+
+/// Read the filter and the sort of a table:database-range to its auto filter, the children are skipped without the auto filter
+fn read_database_range(reader: &mut Reader<BufReader<File>>, mut auto_filter: Option<&mut AutoFilter>) -> Result<()> {
+    // the groups of the conditions being read, true for a table:filter-or
+    let mut groups: Vec<bool> = Vec::new();
+    // attributes and set items of the condition being read
+    let mut condition: Option<(HashMap<String, String>, Vec<String>)> = None;
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event(&mut buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"table:filter-and" => groups.push(false),
+                    b"table:filter-or" => groups.push(true),
+                    b"table:filter-condition" => condition = Some((read_attributes(e)?, Vec::new())),
+                    _ => {},
+                }
+            },
+            Ok(Event::Empty(ref e)) => {
+                match e.name() {
+                    b"table:filter-condition" => {
+                        if let Some(ref mut auto_filter) = auto_filter {
+                            add_filter_condition(auto_filter, &read_attributes(e)?, &[], groups.last() == Some(&true));
+                        }
+                    },
+                    b"table:filter-set-item" => {
+                        if let Some((_, ref mut items)) = condition {
+                            items.push(read_attributes(e)?.remove("table:value").unwrap_or_default());
+                        }
+                    },
+                    b"table:sort-by" => {
+                        let attributes = read_attributes(e)?;
+                        let column = attributes.get("table:field-number").and_then(|number| number.parse::<usize>().ok());
+                        let descending = attributes.get("table:order").is_some_and(|order| order == "descending");
+                        if let (Some(ref mut auto_filter), Some(column)) = (auto_filter.as_mut(), column) {
+                            auto_filter.add_sort_key(SortKey::new(column, descending));
+                        }
+                    },
+                    _ => {},
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                match e.name() {
+                    b"table:filter-and" | b"table:filter-or" => {
+                        groups.pop();
+                    },
+                    b"table:filter-condition" => {
+                        if let (Some(ref mut auto_filter), Some((attributes, items))) = (auto_filter.as_mut(), condition.take()) {
+                            add_filter_condition(auto_filter, &attributes, &items, groups.last() == Some(&true));
+                        }
+                    },
+                    b"table:database-range" => break,
+                    _ => {},
+                }
+            },
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(OdsError::Xml(e)),
+            _ => {},
+        }
+        buf.clear();
+    }
+    Ok(())
+}
+
+/// Add a table:filter-condition to the filter of its column, a comparison is added to the custom filter of the column.
+/// `or` is true for a condition in a table:filter-or.
+fn add_filter_condition(auto_filter: &mut AutoFilter, attributes: &HashMap<String, String>, items: &[String], or: bool) {
+    let column = match attributes.get("table:field-number").and_then(|number| number.parse::<usize>().ok()) {
+        Some(column) => column,
+        None => return,
+    };
+    let value = attributes.get("table:value").cloned().unwrap_or_default();
+    let top = |bottom: bool, percent: bool| Filter::Top {
+        count: value.trim().parse::<f64>().unwrap_or(10.0),
+        bottom,
+        percent,
+    };
+    let filter = match attributes.get("table:operator").map_or("=", |operator| operator.as_str()) {
+        "top values" => top(false, false),
+        "bottom values" => top(true, false),
+        "top percent" => top(false, true),
+        "bottom percent" => top(true, true),
+        "empty" => Filter::Values { values: Vec::new(), blank: true },
+        _ if !items.is_empty() => Filter::Values {
+            values: items.iter().filter(|item| !item.is_empty()).cloned().collect(),
+            blank: items.iter().any(|item| item.is_empty()),
+        },
+        operator => {
+            let comparison = match Comparison::from_symbol(operator) {
+                Some(comparison) => comparison,
+                None => return,
+            };
+            let mut conditions = match auto_filter.remove_filter(column) {
+                Some(Filter::Custom { conditions, .. }) => conditions,
+                _ => Vec::new(),
+            };
+            conditions.push((comparison, value.clone()));
+            Filter::Custom { conditions, and: !or }
+        },
+    };
+    auto_filter.set_filter(column, filter);
+}
+
+/// Rule of the value of a calcext:condition like ">100", "between(1,10)" or "formula-is($C2>0)"
+fn read_conditional_rule(value: &str, style: DifferentialStyle) -> Option<conditional_format::Rule> {
+    use conditional_format::Rule;
+    let value = value.trim();
+    match value {
+        "duplicate" => return Some(Rule::Duplicate(style)),
+        "unique" => return Some(Rule::Unique(style)),
+        _ => {},
+    }
+    if let Some(formula) = function_arguments(value, "formula-is") {
+        return Some(Rule::Expression(expression_to_formula(formula), style));
+    }
+    for &(function, bottom, percent) in [
+        ("top-elements", false, false), ("bottom-elements", true, false),
+        ("top-percent", false, true), ("bottom-percent", true, true),
+    ].iter() {
+        if let Some(rank) = function_arguments(value, function) {
+            return Some(Rule::Top {
+                rank: rank.trim().parse::<u32>().ok()?,
+                bottom,
+                percent,
+                style,
+            });
+        }
+    }
+    for &(function, name) in [("not-between", "notBetween"), ("between", "between")].iter() {
+        if let Some(arguments) = function_arguments(value, function) {
+            let arguments = split_arguments(arguments, &[',', ';']);
+            if arguments.len() != 2 {
+                return None;
+            }
+            let operator = Operator::from_name(name, expression_to_formula(&arguments[0]), Some(expression_to_formula(&arguments[1])))?;
+            return Some(Rule::CellIs(operator, style));
+        }
+    }
+    let symbols = [
+        (">=", "greaterThanOrEqual"), ("<=", "lessThanOrEqual"), ("!=", "notEqual"),
+        ("=", "equal"), (">", "greaterThan"), ("<", "lessThan"),
+    ];
+    let &(symbol, name) = symbols.iter().find(|&&(symbol, _)| value.starts_with(symbol))?;
+    let operator = Operator::from_name(name, expression_to_formula(&value[symbol.len()..]), None)?;
+    Some(Rule::CellIs(operator, style))
+}
+
+/// Attributes by their names
+fn read_attributes(e: &BytesStart) -> Result<HashMap<String, String>> {
+    let mut attributes = HashMap::new();
+    for attr in e.attributes().with_checks(false).flatten() {
+        let key = String::from_utf8_lossy(attr.key).into_owned();
+        attributes.insert(key, condvert_character_reference(&get_attribute_value(&attr)?));
+    }
+    Ok(attributes)
+}
+
+/// Areas of cells merged with the next area of the same rows and then with the area below of the same columns
+fn merge_areas(areas: Vec<(usize, usize, usize, usize)>) -> Vec<(usize, usize, usize, usize)> {
+    let mut rows: Vec<(usize, usize, usize, usize)> = Vec::new();
+    for area in areas {
+        match rows.last_mut() {
+            Some(last) if last.0 == area.0 && last.2 == area.2 && last.3 + 1 == area.1 => last.3 = area.3,
+            _ => rows.push(area),
+        }
+    }
+    let mut merged: Vec<(usize, usize, usize, usize)> = Vec::new();
+    for area in rows {
+        match merged.iter_mut().find(|above| above.1 == area.1 && above.3 == area.3 && above.2 + 1 == area.0) {
+            Some(above) => above.2 = area.2,
+            None => merged.push(area),
+        }
+    }
+    merged
+}
+
+/// Read an office:annotation until its end tag, the paragraphs are the lines of the comment
+fn read_annotation(e: &BytesStart, reader: &mut Reader<BufReader<File>>) -> Result<Comment> {
+    let mut comment = Comment::new("", "");
+    let mut width = None;
+    let mut height = None;
+    for a in e.attributes().with_checks(false) {
+        match a {
+            Ok(ref attr) if attr.key == b"office:display" => comment.set_visible(get_attribute_value(attr)? == "true"),
+            Ok(ref attr) if attr.key == b"svg:width" => width = length_to_points(&get_attribute_value(attr)?),
+            Ok(ref attr) if attr.key == b"svg:height" => height = length_to_points(&get_attribute_value(attr)?),
+            Ok(_) => {},
+            Err(_) => {},
+        }
+    }
+    comment.set_size(width, height);
+    let mut buf = Vec::new();
+    let mut author = String::new();
+    let mut text = String::new();
+    let mut creator_flag = false;
+    let mut paragraph_flag = false;
+    let mut paragraph_count: usize = 0;
+    let mut collapsed = true;
+    loop {
+        match reader.read_event(&mut buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"dc:creator" => creator_flag = true,
+                    b"text:p" => {
+                        if paragraph_count > 0 {
+                            text.push('\n');
+                        }
+                        paragraph_count += 1;
+                        paragraph_flag = true;
+                        collapsed = true;
+                    },
+                    _ => (),
+                }
+            },
+            Ok(Event::Empty(ref e)) => {
+                match e.name() {
+                    b"text:p" => {
+                        if paragraph_count > 0 {
+                            text.push('\n');
+                        }
+                        paragraph_count += 1;
+                    },
+                    b"text:s" if paragraph_flag => {
+                        text.push_str(&" ".repeat(read_repeated(e, b"text:c")?));
+                        collapsed = false;
+                    },
+                    b"text:tab" if paragraph_flag => {
+                        text.push('\t');
+                        collapsed = false;
+                    },
+                    b"text:line-break" if paragraph_flag => {
+                        text.push('\n');
+                        collapsed = true;
+                    },
+                    _ => (),
+                }
+            },
+            Ok(Event::Text(e)) => {
+                if creator_flag {
+                    author.push_str(&e.unescape_and_decode(reader)?);
+                } else if paragraph_flag {
+                    text.push_str(&collapse_white_spaces(&e.unescape_and_decode(reader)?, &mut collapsed));
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                match e.name() {
+                    b"dc:creator" => creator_flag = false,
+                    b"text:p" => paragraph_flag = false,
+                    b"office:annotation" => break,
+                    _ => (),
+                }
+            },
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(OdsError::Xml(e)),
+            _ => (),
+        }
+        buf.clear();
+    }
+    comment.set_author(author);
+    comment.set_text(text);
+    Ok(comment)
+}
+
+/// Read a draw:frame in the cell until its end tag, None for a frame without a PNG or JPEG picture in the file.
+/// The position is the offset in the cell, a frame with an end cell is a two cell anchor.
+fn read_frame(e: &BytesStart, reader: &mut Reader<BufReader<File>>, dir: &TempDir, row: usize, column: usize) -> Result<Option<Image>> {
+    let mut name = String::new();
+    let mut lengths = [0.0; 6];
+    let mut end_cell: Option<(usize, usize)> = None;
+    for attr in e.attributes().with_checks(false).flatten() {
+        let index = match attr.key {
+            b"svg:x" => 0,
+            b"svg:y" => 1,
+            b"svg:width" => 2,
+            b"svg:height" => 3,
+            b"table:end-x" => 4,
+            b"table:end-y" => 5,
+            b"draw:name" => {
+                name = condvert_character_reference(&get_attribute_value(&attr)?);
+                continue;
+            },
+            b"table:end-cell-address" => {
+                let address = condvert_character_reference(&get_attribute_value(&attr)?);
+                end_cell = address.rfind('.')
+                    .and_then(|index| column_and_row_to_index(address[index + 1..].replace('$', "")))
+                    .map(|(column, row)| (row, column));
+                continue;
+            },
+            _ => continue,
+        };
+        lengths[index] = length_to_points(&get_attribute_value(&attr)?).unwrap_or(0.0);
+    }
+    let mut buf = Vec::new();
+    let mut href: Option<String> = None;
+    let mut depth = 1;
+    loop {
+        match reader.read_event(&mut buf) {
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) if e.name() == b"draw:image" && href.is_none() => {
+                for a in e.attributes().with_checks(false) {
+                    match a {
+                        Ok(ref attr) if attr.key == b"xlink:href" => {
+                            href = Some(condvert_character_reference(&get_attribute_value(attr)?));
+                        },
+                        Ok(_) => {},
+                        Err(_) => {},
+                    }
+                }
+            },
+            Ok(Event::Start(ref e)) if e.name() == b"draw:frame" => depth += 1,
+            Ok(Event::End(ref e)) if e.name() == b"draw:frame" => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            },
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(OdsError::Xml(e)),
+            _ => (),
+        }
+        buf.clear();
+    }
+    let path = match href {
+        Some(ref href) if !href.contains(':') => dir.path().join(href.trim_start_matches("./")),
+        _ => return Ok(None),
+    };
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let from = Marker::new(row, column, lengths[0], lengths[1]);
+    let anchor = match end_cell {
+        Some((end_row, end_column)) => Anchor::TwoCell(from, Marker::new(end_row, end_column, lengths[4], lengths[5])),
+        None => Anchor::OneCell(from, lengths[2], lengths[3]),
+    };
+    let image = Image::new(name, fs::read(path)?, anchor);
+    Ok(if image.get_extension().is_some() { Some(image) } else { None })
+}
+
+/// Link of a text:a, "#Sheet2.A1" is the location "#Sheet2!A1" in the book
+fn read_hyperlink(e: &BytesStart) -> Result<Hyperlink> {
+    let mut hyperlink = Hyperlink::new("");
+    for a in e.attributes().with_checks(false) {
+        match a {
+            Ok(ref attr) if attr.key == b"xlink:href" => {
+                let href = condvert_character_reference(&get_attribute_value(attr)?);
+                let location = match href.rfind('.') {
+                    Some(index) if href.starts_with('#') && column_and_row_to_index(href[index + 1..].replace('$', "")).is_some() => {
+                        Some(format!("{}!{}", &href[..index], &href[index + 1..]))
+                    },
+                    _ => None,
+                };
+                hyperlink.set_target(location.unwrap_or(href));
+            },
+            Ok(ref attr) if attr.key == b"office:title" => {
+                hyperlink.set_tooltip(Some(condvert_character_reference(&get_attribute_value(attr)?)));
+            },
+            Ok(_) => {},
+            Err(_) => {},
+        }
+    }
+    Ok(hyperlink)
+}
+
+/// Add a text to the last run of the same font or as a new run
+fn push_text(runs: &mut Vec<TextRun>, text: &str, font: Option<Font>) {
+    if text.is_empty() {
+        return;
+    }
+    match runs.pop() {
+        Some(ref last) if last.get_font() == font.as_ref() => {
+            runs.push(TextRun::new(format!("{}{}", last.get_text(), text), font));
+        },
+        last => {
+            runs.extend(last);
+            runs.push(TextRun::new(text, font));
+        },
+    }
+}
+
+/// Text of paragraph content, a sequence of spaces, tabs and line feeds is a space
+/// and the white spaces at the start of the paragraph are ignored
+fn collapse_white_spaces(text: &str, collapsed: &mut bool) -> String {
+    let mut result = String::new();
+    for c in text.chars() {
+        if c == ' ' || c == '\t' || c == '\n' || c == '\r' {
+            if !*collapsed {
+                result.push(' ');
+                *collapsed = true;
+            }
+        } else {
+            result.push(c);
+            *collapsed = false;
+        }
+    }
+    result
+}
+
+/// Number of the repeated rows or columns of a row or a cell
+fn read_repeated(e: &BytesStart, key: &[u8]) -> Result<usize> {
+    for a in e.attributes().with_checks(false) {
+        match a {
+            Ok(ref attr) if attr.key == key => {
+                return Ok(get_attribute_value(attr)?.parse::<usize>().unwrap_or(1).max(1));
+            },
+            Ok(_) => {},
+            Err(_) => {},
+        }
+    }
+    Ok(1)
+}
+
+/// Add the cells of a row to each of the repeated rows,
+/// the rows of only styled empty cells repeated to the end of the sheet are kept once
+fn add_rows(sheet: &mut Sheet, cells: &[(usize, Cell)], row: usize, repeated: usize, keep_style: bool) {
+    let has_value = cells.iter().any(|(_, cell)| cell.get_value() != &Value::Empty);
+    let count = if has_value || repeated <= MAX_KEPT_REPEAT { repeated } else { 1 };
+    for index in 0..count {
+        if keep_style {
+            sheet.set_row_source(row + index, row + index);
+        }
+        for &(column, ref cell) in cells {
+            sheet.add_cell(cell.clone(), row + index, column);
+        }
+    }
+}
+
+// "[HH]:MM" or the hours over a day is an elapsed time, the others are times of day
+fn duration_to_value(duration: Duration, format: &Format) -> Value {
+    if format.is_elapsed() || duration < Duration::zero() || duration >= Duration::days(1) {
+        Value::Duration(duration)
+    } else {
+        Value::Time(NaiveTime::from_hms_opt(0, 0, 0).unwrap() + duration)
+    }
+}
+
+/// The writing mode of style:table-properties is right to left
+fn is_right_to_left(e: &BytesStart) -> bool {
+    e.attributes().with_checks(false).filter_map(|a| a.ok())
+        .any(|attr| attr.key == b"style:writing-mode" && &*attr.value == b"rl-tb")
+}
+
+/// Name of a style:style of the family
+fn read_style_name(e: &BytesStart, style_family: &str) -> Result<Option<String>> {
+    let mut name = None;
+    let mut family = String::from("");
+    for a in e.attributes().with_checks(false) {
+        match a {
+            Ok(ref attr) if attr.key == b"style:name" => name = Some(get_attribute_value(attr)?),
+            Ok(ref attr) if attr.key == b"style:family" => family = get_attribute_value(attr)?,
+            Ok(_) => {},
+            Err(_) => {},
+        }
+    }
+    Ok(if family == style_family { name } else { None })
+}
+
+pub fn read_text_properties(e: &BytesStart) -> Result<Font> {
+    let mut font = Font::new();
+    for attr in e.attributes().with_checks(false).flatten() {
+        let value = get_attribute_value(&attr)?;
+        match attr.key {
+            b"fo:font-family" | b"style:font-name" => {
+                font.set_name(Some(condvert_character_reference(&value).trim_matches('\'').to_string()));
+            },
+            b"fo:font-size" => font.set_size(value.trim_end_matches("pt").parse::<f64>().ok()),
+            b"fo:font-weight" => font.set_bold(value == "bold"),
+            b"fo:font-style" => font.set_italic(value == "italic"),
+            b"style:text-underline-style" => font.set_underline(value != "none"),
+            b"style:text-line-through-style" => font.set_strike(value != "none"),
+            b"fo:color" => font.set_color(Some(value.trim_start_matches('#').to_uppercase())),
+            _ => {},
+        }
+    }
+    Ok(font)
+}
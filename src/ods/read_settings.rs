@@ -0,0 +1,183 @@
+use super::quick_xml::Reader;
+use super::quick_xml::events::{Event, BytesStart};
+use super::tempdir::TempDir;
+use super::{Book, Sheet};
+use std::collections::HashMap;
+use std::io::BufRead;
+use sheet_view::{Pane, SheetView};
+use file_common::*;
+use super::Result;
+
+const SETTINGS_XML: &str = "settings.xml";
+
+/// Views of the first view of settings.xml
+struct Settings {
+    active_table: Option<String>,
+    /// view of each table by its name
+    views: HashMap<String, SheetView>,
+    /// view of a table without its items
+    default_view: SheetView,
+}
+
+impl Settings {
+    /// View of the sheet, the direction of a sheet is in its table style
+    fn view_of(&self, sheet: &Sheet) -> SheetView {
+        let mut view = self.views.get(sheet.get_name()).unwrap_or(&self.default_view).clone();
+        view.set_right_to_left(sheet.get_view().is_right_to_left());
+        view
+    }
+}
+
+/// Set the views of the sheets and the active sheet from the first view of settings.xml,
+/// a book without settings.xml or with a broken one keeps the default views
+pub fn read(dir: &TempDir, book: &mut Book) -> Result<()> {
+    let path = dir.path().join(SETTINGS_XML);
+    if !path.is_file() {
+        return Ok(());
+    }
+    let settings = match read_settings(&mut Reader::from_file(path)?)? {
+        Some(settings) => settings,
+        None => return Ok(()),
+    };
+    for index in 0..book.get_sheet_size() {
+        let view = settings.view_of(book.get_sheet(index));
+        if settings.active_table.as_ref() == Some(book.get_sheet(index).get_name()) {
+            book.set_active_sheet(index);
+        }
+        book.get_sheet_mut(index).set_view(view);
+    }
+    Ok(())
+}
+
+/// The views of the book and its active sheet are the ones of an original settings.xml
+pub fn has_views_of(data: &[u8], book: &Book) -> Result<bool> {
+    let settings = match read_settings(&mut Reader::from_reader(data))? {
+        Some(settings) => settings,
+        None => return Ok(false),
+    };
+    let active_table = book.get_sheet_vec().get(book.get_active_sheet()).map(|sheet| sheet.get_name());
+    Ok(settings.active_table.as_ref() == active_table
+        && book.get_sheet_vec().iter().all(|sheet| settings.view_of(sheet) == *sheet.get_view()))
+}
+
+fn read_settings<B: BufRead>(reader: &mut Reader<B>) -> Result<Option<Settings>> {
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+    // element names with their config:name from the root
+    let mut stack: Vec<(Vec<u8>, Option<String>)> = Vec::new();
+    let mut views = 0;
+    let mut item: Option<String> = None;
+    let mut global: HashMap<String, String> = HashMap::new();
+    let mut tables: HashMap<String, HashMap<String, String>> = HashMap::new();
+    loop {
+        match reader.read_event(&mut buf) {
+            Ok(Event::Start(ref e)) => {
+                let name = config_name(e)?;
+                if e.name() == b"config:config-item-map-entry" && is_in(&stack, b"config:config-item-map-indexed", "Views") {
+                    views += 1;
+                }
+                if e.name() == b"config:config-item" {
+                    item = name.clone();
+                }
+                stack.push((e.name().to_vec(), name));
+            },
+            Ok(Event::Text(e)) => {
+                let len = stack.len();
+                if let (1, Some(name), true) = (views, item.as_ref(), len >= 3 && is_view_settings(&stack)) {
+                    let value = match e.unescape_and_decode(reader) {
+                        Ok(value) => value,
+                        Err(_) => return Ok(None),
+                    };
+                    let parent = &stack[len - 2];
+                    if parent.0.as_slice() == b"config:config-item-map-entry" {
+                        // the items of a table are in the entry of its name in the Tables map
+                        if is_in(&stack[..len - 2], b"config:config-item-map-named", "Tables") {
+                            if let Some(ref table) = parent.1 {
+                                tables.entry(table.clone()).or_default().insert(name.clone(), value);
+                            }
+                        } else if is_in(&stack[..len - 2], b"config:config-item-map-indexed", "Views") {
+                            global.insert(name.clone(), value);
+                        }
+                    }
+                }
+            },
+            Ok(Event::End(_)) => {
+                item = None;
+                stack.pop();
+            },
+            Ok(Event::Eof) => break,
+            // the settings are only the views, a broken settings.xml keeps the default views
+            Err(_) => return Ok(None),
+            _ => (),
+        }
+        buf.clear();
+    }
+    if views == 0 {
+        return Ok(None);
+    }
+    let mut default_view = SheetView::new();
+    if let Some(show_headers) = global.get("HasColumnRowHeaders") {
+        default_view.set_show_headers(show_headers == "true");
+    }
+    Ok(Some(Settings {
+        active_table: global.get("ActiveTable").cloned(),
+        views: tables.iter().map(|(name, items)| (name.clone(), read_table_settings(items, &global))).collect(),
+        default_view,
+    }))
+}
+
+fn config_name(e: &BytesStart) -> Result<Option<String>> {
+    for a in e.attributes().with_checks(false) {
+        match a {
+            Ok(ref attr) if attr.key == b"config:name" => {
+                return Ok(Some(condvert_character_reference(&get_attribute_value(attr)?)));
+            },
+            Ok(_) => {},
+            Err(_) => {},
+        }
+    }
+    Ok(None)
+}
+
+/// The last element is the element of the name
+fn is_in(stack: &[(Vec<u8>, Option<String>)], element: &[u8], name: &str) -> bool {
+    stack.last().is_some_and(|(e, n)| e.as_slice() == element && n.as_deref() == Some(name))
+}
+
+fn is_view_settings(stack: &[(Vec<u8>, Option<String>)]) -> bool {
+    stack.iter().any(|(e, n)| e.as_slice() == b"config:config-item-set" && n.as_deref() == Some("ooo:view-settings"))
+}
+
+/// View of the items of a table, the zoom and the grid lines of the view are used when the table has none.
+/// A pane split in pixels of 96 dpi is in points.
+fn read_table_settings(items: &HashMap<String, String>, global: &HashMap<String, String>) -> SheetView {
+    let mut view = SheetView::new();
+    let number = |key: &str| items.get(key).and_then(|value| value.parse::<usize>().ok()).unwrap_or(0);
+    let flag = |key: &str| items.get(key).or(global.get(key)).map(|value| value == "true");
+    view.set_active_cell(number("CursorPositionY"), number("CursorPositionX"));
+    let (x_mode, y_mode) = (number("HorizontalSplitMode"), number("VerticalSplitMode"));
+    let (x, y) = (number("HorizontalSplitPosition"), number("VerticalSplitPosition"));
+    let (x, y) = (if x_mode == 0 { 0 } else { x }, if y_mode == 0 { 0 } else { y });
+    let pane = if x_mode == 2 || y_mode == 2 {
+        Some(Pane::Frozen {
+            rows: if y_mode == 2 { y } else { 0 },
+            columns: if x_mode == 2 { x } else { 0 },
+        })
+    } else if x > 0 || y > 0 {
+        Some(Pane::Split { x: x as f64 * 0.75, y: y as f64 * 0.75 })
+    } else {
+        None
+    };
+    view.set_pane(pane.filter(|pane| *pane != Pane::Frozen { rows: 0, columns: 0 }));
+    match items.get("ZoomValue").or(global.get("ZoomValue")).and_then(|zoom| zoom.parse::<u32>().ok()) {
+        Some(zoom) if zoom > 0 => view.set_zoom(zoom),
+        _ => {},
+    }
+    if let Some(show_grid_lines) = flag("ShowGrid") {
+        view.set_show_grid_lines(show_grid_lines);
+    }
+    if let Some(show_headers) = global.get("HasColumnRowHeaders") {
+        view.set_show_headers(show_headers == "true");
+    }
+    view
+}
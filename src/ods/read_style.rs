@@ -1,15 +1,20 @@
-use file_common::*;
 use super::quick_xml::Reader;
 use super::quick_xml::events::{Event};
 use super::tempdir::TempDir;
 use std::collections::HashMap;
+use std::io::BufRead;
+use conditional_format::DifferentialStyle;
+use file_common::*;
 use super::OdsError;
+use super::read_content::read_text_properties;
 
 const STYLES_XML: &'static str = "styles.xml";
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct StyleContent {
     pub date_style_map: HashMap<String, String>,
+    /// cell styles applied by the conditions of the conditional formats
+    pub cell_styles: HashMap<String, DifferentialStyle>,
 }
 
 pub fn read(dir: &TempDir) -> Result<StyleContent, OdsError> {
@@ -24,17 +29,10 @@ pub fn read(dir: &TempDir) -> Result<StyleContent, OdsError> {
         match reader.read_event(&mut buf) {
             Ok(Event::Start(ref e)) => {
                 match e.name() {
-                    b"number:date-style" => {
-                        for a in e.attributes().with_checks(false) {
-                            match a {
-                                Ok(ref attr) if attr.key == b"style:name" => {
-                                    date_style_map.insert(
-                                        get_attribute_value(attr)?,
-                                        super::read_number_date_style(&mut reader)?);
-                                },
-                                Ok(_) => {},
-                                Err(_) => {},
-                            }
+                    b"number:date-style" | b"number:time-style" | b"number:currency-style"
+                    | b"number:percentage-style" | b"number:number-style" => {
+                        if let Some((name, format)) = super::read_data_style(e, &mut reader, &date_style_map)? {
+                            date_style_map.insert(name, format);
                         }
                     },
                     _ => (),
@@ -49,7 +47,76 @@ pub fn read(dir: &TempDir) -> Result<StyleContent, OdsError> {
         buf.clear();
     }
 
+    let cell_styles = read_cell_style_list(&mut Reader::from_file(dir.path().join(STYLES_XML))?)?;
     Ok(StyleContent {
-        date_style_map: date_style_map
+        date_style_map,
+        cell_styles: cell_styles.into_iter().collect(),
     })
 }
+
+/// Cell styles of an original styles.xml
+pub fn read_cell_styles_of(data: &[u8]) -> Result<Vec<(String, DifferentialStyle)>, OdsError> {
+    read_cell_style_list(&mut Reader::from_reader(data))
+}
+
+/// The font and the background color of each cell style, the parent style isn't read
+fn read_cell_style_list<B: BufRead>(reader: &mut Reader<B>) -> Result<Vec<(String, DifferentialStyle)>, OdsError> {
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+    let mut styles = Vec::new();
+    let mut style: Option<(String, DifferentialStyle)> = None;
+    loop {
+        match reader.read_event(&mut buf) {
+            // a style without properties changes nothing
+            Ok(Event::Empty(ref e)) if e.name() == b"style:style" => style = None,
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => {
+                match e.name() {
+                    b"style:style" => {
+                        let mut name = None;
+                        let mut family = String::new();
+                        for a in e.attributes().with_checks(false) {
+                            match a {
+                                Ok(ref attr) if attr.key == b"style:name" => name = Some(get_attribute_value(attr)?),
+                                Ok(ref attr) if attr.key == b"style:family" => family = get_attribute_value(attr)?,
+                                Ok(_) => {},
+                                Err(_) => {},
+                            }
+                        }
+                        style = match name {
+                            Some(name) if family == "table-cell" => Some((name, DifferentialStyle::new())),
+                            _ => None,
+                        };
+                    },
+                    b"style:text-properties" => {
+                        if let Some((_, ref mut style)) = style {
+                            style.set_font(read_text_properties(e)?);
+                        }
+                    },
+                    b"style:table-cell-properties" => {
+                        if let Some((_, ref mut style)) = style {
+                            for a in e.attributes().with_checks(false) {
+                                match a {
+                                    Ok(ref attr) if attr.key == b"fo:background-color" => {
+                                        let color = get_attribute_value(attr)?;
+                                        if color.starts_with('#') {
+                                            style.set_fill_color(Some(color.trim_start_matches('#').to_uppercase()));
+                                        }
+                                    },
+                                    Ok(_) => {},
+                                    Err(_) => {},
+                                }
+                            }
+                        }
+                    },
+                    _ => (),
+                }
+            },
+            Ok(Event::End(ref e)) if e.name() == b"style:style" => styles.extend(style.take()),
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(OdsError::Xml(e)),
+            _ => (),
+        }
+        buf.clear();
+    }
+    Ok(styles)
+}
@@ -0,0 +1,151 @@
+use file_common::*;
+use std::io::Cursor;
+use std::result;
+use super::quick_xml::events::{Event, BytesDecl};
+use super::quick_xml::Writer;
+use super::tempdir::TempDir;
+use super::OdsError;
+use super::write_content::frame_size;
+use super::cell_address;
+use chart::{Axis, Chart, ChartType, LegendPosition};
+
+/// Write the chart document of an object, the series refer to the cells of the spreadsheet
+/// so the chart is drawn from the current values
+pub fn write(dir: &TempDir, object: &str, chart: &Chart) -> result::Result<(), OdsError> {
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    let _ = writer.write_event(Event::Decl(BytesDecl::new(b"1.0", Some(b"UTF-8"), None)));
+    write_text_node(&mut writer, "\n");
+    write_start_tag(&mut writer, "office:document-content", vec![
+        ("xmlns:office", "urn:oasis:names:tc:opendocument:xmlns:office:1.0"),
+        ("xmlns:style", "urn:oasis:names:tc:opendocument:xmlns:style:1.0"),
+        ("xmlns:text", "urn:oasis:names:tc:opendocument:xmlns:text:1.0"),
+        ("xmlns:table", "urn:oasis:names:tc:opendocument:xmlns:table:1.0"),
+        ("xmlns:draw", "urn:oasis:names:tc:opendocument:xmlns:drawing:1.0"),
+        ("xmlns:chart", "urn:oasis:names:tc:opendocument:xmlns:chart:1.0"),
+        ("xmlns:svg", "urn:oasis:names:tc:opendocument:xmlns:svg-compatible:1.0"),
+        ("xmlns:xlink", "http://www.w3.org/1999/xlink"),
+        ("office:version", "1.2"),
+    ], false);
+    write_start_tag(&mut writer, "office:automatic-styles", vec![], false);
+    write_axis_style(&mut writer, "ax1", chart.get_x_axis());
+    write_axis_style(&mut writer, "ax2", chart.get_y_axis());
+    write_end_tag(&mut writer, "office:automatic-styles");
+    write_start_tag(&mut writer, "office:body", vec![], false);
+    write_start_tag(&mut writer, "office:chart", vec![], false);
+    let (width, height) = frame_size(chart.get_anchor());
+    let width = format!("{}pt", width);
+    let height = format!("{}pt", height);
+    let class = match chart.get_chart_type() {
+        ChartType::Bar => "chart:bar",
+        ChartType::Line => "chart:line",
+        ChartType::Pie => "chart:circle",
+        ChartType::Scatter => "chart:scatter",
+    };
+    write_start_tag(&mut writer, "chart:chart", vec![
+        ("svg:width", width.as_str()),
+        ("svg:height", height.as_str()),
+        ("chart:class", class),
+    ], false);
+    if let Some(title) = chart.get_title() {
+        write_title(&mut writer, title);
+    }
+    if let Some(legend) = chart.get_legend() {
+        let position = match legend {
+            LegendPosition::Top => "top",
+            LegendPosition::Bottom => "bottom",
+            LegendPosition::Left => "start",
+            LegendPosition::Right => "end",
+        };
+        write_start_tag(&mut writer, "chart:legend", vec![("chart:legend-position", position)], true);
+    }
+    write_start_tag(&mut writer, "chart:plot-area", vec![], false);
+    let categories = chart.get_categories().map(cell_address);
+    if chart.get_chart_type() == ChartType::Pie {
+        // the categories of a pie chart are the labels of its slices
+        if let Some(ref categories) = categories {
+            write_start_tag(&mut writer, "chart:axis", vec![("chart:dimension", "x"), ("chart:name", "primary-x")], false);
+            write_start_tag(&mut writer, "chart:categories", vec![("table:cell-range-address", categories.as_str())], true);
+            write_end_tag(&mut writer, "chart:axis");
+        }
+    } else {
+        write_start_tag(&mut writer, "chart:axis", vec![
+            ("chart:dimension", "x"),
+            ("chart:name", "primary-x"),
+            ("chart:style-name", "ax1"),
+        ], false);
+        if let Some(title) = chart.get_x_axis().get_title() {
+            write_title(&mut writer, title);
+        }
+        // the x values of a scatter chart are the domain of each series
+        match (chart.get_chart_type(), categories.as_ref()) {
+            (ChartType::Scatter, _) | (_, None) => {},
+            (_, Some(categories)) => {
+                write_start_tag(&mut writer, "chart:categories", vec![("table:cell-range-address", categories.as_str())], true);
+            },
+        }
+        write_end_tag(&mut writer, "chart:axis");
+        write_start_tag(&mut writer, "chart:axis", vec![
+            ("chart:dimension", "y"),
+            ("chart:name", "primary-y"),
+            ("chart:style-name", "ax2"),
+        ], false);
+        if let Some(title) = chart.get_y_axis().get_title() {
+            write_title(&mut writer, title);
+        }
+        write_start_tag(&mut writer, "chart:grid", vec![("chart:class", "major")], true);
+        write_end_tag(&mut writer, "chart:axis");
+    }
+    for series in chart.get_series() {
+        let values = cell_address(series.get_values());
+        let name = series.get_name().map(cell_address);
+        let mut attributes = vec![
+            ("chart:values-cell-range-address", values.as_str()),
+            ("chart:class", class),
+        ];
+        if let Some(ref name) = name {
+            attributes.push(("chart:label-cell-address", name.as_str()));
+        }
+        let domain = match (chart.get_chart_type(), categories.as_ref()) {
+            (ChartType::Scatter, Some(categories)) => Some(categories),
+            _ => None,
+        };
+        write_start_tag(&mut writer, "chart:series", attributes, domain.is_none());
+        if let Some(domain) = domain {
+            write_start_tag(&mut writer, "chart:domain", vec![("table:cell-range-address", domain.as_str())], true);
+            write_end_tag(&mut writer, "chart:series");
+        }
+    }
+    write_end_tag(&mut writer, "chart:plot-area");
+    write_end_tag(&mut writer, "chart:chart");
+    write_end_tag(&mut writer, "office:chart");
+    write_end_tag(&mut writer, "office:body");
+    write_end_tag(&mut writer, "office:document-content");
+    make_file_from_writer(&format!("{}/content.xml", object), dir, writer, Some(object))?;
+    Ok(())
+}
+
+fn write_title(writer: &mut Writer<Cursor<Vec<u8>>>, title: &str) {
+    write_start_tag(writer, "chart:title", vec![], false);
+    for line in title.split('\n') {
+        write_start_tag(writer, "text:p", vec![], false);
+        write_text_node(writer, line);
+        write_end_tag(writer, "text:p");
+    }
+    write_end_tag(writer, "chart:title");
+}
+
+/// Style of an axis with its bounds, the bounds are automatic without them
+fn write_axis_style(writer: &mut Writer<Cursor<Vec<u8>>>, name: &str, axis: &Axis) {
+    let min = axis.get_min().map(|min| min.to_string());
+    let max = axis.get_max().map(|max| max.to_string());
+    let mut attributes = vec![("chart:display-label", "true")];
+    if let Some(ref min) = min {
+        attributes.push(("chart:minimum", min.as_str()));
+    }
+    if let Some(ref max) = max {
+        attributes.push(("chart:maximum", max.as_str()));
+    }
+    write_start_tag(writer, "style:style", vec![("style:name", name), ("style:family", "chart")], false);
+    write_start_tag(writer, "style:chart-properties", attributes, true);
+    write_end_tag(writer, "style:style");
+}
@@ -1,4 +1,19 @@
 use super::{Book,Sheet,Cell,Value};
+use chrono::prelude::*;
+use date::{self, DateSystem};
+use currency::Currency;
+use format::{self, Format, NumberToken};
+use rich_text::Font;
+use comment::Comment;
+use hyperlink::Hyperlink;
+use image::Anchor;
+use range::Range;
+use super::write_frames::{FramePaths, chart_ranges};
+use super::{absolute_cell_address, cell_address};
+use defined_name::{self, DefinedName};
+use validation::{DataValidation, ErrorStyle, Operator, Rule};
+use conditional_format::{self, DifferentialStyle, Threshold};
+use auto_filter::{AutoFilter, Filter};
 use super::tempdir::TempDir;
 use std::collections::HashMap;
 use std::result;
@@ -11,70 +26,377 @@ use file_common::*;
 use super::OdsError;
 
 const CONTENT_XML: &'static str = "content.xml";
+// default width of a column and height of a row in points
+const COLUMN_WIDTH: f64 = 64.0;
+const ROW_HEIGHT: f64 = 12.8;
 
-fn make_content_xml_none_table_cell(writer: &mut Writer<Cursor<Vec<u8>>>, none_count: i64) {
+/// Picture or chart object drawn from the cell of its top left corner
+pub struct Frame<'a> {
+    name: &'a str,
+    anchor: &'a Anchor,
+    href: &'a str,
+    sheet_name: &'a str,
+    // cell ranges a chart is drawn from, None for a picture
+    ranges: Option<String>,
+}
+
+fn make_content_xml_none_table_cell(writer: &mut Writer<Cursor<Vec<u8>>>, none_count: usize, validation: Option<&str>) {
     if none_count > 0 {
-        if none_count == 1 {
-            write_start_tag(writer, "table:table-cell", vec![], true);
-        } else {
-            write_start_tag(writer, "table:table-cell", vec![("table:number-columns-repeated", none_count.to_string().as_str())], true);
+        let none_count = none_count.to_string();
+        let mut attributes = vec![];
+        if none_count != "1" {
+            attributes.push(("table:number-columns-repeated", none_count.as_str()));
+        }
+        if let Some(validation) = validation {
+            attributes.push(("table:content-validation-name", validation));
         }
+        write_start_tag(writer, "table:table-cell", attributes, true);
     }
 }
 
-fn make_content_xml_table_cell(writer: &mut Writer<Cursor<Vec<u8>>>, cell: &Cell, date_hash: &HashMap<String, String>) {
+pub fn make_content_xml_table_cell(writer: &mut Writer<Cursor<Vec<u8>>>, cell: &Cell, style_name: Option<&String>, text_styles: &[(Font, String)], timezone: FixedOffset, frames: &[Frame], validation: Option<&str>) {
+    let mut attributes = vec![];
+    if let Some(style_name) = style_name {
+        attributes.push(("table:style-name", style_name.as_str()));
+    }
+    if let Some(validation) = validation {
+        attributes.push(("table:content-validation-name", validation));
+    }
     match cell.get_value() {
         &Value::Str(ref value) => {
-            write_start_tag(writer, "table:table-cell", vec![
-                ("office:value-type", "string"),
-                ("calcext:value-type", "string")], false);
-            write_start_tag(writer, "text:p", vec![], false);
-            write_text_node(writer, value.to_string());
+            attributes.push(("office:value-type", "string"));
+            attributes.push(("calcext:value-type", "string"));
+            start_table_cell(writer, attributes, cell.get_comment(), frames);
+            make_paragraphs(writer, &vec![(value.as_str(), None)], cell.get_hyperlink());
+        },
+        Value::RichText(runs) => {
+            attributes.push(("office:value-type", "string"));
+            attributes.push(("calcext:value-type", "string"));
+            start_table_cell(writer, attributes, cell.get_comment(), frames);
+            let runs = runs.iter().map(|run| {
+                let text_style = run.get_font()
+                    .and_then(|font| text_styles.iter().find(|&(f, _)| f == font));
+                (run.get_text().as_str(), text_style.map(|(_, name)| name.as_str()))
+            }).collect();
+            make_paragraphs(writer, &runs, cell.get_hyperlink());
         },
         &Value::Float(ref value) => {
-            write_start_tag(writer, "table:table-cell", vec![
-                ("office:value-type", "float"),
-                ("office:value", value.to_string().as_str()),
-                ("calcext:value-type", "float")], false);
-            write_start_tag(writer, "text:p", vec![], false);
-            write_text_node(writer, value.to_string());
+            let value_str = value.to_string();
+            let value_type = if data_style_element(cell) == "number:percentage-style" { "percentage" } else { "float" };
+            attributes.push(("office:value-type", value_type));
+            attributes.push(("office:value", value_str.as_str()));
+            attributes.push(("calcext:value-type", value_type));
+            start_table_cell(writer, attributes, cell.get_comment(), frames);
+            start_paragraph(writer, cell.get_hyperlink());
+            write_text_node(writer, cell.get_formated_value().unwrap_or(value_str.clone()));
         },
         &Value::Date(ref value) => {
-            let formater = cell.get_format().get_content();
-            write_start_tag(writer, "table:table-cell", vec![
-                ("table:style-name", date_hash.get(formater).unwrap().as_str()),
-                ("office:value-type", "date"),
-                ("office:date-value", value.format("%Y-%m-%dT%H:%M:%S").to_string().as_str()),
-                ("calcext:value-type", "date")
-            ], false);
-            write_start_tag(writer, "text:p", vec![], false);
-            write_text_node(writer, cell.get_formated_value().unwrap());
+            // the wall-clock time in the timezone of the book
+            let value = &DateTime::<Utc>::from_naive_utc_and_offset(value.with_timezone(&timezone).naive_local(), Utc);
+            let value_str = value.format("%Y-%m-%dT%H:%M:%S").to_string();
+            attributes.push(("office:value-type", "date"));
+            attributes.push(("office:date-value", value_str.as_str()));
+            attributes.push(("calcext:value-type", "date"));
+            start_table_cell(writer, attributes, cell.get_comment(), frames);
+            start_paragraph(writer, cell.get_hyperlink());
+            write_text_node(writer, cell.get_format().get_formated_date(value).unwrap_or(value.format("%Y-%m-%d").to_string()));
+        },
+        Value::DateOnly(value) => {
+            let value_str = value.format("%Y-%m-%d").to_string();
+            attributes.push(("office:value-type", "date"));
+            attributes.push(("office:date-value", value_str.as_str()));
+            attributes.push(("calcext:value-type", "date"));
+            start_table_cell(writer, attributes, cell.get_comment(), frames);
+            start_paragraph(writer, cell.get_hyperlink());
+            write_text_node(writer, cell.get_formated_value().unwrap_or(value_str.clone()));
+        },
+        Value::Time(value) => {
+            let value_str = value.format("PT%HH%MM%SS").to_string();
+            attributes.push(("office:value-type", "time"));
+            attributes.push(("office:time-value", value_str.as_str()));
+            attributes.push(("calcext:value-type", "time"));
+            start_table_cell(writer, attributes, cell.get_comment(), frames);
+            start_paragraph(writer, cell.get_hyperlink());
+            write_text_node(writer, cell.get_formated_value().unwrap_or(value.format("%H:%M:%S").to_string()));
+        },
+        Value::Duration(value) => {
+            let value_str = date::duration_to_iso8601(value);
+            attributes.push(("office:value-type", "time"));
+            attributes.push(("office:time-value", value_str.as_str()));
+            attributes.push(("calcext:value-type", "time"));
+            start_table_cell(writer, attributes, cell.get_comment(), frames);
+            start_paragraph(writer, cell.get_hyperlink());
+            write_text_node(writer, cell.get_formated_value()
+                .or_else(|| Format::new("[HH]:MM:SS").get_formated_duration(value))
+                .unwrap_or_default());
         },
         &Value::Currency(ref value) => {
-            let formater = cell.get_format().get_content();
-            write_start_tag(writer, "table:table-cell", vec![
-                ("table:style-name", date_hash.get(formater).unwrap().as_str()),
-                ("office:value-type", "currency"),
-                ("office:date-value", value.to_string().as_str()),
-                ("calcext:value-type", "currency")
-            ], false);
-            write_start_tag(writer, "text:p", vec![], false);
-            write_text_node(writer, cell.get_formated_value().unwrap());
+            let value_str = value.get_amount().to_string();
+            attributes.push(("office:value-type", "currency"));
+            attributes.push(("office:currency", value.get_code().as_str()));
+            attributes.push(("office:value", value_str.as_str()));
+            attributes.push(("calcext:value-type", "currency"));
+            start_table_cell(writer, attributes, cell.get_comment(), frames);
+            start_paragraph(writer, cell.get_hyperlink());
+            write_text_node(writer, cell.get_formated_value().unwrap_or(value_str.clone()));
+        },
+        Value::Bool(value) => {
+            let value_str = value.to_string();
+            attributes.push(("office:value-type", "boolean"));
+            attributes.push(("office:boolean-value", value_str.as_str()));
+            attributes.push(("calcext:value-type", "boolean"));
+            start_table_cell(writer, attributes, cell.get_comment(), frames);
+            start_paragraph(writer, cell.get_hyperlink());
+            write_text_node(writer, if *value { "TRUE" } else { "FALSE" });
+        },
+        Value::Error(value) => {
+            attributes.push(("office:value-type", "string"));
+            attributes.push(("calcext:value-type", "error"));
+            start_table_cell(writer, attributes, cell.get_comment(), frames);
+            start_paragraph(writer, cell.get_hyperlink());
+            write_text_node(writer, value.as_str());
+        },
+        &Value::Empty => {
+            if cell.get_comment().is_none() && cell.get_hyperlink().is_none() && frames.is_empty() {
+                write_start_tag(writer, "table:table-cell", attributes, true);
+                return;
+            }
+            start_table_cell(writer, attributes, cell.get_comment(), frames);
+            if cell.get_hyperlink().is_none() {
+                write_end_tag(writer, "table:table-cell");
+                return;
+            }
+            start_paragraph(writer, cell.get_hyperlink());
         },
     }
-    write_end_tag(writer, "text:p");
+    end_paragraph(writer, cell.get_hyperlink());
     write_end_tag(writer, "table:table-cell");
 }
 
-fn make_content_xml_by_sheet(writer: &mut Writer<Cursor<Vec<u8>>>, sheet: &Sheet, date_hash: &HashMap<String, String>) {
-    write_start_tag(writer, "table:table", vec![("table:name", sheet.get_name().as_str()),("table:style-name", "ta1"),], false);
+fn start_table_cell(writer: &mut Writer<Cursor<Vec<u8>>>, attributes: Vec<(&str, &str)>, comment: Option<&Comment>, frames: &[Frame]) {
+    write_start_tag(writer, "table:table-cell", attributes, false);
+    if let Some(comment) = comment {
+        make_annotation(writer, comment);
+    }
+    for frame in frames {
+        make_frame(writer, frame);
+    }
+}
+
+/// Write a picture or a chart anchored to the cell, the position is the offset in the cell.
+fn make_frame(writer: &mut Writer<Cursor<Vec<u8>>>, frame: &Frame) {
+    let from = frame.anchor.get_from();
+    let (width, height) = frame_size(frame.anchor);
+    let x = format!("{}pt", from.get_offset_x());
+    let y = format!("{}pt", from.get_offset_y());
+    let width = format!("{}pt", width);
+    let height = format!("{}pt", height);
+    let mut attributes = vec![
+        ("draw:name", frame.name),
+        ("svg:width", width.as_str()),
+        ("svg:height", height.as_str()),
+        ("svg:x", x.as_str()),
+        ("svg:y", y.as_str()),
+    ];
+    let end = match *frame.anchor {
+        Anchor::TwoCell(_, ref to) => Some((
+            cell_address(&Range::cell(frame.sheet_name, to.get_row(), to.get_column())),
+            format!("{}pt", to.get_offset_x()),
+            format!("{}pt", to.get_offset_y()),
+        )),
+        Anchor::OneCell(..) => None,
+    };
+    if let Some((ref address, ref end_x, ref end_y)) = end {
+        attributes.push(("table:end-cell-address", address.as_str()));
+        attributes.push(("table:end-x", end_x.as_str()));
+        attributes.push(("table:end-y", end_y.as_str()));
+    }
+    write_start_tag(writer, "draw:frame", attributes, false);
+    let link = vec![
+        ("xlink:href", frame.href),
+        ("xlink:type", "simple"),
+        ("xlink:show", "embed"),
+        ("xlink:actuate", "onLoad"),
+    ];
+    match frame.ranges {
+        Some(ref ranges) => {
+            let mut attributes = vec![("draw:notify-on-update-of-ranges", ranges.as_str())];
+            attributes.extend(link);
+            write_start_tag(writer, "draw:object", attributes, true);
+        },
+        None => write_start_tag(writer, "draw:image", link, true),
+    }
+    write_end_tag(writer, "draw:frame");
+}
+
+/// (width, height) in points rounded to 2 decimals,
+/// the size of a two cell anchor is made with the default column width and row height.
+pub fn frame_size(anchor: &Anchor) -> (f64, f64) {
+    let (width, height) = match *anchor {
+        Anchor::OneCell(_, width, height) => (width, height),
+        Anchor::TwoCell(ref from, ref to) => (
+            (to.get_column() as f64 - from.get_column() as f64) * COLUMN_WIDTH + to.get_offset_x() - from.get_offset_x(),
+            (to.get_row() as f64 - from.get_row() as f64) * ROW_HEIGHT + to.get_offset_y() - from.get_offset_y(),
+        ),
+    };
+    ((width.max(0.0) * 100.0).round() / 100.0, (height.max(0.0) * 100.0).round() / 100.0)
+}
+
+/// Largest column of the cells, the anchors and the ranges of the validations
+pub fn max_column_index(sheet: &Sheet) -> Option<usize> {
+    let validation_columns = sheet.get_validations().iter()
+        .flat_map(|validation| validation.get_ranges().iter().map(|range| range.get_last_column()));
+    max_index(sheet).map(|indexes| indexes.1).into_iter().chain(validation_columns).max()
+}
 
-    let indexes = sheet.get_max_index();
-    match indexes {
-        Some(indexes) => {
+/// Largest (row, column) of the cells and the cells the pictures and the charts are anchored to
+pub fn max_index(sheet: &Sheet) -> Option<(usize, usize)> {
+    let mut indexes = sheet.get_max_index();
+    let anchors = sheet.get_images().iter().map(|image| image.get_anchor())
+        .chain(sheet.get_charts().iter().map(|chart| chart.get_anchor()));
+    for anchor in anchors {
+        let from = anchor.get_from();
+        indexes = Some(match indexes {
+            Some((row, column)) => (row.max(from.get_row()), column.max(from.get_column())),
+            None => (from.get_row(), from.get_column()),
+        });
+    }
+    indexes
+}
+
+/// Write the comment of a cell, the annotation comes before the paragraphs of the cell
+fn make_annotation(writer: &mut Writer<Cursor<Vec<u8>>>, comment: &Comment) {
+    let width = comment.get_width().map(|width| format!("{}pt", width));
+    let height = comment.get_height().map(|height| format!("{}pt", height));
+    let mut attributes = vec![("office:display", if comment.is_visible() { "true" } else { "false" })];
+    if let Some(ref width) = width {
+        attributes.push(("svg:width", width.as_str()));
+    }
+    if let Some(ref height) = height {
+        attributes.push(("svg:height", height.as_str()));
+    }
+    write_start_tag(writer, "office:annotation", attributes, false);
+    write_start_tag(writer, "dc:creator", vec![], false);
+    write_text_node(writer, comment.get_author().as_str());
+    write_end_tag(writer, "dc:creator");
+    make_paragraphs(writer, &vec![(comment.get_text().as_str(), None)], None);
+    write_end_tag(writer, "text:p");
+    write_end_tag(writer, "office:annotation");
+}
+
+/// Start a paragraph of a cell, the text of a linked cell is in text:a
+fn start_paragraph(writer: &mut Writer<Cursor<Vec<u8>>>, hyperlink: Option<&Hyperlink>) {
+    write_start_tag(writer, "text:p", vec![], false);
+    if let Some(hyperlink) = hyperlink {
+        let href = hyperlink_href(hyperlink);
+        let mut attributes = vec![("xlink:href", href.as_str()), ("xlink:type", "simple")];
+        if let Some(tooltip) = hyperlink.get_tooltip() {
+            attributes.push(("office:title", tooltip.as_str()));
+        }
+        write_start_tag(writer, "text:a", attributes, false);
+    }
+}
+
+fn end_paragraph(writer: &mut Writer<Cursor<Vec<u8>>>, hyperlink: Option<&Hyperlink>) {
+    if hyperlink.is_some() {
+        write_end_tag(writer, "text:a");
+    }
+    write_end_tag(writer, "text:p");
+}
+
+/// A location in the book is "#Sheet2.A1" in ods and "#Sheet2!A1" in the model
+fn hyperlink_href(hyperlink: &Hyperlink) -> String {
+    match hyperlink.get_location().and_then(|location| location.rfind('!').map(|index| (location, index))) {
+        Some((location, index)) => format!("#{}.{}", &location[..index], &location[index + 1..]),
+        None => hyperlink.get_target().clone(),
+    }
+}
+
+/// Write the lines of a text as the paragraphs of a cell, each run in the span of its text style.
+/// The text of each paragraph of a linked cell is in its own text:a, the last text:p is left open.
+fn make_paragraphs(writer: &mut Writer<Cursor<Vec<u8>>>, runs: &Vec<(&str, Option<&str>)>, hyperlink: Option<&Hyperlink>) {
+    start_paragraph(writer, hyperlink);
+    // at the start of the paragraph or after a space
+    let mut after_space = true;
+    for &(text, style_name) in runs {
+        for (index, line) in text.split('\n').enumerate() {
+            if index > 0 {
+                end_paragraph(writer, hyperlink);
+                start_paragraph(writer, hyperlink);
+                after_space = true;
+            }
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(name) = style_name {
+                write_start_tag(writer, "text:span", vec![("text:style-name", name)], false);
+            }
+            make_paragraph_text(writer, line, &mut after_space);
+            if style_name.is_some() {
+                write_end_tag(writer, "text:span");
+            }
+        }
+    }
+}
+
+/// Write a text of a paragraph, the spaces which would be collapsed are text:s and the tabs are text:tab
+fn make_paragraph_text(writer: &mut Writer<Cursor<Vec<u8>>>, text: &str, after_space: &mut bool) {
+    let chars: Vec<char> = text.chars().collect();
+    let mut plain = String::new();
+    let mut index = 0;
+    while index < chars.len() {
+        match chars[index] {
+            ' ' => {
+                let mut count = chars[index..].iter().take_while(|&&c| c == ' ').count();
+                index += count;
+                if !*after_space {
+                    plain.push(' ');
+                    count -= 1;
+                }
+                *after_space = true;
+                if count == 0 {
+                    continue;
+                }
+                write_text_node(writer, plain.as_str());
+                plain.clear();
+                if count == 1 {
+                    write_start_tag(writer, "text:s", vec![], true);
+                } else {
+                    write_start_tag(writer, "text:s", vec![("text:c", count.to_string().as_str())], true);
+                }
+            },
+            '\t' => {
+                index += 1;
+                write_text_node(writer, plain.as_str());
+                plain.clear();
+                write_start_tag(writer, "text:tab", vec![], true);
+                *after_space = false;
+            },
+            '\r' => index += 1,
+            c => {
+                index += 1;
+                plain.push(c);
+                *after_space = false;
+            },
+        }
+    }
+    write_text_node(writer, plain.as_str());
+}
+
+fn make_content_xml_by_sheet(writer: &mut Writer<Cursor<Vec<u8>>>, sheet: &Sheet, refs: &SheetRefs, date_hash: &HashMap<String, String>, text_styles: &[(Font, String)], style_names: &[(DifferentialStyle, String)], timezone: FixedOffset) {
+    let print_ranges = print_ranges(sheet);
+    let table_style = if sheet.get_view().is_right_to_left() { "ta2" } else { "ta1" };
+    let mut attributes = vec![("table:name", sheet.get_name().as_str()),("table:style-name", table_style),];
+    if let Some(ref print_ranges) = print_ranges {
+        attributes.push(("table:print-ranges", print_ranges.as_str()));
+    }
+    write_start_tag(writer, "table:table", attributes, false);
+
+    match max_column_index(sheet) {
+        Some(max_column_index) => {
             write_start_tag(writer, "table:table-column", vec![
                 ("table:style-name", "co1"),
-                ("table:number-columns-repeated", (indexes.1 + 1).to_string().as_str()),
+                ("table:number-columns-repeated", (max_column_index + 1).to_string().as_str()),
                 ("table:default-cell-style-name", "Default")
             ], true);
         },
@@ -86,60 +408,577 @@ fn make_content_xml_by_sheet(writer: &mut Writer<Cursor<Vec<u8>>>, sheet: &Sheet
         }
     };
 
-    match indexes {
-        Some(indexes) => {
-            for row_index in 0..indexes.0 + 1 {
-                match sheet.get_rows().get(&row_index) {
-                    Some(columns) => {
-                        write_start_tag(writer, "table:table-row", vec![("table:style-name", "ro1"),], false);
-                        let mut max_column_index = 0;
-                        for (column_index, _) in columns.iter() {
-                            if *column_index > max_column_index {
-                                max_column_index = *column_index;
-                            }
-                        }
-                        let mut none_count = 0;
-                        for column_index in 0..max_column_index+1 {
-                            match columns.get(&column_index) {
-                                Some(cell) => {
-                                    let _ = make_content_xml_none_table_cell(writer, none_count);
-                                    none_count = 0;
-                                    let _ = make_content_xml_table_cell(writer, cell, date_hash);
-                                },
-                                None => {
-                                    none_count = none_count + 1;
-                                }
-                            }
-                        }
-                        let _ = make_content_xml_none_table_cell(writer, none_count);
-                        write_end_tag(writer, "table:table-row");
-                    },
-                    None => {
-                        // some row not found
-                        write_start_tag(writer, "table:table-row", vec![
-                            ("table:style-name", "ro1")
-                        ], false);
-                        write_start_tag(writer, "table:table-cell", vec![
-                            ("table:number-columns-repeated", (indexes.1 + 1).to_string().as_str())
-                        ], true);
-                        write_end_tag(writer, "table:table-row");
-                    }
+    make_content_xml_rows(writer, sheet, refs, |writer, _| {
+        write_start_tag(writer, "table:table-row", vec![("table:style-name", "ro1"),], false);
+    }, |cell| {
+        if has_data_style(cell) {
+            date_hash.get(cell.get_format().get_content()).cloned()
+        } else {
+            None
+        }
+    }, text_styles, timezone);
+    if has_named_expressions(sheet.get_defined_names()) {
+        make_named_expressions(writer, sheet.get_defined_names());
+    }
+    if !sheet.get_conditional_formats().is_empty() {
+        make_conditional_formats(writer, sheet, style_names);
+    }
+    write_end_tag(writer, "table:table");
+}
+
+/// The print area of the sheet, the areas are separated by spaces
+pub fn print_ranges(sheet: &Sheet) -> Option<String> {
+    let ranges = sheet.get_defined_name(defined_name::PRINT_AREA)?.get_ranges();
+    if ranges.is_empty() {
+        return None;
+    }
+    Some(ranges.iter().map(absolute_cell_address).collect::<Vec<String>>().join(" "))
+}
+
+/// The built-in names like the print area aren't named expressions
+pub fn has_named_expressions(names: &[DefinedName]) -> bool {
+    names.iter().any(|name| !name.get_name().starts_with("_xlnm."))
+}
+
+/// Write the names, a name of one area is a named range and the other names are named expressions.
+/// The areas of a name are joined with the union operator, a constant or a formula is written as is.
+pub fn make_named_expressions(writer: &mut Writer<Cursor<Vec<u8>>>, names: &[DefinedName]) {
+    write_start_tag(writer, "table:named-expressions", vec![], false);
+    for defined_name in names.iter().filter(|name| !name.get_name().starts_with("_xlnm.")) {
+        let ranges = defined_name.get_ranges();
+        let base = ranges.first().map(|range| {
+            absolute_cell_address(&Range::cell(range.get_sheet_name().as_str(), range.get_first_row(), range.get_first_column()))
+        });
+        let mut attributes = vec![("table:name", defined_name.get_name().as_str())];
+        if let Some(ref base) = base {
+            attributes.push(("table:base-cell-address", base.as_str()));
+        }
+        if ranges.len() == 1 {
+            let address = absolute_cell_address(&ranges[0]);
+            attributes.push(("table:cell-range-address", address.as_str()));
+            write_start_tag(writer, "table:named-range", attributes, true);
+        } else {
+            let expression = if ranges.is_empty() {
+                format!("of:={}", defined_name.get_formula())
+            } else {
+                let areas: Vec<String> = ranges.iter().map(|range| format!("[{}]", absolute_cell_address(range))).collect();
+                format!("of:={}", areas.join("~"))
+            };
+            attributes.push(("table:expression", expression.as_str()));
+            write_start_tag(writer, "table:named-expression", attributes, true);
+        }
+    }
+    write_end_tag(writer, "table:named-expressions");
+}
+
+/// Name of the database range of the auto filter of a sheet
+pub const ANONYMOUS_DATABASE: &str = "__Anonymous_Sheet_DB__";
+
+pub fn has_database_ranges(book: &Book) -> bool {
+    book.get_sheet_vec().iter().any(|sheet| sheet.get_auto_filter().is_some())
+}
+
+/// Write the auto filters of the sheets as the anonymous database ranges of the sheets after the `kept` database ranges
+pub fn make_database_ranges(writer: &mut Writer<Cursor<Vec<u8>>>, book: &Book, kept: &[RawElement]) {
+    write_start_tag(writer, "table:database-ranges", vec![], false);
+    for range in kept {
+        write_raw(writer, &range.data);
+    }
+    for (index, sheet) in book.get_sheet_vec().iter().enumerate() {
+        if let Some(auto_filter) = sheet.get_auto_filter() {
+            make_database_range(writer, auto_filter, &format!("{}{}", ANONYMOUS_DATABASE, index));
+        }
+    }
+    write_end_tag(writer, "table:database-ranges");
+}
+
+fn make_database_range(writer: &mut Writer<Cursor<Vec<u8>>>, auto_filter: &AutoFilter, name: &str) {
+    let address = cell_address(auto_filter.get_range());
+    let empty = auto_filter.get_filters().is_empty() && auto_filter.get_sort_keys().is_empty();
+    write_start_tag(writer, "table:database-range", vec![
+        ("table:name", name),
+        ("table:target-range-address", address.as_str()),
+        ("table:display-filter-buttons", "true"),
+    ], empty);
+    if empty {
+        return;
+    }
+    if !auto_filter.get_filters().is_empty() {
+        make_filter(writer, auto_filter);
+    }
+    if !auto_filter.get_sort_keys().is_empty() {
+        write_start_tag(writer, "table:sort", vec![], false);
+        for key in auto_filter.get_sort_keys() {
+            write_start_tag(writer, "table:sort-by", vec![
+                ("table:field-number", key.get_column().to_string().as_str()),
+                ("table:data-type", "automatic"),
+                ("table:order", if key.is_descending() { "descending" } else { "ascending" }),
+            ], true);
+        }
+        write_end_tag(writer, "table:sort");
+    }
+    write_end_tag(writer, "table:database-range");
+}
+
+/// Condition of a column of a table:filter
+struct FilterCondition {
+    field: usize,
+    operator: &'static str,
+    value: String,
+    data_type: &'static str,
+    /// values of a list of values
+    items: Vec<String>,
+}
+
+/// The filters of the columns are all needed, the conditions of a custom filter without `and` are in a table:filter-or.
+/// A list of values is a condition with the values as its set items, an empty item for the blank cells.
+fn make_filter(writer: &mut Writer<Cursor<Vec<u8>>>, auto_filter: &AutoFilter) {
+    // conditions of each column and whether one of them is enough
+    let mut columns: Vec<(Vec<FilterCondition>, bool)> = Vec::new();
+    for (&column, filter) in auto_filter.get_filters() {
+        let condition = |operator: &'static str, value: String, data_type: &'static str, items: Vec<String>| {
+            FilterCondition { field: column, operator, value, data_type, items }
+        };
+        let (conditions, or) = match *filter {
+            Filter::Values { ref values, blank } => {
+                let mut items = values.clone();
+                if blank {
+                    items.push(String::new());
                 }
+                (vec![condition("=", items.first().cloned().unwrap_or_default(), "text", items)], false)
+            },
+            Filter::Custom { ref conditions, and } => {
+                let conditions = conditions.iter().map(|&(comparison, ref value)| {
+                    let data_type = if value.trim().parse::<f64>().is_ok() { "number" } else { "text" };
+                    condition(comparison.symbol(), value.clone(), data_type, Vec::new())
+                }).collect();
+                (conditions, !and)
+            },
+            Filter::Top { count, bottom, percent } => {
+                let operator = match (bottom, percent) {
+                    (false, false) => "top values",
+                    (true, false) => "bottom values",
+                    (false, true) => "top percent",
+                    (true, true) => "bottom percent",
+                };
+                (vec![condition(operator, count.to_string(), "number", Vec::new())], false)
+            },
+        };
+        if !conditions.is_empty() {
+            columns.push((conditions, or));
+        }
+    }
+    write_start_tag(writer, "table:filter", vec![], false);
+    match *columns.as_slice() {
+        [(ref conditions, or)] if conditions.len() > 1 => {
+            make_filter_conditions(writer, conditions, Some(if or { "table:filter-or" } else { "table:filter-and" }));
+        },
+        [(ref conditions, _)] => make_filter_conditions(writer, conditions, None),
+        _ => {
+            write_start_tag(writer, "table:filter-and", vec![], false);
+            for &(ref conditions, or) in &columns {
+                make_filter_conditions(writer, conditions, if or && conditions.len() > 1 { Some("table:filter-or") } else { None });
             }
+            write_end_tag(writer, "table:filter-and");
         },
-        None => {
-            // all row not found
-            write_start_tag(writer, "table:table-row", vec![
-                ("table:style-name", "ro1")
-            ], false);
-            write_start_tag(writer, "table:table-cell", vec![], true);
-            write_end_tag(writer, "table:table-row");
+    }
+    write_end_tag(writer, "table:filter");
+}
+
+/// Write the conditions in the `group` element
+fn make_filter_conditions(writer: &mut Writer<Cursor<Vec<u8>>>, conditions: &[FilterCondition], group: Option<&str>) {
+    if let Some(group) = group {
+        write_start_tag(writer, group, vec![], false);
+    }
+    for condition in conditions {
+        write_start_tag(writer, "table:filter-condition", vec![
+            ("table:field-number", condition.field.to_string().as_str()),
+            ("table:value", condition.value.as_str()),
+            ("table:operator", condition.operator),
+            ("table:data-type", condition.data_type),
+        ], condition.items.is_empty());
+        if !condition.items.is_empty() {
+            for item in &condition.items {
+                write_start_tag(writer, "table:filter-set-item", vec![("table:value", item.as_str())], true);
+            }
+            write_end_tag(writer, "table:filter-condition");
+        }
+    }
+    if let Some(group) = group {
+        write_end_tag(writer, group);
+    }
+}
+
+/// Names of the validations of each sheet, the names are numbered through the book
+pub fn validation_names(book: &Book) -> Vec<Vec<String>> {
+    let mut count = 0;
+    book.get_sheet_vec().iter().map(|sheet| {
+        sheet.get_validations().iter().map(|_| {
+            count += 1;
+            format!("val{}", count)
+        }).collect()
+    }).collect()
+}
+
+pub fn has_content_validations(book: &Book) -> bool {
+    book.get_sheet_vec().iter().any(|sheet| !sheet.get_validations().is_empty())
+}
+
+/// Write the validations of the sheets, the cells refer to them by name.
+/// The base cell is the first cell of the validation and an alert is always shown for an invalid value.
+pub fn make_content_validations(writer: &mut Writer<Cursor<Vec<u8>>>, book: &Book, names: &[Vec<String>]) {
+    write_start_tag(writer, "table:content-validations", vec![], false);
+    for (sheet, names) in book.get_sheet_vec().iter().zip(names.iter()) {
+        for (validation, name) in sheet.get_validations().iter().zip(names.iter()) {
+            make_content_validation(writer, sheet, validation, name);
+        }
+    }
+    write_end_tag(writer, "table:content-validations");
+}
+
+fn make_content_validation(writer: &mut Writer<Cursor<Vec<u8>>>, sheet: &Sheet, validation: &DataValidation, name: &str) {
+    let condition = validation_condition(validation.get_rule());
+    let base_cell = validation.get_ranges().first()
+        .map(|range| cell_address(&Range::cell(sheet.get_name().as_str(), range.get_first_row(), range.get_first_column())))
+        .unwrap_or_default();
+    let mut attributes = vec![
+        ("table:name", name),
+        ("table:condition", condition.as_str()),
+        ("table:allow-empty-cell", if validation.is_allow_blank() { "true" } else { "false" }),
+        ("table:base-cell-address", base_cell.as_str()),
+    ];
+    match *validation.get_rule() {
+        Rule::List(_) | Rule::ListRange(_) => {
+            attributes.push(("table:display-list", if validation.is_drop_down() { "unsorted" } else { "none" }));
+        },
+        _ => {},
+    }
+    write_start_tag(writer, "table:content-validation", attributes, false);
+    if let Some(prompt) = validation.get_prompt() {
+        write_start_tag(writer, "table:help-message", vec![("table:title", prompt.get_title().as_str()), ("table:display", "true")], false);
+        make_paragraphs(writer, &vec![(prompt.get_text().as_str(), None)], None);
+        write_end_tag(writer, "text:p");
+        write_end_tag(writer, "table:help-message");
+    }
+    let message_type = match validation.get_error_style() {
+        ErrorStyle::Stop => "stop",
+        ErrorStyle::Warning => "warning",
+        ErrorStyle::Information => "information",
+    };
+    let mut attributes = vec![("table:message-type", message_type), ("table:display", "true")];
+    match validation.get_error() {
+        Some(error) => {
+            attributes.push(("table:title", error.get_title().as_str()));
+            write_start_tag(writer, "table:error-message", attributes, false);
+            make_paragraphs(writer, &vec![(error.get_text().as_str(), None)], None);
+            write_end_tag(writer, "text:p");
+            write_end_tag(writer, "table:error-message");
+        },
+        None => write_start_tag(writer, "table:error-message", attributes, true),
+    }
+    write_end_tag(writer, "table:content-validation");
+}
+
+/// Condition of a rule like "of:cell-content-is-whole-number() and cell-content-is-between(1;10)",
+/// a date is its serial and a custom formula is written as it is
+fn validation_condition(rule: &Rule) -> String {
+    fn operator_condition<T, F>(operator: &Operator<T>, function: &str, value: F) -> String
+        where F: Fn(&T) -> String
+    {
+        let (bound, upper) = operator.bounds();
+        let upper = upper.map(&value).unwrap_or_default();
+        let symbol = match *operator {
+            Operator::Between(..) => return format!("{}-is-between({};{})", function, value(bound), upper),
+            Operator::NotBetween(..) => return format!("{}-is-not-between({};{})", function, value(bound), upper),
+            Operator::Equal(_) => "=",
+            Operator::NotEqual(_) => "!=",
+            Operator::GreaterThan(_) => ">",
+            Operator::LessThan(_) => "<",
+            Operator::GreaterThanOrEqual(_) => ">=",
+            Operator::LessThanOrEqual(_) => "<=",
+        };
+        format!("{}(){}{}", function, symbol, value(bound))
+    }
+    match *rule {
+        Rule::List(ref items) => {
+            let items: Vec<String> = items.iter().map(|item| format!("\"{}\"", item.replace('"', "\"\""))).collect();
+            format!("of:cell-content-is-in-list({})", items.join(";"))
+        },
+        Rule::ListRange(ref range) => format!("of:cell-content-is-in-list([{}])", absolute_cell_address(range)),
+        Rule::WholeNumber(ref operator) => {
+            format!("of:cell-content-is-whole-number() and {}", operator_condition(operator, "cell-content", |value| value.to_string()))
+        },
+        Rule::Decimal(ref operator) => {
+            format!("of:cell-content-is-decimal-number() and {}", operator_condition(operator, "cell-content", |value| value.to_string()))
+        },
+        Rule::Date(ref operator) => {
+            let condition = operator_condition(operator, "cell-content", |value| {
+                date::datetime_to_serial(&value.and_hms_opt(0, 0, 0).unwrap(), DateSystem::Date1900).to_string()
+            });
+            format!("of:cell-content-is-date() and {}", condition)
+        },
+        Rule::TextLength(ref operator) => {
+            format!("of:{}", operator_condition(operator, "cell-content-text-length", |value| value.to_string()))
+        },
+        Rule::Custom(ref formula) => format!("of:is-true-formula({})", formula),
+    }
+}
+
+/// Write the conditional formats of the sheet at the end of its table,
+/// a condition applies the cell style named for its differential style in styles.xml
+pub fn make_conditional_formats(writer: &mut Writer<Cursor<Vec<u8>>>, sheet: &Sheet, style_names: &[(DifferentialStyle, String)]) {
+    write_start_tag(writer, "calcext:conditional-formats", vec![], false);
+    for format in sheet.get_conditional_formats() {
+        let target = format.get_ranges().iter().map(cell_address).collect::<Vec<String>>().join(" ");
+        let base_cell = format.get_ranges().first()
+            .map(|range| cell_address(&Range::cell(sheet.get_name().as_str(), range.get_first_row(), range.get_first_column())))
+            .unwrap_or_default();
+        write_start_tag(writer, "calcext:conditional-format", vec![("calcext:target-range-address", target.as_str())], false);
+        for rule in format.get_rules() {
+            make_conditional_rule(writer, rule, &base_cell, style_names);
         }
+        write_end_tag(writer, "calcext:conditional-format");
+    }
+    write_end_tag(writer, "calcext:conditional-formats");
+}
+
+fn make_conditional_rule(writer: &mut Writer<Cursor<Vec<u8>>>, rule: &conditional_format::Rule, base_cell: &str, style_names: &[(DifferentialStyle, String)]) {
+    use conditional_format::Rule;
+    let value = match *rule {
+        Rule::CellIs(ref operator, _) => {
+            let (bound, upper) = operator.bounds();
+            match *operator {
+                Operator::Between(..) => format!("between({},{})", bound, upper.cloned().unwrap_or_default()),
+                Operator::NotBetween(..) => format!("not-between({},{})", bound, upper.cloned().unwrap_or_default()),
+                Operator::Equal(_) => format!("={}", bound),
+                Operator::NotEqual(_) => format!("!={}", bound),
+                Operator::GreaterThan(_) => format!(">{}", bound),
+                Operator::LessThan(_) => format!("<{}", bound),
+                Operator::GreaterThanOrEqual(_) => format!(">={}", bound),
+                Operator::LessThanOrEqual(_) => format!("<={}", bound),
+            }
+        },
+        Rule::Expression(ref formula, _) => format!("formula-is({})", formula),
+        Rule::Top { rank, bottom, percent, .. } => {
+            let function = match (bottom, percent) {
+                (false, false) => "top-elements",
+                (true, false) => "bottom-elements",
+                (false, true) => "top-percent",
+                (true, true) => "bottom-percent",
+            };
+            format!("{}({})", function, rank)
+        },
+        Rule::Duplicate(_) => String::from("duplicate"),
+        Rule::Unique(_) => String::from("unique"),
+        Rule::ColorScale(ref stops) => {
+            write_start_tag(writer, "calcext:color-scale", vec![], false);
+            for (threshold, color) in stops {
+                let (entry_type, value) = threshold_entry(threshold);
+                write_start_tag(writer, "calcext:color-scale-entry", vec![
+                    ("calcext:value", value.as_str()),
+                    ("calcext:type", entry_type),
+                    ("calcext:color", format!("#{}", color).as_str()),
+                ], true);
+            }
+            write_end_tag(writer, "calcext:color-scale");
+            return;
+        },
+        Rule::DataBar { ref min, ref max, ref color } => {
+            write_start_tag(writer, "calcext:data-bar", vec![("calcext:positive-color", format!("#{}", color).as_str())], false);
+            make_formatting_entry(writer, min);
+            make_formatting_entry(writer, max);
+            write_end_tag(writer, "calcext:data-bar");
+            return;
+        },
+        Rule::IconSet { icons, ref thresholds } => {
+            write_start_tag(writer, "calcext:icon-set", vec![("calcext:icon-set-type", icons.name())], false);
+            for threshold in thresholds {
+                make_formatting_entry(writer, threshold);
+            }
+            write_end_tag(writer, "calcext:icon-set");
+            return;
+        },
     };
-    write_end_tag(writer, "table:table");
+    let style_name = rule.get_style()
+        .and_then(|style| style_names.iter().find(|&(s, _)| s == style))
+        .map(|(_, name)| name.as_str())
+        .unwrap_or("Default");
+    write_start_tag(writer, "calcext:condition", vec![
+        ("calcext:apply-style-name", style_name),
+        ("calcext:value", value.as_str()),
+        ("calcext:base-cell-address", base_cell),
+    ], true);
+}
+
+fn make_formatting_entry(writer: &mut Writer<Cursor<Vec<u8>>>, threshold: &Threshold) {
+    let (entry_type, value) = threshold_entry(threshold);
+    write_start_tag(writer, "calcext:formatting-entry", vec![
+        ("calcext:value", value.as_str()),
+        ("calcext:type", entry_type),
+    ], true);
 }
 
-fn make_number_format(writer: &mut Writer<Cursor<Vec<u8>>>, formats: &Vec<&str>) {
+/// Type and value of a threshold, the lowest and the highest values have the value "0"
+fn threshold_entry(threshold: &Threshold) -> (&'static str, String) {
+    match *threshold {
+        Threshold::Min => ("minimum", String::from("0")),
+        Threshold::Max => ("maximum", String::from("0")),
+        Threshold::Number(value) => ("number", value.to_string()),
+        Threshold::Percent(value) => ("percent", value.to_string()),
+        Threshold::Percentile(value) => ("percentile", value.to_string()),
+        Threshold::Formula(ref formula) => ("formula", formula.clone()),
+    }
+}
+
+/// Paths of the frames and names of the validations of a sheet, which its cells refer to
+pub struct SheetRefs<'a> {
+    pub paths: &'a FramePaths,
+    pub validation_names: &'a [String],
+}
+
+/// Write the rows of the sheet, the start tag of each row and the style name of each cell are given by the callbacks.
+/// The pictures and the charts with their paths are written in the cells they are anchored to.
+/// The cells of the validations have the names of their validations, the empty ones after the cells
+/// and the rows after the rows of the cells are repeated cells and rows.
+pub fn make_content_xml_rows<R, S>(writer: &mut Writer<Cursor<Vec<u8>>>, sheet: &Sheet, refs: &SheetRefs, mut row_start: R, cell_style: S, text_styles: &[(Font, String)], timezone: FixedOffset)
+where
+    R: FnMut(&mut Writer<Cursor<Vec<u8>>>, usize),
+    S: Fn(&Cell) -> Option<String>,
+{
+    let mut frames: HashMap<(usize, usize), Vec<Frame>> = HashMap::new();
+    for (image, href) in sheet.get_images().iter().zip(refs.paths.images.iter()) {
+        if let Some(ref href) = *href {
+            let from = image.get_anchor().get_from();
+            frames.entry((from.get_row(), from.get_column())).or_default().push(Frame {
+                name: image.get_name(),
+                anchor: image.get_anchor(),
+                href,
+                sheet_name: sheet.get_name(),
+                ranges: None,
+            });
+        }
+    }
+    for (chart, href) in sheet.get_charts().iter().zip(refs.paths.charts.iter()) {
+        let from = chart.get_anchor().get_from();
+        frames.entry((from.get_row(), from.get_column())).or_default().push(Frame {
+            name: href.trim_start_matches("./"),
+            anchor: chart.get_anchor(),
+            href,
+            sheet_name: sheet.get_name(),
+            ranges: Some(chart_ranges(chart)),
+        });
+    }
+    let validations: Vec<(&Range, &str)> = sheet.get_validations().iter().zip(refs.validation_names.iter())
+        .flat_map(|(validation, name)| validation.get_ranges().iter().map(move |range| (range, name.as_str())))
+        .collect();
+    let validation_at = |row_index: usize, column_index: usize| validations.iter()
+        .find(|&&(range, _)| range.contains(row_index, column_index))
+        .map(|&(_, name)| name);
+    let empty_columns = HashMap::new();
+    let empty_cell = Cell::new(Value::Empty, "");
+    let indexes = max_index(sheet);
+    if indexes.is_none() && validations.is_empty() {
+        // all row not found
+        row_start(writer, 0);
+        write_start_tag(writer, "table:table-cell", vec![], true);
+        write_end_tag(writer, "table:table-row");
+        return;
+    }
+    let row_count = indexes.map(|indexes| indexes.0 + 1).unwrap_or(0);
+    for row_index in 0..row_count {
+        let columns = sheet.get_rows().get(&row_index).unwrap_or(&empty_columns);
+        let frame_column_index = frames.keys().filter(|&&(row, _)| row == row_index).map(|&(_, column)| column).max();
+        let max_column_index = columns.keys().cloned().chain(frame_column_index).max();
+        let next_column_index = max_column_index.map(|index| index + 1).unwrap_or(0);
+        let runs = validation_runs(&validations, row_index, next_column_index);
+        row_start(writer, row_index);
+        if max_column_index.is_none() && runs.is_empty() {
+            // some row not found
+            write_start_tag(writer, "table:table-cell", vec![
+                ("table:number-columns-repeated", (indexes.map(|indexes| indexes.1).unwrap_or(0) + 1).to_string().as_str())
+            ], true);
+            write_end_tag(writer, "table:table-row");
+            continue;
+        }
+        // empty cells not written yet with their validation
+        let mut empty_cells: (Option<&str>, usize) = (None, 0);
+        for column_index in 0..next_column_index {
+            let cell_frames = frames.get(&(row_index, column_index)).map(|f| &f[..]).unwrap_or(&[]);
+            let validation = validation_at(row_index, column_index);
+            match columns.get(&column_index) {
+                None if cell_frames.is_empty() => add_empty_cells(writer, &mut empty_cells, validation, 1),
+                cell => {
+                    make_content_xml_none_table_cell(writer, empty_cells.1, empty_cells.0);
+                    empty_cells = (None, 0);
+                    let cell = cell.unwrap_or(&empty_cell);
+                    make_content_xml_table_cell(writer, cell, cell_style(cell).as_ref(), text_styles, timezone, cell_frames, validation);
+                },
+            }
+        }
+        for (validation, count) in runs {
+            add_empty_cells(writer, &mut empty_cells, validation, count);
+        }
+        make_content_xml_none_table_cell(writer, empty_cells.1, empty_cells.0);
+        write_end_tag(writer, "table:table-row");
+    }
+    // the rows after the cells only have the cells of the validations, the rows of the same cells are repeated
+    let mut bounds: Vec<usize> = validations.iter()
+        .flat_map(|&(range, _)| vec![range.get_first_row(), range.get_last_row() + 1])
+        .filter(|&row_index| row_index > row_count)
+        .chain(Some(row_count))
+        .collect();
+    bounds.sort();
+    bounds.dedup();
+    for rows in bounds.windows(2) {
+        let repeated = (rows[1] - rows[0]).to_string();
+        let mut attributes = vec![];
+        if rows[1] - rows[0] > 1 {
+            attributes.push(("table:number-rows-repeated", repeated.as_str()));
+        }
+        write_start_tag(writer, "table:table-row", attributes, false);
+        let runs = validation_runs(&validations, rows[0], 0);
+        if runs.is_empty() {
+            write_start_tag(writer, "table:table-cell", vec![], true);
+        }
+        for (validation, count) in runs {
+            make_content_xml_none_table_cell(writer, count, validation);
+        }
+        write_end_tag(writer, "table:table-row");
+    }
+}
+
+/// Add empty cells to the ones not written yet, the ones of another validation are written before
+fn add_empty_cells<'a>(writer: &mut Writer<Cursor<Vec<u8>>>, empty_cells: &mut (Option<&'a str>, usize), validation: Option<&'a str>, count: usize) {
+    if empty_cells.0 != validation {
+        make_content_xml_none_table_cell(writer, empty_cells.1, empty_cells.0);
+        *empty_cells = (validation, 0);
+    }
+    empty_cells.1 += count;
+}
+
+/// (validation, count) of the runs of the cells of the row from `column_index` to the last cell having a validation
+fn validation_runs<'a>(validations: &[(&Range, &'a str)], row_index: usize, column_index: usize) -> Vec<(Option<&'a str>, usize)> {
+    let mut bounds: Vec<usize> = validations.iter()
+        .filter(|&&(range, _)| range.get_first_row() <= row_index && row_index <= range.get_last_row())
+        .flat_map(|&(range, _)| vec![range.get_first_column(), range.get_last_column() + 1])
+        .filter(|&index| index > column_index)
+        .chain(Some(column_index))
+        .collect();
+    bounds.sort();
+    bounds.dedup();
+    let mut runs: Vec<(Option<&str>, usize)> = bounds.windows(2).map(|columns| {
+        let validation = validations.iter()
+            .find(|&&(range, _)| range.contains(row_index, columns[0]))
+            .map(|&(_, name)| name);
+        (validation, columns[1] - columns[0])
+    }).collect();
+    while runs.last().is_some_and(|run| run.0.is_none()) {
+        runs.pop();
+    }
+    runs
+}
+
+pub fn make_number_format(writer: &mut Writer<Cursor<Vec<u8>>>, formats: &Vec<&str>) {
     for it in formats {
         match *it {
             "%Y" => {
@@ -228,41 +1067,251 @@ fn make_number_format(writer: &mut Writer<Cursor<Vec<u8>>>, formats: &Vec<&str>)
     }
 }
 
+/// Element of the number style of a date, time, currency or number cell
+pub fn data_style_element(cell: &Cell) -> &'static str {
+    match cell.get_value() {
+        &Value::Time(_) | &Value::Duration(_) => "number:time-style",
+        &Value::Currency(_) => "number:currency-style",
+        &Value::Float(_) => {
+            let sections = cell.get_format().get_number_sections();
+            number_style_element(sections.first().unwrap_or(&vec![]))
+        },
+        _ => "number:date-style",
+    }
+}
+
+/// Element of a section of a number format code
+fn number_style_element(tokens: &[NumberToken]) -> &'static str {
+    if tokens.iter().any(|token| matches!(*token, NumberToken::Currency { .. })) {
+        "number:currency-style"
+    } else if tokens.contains(&NumberToken::Percent) {
+        "number:percentage-style"
+    } else {
+        "number:number-style"
+    }
+}
+
+/// Write the number style of the format of a date, time, currency or number cell,
+/// the hours of an elapsed time like "[h]:mm" don't wrap at a day
+pub fn make_data_style(writer: &mut Writer<Cursor<Vec<u8>>>, cell: &Cell, name: &str) {
+    let element = data_style_element(cell);
+    if element != "number:date-style" && element != "number:time-style" {
+        make_number_style(writer, cell.get_format(), name);
+        return;
+    }
+    let elapsed = cell.get_format().is_elapsed();
+    let format = if elapsed {
+        Format::new(cell.get_format().get_content().replace(['[', ']'], ""))
+    } else {
+        cell.get_format().clone()
+    };
+    let mut attributes = vec![("style:name", name)];
+    if element == "number:date-style" {
+        attributes.push(("number:automatic-order", "true"));
+    } else if elapsed {
+        attributes.push(("number:truncate-on-overflow", "false"));
+    }
+    write_start_tag(writer, element, attributes, false);
+    make_number_format(writer, &format.get_date_formats().unwrap_or_default());
+    write_end_tag(writer, element);
+}
+
+/// Write the styles of the sections of a number format code like "#,##0;[Red]-#,##0",
+/// the sections but the last are the styles "{name}P0" and "{name}P1"
+/// which the last one applies by style:map like LibreOffice
+pub fn make_number_style(writer: &mut Writer<Cursor<Vec<u8>>>, format: &Format, name: &str) {
+    let mut sections = format.get_number_sections();
+    // the section of texts has no number style
+    sections.truncate(3);
+    let last = sections.pop().unwrap_or_default();
+    let names: Vec<String> = (0..sections.len()).map(|index| format!("{}P{}", name, index)).collect();
+    for (tokens, name) in sections.iter().zip(names.iter()) {
+        make_number_section(writer, tokens, name, vec![]);
+    }
+    let conditions = if sections.len() == 1 { vec!["value()>=0"] } else { vec!["value()>0", "value()<0"] };
+    let maps = conditions.into_iter().zip(names.iter().map(|name| name.as_str())).collect();
+    make_number_section(writer, &last, name, maps);
+}
+
+fn make_number_section(writer: &mut Writer<Cursor<Vec<u8>>>, tokens: &Vec<NumberToken>, name: &str, maps: Vec<(&str, &str)>) {
+    let element = number_style_element(tokens);
+    write_start_tag(writer, element, vec![("style:name", name)], false);
+    for token in tokens {
+        if let Some(rgb) = match *token { NumberToken::Color(ref color) => format::color_to_rgb(color), _ => None } {
+            write_start_tag(writer, "style:text-properties", vec![
+                ("fo:color", format!("#{}", rgb.to_lowercase()).as_str()),
+            ], true);
+        }
+    }
+    make_number_tokens(writer, tokens);
+    for (condition, apply_style_name) in maps {
+        write_start_tag(writer, "style:map", vec![
+            ("style:condition", condition),
+            ("style:apply-style-name", apply_style_name),
+        ], true);
+    }
+    write_end_tag(writer, element);
+}
+
+/// Write the elements of a section of a number format code
+pub fn make_number_tokens(writer: &mut Writer<Cursor<Vec<u8>>>, tokens: &Vec<NumberToken>) {
+    for token in tokens {
+        match *token {
+            NumberToken::General => {
+                write_start_tag(writer, "number:number", vec![("number:min-integer-digits", "1")], true);
+            },
+            NumberToken::Number { min_integer_digits, decimal_places, grouping } => {
+                let min_integer_digits = min_integer_digits.to_string();
+                let decimal_places = decimal_places.to_string();
+                let mut attributes = vec![
+                    ("number:decimal-places", decimal_places.as_str()),
+                    ("number:min-decimal-places", decimal_places.as_str()),
+                    ("number:min-integer-digits", min_integer_digits.as_str()),
+                ];
+                if grouping {
+                    attributes.push(("number:grouping", "true"));
+                }
+                write_start_tag(writer, "number:number", attributes, true);
+            },
+            NumberToken::Scientific { min_integer_digits, decimal_places, min_exponent_digits } => {
+                write_start_tag(writer, "number:scientific-number", vec![
+                    ("number:decimal-places", decimal_places.to_string().as_str()),
+                    ("number:min-integer-digits", min_integer_digits.to_string().as_str()),
+                    ("number:min-exponent-digits", min_exponent_digits.to_string().as_str()),
+                ], true);
+            },
+            NumberToken::Fraction { min_integer_digits, min_numerator_digits, min_denominator_digits, denominator } => {
+                let min_integer_digits = min_integer_digits.map(|digits| digits.to_string());
+                let min_numerator_digits = min_numerator_digits.to_string();
+                let min_denominator_digits = min_denominator_digits.to_string();
+                let denominator = denominator.map(|value| value.to_string());
+                let mut attributes = vec![];
+                if let Some(ref digits) = min_integer_digits {
+                    attributes.push(("number:min-integer-digits", digits.as_str()));
+                }
+                attributes.push(("number:min-numerator-digits", min_numerator_digits.as_str()));
+                attributes.push(("number:min-denominator-digits", min_denominator_digits.as_str()));
+                if let Some(ref value) = denominator {
+                    attributes.push(("number:denominator-value", value.as_str()));
+                }
+                write_start_tag(writer, "number:fraction", attributes, true);
+            },
+            NumberToken::Currency { ref symbol, ref locale } => {
+                let mut attributes = vec![];
+                if let Some((language, country)) = locale.as_ref().and_then(|locale| Currency::language_of_locale(locale)) {
+                    attributes.push(("number:language", language));
+                    attributes.push(("number:country", country));
+                }
+                write_start_tag(writer, "number:currency-symbol", attributes, false);
+                write_text_node(writer, symbol.as_str());
+                write_end_tag(writer, "number:currency-symbol");
+            },
+            NumberToken::Percent => {
+                write_start_tag(writer, "number:text", vec![], false);
+                write_text_node(writer, "%");
+                write_end_tag(writer, "number:text");
+            },
+            NumberToken::Color(_) => {},
+            NumberToken::Text(ref text) => {
+                write_start_tag(writer, "number:text", vec![], false);
+                write_text_node(writer, text.as_str());
+                write_end_tag(writer, "number:text");
+            },
+        }
+    }
+}
+
+/// A date or time cell, or a currency or number cell with a format code,
+/// a currency or number without format is a plain number
+pub fn has_data_style(cell: &Cell) -> bool {
+    match cell.get_value() {
+        &Value::Date(_) | &Value::DateOnly(_) | &Value::Time(_) | &Value::Duration(_) => true,
+        &Value::Currency(_) => cell.get_format().get_content() != "",
+        &Value::Float(_) => cell.get_format().get_content() != "" && !cell.get_format().is_date(),
+        _ => false,
+    }
+}
+
 fn make_num_styles(writer: &mut Writer<Cursor<Vec<u8>>>, book: &Book) -> HashMap<String, String> {
     let mut result = HashMap::new();
     let mut count: usize = 0;
     for sheet in book.get_sheet_vec() {
         sheet.walk_through(|_, _, cell| {
-            match cell.get_value() {
-                &Value::Date(_) => {
-                    let format = cell.get_format().get_content();
-                    if !result.contains_key(format) {
-                        count = count + 1;
-                        let n_name = format!("N{}", count);
-                        let s_name = format!("ce{}", count);
-                        result.insert(format.clone(), s_name.clone());
-                         write_start_tag(writer, "number:date-style", vec![
-                            ("style:name", n_name.as_str()),
-                            ("number:automatic-order", "true"),
-                            ], false);
-                        make_number_format(writer, &cell.get_format().get_date_formats().unwrap());
-                        write_end_tag(writer, "number:date-style");
-                        write_start_tag(writer, "style:style", vec![
-                            ("style:name", s_name.as_str()),
-                            ("style:family", "table-cell"),
-                            ("style:parent-style-name", "Default"),
-                            ("style:data-style-name", n_name.as_str()),
-                            ], true);
+            let format = cell.get_format().get_content();
+            if has_data_style(cell) && !result.contains_key(format) {
+                count += 1;
+                let n_name = format!("N{}", count);
+                let s_name = format!("ce{}", count);
+                result.insert(format.clone(), s_name.clone());
+                make_data_style(writer, cell, n_name.as_str());
+                write_start_tag(writer, "style:style", vec![
+                    ("style:name", s_name.as_str()),
+                    ("style:family", "table-cell"),
+                    ("style:parent-style-name", "Default"),
+                    ("style:data-style-name", n_name.as_str()),
+                    ], true);
+            }
+        });
+    }
+    result
+}
+
+/// Automatic text styles for the fonts of the rich texts, the font and the style name
+pub fn make_text_styles(book: &Book, prefix: &str) -> Vec<(Font, String)> {
+    let mut result: Vec<(Font, String)> = Vec::new();
+    for sheet in book.get_sheet_vec() {
+        sheet.sorted_access(|_, _, cell| {
+            if let Value::RichText(runs) = cell.get_value() {
+                for font in runs.iter().filter_map(|run| run.get_font()) {
+                    if !result.iter().any(|(f, _)| f == font) {
+                        let name = format!("{}{}", prefix, result.len() + 1);
+                        result.push((font.clone(), name));
                     }
-                },
-                _ => {}
+                }
             }
         });
     }
     result
 }
 
-pub fn write(book: &Book, dir: &TempDir) -> result::Result<(), OdsError> {
+pub fn make_text_style(writer: &mut Writer<Cursor<Vec<u8>>>, font: &Font, name: &str) {
+    write_start_tag(writer, "style:style", vec![
+        ("style:name", name),
+        ("style:family", "text"),
+    ], false);
+    make_text_properties(writer, font);
+    write_end_tag(writer, "style:style");
+}
+
+pub fn make_text_properties(writer: &mut Writer<Cursor<Vec<u8>>>, font: &Font) {
+    let color = font.get_color().map(|color| format!("#{}", color));
+    let size = font.get_size().map(|size| format!("{}pt", size));
+    let mut attributes = vec![];
+    if let Some(name) = font.get_name() {
+        attributes.push(("fo:font-family", name.as_str()));
+    }
+    if let Some(ref size) = size {
+        attributes.push(("fo:font-size", size.as_str()));
+    }
+    if font.is_bold() {
+        attributes.push(("fo:font-weight", "bold"));
+    }
+    if font.is_italic() {
+        attributes.push(("fo:font-style", "italic"));
+    }
+    if font.is_underline() {
+        attributes.push(("style:text-underline-style", "solid"));
+    }
+    if font.is_strike() {
+        attributes.push(("style:text-line-through-style", "solid"));
+    }
+    if let Some(ref color) = color {
+        attributes.push(("fo:color", color.as_str()));
+    }
+    write_start_tag(writer, "style:text-properties", attributes, true);
+}
+
+pub fn write(book: &Book, dir: &TempDir, paths: &[FramePaths], style_names: &[(DifferentialStyle, String)]) -> result::Result<(), OdsError> {
     let mut writer = Writer::new(Cursor::new(Vec::new()));
     let _ = writer.write_event(Event::Decl(BytesDecl::new(b"1.0", Some(b"UTF-8"), None)));
     write_text_node(&mut writer, "\n");
@@ -292,8 +1341,17 @@ pub fn write(book: &Book, dir: &TempDir) -> result::Result<(), OdsError> {
     write_start_tag(&mut writer, "style:table-properties", vec![("table:display", "true"),("style:writing-mode", "lr-tb"),], false);
     write_end_tag(&mut writer, "style:table-properties");
     write_end_tag(&mut writer, "style:style");
+    if book.get_sheet_vec().iter().any(|sheet| sheet.get_view().is_right_to_left()) {
+        write_start_tag(&mut writer, "style:style", vec![("style:name", "ta2"),("style:family", "table"),("style:master-page-name", "Default"),], false);
+        write_start_tag(&mut writer, "style:table-properties", vec![("table:display", "true"),("style:writing-mode", "rl-tb"),], true);
+        write_end_tag(&mut writer, "style:style");
+    }
 
     let date_hash = make_num_styles(&mut writer, book);
+    let text_styles = make_text_styles(book, "T");
+    for (font, name) in text_styles.iter() {
+        make_text_style(&mut writer, font, name);
+    }
 
     write_end_tag(&mut writer, "office:automatic-styles");
     write_start_tag(&mut writer, "office:body", vec![], false);
@@ -301,12 +1359,19 @@ pub fn write(book: &Book, dir: &TempDir) -> result::Result<(), OdsError> {
     write_start_tag(&mut writer, "table:calculation-settings", vec![("table:automatic-find-labels", "false"),("table:use-regular-expressions", "false"),("table:use-wildcards", "true"),], false);
     write_end_tag(&mut writer, "table:calculation-settings");
 
-    for sheet in book.get_sheet_vec() {
-        let _ = make_content_xml_by_sheet(&mut writer, &sheet, &date_hash);
+    let validation_names = validation_names(book);
+    if has_content_validations(book) {
+        make_content_validations(&mut writer, book, &validation_names);
+    }
+    for ((sheet, sheet_paths), names) in book.get_sheet_vec().iter().zip(paths.iter()).zip(validation_names.iter()) {
+        let refs = SheetRefs { paths: sheet_paths, validation_names: names };
+        make_content_xml_by_sheet(&mut writer, sheet, &refs, &date_hash, &text_styles, style_names, book.get_timezone());
     }
 
-    write_start_tag(&mut writer, "table:named-expressions", vec![], false);
-    write_end_tag(&mut writer, "table:named-expressions");
+    make_named_expressions(&mut writer, book.get_defined_names());
+    if has_database_ranges(book) {
+        make_database_ranges(&mut writer, book, &[]);
+    }
     write_end_tag(&mut writer, "office:spreadsheet");
     write_end_tag(&mut writer, "office:body");
     write_end_tag(&mut writer, "office:document-content");
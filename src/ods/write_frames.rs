@@ -0,0 +1,112 @@
+use super::Book;
+use super::tempdir::TempDir;
+use std::fs;
+use std::result;
+use file_common::*;
+use super::OdsError;
+use super::write_chart;
+use chart::Chart;
+use super::cell_address;
+
+const MANIFEST_XML: &str = "META-INF/manifest.xml";
+const CHART_MEDIA_TYPE: &str = "application/vnd.oasis.opendocument.chart";
+
+/// Full path and media type of a file added to the manifest
+pub type ManifestEntry = (String, String);
+
+/// Paths of the files drawn in the frames of a sheet, in the order of its images and its charts
+#[derive(Debug, Default)]
+pub struct FramePaths {
+    /// Path of each picture, None for a picture which isn't PNG or JPEG
+    pub images: Vec<Option<String>>,
+    /// Link to the object of each chart like "./Object 1"
+    pub charts: Vec<String>,
+}
+
+/// Write the pictures of the sheets to Pictures, a file with the same bytes is shared,
+/// and each chart to a new object. The paths are returned by sheet with the manifest entries of the new files.
+pub fn write(book: &Book, dir: &TempDir) -> result::Result<(Vec<FramePaths>, Vec<ManifestEntry>), OdsError> {
+    let mut paths = Vec::new();
+    let mut added = Vec::new();
+    for sheet in book.get_sheet_vec() {
+        let mut sheet_paths = FramePaths::default();
+        for image in sheet.get_images() {
+            let (extension, media_type) = match (image.get_extension(), image.get_media_type()) {
+                (Some(extension), Some(media_type)) => (extension, media_type),
+                _ => {
+                    sheet_paths.images.push(None);
+                    continue;
+                },
+            };
+            let mut index = 0;
+            let path = loop {
+                index += 1;
+                let path = format!("Pictures/image{}.{}", index, extension);
+                let file_path = dir.path().join(&path);
+                if !file_path.exists() {
+                    make_file_from_bytes(dir, &path, image.get_data())?;
+                    added.push((path.clone(), String::from(media_type)));
+                    break path;
+                }
+                if &fs::read(&file_path)? == image.get_data() {
+                    break path;
+                }
+            };
+            sheet_paths.images.push(Some(path));
+        }
+        for chart in sheet.get_charts() {
+            let mut index = 1;
+            while dir.path().join(format!("Object {}", index)).exists() {
+                index += 1;
+            }
+            let object = format!("Object {}", index);
+            write_chart::write(dir, &object, chart)?;
+            added.push((format!("{}/", object), String::from(CHART_MEDIA_TYPE)));
+            added.push((format!("{}/content.xml", object), String::from("text/xml")));
+            sheet_paths.charts.push(format!("./{}", object));
+        }
+        paths.push(sheet_paths);
+    }
+    Ok((paths, added))
+}
+
+/// Cell ranges the chart is drawn from separated by spaces, the chart is updated when they change
+pub fn chart_ranges(chart: &Chart) -> String {
+    let mut ranges: Vec<String> = Vec::new();
+    if let Some(categories) = chart.get_categories() {
+        ranges.push(cell_address(categories));
+    }
+    for series in chart.get_series() {
+        if let Some(name) = series.get_name() {
+            ranges.push(cell_address(name));
+        }
+        ranges.push(cell_address(series.get_values()));
+    }
+    ranges.join(" ")
+}
+
+/// Patch the manifest with the entries of the new files, `added` are (full path, media type)
+pub fn write_manifest(dir: &TempDir, original: &[u8], added: &[ManifestEntry]) -> result::Result<(), OdsError> {
+    if added.is_empty() {
+        return Ok(());
+    }
+    let (root, mut children) = read_children(original)?;
+    let root = match root {
+        Some(root) => root,
+        None => return Ok(()),
+    };
+    children.retain(|child| {
+        let path = child.get_attribute("manifest:full-path").unwrap_or_default();
+        !added.iter().any(|a| a.0 == path)
+    });
+    for (path, media_type) in added {
+        children.push(RawElement::new("manifest:file-entry", |writer| {
+            write_start_tag(writer, "manifest:file-entry", vec![
+                ("manifest:full-path", path.as_str()),
+                ("manifest:media-type", media_type.as_str()),
+            ], true);
+        }));
+    }
+    make_file_from_elements(MANIFEST_XML, dir, &root, &children)?;
+    Ok(())
+}
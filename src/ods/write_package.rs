@@ -0,0 +1,465 @@
+use super::{Book, Cell, Package, Sheet};
+use chrono::FixedOffset;
+use super::tempdir::TempDir;
+use super::quick_xml::events::{BytesStart, Event};
+use super::quick_xml::Writer;
+use super::write_content::{data_style_element, has_data_style, make_content_xml_rows, make_data_style, make_text_style, make_text_styles, max_column_index};
+use super::write_content::{has_named_expressions, make_named_expressions, print_ranges};
+use super::write_content::{has_content_validations, make_content_validations, validation_names, SheetRefs};
+use super::write_content::make_conditional_formats;
+use super::write_content::{has_database_ranges, make_database_ranges};
+use super::write_frames::{self, FramePaths};
+use super::write_settings;
+use super::write_style;
+use conditional_format::DifferentialStyle;
+use rich_text::Font;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Cursor;
+use std::path::Path;
+use std::result;
+use file_common::*;
+use super::OdsError;
+
+const CONTENT_XML: &str = "content.xml";
+const MANIFEST_XML: &str = "META-INF/manifest.xml";
+const STYLES_XML: &str = "styles.xml";
+const SETTINGS_XML: &str = "settings.xml";
+// rows repeated more than this are the rest of the sheet
+const MAX_KEPT_REPEAT: usize = 256;
+
+/// Write a book read by `open`, copying every file except content.xml, styles.xml which gets the styles of the conditional formats
+/// and settings.xml which gets the views of the sheets.
+/// The pictures and the charts of the book are added to the package and the manifest, the original files stay.
+pub fn write(book: &Book, package: &Package, path: &Path) -> result::Result<(), OdsError> {
+    let dir = TempDir::new("shreadsheet")?;
+    for (name, data) in package.get_parts() {
+        if name != CONTENT_XML && name != STYLES_XML && name != SETTINGS_XML {
+            make_file_from_bytes(&dir, name, data)?;
+        }
+    }
+    let style_names = match package.get_part(STYLES_XML) {
+        Some(original) => write_style::write_with_package(book, &dir, original)?,
+        None => write_style::write(book, &dir)?,
+    };
+    let (paths, added) = write_frames::write(book, &dir)?;
+    if let Some(original) = package.get_part(CONTENT_XML) {
+        write_content(book, &dir, original, &paths, &style_names)?;
+    }
+    // a package without settings.xml has none in its manifest
+    if let Some(original) = package.get_part(SETTINGS_XML) {
+        write_settings::write_with_package(book, &dir, original)?;
+    }
+    if let Some(original) = package.get_part(MANIFEST_XML) {
+        write_frames::write_manifest(&dir, original, &added)?;
+    }
+    write_to_file(path, &dir)?;
+    dir.close()?;
+    Ok(())
+}
+
+/// Patch content.xml, the tables are made again from the book keeping
+/// the columns, the row styles and the cell styles of the original tables
+fn write_content(book: &Book, dir: &TempDir, original: &[u8], paths: &[FramePaths], style_names: &[(DifferentialStyle, String)]) -> result::Result<(), OdsError> {
+    let (root, children) = read_children(original)?;
+    let root = match root {
+        Some(root) => root,
+        None => return Ok(()),
+    };
+    let mut cell_styles: HashMap<String, RawElement> = HashMap::new();
+    let mut table_styles: HashMap<String, RawElement> = HashMap::new();
+    let mut tables: HashMap<String, RawElement> = HashMap::new();
+    for child in &children {
+        match child.name.as_str() {
+            "office:automatic-styles" => {
+                for style in read_children(&child.data)?.1 {
+                    if style.name != "style:style" {
+                        continue;
+                    }
+                    match (style.get_attribute("style:name"), style.get_attribute("style:family")) {
+                        (Some(name), Some(ref family)) if family == "table-cell" => {
+                            cell_styles.insert(name, style);
+                        },
+                        (Some(name), Some(ref family)) if family == "table" => {
+                            table_styles.insert(name, style);
+                        },
+                        _ => {},
+                    }
+                }
+            },
+            "office:body" => {
+                for body_child in read_children(&child.data)?.1 {
+                    if body_child.name == "office:spreadsheet" {
+                        for table in read_children(&body_child.data)?.1 {
+                            if let Some(name) = table.get_attribute("table:name") {
+                                if table.name == "table:table" {
+                                    tables.insert(name, table);
+                                }
+                            }
+                        }
+                    }
+                }
+            },
+            _ => {},
+        }
+    }
+
+    // a date, time, currency or number cell gets a copy of its original style with the number style of its format
+    let mut generated_styles: Vec<RawElement> = Vec::new();
+    let mut date_styles: HashMap<(Option<String>, String), String> = HashMap::new();
+    for sheet in book.get_sheet_vec() {
+        sheet.sorted_access(|_, _, cell| {
+            // a date without format is shown by the original style
+            if !has_data_style(cell) || cell.get_format().get_content() == "" {
+                return;
+            }
+            let key = (cell.get_style_id().cloned(), cell.get_format().get_content().clone());
+            if date_styles.contains_key(&key) {
+                return;
+            }
+            let count = date_styles.len() + 1;
+            let n_name = format!("spN{}", count);
+            let s_name = format!("spce{}", count);
+            generated_styles.push(RawElement::new(data_style_element(cell), |writer| {
+                make_data_style(writer, cell, n_name.as_str());
+            }));
+            let style = match key.0.as_ref().and_then(|name| cell_styles.get(name)) {
+                Some(original) => original.with_replaced_attributes(vec![
+                    ("style:name", s_name.as_str()),
+                    ("style:data-style-name", n_name.as_str()),
+                ]),
+                None => RawElement::new("style:style", |writer| {
+                    write_start_tag(writer, "style:style", vec![
+                        ("style:name", s_name.as_str()),
+                        ("style:family", "table-cell"),
+                        ("style:parent-style-name", key.0.as_deref().unwrap_or("Default")),
+                        ("style:data-style-name", n_name.as_str()),
+                    ], true);
+                }),
+            };
+            generated_styles.push(style);
+            date_styles.insert(key, s_name);
+        });
+    }
+
+    let text_styles = make_text_styles(book, "spT");
+    for (font, name) in text_styles.iter() {
+        generated_styles.push(RawElement::new("style:style", |writer| make_text_style(writer, font, name)));
+    }
+    let cell_styles = CellStyleNames {
+        dates: &date_styles,
+        texts: &text_styles,
+        conditions: style_names,
+    };
+
+    // a table in the other direction than its original style gets a copy of the style with the direction of the sheet
+    let mut direction_styles: HashMap<String, String> = HashMap::new();
+    let mut copied_table_styles: HashMap<Option<String>, String> = HashMap::new();
+    for sheet in book.get_sheet_vec() {
+        let original_style = sheet.get_part_name()
+            .and_then(|name| tables.get(name))
+            .and_then(|table| table.get_attribute("table:style-name"));
+        let original = original_style.as_ref().and_then(|name| table_styles.get(name));
+        let right_to_left = sheet.get_view().is_right_to_left();
+        if right_to_left == original.is_some_and(is_right_to_left) {
+            continue;
+        }
+        let name = match copied_table_styles.get(&original_style) {
+            Some(name) => name.clone(),
+            None => {
+                let name = format!("spta{}", copied_table_styles.len() + 1);
+                generated_styles.push(make_table_style(original, &name, right_to_left)?);
+                name
+            },
+        };
+        copied_table_styles.insert(original_style, name.clone());
+        direction_styles.insert(sheet.get_name().clone(), name);
+    }
+
+    let mut result = Vec::new();
+    for child in children {
+        match child.name.as_str() {
+            "office:automatic-styles" => {
+                let (tag, styles) = read_children(&child.data)?;
+                result.push(RawElement::new("office:automatic-styles", |writer| {
+                    if let Some(ref tag) = tag {
+                        write_start_tag_with_replaced_attributes(writer, tag, vec![], false);
+                    } else {
+                        write_start_tag(writer, "office:automatic-styles", vec![], false);
+                    }
+                    for style in styles.iter().chain(generated_styles.iter()) {
+                        write_raw(writer, &style.data);
+                    }
+                    write_end_tag(writer, "office:automatic-styles");
+                }));
+            },
+            "office:body" => {
+                let (body_tag, body_children) = read_children(&child.data)?;
+                let mut spreadsheets = Vec::new();
+                for body_child in body_children {
+                    if body_child.name != "office:spreadsheet" {
+                        spreadsheets.push(body_child);
+                        continue;
+                    }
+                    let (tag, elements) = read_children(&body_child.data)?;
+                    let validation_names = validation_names(book);
+                    // the database ranges with filter buttons are the auto filters made again from the sheets
+                    let mut kept_databases = Vec::new();
+                    for element in elements.iter().filter(|element| element.name == "table:database-ranges") {
+                        kept_databases.extend(read_children(&element.data)?.1.into_iter()
+                            .filter(|range| range.get_attribute("table:display-filter-buttons") != Some(String::from("true"))));
+                    }
+                    let has_databases = has_database_ranges(book) || !kept_databases.is_empty();
+                    spreadsheets.push(RawElement::new("office:spreadsheet", |writer| {
+                        match tag {
+                            Some(ref tag) => write_start_tag_with_replaced_attributes(writer, tag, vec![], false),
+                            None => write_start_tag(writer, "office:spreadsheet", vec![], false),
+                        }
+                        let mut written = false;
+                        let mut names_written = false;
+                        let mut validations_written = false;
+                        let mut databases_written = false;
+                        for element in &elements {
+                            // the validations and the names are made again from the book
+                            if element.name == "table:content-validations" {
+                                if !validations_written && has_content_validations(book) {
+                                    make_content_validations(writer, book, &validation_names);
+                                }
+                                validations_written = true;
+                                continue;
+                            }
+                            if element.name == "table:named-expressions" {
+                                if !names_written && has_named_expressions(book.get_defined_names()) {
+                                    make_named_expressions(writer, book.get_defined_names());
+                                }
+                                names_written = true;
+                                continue;
+                            }
+                            // the database ranges are after the names and before the data pilot tables
+                            let after_databases = ["table:data-pilot-tables", "table:consolidation", "table:dde-links"].contains(&element.name.as_str());
+                            if element.name == "table:database-ranges" || after_databases {
+                                if !names_written && has_named_expressions(book.get_defined_names()) {
+                                    make_named_expressions(writer, book.get_defined_names());
+                                }
+                                names_written = true;
+                                if !databases_written && has_databases {
+                                    make_database_ranges(writer, book, &kept_databases);
+                                }
+                                databases_written = true;
+                                if !after_databases {
+                                    continue;
+                                }
+                            }
+                            if element.name != "table:table" {
+                                write_raw(writer, &element.data);
+                                continue;
+                            }
+                            if !validations_written && has_content_validations(book) {
+                                make_content_validations(writer, book, &validation_names);
+                            }
+                            validations_written = true;
+                            if !written {
+                                for ((sheet, sheet_paths), names) in book.get_sheet_vec().iter().zip(paths.iter()).zip(validation_names.iter()) {
+                                    let table = sheet.get_part_name().and_then(|name| tables.get(name));
+                                    let refs = SheetRefs { paths: sheet_paths, validation_names: names };
+                                    make_table(writer, sheet, table, direction_styles.get(sheet.get_name()), &refs, &cell_styles, book.get_timezone());
+                                }
+                                written = true;
+                            }
+                        }
+                        if !written {
+                            if !validations_written && has_content_validations(book) {
+                                make_content_validations(writer, book, &validation_names);
+                            }
+                            for ((sheet, sheet_paths), names) in book.get_sheet_vec().iter().zip(paths.iter()).zip(validation_names.iter()) {
+                                let refs = SheetRefs { paths: sheet_paths, validation_names: names };
+                                make_table(writer, sheet, None, direction_styles.get(sheet.get_name()), &refs, &cell_styles, book.get_timezone());
+                            }
+                        }
+                        if !names_written && has_named_expressions(book.get_defined_names()) {
+                            make_named_expressions(writer, book.get_defined_names());
+                        }
+                        if !databases_written && has_databases {
+                            make_database_ranges(writer, book, &kept_databases);
+                        }
+                        write_end_tag(writer, "office:spreadsheet");
+                    }));
+                }
+                result.push(RawElement::new("office:body", |writer| {
+                    match body_tag {
+                        Some(ref tag) => write_start_tag_with_replaced_attributes(writer, tag, vec![], false),
+                        None => write_start_tag(writer, "office:body", vec![], false),
+                    }
+                    for element in &spreadsheets {
+                        write_raw(writer, &element.data);
+                    }
+                    write_end_tag(writer, "office:body");
+                }));
+            },
+            _ => result.push(child),
+        }
+    }
+    make_file_from_elements(CONTENT_XML, dir, &root, &result)?;
+    Ok(())
+}
+
+/// Names of the styles made for the cells, the data styles by (style, format), the fonts of the text runs and the conditions
+struct CellStyleNames<'a> {
+    dates: &'a HashMap<(Option<String>, String), String>,
+    texts: &'a [(Font, String)],
+    conditions: &'a [(DifferentialStyle, String)],
+}
+
+/// A table of a sheet in the other direction than its original style has the `table_style` with the direction
+fn make_table(writer: &mut Writer<Cursor<Vec<u8>>>, sheet: &Sheet, table: Option<&RawElement>, table_style: Option<&String>, refs: &SheetRefs, cell_styles: &CellStyleNames, timezone: FixedOffset) {
+    let CellStyleNames { dates: date_styles, texts: text_styles, conditions: style_names } = *cell_styles;
+    let cell_style = |cell: &Cell| {
+        if has_data_style(cell) {
+            let key = (cell.get_style_id().cloned(), cell.get_format().get_content().clone());
+            date_styles.get(&key).cloned().or(cell.get_style_id().cloned())
+        } else {
+            cell.get_style_id().cloned()
+        }
+    };
+    let print_ranges = print_ranges(sheet);
+    let mut attributes = vec![("table:name", sheet.get_name().as_str())];
+    if let Some(ref print_ranges) = print_ranges {
+        attributes.push(("table:print-ranges", print_ranges.as_str()));
+    }
+    if let Some(table_style) = table_style {
+        attributes.push(("table:style-name", table_style.as_str()));
+    }
+    let (tag, children) = match table.and_then(|t| read_children(&t.data).ok()) {
+        Some((Some(tag), children)) => (tag, children),
+        _ => {
+            // a new table only refers to the default styles
+            write_start_tag(writer, "table:table", attributes, false);
+            let columns = max_column_index(sheet).map(|index| index + 1).unwrap_or(1);
+            write_start_tag(writer, "table:table-column", vec![
+                ("table:number-columns-repeated", columns.to_string().as_str()),
+            ], true);
+            make_content_xml_rows(writer, sheet, refs, |writer, _| {
+                write_start_tag(writer, "table:table-row", vec![], false);
+            }, cell_style, text_styles, timezone);
+            if has_named_expressions(sheet.get_defined_names()) {
+                make_named_expressions(writer, sheet.get_defined_names());
+            }
+            if !sheet.get_conditional_formats().is_empty() {
+                make_conditional_formats(writer, sheet, style_names);
+            }
+            write_end_tag(writer, "table:table");
+            return;
+        },
+    };
+
+    let mut row_tags: BTreeMap<usize, BytesStart> = BTreeMap::new();
+    let mut row_index = 0;
+    for child in &children {
+        let rows = match child.name.as_str() {
+            "table:table-row" => vec![child.clone()],
+            "table:table-header-rows" | "table:table-rows" | "table:table-row-group" => {
+                read_children(&child.data).map(|(_, rows)| rows).unwrap_or(vec![])
+            },
+            _ => continue,
+        };
+        for row in rows.iter().filter(|r| r.name == "table:table-row") {
+            let repeated = row.get_attribute("table:number-rows-repeated")
+                .and_then(|r| r.parse::<usize>().ok())
+                .unwrap_or(1);
+            if let Some(row_tag) = row.start_tag() {
+                if repeated <= MAX_KEPT_REPEAT {
+                    for index in 0..repeated {
+                        row_tags.insert(row_index + index, row_tag.clone());
+                    }
+                }
+            }
+            row_index += repeated;
+        }
+    }
+
+    // the print ranges are made again from the names of the sheet
+    let tag = without_attributes(&tag, &["table:print-ranges"]);
+    write_start_tag_with_replaced_attributes(writer, &tag, attributes, false);
+    let mut written = false;
+    let mut names_written = false;
+    for child in &children {
+        match child.name.as_str() {
+            "table:table-row" | "table:table-header-rows" | "table:table-rows" | "table:table-row-group" => {
+                if !written {
+                    make_rows(writer, sheet, &row_tags, refs, &cell_style, text_styles, timezone);
+                    written = true;
+                }
+            },
+            "table:named-expressions" => {
+                if has_named_expressions(sheet.get_defined_names()) {
+                    make_named_expressions(writer, sheet.get_defined_names());
+                }
+                names_written = true;
+            },
+            // the conditional formats are made again at the end of the table
+            "calcext:conditional-formats" => {},
+            _ => write_raw(writer, &child.data),
+        }
+    }
+    if !written {
+        make_rows(writer, sheet, &row_tags, refs, &cell_style, text_styles, timezone);
+    }
+    if !names_written && has_named_expressions(sheet.get_defined_names()) {
+        make_named_expressions(writer, sheet.get_defined_names());
+    }
+    if !sheet.get_conditional_formats().is_empty() {
+        make_conditional_formats(writer, sheet, style_names);
+    }
+    write_end_tag(writer, "table:table");
+}
+
+/// The table properties of a right to left table style have style:writing-mode="rl-tb"
+fn is_right_to_left(style: &RawElement) -> bool {
+    read_children(&style.data).map(|(_, children)| {
+        children.iter().any(|child| child.name == "style:table-properties" && child.get_attribute("style:writing-mode") == Some(String::from("rl-tb")))
+    }).unwrap_or(false)
+}
+
+/// Copy of an original table style with the writing mode of the direction, a new table style without the original one
+fn make_table_style(original: Option<&RawElement>, name: &str, right_to_left: bool) -> result::Result<RawElement, OdsError> {
+    let writing_mode = if right_to_left { "rl-tb" } else { "lr-tb" };
+    let (tag, children) = match original {
+        Some(style) => read_children(&style.data)?,
+        None => (None, Vec::new()),
+    };
+    Ok(RawElement::new("style:style", |writer| {
+        match tag {
+            Some(ref tag) => write_start_tag_with_replaced_attributes(writer, tag, vec![("style:name", name)], false),
+            None => write_start_tag(writer, "style:style", vec![("style:name", name),("style:family", "table"),("style:master-page-name", "Default")], false),
+        }
+        let mut written = false;
+        for child in &children {
+            if child.name == "style:table-properties" {
+                write_raw(writer, &child.with_replaced_attributes(vec![("style:writing-mode", writing_mode)]).data);
+                written = true;
+            } else {
+                write_raw(writer, &child.data);
+            }
+        }
+        if !written {
+            write_start_tag(writer, "style:table-properties", vec![("table:display", "true"),("style:writing-mode", writing_mode)], true);
+        }
+        write_end_tag(writer, "style:style");
+    }))
+}
+
+fn make_rows<S>(writer: &mut Writer<Cursor<Vec<u8>>>, sheet: &Sheet, row_tags: &BTreeMap<usize, BytesStart>, refs: &SheetRefs, cell_style: &S, text_styles: &[(Font, String)], timezone: FixedOffset)
+where
+    S: Fn(&Cell) -> Option<String>,
+{
+    make_content_xml_rows(writer, sheet, refs, |writer, row_index| {
+        let mut elem = BytesStart::owned(b"table:table-row".to_vec(), "table:table-row".len());
+        if let Some(row_tag) = sheet.get_row_source(row_index).and_then(|source| row_tags.get(&source)) {
+            for attr in row_tag.attributes().with_checks(false).flatten() {
+                if attr.key != b"table:number-rows-repeated" {
+                    elem.push_attribute(attr);
+                }
+            }
+        }
+        let _ = writer.write_event(Event::Start(elem));
+    }, cell_style, text_styles, timezone);
+}
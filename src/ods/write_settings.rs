@@ -0,0 +1,150 @@
+use super::{Book, Sheet};
+use super::tempdir::TempDir;
+use super::quick_xml::events::{Event, BytesDecl};
+use super::quick_xml::Writer;
+use std::io::Cursor;
+use std::result;
+use sheet_view::Pane;
+use file_common::*;
+use super::OdsError;
+use super::read_settings;
+
+const SETTINGS_XML: &str = "settings.xml";
+const VIEW_SETTINGS: &str = "ooo:view-settings";
+
+/// Write settings.xml with the views of the sheets
+pub fn write(book: &Book, dir: &TempDir) -> result::Result<(), OdsError> {
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    let _ = writer.write_event(Event::Decl(BytesDecl::new(b"1.0", Some(b"UTF-8"), None)));
+    write_text_node(&mut writer, "\n");
+    write_start_tag(&mut writer, "office:document-settings", vec![
+        ("xmlns:office", "urn:oasis:names:tc:opendocument:xmlns:office:1.0"),
+        ("xmlns:xlink", "http://www.w3.org/1999/xlink"),
+        ("xmlns:config", "urn:oasis:names:tc:opendocument:xmlns:config:1.0"),
+        ("xmlns:ooo", "http://openoffice.org/2004/office"),
+        ("office:version", "1.2"),
+    ], false);
+    write_start_tag(&mut writer, "office:settings", vec![], false);
+    make_view_settings(&mut writer, book);
+    write_end_tag(&mut writer, "office:settings");
+    write_end_tag(&mut writer, "office:document-settings");
+    make_file_from_writer(SETTINGS_XML, dir, writer, None)?;
+    Ok(())
+}
+
+/// Patch the original settings.xml, the view settings are made again from the book
+/// and the other settings like the printer are kept. The original is copied when the views haven't changed,
+/// a broken original is made again like a new book.
+pub fn write_with_package(book: &Book, dir: &TempDir, original: &[u8]) -> result::Result<(), OdsError> {
+    let (root, children) = match read_children(original) {
+        Ok(parsed) => parsed,
+        Err(_) => return write(book, dir),
+    };
+    let root = match root {
+        Some(ref root) if !read_settings::has_views_of(original, book)? => root,
+        _ => {
+            make_file_from_bytes(dir, SETTINGS_XML, original)?;
+            return Ok(());
+        },
+    };
+    let mut result = Vec::new();
+    for child in children {
+        if child.name != "office:settings" {
+            result.push(child);
+            continue;
+        }
+        let (tag, sets) = read_children(&child.data)?;
+        result.push(RawElement::new("office:settings", |writer| {
+            match tag {
+                Some(ref tag) => write_start_tag_with_replaced_attributes(writer, tag, vec![], false),
+                None => write_start_tag(writer, "office:settings", vec![], false),
+            }
+            let mut written = false;
+            for set in &sets {
+                if set.get_attribute("config:name").is_some_and(|name| name == VIEW_SETTINGS) {
+                    if !written {
+                        make_view_settings(writer, book);
+                        written = true;
+                    }
+                } else {
+                    write_raw(writer, &set.data);
+                }
+            }
+            if !written {
+                make_view_settings(writer, book);
+            }
+            write_end_tag(writer, "office:settings");
+        }));
+    }
+    make_file_from_elements(SETTINGS_XML, dir, root, &result)?;
+    Ok(())
+}
+
+/// The headers are shown or hidden on every sheet by the view of the active sheet
+fn make_view_settings(writer: &mut Writer<Cursor<Vec<u8>>>, book: &Book) {
+    let active_sheet = book.get_sheet_vec().get(book.get_active_sheet()).or(book.get_sheet_vec().first());
+    write_start_tag(writer, "config:config-item-set", vec![("config:name", VIEW_SETTINGS)], false);
+    write_start_tag(writer, "config:config-item-map-indexed", vec![("config:name", "Views")], false);
+    write_start_tag(writer, "config:config-item-map-entry", vec![], false);
+    write_config_item(writer, "ViewId", "string", "view1");
+    write_start_tag(writer, "config:config-item-map-named", vec![("config:name", "Tables")], false);
+    for sheet in book.get_sheet_vec() {
+        make_table_settings(writer, sheet);
+    }
+    write_end_tag(writer, "config:config-item-map-named");
+    if let Some(sheet) = active_sheet {
+        write_config_item(writer, "ActiveTable", "string", sheet.get_name());
+        write_config_item(writer, "HasColumnRowHeaders", "boolean", if sheet.get_view().is_show_headers() { "true" } else { "false" });
+    }
+    write_end_tag(writer, "config:config-item-map-entry");
+    write_end_tag(writer, "config:config-item-map-indexed");
+    write_end_tag(writer, "config:config-item-set");
+}
+
+/// A frozen pane is at the number of the rows and columns, a split pane at pixels of 96 dpi
+fn make_table_settings(writer: &mut Writer<Cursor<Vec<u8>>>, sheet: &Sheet) {
+    let view = sheet.get_view();
+    let (row, column) = view.get_active_cell();
+    // (mode, position) of the columns and the rows, mode 1 is split and 2 is frozen
+    let (x, y) = match view.get_pane() {
+        Some(&Pane::Frozen { rows, columns }) => ((2, columns), (2, rows)),
+        Some(&Pane::Split { x, y }) => ((1, (x * 4.0 / 3.0).round() as usize), (1, (y * 4.0 / 3.0).round() as usize)),
+        None => ((0, 0), (0, 0)),
+    };
+    let x = if x.1 == 0 { (0, 0) } else { x };
+    let y = if y.1 == 0 { (0, 0) } else { y };
+    // the cursor is in the pane right and below the split
+    let active_split_range = match (x.0, y.0) {
+        (0, 0) => 2,
+        (_, 0) => 1,
+        (0, _) => 2,
+        _ => 3,
+    };
+    let (position_right, position_bottom) = match view.get_pane() {
+        Some(&Pane::Frozen { rows, columns }) => (columns, rows),
+        _ => (0, 0),
+    };
+    write_start_tag(writer, "config:config-item-map-entry", vec![("config:name", sheet.get_name().as_str())], false);
+    write_config_item(writer, "CursorPositionX", "int", &column.to_string());
+    write_config_item(writer, "CursorPositionY", "int", &row.to_string());
+    write_config_item(writer, "HorizontalSplitMode", "short", &x.0.to_string());
+    write_config_item(writer, "VerticalSplitMode", "short", &y.0.to_string());
+    write_config_item(writer, "HorizontalSplitPosition", "int", &x.1.to_string());
+    write_config_item(writer, "VerticalSplitPosition", "int", &y.1.to_string());
+    write_config_item(writer, "ActiveSplitRange", "short", &active_split_range.to_string());
+    write_config_item(writer, "PositionLeft", "int", "0");
+    write_config_item(writer, "PositionRight", "int", &position_right.to_string());
+    write_config_item(writer, "PositionTop", "int", "0");
+    write_config_item(writer, "PositionBottom", "int", &position_bottom.to_string());
+    write_config_item(writer, "ZoomType", "short", "0");
+    write_config_item(writer, "ZoomValue", "int", &view.get_zoom().to_string());
+    write_config_item(writer, "PageViewZoomValue", "int", "60");
+    write_config_item(writer, "ShowGrid", "boolean", if view.is_show_grid_lines() { "true" } else { "false" });
+    write_end_tag(writer, "config:config-item-map-entry");
+}
+
+fn write_config_item(writer: &mut Writer<Cursor<Vec<u8>>>, name: &str, item_type: &str, value: &str) {
+    write_start_tag(writer, "config:config-item", vec![("config:name", name),("config:type", item_type)], false);
+    write_text_node(writer, value);
+    write_end_tag(writer, "config:config-item");
+}
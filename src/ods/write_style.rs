@@ -1,19 +1,112 @@
 use super::{Book};
 use super::tempdir::TempDir;
+use super::quick_xml::Writer;
+use std::io::Cursor;
 use std::result;
+use conditional_format::{self, DifferentialStyle};
 use file_common::*;
 use super::OdsError;
+use super::read_style;
+use super::write_content::make_text_properties;
 
 const STYLES_XML: &'static str = "styles.xml";
+const DOCUMENT_STYLES_ELEMENTS: [&str; 4] = [
+    "office:font-face-decls", "office:styles", "office:automatic-styles", "office:master-styles",
+];
+// the cell styles of the conditions are "Conditional_20_1", "Conditional_20_2", ...
+const CONDITIONAL_STYLE_PREFIX: &str = "Conditional_20_";
 
 const STYLES_XML_CONTENT: &'static str = r###"<?xml version="1.0" encoding="UTF-8"?>
 <office:document-styles xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:style="urn:oasis:names:tc:opendocument:xmlns:style:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0" xmlns:table="urn:oasis:names:tc:opendocument:xmlns:table:1.0" xmlns:draw="urn:oasis:names:tc:opendocument:xmlns:drawing:1.0" xmlns:fo="urn:oasis:names:tc:opendocument:xmlns:xsl-fo-compatible:1.0" xmlns:xlink="http://www.w3.org/1999/xlink" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:meta="urn:oasis:names:tc:opendocument:xmlns:meta:1.0" xmlns:number="urn:oasis:names:tc:opendocument:xmlns:datastyle:1.0" xmlns:presentation="urn:oasis:names:tc:opendocument:xmlns:presentation:1.0" xmlns:svg="urn:oasis:names:tc:opendocument:xmlns:svg-compatible:1.0" xmlns:chart="urn:oasis:names:tc:opendocument:xmlns:chart:1.0" xmlns:dr3d="urn:oasis:names:tc:opendocument:xmlns:dr3d:1.0" xmlns:math="http://www.w3.org/1998/Math/MathML" xmlns:form="urn:oasis:names:tc:opendocument:xmlns:form:1.0" xmlns:script="urn:oasis:names:tc:opendocument:xmlns:script:1.0" xmlns:ooo="http://openoffice.org/2004/office" xmlns:ooow="http://openoffice.org/2004/writer" xmlns:oooc="http://openoffice.org/2004/calc" xmlns:dom="http://www.w3.org/2001/xml-events" xmlns:rpt="http://openoffice.org/2005/report" xmlns:of="urn:oasis:names:tc:opendocument:xmlns:of:1.2" xmlns:xhtml="http://www.w3.org/1999/xhtml" xmlns:grddl="http://www.w3.org/2003/g/data-view#" xmlns:tableooo="http://openoffice.org/2009/table" xmlns:drawooo="http://openoffice.org/2010/draw" xmlns:calcext="urn:org:documentfoundation:names:experimental:calc:xmlns:calcext:1.0" xmlns:loext="urn:org:documentfoundation:names:experimental:office:xmlns:loext:1.0" xmlns:field="urn:openoffice:names:experimental:ooo-ms-interop:xmlns:field:1.0" xmlns:css3t="http://www.w3.org/TR/css3-text/" office:version="1.2"><office:font-face-decls><style:font-face style:name="Liberation Sans" svg:font-family="&apos;Liberation Sans&apos;" style:font-family-generic="swiss" style:font-pitch="variable"/><style:font-face style:name="Arial Unicode MS" svg:font-family="&apos;Arial Unicode MS&apos;" style:font-family-generic="system" style:font-pitch="variable"/><style:font-face style:name="Tahoma" svg:font-family="Tahoma" style:font-family-generic="system" style:font-pitch="variable"/><style:font-face style:name="ヒラギノ明朝 ProN" svg:font-family="&apos;ヒラギノ明朝 ProN&apos;" style:font-family-generic="system" style:font-pitch="variable"/></office:font-face-decls><office:styles><style:default-style style:family="table-cell"><style:paragraph-properties style:tab-stop-distance="12.5mm"/><style:text-properties style:font-name="Liberation Sans" fo:language="en" fo:country="US" style:font-name-asian="Tahoma" style:language-asian="ja" style:country-asian="JP" style:font-name-complex="Tahoma" style:language-complex="hi" style:country-complex="IN"/></style:default-style><number:number-style style:name="N0"><number:number number:min-integer-digits="1"/></number:number-style><style:style style:name="Default" style:family="table-cell"><style:text-properties style:font-name-asian="ヒラギノ明朝 ProN" style:font-family-asian="&apos;ヒラギノ明朝 ProN&apos;" style:font-family-generic-asian="system" style:font-pitch-asian="variable" style:font-name-complex="Arial Unicode MS" style:font-family-complex="&apos;Arial Unicode MS&apos;" style:font-family-generic-complex="system" style:font-pitch-complex="variable"/></style:style><style:style style:name="Heading_20__28_user_29_" style:display-name="Heading (user)" style:family="table-cell" style:parent-style-name="Default"><style:text-properties fo:color="#000000" fo:font-size="24pt" fo:font-style="normal" fo:font-weight="bold"/></style:style><style:style style:name="Heading_20_1" style:display-name="Heading 1" style:family="table-cell" style:parent-style-name="Heading_20__28_user_29_"><style:text-properties fo:color="#000000" fo:font-size="18pt" fo:font-style="normal" fo:font-weight="normal"/></style:style><style:style style:name="Heading_20_2" style:display-name="Heading 2" style:family="table-cell" style:parent-style-name="Heading_20__28_user_29_"><style:text-properties fo:color="#000000" fo:font-size="12pt" fo:font-style="normal" fo:font-weight="normal"/></style:style><style:style style:name="Text" style:family="table-cell" style:parent-style-name="Default"/><style:style style:name="Note" style:family="table-cell" style:parent-style-name="Text"><style:table-cell-properties fo:background-color="#ffffcc" style:diagonal-bl-tr="none" style:diagonal-tl-br="none" fo:border="0.74pt solid #808080"/><style:text-properties fo:color="#333333" fo:font-size="10pt" fo:font-style="normal" fo:font-weight="normal"/></style:style><style:style style:name="Footnote" style:family="table-cell" style:parent-style-name="Text"><style:text-properties fo:color="#808080" fo:font-size="10pt" fo:font-style="italic" fo:font-weight="normal"/></style:style><style:style style:name="Status" style:family="table-cell" style:parent-style-name="Default"/><style:style style:name="Good" style:family="table-cell" style:parent-style-name="Status"><style:table-cell-properties fo:background-color="#ccffcc"/><style:text-properties fo:color="#006600" fo:font-size="10pt" fo:font-style="normal" fo:font-weight="normal"/></style:style><style:style style:name="Neutral" style:family="table-cell" style:parent-style-name="Status"><style:table-cell-properties fo:background-color="#ffffcc"/><style:text-properties fo:color="#996600" fo:font-size="10pt" fo:font-style="normal" fo:font-weight="normal"/></style:style><style:style style:name="Bad" style:family="table-cell" style:parent-style-name="Status"><style:table-cell-properties fo:background-color="#ffcccc"/><style:text-properties fo:color="#cc0000" fo:font-size="10pt" fo:font-style="normal" fo:font-weight="normal"/></style:style><style:style style:name="Warning" style:family="table-cell" style:parent-style-name="Status"><style:text-properties fo:color="#cc0000" fo:font-size="10pt" fo:font-style="normal" fo:font-weight="normal"/></style:style><style:style style:name="Error" style:family="table-cell" style:parent-style-name="Status"><style:table-cell-properties fo:background-color="#cc0000"/><style:text-properties fo:color="#ffffff" fo:font-size="10pt" fo:font-style="normal" fo:font-weight="bold"/></style:style><style:style style:name="Accent" style:family="table-cell" style:parent-style-name="Default"><style:text-properties fo:color="#000000" fo:font-size="10pt" fo:font-style="normal" fo:font-weight="bold"/></style:style><style:style style:name="Accent_20_1" style:display-name="Accent 1" style:family="table-cell" style:parent-style-name="Accent"><style:table-cell-properties fo:background-color="#000000"/><style:text-properties fo:color="#ffffff" fo:font-size="10pt" fo:font-style="normal" fo:font-weight="normal"/></style:style><style:style style:name="Accent_20_2" style:display-name="Accent 2" style:family="table-cell" style:parent-style-name="Accent"><style:table-cell-properties fo:background-color="#808080"/><style:text-properties fo:color="#ffffff" fo:font-size="10pt" fo:font-style="normal" fo:font-weight="normal"/></style:style><style:style style:name="Accent_20_3" style:display-name="Accent 3" style:family="table-cell" style:parent-style-name="Accent"><style:table-cell-properties fo:background-color="#dddddd"/></style:style></office:styles><office:automatic-styles><style:page-layout style:name="Mpm1"><style:page-layout-properties style:writing-mode="lr-tb"/><style:header-style><style:header-footer-properties fo:min-height="7.5mm" fo:margin-left="0mm" fo:margin-right="0mm" fo:margin-bottom="2.5mm"/></style:header-style><style:footer-style><style:header-footer-properties fo:min-height="7.5mm" fo:margin-left="0mm" fo:margin-right="0mm" fo:margin-top="2.5mm"/></style:footer-style></style:page-layout><style:page-layout style:name="Mpm2"><style:page-layout-properties style:writing-mode="lr-tb"/><style:header-style><style:header-footer-properties fo:min-height="7.5mm" fo:margin-left="0mm" fo:margin-right="0mm" fo:margin-bottom="2.5mm" fo:border="2.49pt solid #000000" fo:padding="0.18mm" fo:background-color="#c0c0c0"><style:background-image/></style:header-footer-properties></style:header-style><style:footer-style><style:header-footer-properties fo:min-height="7.5mm" fo:margin-left="0mm" fo:margin-right="0mm" fo:margin-top="2.5mm" fo:border="2.49pt solid #000000" fo:padding="0.18mm" fo:background-color="#c0c0c0"><style:background-image/></style:header-footer-properties></style:footer-style></style:page-layout></office:automatic-styles><office:master-styles><style:master-page style:name="Default" style:page-layout-name="Mpm1"><style:header><text:p><text:sheet-name>???</text:sheet-name></text:p></style:header><style:header-left style:display="false"/><style:footer><text:p>ページ <text:page-number>1</text:page-number></text:p></style:footer><style:footer-left style:display="false"/></style:master-page><style:master-page style:name="Report" style:page-layout-name="Mpm2"><style:header><style:region-left><text:p><text:sheet-name>???</text:sheet-name><text:s/>(<text:title>???</text:title>)</text:p></style:region-left><style:region-right><text:p><text:date style:data-style-name="N2" text:date-value="2017-12-05">0000/00/00</text:date>, <text:time style:data-style-name="N2" text:time-value="07:05:40.815451544">00:00:00</text:time></text:p></style:region-right></style:header><style:header-left style:display="false"/><style:footer><text:p>ページ <text:page-number>1</text:page-number><text:s/>/ <text:page-count>99</text:page-count></text:p></style:footer><style:footer-left style:display="false"/></style:master-page></office:master-styles></office:document-styles>
 "###;
 
-pub fn write(_book: &Book, dir: &TempDir) -> result::Result<(), OdsError> {
-    let _ = make_static_file(
-        &dir, STYLES_XML, 
-        STYLES_XML_CONTENT, 
+/// Write styles.xml with the cell styles of the conditions, the names of the styles are returned
+pub fn write(book: &Book, dir: &TempDir) -> result::Result<Vec<(DifferentialStyle, String)>, OdsError> {
+    let names = conditional_style_names(book, &[]);
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    for (style, name) in &names {
+        make_conditional_style(&mut writer, style, name);
+    }
+    let styles = String::from_utf8(writer.into_inner().into_inner())?;
+    let content = STYLES_XML_CONTENT.replacen("</office:styles>", &format!("{}</office:styles>", styles), 1);
+    make_static_file(
+        dir, STYLES_XML,
+        &content,
         None)?;
-    Ok(())
-}
\ No newline at end of file
+    Ok(names)
+}
+
+/// Patch the original styles.xml adding the cell styles of the conditions,
+/// a style added by a previous write with the same properties is used again
+pub fn write_with_package(book: &Book, dir: &TempDir, original: &[u8]) -> result::Result<Vec<(DifferentialStyle, String)>, OdsError> {
+    let existing: Vec<(String, DifferentialStyle)> = read_style::read_cell_styles_of(original)?.into_iter()
+        .filter(|(name, _)| name.starts_with(CONDITIONAL_STYLE_PREFIX))
+        .collect();
+    let names = conditional_style_names(book, &existing);
+    let added: Vec<&(DifferentialStyle, String)> = names.iter()
+        .filter(|&(_, name)| !existing.iter().any(|(n, _)| n == name))
+        .collect();
+    let (root, children) = read_children(original)?;
+    let root = match root {
+        Some(ref root) if !added.is_empty() => root,
+        _ => {
+            make_file_from_bytes(dir, STYLES_XML, original)?;
+            return Ok(names);
+        },
+    };
+    let (tag, styles) = match children.iter().find(|child| child.name == "office:styles") {
+        Some(child) => read_children(&child.data)?,
+        None => (None, Vec::new()),
+    };
+    let generated = RawElement::new("office:styles", |writer| {
+        match tag {
+            Some(ref tag) => write_start_tag_with_replaced_attributes(writer, tag, vec![], false),
+            None => write_start_tag(writer, "office:styles", vec![], false),
+        }
+        for style in &styles {
+            write_raw(writer, &style.data);
+        }
+        for &(style, name) in &added {
+            make_conditional_style(writer, style, name);
+        }
+        write_end_tag(writer, "office:styles");
+    });
+    let children = merge_elements(&DOCUMENT_STYLES_ELEMENTS, children, vec![generated]);
+    make_file_from_elements(STYLES_XML, dir, root, &children)?;
+    Ok(names)
+}
+
+/// Name of each differential style of the book, a new name follows the numbers of the existing ones
+fn conditional_style_names(book: &Book, existing: &[(String, DifferentialStyle)]) -> Vec<(DifferentialStyle, String)> {
+    let mut number = existing.iter()
+        .filter_map(|(name, _)| name[CONDITIONAL_STYLE_PREFIX.len()..].parse::<usize>().ok())
+        .max()
+        .unwrap_or(0);
+    conditional_format::differential_styles(book).into_iter().map(|style| {
+        let name = match existing.iter().find(|&(_, s)| s == style) {
+            Some((name, _)) => name.clone(),
+            None => {
+                number += 1;
+                format!("{}{}", CONDITIONAL_STYLE_PREFIX, number)
+            },
+        };
+        (style.clone(), name)
+    }).collect()
+}
+
+fn make_conditional_style(writer: &mut Writer<Cursor<Vec<u8>>>, style: &DifferentialStyle, name: &str) {
+    let display_name = name.replace("_20_", " ");
+    write_start_tag(writer, "style:style", vec![
+        ("style:name", name),
+        ("style:display-name", display_name.as_str()),
+        ("style:family", "table-cell"),
+        ("style:parent-style-name", "Default"),
+    ], false);
+    if let Some(color) = style.get_fill_color() {
+        write_start_tag(writer, "style:table-cell-properties", vec![
+            ("fo:background-color", format!("#{}", color).as_str()),
+        ], true);
+    }
+    make_text_properties(writer, style.get_font());
+    write_end_tag(writer, "style:style");
+}
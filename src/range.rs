@@ -0,0 +1,187 @@
+//! Cell ranges of a sheet like "Sheet1!$B$2:$B$5"
+use std::borrow::Cow;
+use std::fmt;
+use {column_to_index, index_to_column};
+
+// last row of a whole column and last column of a whole row, the size of a xlsx sheet
+pub(crate) const MAX_ROW: usize = 1048575;
+pub(crate) const MAX_COLUMN: usize = 16383;
+
+/// Range is the cells of a sheet between two corners, both included.
+///
+/// ```
+/// use spsheet::range::Range;
+///
+/// let range = Range::parse("'Sales 2017'!B2:$B$5").unwrap();
+/// assert_eq!("Sales 2017", range.get_sheet_name());
+/// assert_eq!((1, 1, 4, 1), (range.get_first_row(), range.get_first_column(), range.get_last_row(), range.get_last_column()));
+/// assert_eq!("'Sales 2017'!$B$2:$B$5", range.to_string());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Range {
+    sheet_name: String,
+    first_row: usize,
+    first_column: usize,
+    last_row: usize,
+    last_column: usize,
+}
+
+impl Range {
+    pub fn new<'a, S>(sheet_name: S, first_row: usize, first_column: usize, last_row: usize, last_column: usize) -> Range
+        where S: Into<Cow<'a, str>>
+    {
+        Range {
+            sheet_name: sheet_name.into().into_owned(),
+            first_row: first_row.min(last_row),
+            first_column: first_column.min(last_column),
+            last_row: first_row.max(last_row),
+            last_column: first_column.max(last_column),
+        }
+    }
+
+    /// A range of one cell
+    pub fn cell<'a, S>(sheet_name: S, row: usize, column: usize) -> Range
+        where S: Into<Cow<'a, str>>
+    {
+        Range::new(sheet_name, row, column, row, column)
+    }
+
+    /// "Sheet1!A1:B5", "'My Sheet'!$A$1", "Sheet1!A:A" or "Sheet1!$1:$1" are ranges, a reference without the sheet name is not
+    pub fn parse(src: &str) -> Option<Range> {
+        let index = src.rfind('!')?;
+        let sheet_name = unquote_sheet_name(src[..index].trim())?;
+        let mut corners = src[index + 1..].trim().splitn(2, ':');
+        let first = corners.next()?;
+        let last = corners.next().unwrap_or(first);
+        let (first_row, first_column) = parse_reference(first, 0, 0)?;
+        let (last_row, last_column) = parse_reference(last, MAX_ROW, MAX_COLUMN)?;
+        Some(Range::new(sheet_name, first_row, first_column, last_row, last_column))
+    }
+
+    pub fn get_sheet_name(&self) -> &String {
+        &self.sheet_name
+    }
+
+    pub fn set_sheet_name<'a, S>(&mut self, sheet_name: S)
+        where S: Into<Cow<'a, str>>
+    {
+        self.sheet_name = sheet_name.into().into_owned();
+    }
+
+    pub fn get_first_row(&self) -> usize {
+        self.first_row
+    }
+
+    pub fn get_first_column(&self) -> usize {
+        self.first_column
+    }
+
+    pub fn get_last_row(&self) -> usize {
+        self.last_row
+    }
+
+    pub fn get_last_column(&self) -> usize {
+        self.last_column
+    }
+
+    pub fn contains(&self, row: usize, column: usize) -> bool {
+        self.first_row <= row && row <= self.last_row && self.first_column <= column && column <= self.last_column
+    }
+}
+
+impl fmt::Display for Range {
+    /// "'My Sheet'!$A$1:$B$5", "Sheet1!$A$1" for a range of one cell
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}!${}${}", quote_sheet_name(&self.sheet_name), index_to_column(self.first_column), self.first_row + 1)?;
+        if self.first_row != self.last_row || self.first_column != self.last_column {
+            write!(f, ":${}${}", index_to_column(self.last_column), self.last_row + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// "Sheet1" as is, "'My Sheet'" for a name with other characters than letters, digits and underscores
+pub fn quote_sheet_name(name: &str) -> String {
+    let plain = !name.is_empty()
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+        && !name.chars().next().is_some_and(|c| c.is_ascii_digit());
+    if plain {
+        name.to_string()
+    } else {
+        format!("'{}'", name.replace('\'', "''"))
+    }
+}
+
+pub(crate) fn unquote_sheet_name(name: &str) -> Option<String> {
+    if name.len() >= 2 && name.starts_with('\'') && name.ends_with('\'') {
+        Some(name[1..name.len() - 1].replace("''", "'"))
+    } else if name.is_empty() || name.contains('\'') {
+        None
+    } else {
+        Some(name.to_string())
+    }
+}
+
+/// (row, column) of "$B$2", a whole column like "B" has `row` as its row and a whole row like "2" has `column` as its column
+pub(crate) fn parse_reference(src: &str, row: usize, column: usize) -> Option<(usize, usize)> {
+    let src = src.replace('$', "");
+    let split = src.find(|c: char| c.is_ascii_digit()).unwrap_or(src.len());
+    let (letters, digits) = src.split_at(split);
+    if (letters.is_empty() && digits.is_empty()) || !letters.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let row = if digits.is_empty() {
+        row
+    } else {
+        match digits.parse::<usize>() {
+            Ok(number) if number > 0 => number - 1,
+            _ => return None,
+        }
+    };
+    let column = if letters.is_empty() { column } else { column_to_index(letters.to_uppercase()) };
+    Some((row, column))
+}
+
+/// Split at the separators outside of quoted sheet names
+pub(crate) fn split_unquoted(src: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut quoted = false;
+    let mut start = 0;
+    for (index, c) in src.char_indices() {
+        if c == '\'' {
+            quoted = !quoted;
+        } else if c == separator && !quoted {
+            parts.push(&src[start..index]);
+            start = index + c.len_utf8();
+        }
+    }
+    parts.push(&src[start..]);
+    parts
+}
+
+/// Move the ranges like the rows inserted at `row_index`
+pub(crate) fn insert_rows(ranges: &mut [Range], row_index: usize, count: usize) {
+    let shift = |index: usize| if index >= row_index { (index + count).min(MAX_ROW) } else { index };
+    for range in ranges.iter_mut() {
+        range.first_row = shift(range.first_row);
+        range.last_row = shift(range.last_row);
+    }
+}
+
+/// Move the ranges like the rows removed at `row_index`, the ranges only on the removed rows are dropped
+pub(crate) fn remove_rows(ranges: &mut Vec<Range>, row_index: usize, count: usize) {
+    let end = row_index + count;
+    ranges.retain(|range| range.first_row < row_index || range.last_row >= end);
+    for range in ranges.iter_mut() {
+        range.first_row = match range.first_row {
+            first if first >= end => first - count,
+            first => first.min(row_index),
+        };
+        range.last_row = match range.last_row {
+            last if last >= end => last - count,
+            last if last >= row_index => row_index - 1,
+            last => last,
+        };
+    }
+}
@@ -0,0 +1,232 @@
+//! Rich text, a text made of runs with their own font, and its phonetic reading
+use std::borrow::Cow;
+
+/// TextRun is a part of a rich text with its font.
+///
+/// ```
+/// use spsheet::rich_text::{Font, TextRun};
+///
+/// let mut font = Font::new();
+/// font.set_bold(true);
+/// let runs = vec![TextRun::new("bold", Some(font)), TextRun::new(" text", None)];
+/// assert_eq!("bold text", TextRun::concat(&runs));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TextRun {
+    text: String,
+    font: Option<Font>,
+}
+
+impl TextRun {
+    pub fn new<'a, S>(text: S, font: Option<Font>) -> TextRun
+        where S: Into<Cow<'a, str>>
+    {
+        TextRun {
+            text: text.into().into_owned(),
+            font,
+        }
+    }
+
+    pub fn get_text(&self) -> &String {
+        &self.text
+    }
+
+    /// Font of the run, None is the font of the cell
+    pub fn get_font(&self) -> Option<&Font> {
+        self.font.as_ref()
+    }
+
+    /// Plain text of the runs
+    pub fn concat(runs: &[TextRun]) -> String {
+        runs.iter().map(|run| run.text.as_str()).collect()
+    }
+}
+
+/// Font has the attributes of a run, None is the attribute of the cell.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Font {
+    name: Option<String>,
+    size: Option<f64>,
+    bold: bool,
+    italic: bool,
+    underline: bool,
+    strike: bool,
+    color: Option<String>,
+}
+
+impl Font {
+    pub fn new() -> Font {
+        Font::default()
+    }
+
+    pub fn get_name(&self) -> Option<&String> {
+        self.name.as_ref()
+    }
+
+    pub fn set_name(&mut self, name: Option<String>) {
+        self.name = name;
+    }
+
+    /// Size in points
+    pub fn get_size(&self) -> Option<f64> {
+        self.size
+    }
+
+    pub fn set_size(&mut self, size: Option<f64>) {
+        self.size = size;
+    }
+
+    pub fn is_bold(&self) -> bool {
+        self.bold
+    }
+
+    pub fn set_bold(&mut self, bold: bool) {
+        self.bold = bold;
+    }
+
+    pub fn is_italic(&self) -> bool {
+        self.italic
+    }
+
+    pub fn set_italic(&mut self, italic: bool) {
+        self.italic = italic;
+    }
+
+    pub fn is_underline(&self) -> bool {
+        self.underline
+    }
+
+    pub fn set_underline(&mut self, underline: bool) {
+        self.underline = underline;
+    }
+
+    pub fn is_strike(&self) -> bool {
+        self.strike
+    }
+
+    pub fn set_strike(&mut self, strike: bool) {
+        self.strike = strike;
+    }
+
+    /// Color like "FF0000"
+    pub fn get_color(&self) -> Option<&String> {
+        self.color.as_ref()
+    }
+
+    pub fn set_color(&mut self, color: Option<String>) {
+        self.color = color;
+    }
+}
+
+/// Phonetic has the reading (furigana) of a text.
+///
+/// ```
+/// use spsheet::Cell;
+/// use spsheet::rich_text::{Phonetic, PhoneticRun};
+///
+/// let mut phonetic = Phonetic::new();
+/// phonetic.add_run(PhoneticRun::new("ヤマダ", 0, 2));
+/// phonetic.add_run(PhoneticRun::new("タロウ", 2, 4));
+/// let mut cell = Cell::str("山田太郎", "");
+/// cell.set_phonetic(Some(phonetic));
+/// assert_eq!("ヤマダタロウ", cell.get_phonetic().unwrap().get_text());
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Phonetic {
+    runs: Vec<PhoneticRun>,
+    font_id: usize,
+    kind: Option<String>,
+    alignment: Option<String>,
+    visible: bool,
+}
+
+impl Phonetic {
+    pub fn new() -> Phonetic {
+        Phonetic::default()
+    }
+
+    pub fn get_runs(&self) -> &Vec<PhoneticRun> {
+        &self.runs
+    }
+
+    pub fn add_run(&mut self, run: PhoneticRun) {
+        self.runs.push(run);
+    }
+
+    /// Reading of the whole text
+    pub fn get_text(&self) -> String {
+        self.runs.iter().map(|run| run.text.as_str()).collect()
+    }
+
+    /// Font of the reading, an index of the fonts in the styles of the xlsx
+    pub fn get_font_id(&self) -> usize {
+        self.font_id
+    }
+
+    pub fn set_font_id(&mut self, font_id: usize) {
+        self.font_id = font_id;
+    }
+
+    /// Character type like "Hiragana", "fullwidthKatakana" or "halfwidthKatakana"
+    pub fn get_kind(&self) -> Option<&String> {
+        self.kind.as_ref()
+    }
+
+    pub fn set_kind(&mut self, kind: Option<String>) {
+        self.kind = kind;
+    }
+
+    /// Alignment like "left", "center", "distributed" or "noControl"
+    pub fn get_alignment(&self) -> Option<&String> {
+        self.alignment.as_ref()
+    }
+
+    pub fn set_alignment(&mut self, alignment: Option<String>) {
+        self.alignment = alignment;
+    }
+
+    /// The reading is shown above the text
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+}
+
+/// PhoneticRun is the reading of the characters from `start` to `end` (exclusive) of the text.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PhoneticRun {
+    text: String,
+    start: usize,
+    end: usize,
+}
+
+impl PhoneticRun {
+    pub fn new<'a, S>(text: S, start: usize, end: usize) -> PhoneticRun
+        where S: Into<Cow<'a, str>>
+    {
+        PhoneticRun {
+            text: text.into().into_owned(),
+            start,
+            end,
+        }
+    }
+
+    pub fn get_text(&self) -> &String {
+        &self.text
+    }
+
+    pub fn get_start(&self) -> usize {
+        self.start
+    }
+
+    pub fn get_end(&self) -> usize {
+        self.end
+    }
+}
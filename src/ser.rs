@@ -0,0 +1,453 @@
+//! Serialize structs into sheet rows
+//!
+//! The field names of the first value become the header row.
+//! A text is always a string cell, the chrono fields are date cells with the helpers of `date`
+//! like `#[serde(with = "spsheet::date::datetime")]`.
+//!
+//! ```
+//! #[macro_use]
+//! extern crate serde_derive;
+//! extern crate spsheet;
+//! use spsheet::Cell;
+//!
+//! #[derive(Serialize)]
+//! struct Item {
+//!     name: String,
+//!     price: u32,
+//! }
+//!
+//! fn main() {
+//!     let items = vec![Item { name: String::from("apple"), price: 120 }];
+//!     let sheet = spsheet::ser::to_sheet("items", &items).unwrap();
+//!     assert_eq!(Some(&Cell::str("price", "")), sheet.get_cell(0, 1));
+//!     assert_eq!(Some(&Cell::float(120.0, "")), sheet.get_cell(1, 1));
+//! }
+//! ```
+use serde::ser;
+use serde::ser::{Impossible, Serialize};
+use std::borrow::Cow;
+use std::error;
+use std::fmt;
+use std::result;
+use chrono::prelude::*;
+use super::{Cell, Sheet, Value, index_to_column_and_row};
+use super::date;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SerError {
+    message: String,
+    reference: Option<String>,
+}
+
+impl SerError {
+    /// Reference of the failing cell like "B3"
+    pub fn get_reference(&self) -> Option<&String> {
+        self.reference.as_ref()
+    }
+
+    pub fn get_message(&self) -> &String {
+        &self.message
+    }
+
+    fn at(mut self, row_index: usize, column_index: usize) -> SerError {
+        if self.reference.is_none() {
+            self.reference = Some(index_to_column_and_row(column_index, row_index));
+        }
+        self
+    }
+}
+
+impl fmt::Display for SerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.reference {
+            Some(ref reference) => write!(f, "{}: {}", reference, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl error::Error for SerError {
+    fn description(&self) -> &str {
+        &self.message
+    }
+}
+
+impl ser::Error for SerError {
+    fn custom<T: fmt::Display>(msg: T) -> SerError {
+        SerError {
+            message: msg.to_string(),
+            reference: None,
+        }
+    }
+}
+
+type Result<T> = result::Result<T, SerError>;
+
+/// Serialize the values as rows below a generated header row.
+pub fn to_sheet<'a, S, T>(name: S, values: &[T]) -> Result<Sheet>
+    where S: Into<Cow<'a, str>>, T: Serialize
+{
+    let mut serializer = Serializer::new(name);
+    for value in values {
+        serializer.serialize_row(value)?;
+    }
+    Ok(serializer.into_sheet())
+}
+
+/// Serializer which appends rows to a sheet.
+pub struct Serializer {
+    sheet: Sheet,
+    headers: Vec<String>,
+    row_index: usize,
+}
+
+impl Serializer {
+    pub fn new<'a, S>(name: S) -> Serializer
+        where S: Into<Cow<'a, str>>
+    {
+        Serializer {
+            sheet: Sheet::new(name),
+            headers: Vec::new(),
+            row_index: 1,
+        }
+    }
+
+    /// Serialize a value to the next row, a value which fails adds no cells and no header
+    pub fn serialize_row<T: Serialize>(&mut self, value: &T) -> Result<()> {
+        value.serialize(RowSerializer {
+            serializer: self,
+            key: None,
+            headers: Vec::new(),
+            cells: Vec::new(),
+        })?;
+        self.row_index += 1;
+        Ok(())
+    }
+
+    pub fn into_sheet(self) -> Sheet {
+        self.sheet
+    }
+}
+
+// The cells of a row and the headers of its new fields are added to the sheet at the end of the row
+struct RowSerializer<'a> {
+    serializer: &'a mut Serializer,
+    key: Option<String>,
+    headers: Vec<String>,
+    cells: Vec<(usize, Value)>,
+}
+
+impl<'a> RowSerializer<'a> {
+    fn column_index(&mut self, key: &str) -> usize {
+        if let Some(column_index) = self.serializer.headers.iter().position(|header| header == key) {
+            return column_index;
+        }
+        let position = match self.headers.iter().position(|header| header == key) {
+            Some(position) => position,
+            None => {
+                self.headers.push(String::from(key));
+                self.headers.len() - 1
+            },
+        };
+        self.serializer.headers.len() + position
+    }
+
+    fn add_field<T: ?Sized + Serialize>(&mut self, key: &str, value: &T) -> Result<()> {
+        let column_index = self.column_index(key);
+        match value.serialize(CellSerializer) {
+            Ok(Some(value)) => {
+                self.cells.push((column_index, value));
+                Ok(())
+            },
+            Ok(None) => Ok(()),
+            Err(err) => Err(err.at(self.serializer.row_index, column_index)),
+        }
+    }
+
+    fn end_row(self) -> Result<()> {
+        let serializer = self.serializer;
+        for header in self.headers {
+            serializer.sheet.add_cell(Cell::str(header.as_str(), ""), 0, serializer.headers.len());
+            serializer.headers.push(header);
+        }
+        for (column_index, value) in self.cells {
+            serializer.sheet.add_cell(Cell::new(value, ""), serializer.row_index, column_index);
+        }
+        Ok(())
+    }
+
+    fn unsupported<T>(&self) -> Result<T> {
+        Err(ser::Error::custom(format!("row {} must be a struct or a map", self.serializer.row_index + 1)))
+    }
+}
+
+impl<'a> ser::Serializer for RowSerializer<'a> {
+    type Ok = ();
+    type Error = SerError;
+    type SerializeSeq = Impossible<(), SerError>;
+    type SerializeTuple = Impossible<(), SerError>;
+    type SerializeTupleStruct = Impossible<(), SerError>;
+    type SerializeTupleVariant = Impossible<(), SerError>;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Impossible<(), SerError>;
+
+    fn serialize_bool(self, _v: bool) -> Result<()> { self.unsupported() }
+    fn serialize_i8(self, _v: i8) -> Result<()> { self.unsupported() }
+    fn serialize_i16(self, _v: i16) -> Result<()> { self.unsupported() }
+    fn serialize_i32(self, _v: i32) -> Result<()> { self.unsupported() }
+    fn serialize_i64(self, _v: i64) -> Result<()> { self.unsupported() }
+    fn serialize_u8(self, _v: u8) -> Result<()> { self.unsupported() }
+    fn serialize_u16(self, _v: u16) -> Result<()> { self.unsupported() }
+    fn serialize_u32(self, _v: u32) -> Result<()> { self.unsupported() }
+    fn serialize_u64(self, _v: u64) -> Result<()> { self.unsupported() }
+    fn serialize_f32(self, _v: f32) -> Result<()> { self.unsupported() }
+    fn serialize_f64(self, _v: f64) -> Result<()> { self.unsupported() }
+    fn serialize_char(self, _v: char) -> Result<()> { self.unsupported() }
+    fn serialize_str(self, _v: &str) -> Result<()> { self.unsupported() }
+    fn serialize_bytes(self, _v: &[u8]) -> Result<()> { self.unsupported() }
+    fn serialize_none(self) -> Result<()> { self.unsupported() }
+    fn serialize_unit(self) -> Result<()> { self.unsupported() }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> { self.unsupported() }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _variant_index: u32, _variant: &'static str) -> Result<()> {
+        self.unsupported()
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _name: &'static str, value: &T) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _value: &T) -> Result<()> {
+        self.unsupported()
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        self.unsupported()
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        self.unsupported()
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct> {
+        self.unsupported()
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeTupleVariant> {
+        self.unsupported()
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(self)
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeStructVariant> {
+        self.unsupported()
+    }
+}
+
+impl<'a> ser::SerializeStruct for RowSerializer<'a> {
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T) -> Result<()> {
+        self.add_field(key, value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.end_row()
+    }
+}
+
+impl<'a> ser::SerializeMap for RowSerializer<'a> {
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<()> {
+        match key.serialize(CellSerializer)? {
+            Some(Value::Str(key)) => {
+                self.key = Some(key);
+                Ok(())
+            },
+            _ => Err(ser::Error::custom("map keys must be strings")),
+        }
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        match self.key.take() {
+            Some(key) => self.add_field(key.as_str(), value),
+            None => Err(ser::Error::custom("map value without a key")),
+        }
+    }
+
+    fn end(self) -> Result<()> {
+        self.end_row()
+    }
+}
+
+// Serializes one field into the value of a cell, None leaves the cell empty.
+// The date helpers write the chrono types in a newtype of their text, which is a date.
+struct CellSerializer;
+
+impl CellSerializer {
+    fn unsupported<T>(&self, kind: &str) -> Result<T> {
+        Err(ser::Error::custom(format!("{} can not be written to a cell", kind)))
+    }
+}
+
+impl ser::Serializer for CellSerializer {
+    type Ok = Option<Value>;
+    type Error = SerError;
+    type SerializeSeq = Impossible<Option<Value>, SerError>;
+    type SerializeTuple = Impossible<Option<Value>, SerError>;
+    type SerializeTupleStruct = Impossible<Option<Value>, SerError>;
+    type SerializeTupleVariant = Impossible<Option<Value>, SerError>;
+    type SerializeMap = Impossible<Option<Value>, SerError>;
+    type SerializeStruct = Impossible<Option<Value>, SerError>;
+    type SerializeStructVariant = Impossible<Option<Value>, SerError>;
+
+    fn serialize_bool(self, v: bool) -> Result<Option<Value>> {
+        Ok(Some(Value::Bool(v)))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Option<Value>> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Option<Value>> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Option<Value>> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Option<Value>> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Option<Value>> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Option<Value>> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Option<Value>> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Option<Value>> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Option<Value>> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Option<Value>> {
+        Ok(Some(Value::Float(v)))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Option<Value>> {
+        Ok(Some(Value::Str(v.to_string())))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Option<Value>> {
+        Ok(Some(Value::Str(String::from(v))))
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Option<Value>> {
+        self.unsupported("bytes")
+    }
+
+    fn serialize_none(self) -> Result<Option<Value>> {
+        Ok(None)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Option<Value>> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Option<Value>> {
+        Ok(None)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Option<Value>> {
+        Ok(None)
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str) -> Result<Option<Value>> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, name: &'static str, value: &T) -> Result<Option<Value>> {
+        let value = value.serialize(self)?;
+        if name != date::CELL_DATE {
+            return Ok(value);
+        }
+        match value {
+            Some(Value::Str(text)) => text_to_date(&text).map(Some)
+                .ok_or_else(|| ser::Error::custom(format!("invalid date: {}", text))),
+            value => Ok(value),
+        }
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _value: &T) -> Result<Option<Value>> {
+        self.unsupported("a newtype variant")
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        self.unsupported("a sequence")
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        self.unsupported("a tuple")
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct> {
+        self.unsupported("a tuple struct")
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeTupleVariant> {
+        self.unsupported("a tuple variant")
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        self.unsupported("a map")
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        self.unsupported("a struct")
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeStructVariant> {
+        self.unsupported("a struct variant")
+    }
+}
+
+// RFC 3339 like "2017-12-02T13:30:00+09:00" and "2017-12-02T13:30:00" in UTC are dates,
+// "2017-12-02" is a date only and "13:30:00" is a time
+fn text_to_date(text: &str) -> Option<Value> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(text) {
+        Some(Value::Date(dt.with_timezone(&Utc)))
+    } else if let Ok(dt) = NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f") {
+        Some(Value::Date(DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc)))
+    } else if let Ok(date) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        Some(Value::DateOnly(date))
+    } else if let Ok(time) = NaiveTime::parse_from_str(text, "%H:%M:%S%.f") {
+        Some(Value::Time(time))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,144 @@
+//! Settings of the window showing a sheet, the frozen or split panes, the zoom and the selected cells
+use range::Range;
+
+/// Panes of the window, the rows above and the columns left of the split stay or scroll by themselves
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Pane {
+    /// The first `rows` rows and `columns` columns don't scroll
+    Frozen { rows: usize, columns: usize },
+    /// The window is split at `x` points from the left and `y` points from the top, 0 for no split,
+    /// each pane scrolls by itself
+    Split { x: f64, y: f64 },
+}
+
+impl Pane {
+    /// Number of the frozen rows and columns, (0, 0) for a split
+    pub fn get_frozen(&self) -> (usize, usize) {
+        match *self {
+            Pane::Frozen { rows, columns } => (rows, columns),
+            Pane::Split { .. } => (0, 0),
+        }
+    }
+}
+
+/// SheetView is how the sheet is shown when the file is opened.
+///
+/// ```
+/// use spsheet::Sheet;
+/// use spsheet::range::Range;
+/// use spsheet::sheet_view::{Pane, SheetView};
+///
+/// let mut view = SheetView::new();
+/// view.set_pane(Some(Pane::Frozen { rows: 1, columns: 0 }));
+/// view.set_zoom(150);
+/// view.set_active_cell(3, 1);
+/// view.set_selection(vec![Range::new("report", 3, 1, 5, 2)]);
+/// let mut sheet = Sheet::new("report");
+/// sheet.set_view(view);
+/// assert_eq!(Some(&Pane::Frozen { rows: 1, columns: 0 }), sheet.get_view().get_pane());
+/// assert!(sheet.get_view().is_show_grid_lines());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct SheetView {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pane: Option<Pane>,
+    zoom: u32,
+    show_grid_lines: bool,
+    show_headers: bool,
+    right_to_left: bool,
+    active_cell: (usize, usize),
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    selection: Vec<Range>,
+}
+
+impl Default for SheetView {
+    fn default() -> SheetView {
+        SheetView {
+            pane: None,
+            zoom: 100,
+            show_grid_lines: true,
+            show_headers: true,
+            right_to_left: false,
+            active_cell: (0, 0),
+            selection: Vec::new(),
+        }
+    }
+}
+
+impl SheetView {
+    pub fn new() -> SheetView {
+        SheetView::default()
+    }
+
+    pub fn get_pane(&self) -> Option<&Pane> {
+        self.pane.as_ref()
+    }
+
+    pub fn set_pane(&mut self, pane: Option<Pane>) {
+        self.pane = pane;
+    }
+
+    /// Zoom in percent, 100 by default
+    pub fn get_zoom(&self) -> u32 {
+        self.zoom
+    }
+
+    /// The zoom is kept between 10 and 400 percent
+    pub fn set_zoom(&mut self, zoom: u32) {
+        self.zoom = zoom.clamp(10, 400);
+    }
+
+    pub fn is_show_grid_lines(&self) -> bool {
+        self.show_grid_lines
+    }
+
+    pub fn set_show_grid_lines(&mut self, show_grid_lines: bool) {
+        self.show_grid_lines = show_grid_lines;
+    }
+
+    /// The column letters and the row numbers are shown.
+    /// ods shows or hides them on every sheet by the view of the active sheet.
+    pub fn is_show_headers(&self) -> bool {
+        self.show_headers
+    }
+
+    pub fn set_show_headers(&mut self, show_headers: bool) {
+        self.show_headers = show_headers;
+    }
+
+    /// The first column is on the right
+    pub fn is_right_to_left(&self) -> bool {
+        self.right_to_left
+    }
+
+    pub fn set_right_to_left(&mut self, right_to_left: bool) {
+        self.right_to_left = right_to_left;
+    }
+
+    /// (row, column) of the cell with the cursor
+    pub fn get_active_cell(&self) -> (usize, usize) {
+        self.active_cell
+    }
+
+    pub fn set_active_cell(&mut self, row_index: usize, column_index: usize) {
+        self.active_cell = (row_index, column_index);
+    }
+
+    /// Selected ranges, empty when only the active cell is selected.
+    /// ods keeps only the active cell.
+    pub fn get_selection(&self) -> &Vec<Range> {
+        &self.selection
+    }
+
+    pub fn set_selection(&mut self, selection: Vec<Range>) {
+        self.selection = selection;
+    }
+
+    #[cfg(feature = "serde")]
+    pub(crate) fn is_default(&self) -> bool {
+        *self == SheetView::default()
+    }
+}
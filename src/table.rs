@@ -0,0 +1,484 @@
+//! Tables of a sheet, the named lists with a header row, an optional totals row and a style,
+//! and their structured references like "Sales[Amount]"
+use range::{self, Range};
+
+/// Function of the totals row for a column
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TotalsFunction {
+    Sum,
+    Average,
+    Count,
+    CountNumbers,
+    Max,
+    Min,
+    StdDev,
+    Var,
+}
+
+const TOTALS_FUNCTIONS: [(TotalsFunction, &str, u32); 8] = [
+    (TotalsFunction::Sum, "sum", 109),
+    (TotalsFunction::Average, "average", 101),
+    (TotalsFunction::Count, "count", 103),
+    (TotalsFunction::CountNumbers, "countNums", 102),
+    (TotalsFunction::Max, "max", 104),
+    (TotalsFunction::Min, "min", 105),
+    (TotalsFunction::StdDev, "stdDev", 107),
+    (TotalsFunction::Var, "var", 110),
+];
+
+impl TotalsFunction {
+    /// Name of the totalsRowFunction of a xlsx tableColumn like "sum"
+    pub(crate) fn name(&self) -> &'static str {
+        TOTALS_FUNCTIONS.iter().find(|&&(function, _, _)| function == *self).map(|&(_, name, _)| name).unwrap_or("sum")
+    }
+
+    pub(crate) fn from_name(name: &str) -> Option<TotalsFunction> {
+        TOTALS_FUNCTIONS.iter().find(|&&(_, n, _)| n == name).map(|&(function, _, _)| function)
+    }
+
+    /// Number of the function of SUBTOTAL skipping the hidden rows, 109 for the sum
+    pub fn subtotal_number(&self) -> u32 {
+        TOTALS_FUNCTIONS.iter().find(|&&(function, _, _)| function == *self).map(|&(_, _, number)| number).unwrap_or(109)
+    }
+}
+
+/// Cell of the totals row under a column
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Totals {
+    /// A text like "Total"
+    Label(String),
+    Function(TotalsFunction),
+}
+
+/// Column of a table, its name is the text of its header cell
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TableColumn {
+    name: String,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    totals: Option<Totals>,
+}
+
+impl TableColumn {
+    pub fn new(name: &str) -> TableColumn {
+        TableColumn {
+            name: name.to_string(),
+            totals: None,
+        }
+    }
+
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn set_name(&mut self, name: &str) {
+        self.name = name.to_string();
+    }
+
+    pub fn get_totals(&self) -> Option<&Totals> {
+        self.totals.as_ref()
+    }
+
+    pub fn set_totals(&mut self, totals: Option<Totals>) {
+        self.totals = totals;
+    }
+}
+
+/// Built-in style of a table like "TableStyleMedium2" with the parts it emphasizes
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TableStyle {
+    name: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    show_first_column: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    show_last_column: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    show_row_stripes: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    show_column_stripes: bool,
+}
+
+impl TableStyle {
+    /// A style with the row stripes
+    pub fn new(name: &str) -> TableStyle {
+        TableStyle {
+            name: name.to_string(),
+            show_first_column: false,
+            show_last_column: false,
+            show_row_stripes: true,
+            show_column_stripes: false,
+        }
+    }
+
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn set_name(&mut self, name: &str) {
+        self.name = name.to_string();
+    }
+
+    pub fn is_show_first_column(&self) -> bool {
+        self.show_first_column
+    }
+
+    pub fn set_show_first_column(&mut self, show_first_column: bool) {
+        self.show_first_column = show_first_column;
+    }
+
+    pub fn is_show_last_column(&self) -> bool {
+        self.show_last_column
+    }
+
+    pub fn set_show_last_column(&mut self, show_last_column: bool) {
+        self.show_last_column = show_last_column;
+    }
+
+    pub fn is_show_row_stripes(&self) -> bool {
+        self.show_row_stripes
+    }
+
+    pub fn set_show_row_stripes(&mut self, show_row_stripes: bool) {
+        self.show_row_stripes = show_row_stripes;
+    }
+
+    pub fn is_show_column_stripes(&self) -> bool {
+        self.show_column_stripes
+    }
+
+    pub fn set_show_column_stripes(&mut self, show_column_stripes: bool) {
+        self.show_column_stripes = show_column_stripes;
+    }
+}
+
+/// Table is a named list of the cells of its range.
+/// The cells aren't changed by the table, its header cells have the names of its columns
+/// and its totals cells have the labels or formulas like "SUBTOTAL(109,Sales[Amount])".
+/// A column without a name in the table is named by its header cell when the table is written.
+/// Only xlsx has tables, ods keeps the cells of them.
+///
+/// ```
+/// use spsheet::Sheet;
+/// use spsheet::range::Range;
+/// use spsheet::table::{Table, TableColumn, Totals, TotalsFunction};
+///
+/// let mut amount = TableColumn::new("Amount");
+/// amount.set_totals(Some(Totals::Function(TotalsFunction::Sum)));
+/// let mut table = Table::new("Sales", Range::new("orders", 0, 0, 4, 1));
+/// table.set_columns(vec![TableColumn::new("Region"), amount]);
+/// table.set_totals_row(true);
+/// assert_eq!("orders!$B$2:$B$4", table.get_reference_range("Sales[Amount]").unwrap().to_string());
+/// assert_eq!("orders!$A$5:$B$5", table.get_reference_range("Sales[#Totals]").unwrap().to_string());
+/// let mut sheet = Sheet::new("orders");
+/// sheet.add_table(table);
+/// assert!(sheet.get_table("sales").is_some());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Table {
+    name: String,
+    range: Range,
+    #[cfg_attr(feature = "serde", serde(default = "default_header_row"))]
+    header_row: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    totals_row: bool,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Vec::is_empty"))]
+    columns: Vec<TableColumn>,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    style: Option<TableStyle>,
+}
+
+#[cfg(feature = "serde")]
+fn default_header_row() -> bool {
+    true
+}
+
+impl Table {
+    /// A table with the header row and the style "TableStyleMedium2"
+    pub fn new(name: &str, range: Range) -> Table {
+        Table {
+            name: name.to_string(),
+            range,
+            header_row: true,
+            totals_row: false,
+            columns: Vec::new(),
+            style: Some(TableStyle::new("TableStyleMedium2")),
+        }
+    }
+
+    /// Name of the table in the formulas, unique in the book regardless of case
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn set_name(&mut self, name: &str) {
+        self.name = name.to_string();
+    }
+
+    /// Cells of the table with its header and totals rows
+    pub fn get_range(&self) -> &Range {
+        &self.range
+    }
+
+    pub fn set_range(&mut self, range: Range) {
+        self.range = range;
+    }
+
+    pub fn has_header_row(&self) -> bool {
+        self.header_row
+    }
+
+    pub fn set_header_row(&mut self, header_row: bool) {
+        self.header_row = header_row;
+    }
+
+    /// The last row of the range is the totals row
+    pub fn has_totals_row(&self) -> bool {
+        self.totals_row
+    }
+
+    pub fn set_totals_row(&mut self, totals_row: bool) {
+        self.totals_row = totals_row;
+    }
+
+    /// Columns from the first column of the range
+    pub fn get_columns(&self) -> &Vec<TableColumn> {
+        &self.columns
+    }
+
+    pub fn set_columns(&mut self, columns: Vec<TableColumn>) {
+        self.columns = columns;
+    }
+
+    pub fn add_column(&mut self, column: TableColumn) {
+        self.columns.push(column);
+    }
+
+    /// Column of the range of a column name regardless of case, 0 for its first column
+    pub fn get_column_index(&self, name: &str) -> Option<usize> {
+        let name = name.to_lowercase();
+        let width = self.range.get_last_column() - self.range.get_first_column() + 1;
+        self.columns.iter().take(width).position(|column| column.name.to_lowercase() == name)
+    }
+
+    /// Style of the table, None for a table without a style
+    pub fn get_style(&self) -> Option<&TableStyle> {
+        self.style.as_ref()
+    }
+
+    pub fn set_style(&mut self, style: Option<TableStyle>) {
+        self.style = style;
+    }
+
+    pub fn get_header_range(&self) -> Option<Range> {
+        self.rows(Part::Headers)
+    }
+
+    /// Rows between the header row and the totals row, None for a table without them
+    pub fn get_data_range(&self) -> Option<Range> {
+        self.rows(Part::Data)
+    }
+
+    pub fn get_totals_range(&self) -> Option<Range> {
+        self.rows(Part::Totals)
+    }
+
+    /// Range of a structured reference to the table like "Sales", "Sales[Amount]", "Sales[#Headers]",
+    /// "Sales[[#All],[Region]:[Amount]]" or "Sales[[#Headers],[#Data],[Amount]]".
+    /// The names are compared regardless of case, a reference to "#This Row" or to another table is None.
+    pub fn get_reference_range(&self, reference: &str) -> Option<Range> {
+        let (name, specifier) = parse_structured_reference(reference)?;
+        if name.to_lowercase() != self.name.to_lowercase() {
+            return None;
+        }
+        let mut parts = Vec::new();
+        let mut columns = None;
+        for item in specifier {
+            match item {
+                Item::Part(part) => parts.push(part),
+                Item::Columns(first, last) => {
+                    if columns.is_some() {
+                        return None;
+                    }
+                    let (first, last) = (self.get_column_index(&first)?, self.get_column_index(&last)?);
+                    columns = Some((first.min(last), first.max(last)));
+                },
+            }
+        }
+        if parts.is_empty() {
+            parts.push(Part::Data);
+        }
+        // the parts are rows next to each other
+        let mut rows: Option<Range> = None;
+        for part in parts {
+            let part_rows = self.rows(part)?;
+            rows = Some(match rows {
+                Some(rows) => {
+                    if part_rows.get_first_row() > rows.get_last_row() + 1 || rows.get_first_row() > part_rows.get_last_row() + 1 {
+                        return None;
+                    }
+                    Range::new(rows.get_sheet_name().as_str(), rows.get_first_row().min(part_rows.get_first_row()), rows.get_first_column(),
+                        rows.get_last_row().max(part_rows.get_last_row()), rows.get_last_column())
+                },
+                None => part_rows,
+            });
+        }
+        let rows = rows?;
+        Some(match columns {
+            Some((first, last)) => {
+                let first_column = self.range.get_first_column();
+                Range::new(rows.get_sheet_name().as_str(), rows.get_first_row(), first_column + first, rows.get_last_row(), first_column + last)
+            },
+            None => rows,
+        })
+    }
+
+    /// Rows of a part of the table with all its columns
+    fn rows(&self, part: Part) -> Option<Range> {
+        let range = &self.range;
+        let header = if self.header_row { 1 } else { 0 };
+        let totals = if self.totals_row { 1 } else { 0 };
+        let (first, last) = match part {
+            Part::All => (range.get_first_row(), range.get_last_row()),
+            Part::Headers if self.header_row => (range.get_first_row(), range.get_first_row()),
+            Part::Totals if self.totals_row => (range.get_last_row(), range.get_last_row()),
+            Part::Data if range.get_first_row() + header + totals <= range.get_last_row() => {
+                (range.get_first_row() + header, range.get_last_row() - totals)
+            },
+            _ => return None,
+        };
+        Some(Range::new(range.get_sheet_name().as_str(), first, range.get_first_column(), last, range.get_last_column()))
+    }
+
+    /// Move the range like the rows inserted at `row_index`
+    pub(crate) fn insert_rows(&mut self, row_index: usize, count: usize) {
+        let mut ranges = vec![self.range.clone()];
+        range::insert_rows(&mut ranges, row_index, count);
+        self.range = ranges.remove(0);
+    }
+
+    /// Move the range like the rows removed at `row_index`, false when all the rows of the range are removed
+    pub(crate) fn remove_rows(&mut self, row_index: usize, count: usize) -> bool {
+        let mut ranges = vec![self.range.clone()];
+        range::remove_rows(&mut ranges, row_index, count);
+        match ranges.pop() {
+            Some(range) => {
+                self.range = range;
+                true
+            },
+            None => false,
+        }
+    }
+}
+
+/// Rows of a table a structured reference refers to
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Part {
+    All,
+    Headers,
+    Data,
+    Totals,
+}
+
+/// Item of the specifier of a structured reference, the columns are the first and the last one
+#[derive(Debug, Clone, PartialEq)]
+enum Item {
+    Part(Part),
+    Columns(String, String),
+}
+
+/// The table name of a structured reference and the items of its specifier, "Sales" has no items.
+/// A character after "'" in a column name is taken as it is like "Price'[USD']".
+fn parse_structured_reference(src: &str) -> Option<(String, Vec<Item>)> {
+    let src = src.trim();
+    let (name, specifier) = match src.find('[') {
+        Some(index) => (src[..index].trim(), &src[index..]),
+        None => (src, ""),
+    };
+    if name.is_empty() || name.contains(|c: char| c == ']' || c == '!' || c.is_whitespace()) {
+        return None;
+    }
+    if specifier.is_empty() {
+        return Some((name.to_string(), Vec::new()));
+    }
+    if !specifier.ends_with(']') {
+        return None;
+    }
+    let inner = specifier[1..specifier.len() - 1].trim();
+    let items = if inner.starts_with('[') {
+        let mut items = Vec::new();
+        let mut rest = inner;
+        loop {
+            let (first, after) = bracketed(rest)?;
+            rest = after.trim_start();
+            let item = if rest.starts_with(':') {
+                let (last, after) = bracketed(rest[1..].trim_start())?;
+                rest = after.trim_start();
+                Item::Columns(first, last)
+            } else {
+                item(&first)?
+            };
+            items.push(item);
+            if rest.is_empty() {
+                break;
+            }
+            if !rest.starts_with(',') {
+                return None;
+            }
+            rest = rest[1..].trim_start();
+        }
+        items
+    } else if inner.is_empty() {
+        Vec::new()
+    } else {
+        vec![item(&unescape(inner)?)?]
+    };
+    Some((name.to_string(), items))
+}
+
+/// "[#Headers]" or "[Amount]" at the start with the rest after it
+fn bracketed(src: &str) -> Option<(String, &str)> {
+    if !src.starts_with('[') {
+        return None;
+    }
+    let mut escaped = false;
+    for (index, c) in src.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\'' => escaped = true,
+            ']' => return Some((unescape(&src[1..index])?, &src[index + 1..])),
+            '[' => return None,
+            _ => {},
+        }
+    }
+    None
+}
+
+fn unescape(src: &str) -> Option<String> {
+    let mut result = String::new();
+    let mut chars = src.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => result.push(chars.next()?),
+            '[' | ']' => return None,
+            _ => result.push(c),
+        }
+    }
+    Some(result)
+}
+
+/// A special item like "#All" or a column, "#This Row" is None
+fn item(src: &str) -> Option<Item> {
+    let part = match src.to_lowercase().as_str() {
+        "#all" => Part::All,
+        "#headers" => Part::Headers,
+        "#data" => Part::Data,
+        "#totals" => Part::Totals,
+        special if special.starts_with('#') => return None,
+        _ => return Some(Item::Columns(src.to_string(), src.to_string())),
+    };
+    Some(Item::Part(part))
+}
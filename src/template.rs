@@ -0,0 +1,309 @@
+//! Fill a designed xlsx or ods file
+//!
+//! A cell like `{{customer_name}}` is replaced with the value of the key,
+//! a placeholder inside a text like `Dear {{customer_name}}` is replaced with the text of the value.
+//! The rows which refer to a list like `{{items.name}}` are a block,
+//! the block is repeated for each record of the list.
+//! The cells keep their style, the repeated rows keep the height and style of the block.
+//! The formulas of the block are moved to each record like copied cells,
+//! a formula below the block like `SUM(B2:B2)` over the block is extended over the records.
+//!
+//! ```
+//! use spsheet::{Sheet, Cell, Value};
+//! use spsheet::template::{self, Data};
+//!
+//! let mut sheet = Sheet::new("invoice");
+//! sheet.add_cell(Cell::str("Dear {{customer_name}}", ""), 0, 0);
+//! sheet.add_cell(Cell::str("{{items.name}}", ""), 1, 0);
+//! sheet.add_cell(Cell::str("{{items.price}}", ""), 1, 1);
+//! sheet.add_cell(Cell::str("{{total}}", ""), 2, 1);
+//!
+//! let mut data = Data::new();
+//! data.set_value("customer_name", Value::Str(String::from("Tanaka")));
+//! data.set_value("total", Value::Float(300.0));
+//! for (name, price) in vec![("apple", 100.0), ("orange", 200.0)] {
+//!     let mut record = Data::new();
+//!     record.set_value("name", Value::Str(String::from(name)));
+//!     record.set_value("price", Value::Float(price));
+//!     data.add_record("items", record);
+//! }
+//! template::fill_sheet(&mut sheet, &data).unwrap();
+//!
+//! assert_eq!(Some(&Cell::str("Dear Tanaka", "")), sheet.get_cell(0, 0));
+//! assert_eq!(Some(&Cell::str("orange", "")), sheet.get_cell(2, 0));
+//! assert_eq!(Some(&Cell::float(300.0, "")), sheet.get_cell(3, 1));
+//! ```
+use std::collections::{BTreeMap, HashMap};
+use super::{Book, Sheet, Cell, Value, index_to_column_and_row};
+
+#[cfg(any(feature = "xlsx", feature = "ods"))]
+use std::path::Path;
+#[cfg(feature = "ods")]
+use super::ods::{self, OdsError};
+#[cfg(feature = "xlsx")]
+use super::xlsx::{self, XlsxError};
+
+#[derive(Debug)]
+pub enum TemplateError {
+    /// A placeholder without value, the key and the cell like "B2"
+    Missing(String, String),
+    #[cfg(feature = "xlsx")]
+    Xlsx(XlsxError),
+    #[cfg(feature = "ods")]
+    Ods(OdsError),
+}
+
+#[cfg(feature = "xlsx")]
+impl From<XlsxError> for TemplateError {
+    fn from(err: XlsxError) -> TemplateError {
+        TemplateError::Xlsx(err)
+    }
+}
+
+#[cfg(feature = "ods")]
+impl From<OdsError> for TemplateError {
+    fn from(err: OdsError) -> TemplateError {
+        TemplateError::Ods(err)
+    }
+}
+
+type Result<T> = ::std::result::Result<T, TemplateError>;
+
+/// Data has the values of the placeholders and the records of the lists.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Data {
+    values: HashMap<String, Cell>,
+    lists: HashMap<String, Vec<Data>>,
+}
+
+impl Data {
+    pub fn new() -> Data {
+        Data {
+            values: HashMap::new(),
+            lists: HashMap::new(),
+        }
+    }
+
+    pub fn set_value<S: Into<String>>(&mut self, key: S, value: Value) {
+        self.values.insert(key.into(), Cell::new(value, ""));
+    }
+
+    /// Set a value with its format, a date without format is shown by the style of the template cell
+    pub fn set_cell<S: Into<String>>(&mut self, key: S, cell: Cell) {
+        self.values.insert(key.into(), cell);
+    }
+
+    pub fn get_cell(&self, key: &str) -> Option<&Cell> {
+        self.values.get(key)
+    }
+
+    pub fn add_record<S: Into<String>>(&mut self, list: S, record: Data) {
+        self.lists.entry(list.into()).or_default().push(record);
+    }
+
+    pub fn get_records(&self, list: &str) -> Option<&Vec<Data>> {
+        self.lists.get(list)
+    }
+}
+
+/// Fill every sheet of the book
+pub fn fill_book(book: &mut Book, data: &Data) -> Result<()> {
+    for index in 0..book.get_sheet_size() {
+        fill_sheet(book.get_sheet_mut(index), data)?;
+    }
+    Ok(())
+}
+
+/// Replace the sheet at `index` with a filled copy for each page, named by the page.
+/// The tables of the first page keep their names, the tables of the other pages are named like "Sales_2".
+pub fn fill_sheets(book: &mut Book, index: usize, pages: Vec<(String, Data)>) -> Result<()> {
+    let template = book.remove_sheet(index);
+    for (page_index, (name, data)) in pages.into_iter().enumerate() {
+        let mut sheet = template.clone();
+        sheet.set_name(name);
+        if page_index > 0 {
+            let names: Vec<String> = sheet.get_tables().iter().map(|table| table.get_name().clone()).collect();
+            for name in names {
+                let new_name = (page_index + 1..).map(|number| format!("{}_{}", name, number))
+                    .find(|new_name| book.get_table(new_name).is_none() && sheet.get_table(new_name).is_none())
+                    .unwrap();
+                sheet.rename_table(&name, &new_name);
+            }
+        }
+        fill_sheet(&mut sheet, &data)?;
+        book.insert_sheet(index + page_index, sheet);
+    }
+    Ok(())
+}
+
+pub fn fill_sheet(sheet: &mut Sheet, data: &Data) -> Result<()> {
+    // blocks are expanded from the bottom, so the rows above keep their index
+    let mut blocks = find_blocks(sheet, data);
+    blocks.reverse();
+    let mut filled_rows = Vec::new();
+    for (start, length, list) in blocks {
+        let records = data.get_records(&list).unwrap();
+        if records.is_empty() {
+            sheet.remove_rows(start, length);
+            for row_index in filled_rows.iter_mut().filter(|row_index| **row_index >= start + length) {
+                *row_index -= length;
+            }
+            continue;
+        }
+        // the ranges of the formulas ending at the block, like a total, are extended over the records
+        let count = length * (records.len() - 1);
+        sheet.insert_rows_extending(start + length, count, true);
+        for row_index in filled_rows.iter_mut().filter(|row_index| **row_index >= start + length) {
+            *row_index += count;
+        }
+        for record_index in 1..records.len() {
+            for offset in 0..length {
+                sheet.copy_row(start + offset, start + record_index * length + offset);
+            }
+        }
+        for (record_index, record) in records.iter().enumerate() {
+            for offset in 0..length {
+                fill_row(sheet, start + record_index * length + offset, data, Some((&list, record)))?;
+                filled_rows.push(start + record_index * length + offset);
+            }
+        }
+    }
+    // the rows of the records are filled, a value like "{{total}}" in a record is kept as it is
+    let mut row_indexes: Vec<usize> = sheet.get_rows().keys().cloned().filter(|row_index| !filled_rows.contains(row_index)).collect();
+    row_indexes.sort();
+    for row_index in row_indexes {
+        fill_row(sheet, row_index, data, None)?;
+    }
+    Ok(())
+}
+
+/// Blocks of rows referring to the same list, (first row, number of rows, list)
+fn find_blocks(sheet: &Sheet, data: &Data) -> Vec<(usize, usize, String)> {
+    let mut row_lists: BTreeMap<usize, String> = BTreeMap::new();
+    sheet.sorted_access(|row_index, _, cell| {
+        if let Value::Str(text) = cell.get_value() {
+            for (_, _, key) in placeholders(text) {
+                if let Some(position) = key.find('.') {
+                    let list = &key[..position];
+                    if data.get_records(list).is_some() && !row_lists.contains_key(&row_index) {
+                        row_lists.insert(row_index, String::from(list));
+                    }
+                }
+            }
+        }
+    });
+    let mut blocks: Vec<(usize, usize, String)> = Vec::new();
+    for (row_index, list) in row_lists {
+        if let Some(last) = blocks.last_mut() {
+            if last.0 + last.1 == row_index && last.2 == list {
+                last.1 += 1;
+                continue;
+            }
+        }
+        blocks.push((row_index, 1, list));
+    }
+    blocks
+}
+
+fn fill_row(sheet: &mut Sheet, row_index: usize, data: &Data, record: Option<(&String, &Data)>) -> Result<()> {
+    let columns: Vec<(usize, Cell)> = match sheet.get_rows().get(&row_index) {
+        Some(columns) => columns.iter().map(|(index, cell)| (*index, cell.clone())).collect(),
+        None => return Ok(()),
+    };
+    for (column_index, cell) in columns {
+        let text = match cell.get_value() {
+            Value::Str(text) => text.clone(),
+            _ => continue,
+        };
+        let found = placeholders(&text);
+        if found.is_empty() {
+            continue;
+        }
+        let lookup = |key: &String| -> Result<Option<&Cell>> {
+            if let Some((list, values)) = record {
+                if key.starts_with(list.as_str()) && key[list.len()..].starts_with('.') {
+                    let field = &key[list.len() + 1..];
+                    return match values.get_cell(field) {
+                        Some(value) => Ok(Some(value)),
+                        None => Err(TemplateError::Missing(key.clone(), index_to_column_and_row(column_index, row_index))),
+                    };
+                }
+            }
+            if let Some(position) = key.find('.') {
+                // the list of another block, filled with its own records
+                if data.get_records(&key[..position]).is_some() {
+                    return Ok(None);
+                }
+            }
+            match data.get_cell(key) {
+                Some(value) => Ok(Some(value)),
+                None => Err(TemplateError::Missing(key.clone(), index_to_column_and_row(column_index, row_index))),
+            }
+        };
+
+        // a cell which is only a placeholder takes the value itself
+        if found.len() == 1 && text.trim() == &text[found[0].0..found[0].1] {
+            if let Some(value) = lookup(&found[0].2)? {
+                let mut new_cell = value.clone();
+                new_cell.set_style_id(cell.get_style_id().cloned());
+                sheet.add_cell(new_cell, row_index, column_index);
+            }
+            continue;
+        }
+        let mut result = String::new();
+        let mut last = 0;
+        for (start, end, key) in found {
+            result.push_str(&text[last..start]);
+            match lookup(&key)? {
+                Some(value) => result.push_str(&value.get_text()),
+                None => result.push_str(&text[start..end]),
+            }
+            last = end;
+        }
+        result.push_str(&text[last..]);
+        let mut new_cell = Cell::new(Value::Str(result), cell.get_format().get_content().as_str());
+        new_cell.set_style_id(cell.get_style_id().cloned());
+        sheet.add_cell(new_cell, row_index, column_index);
+    }
+    Ok(())
+}
+
+
+/// Placeholders in the text, (start, end, key)
+fn placeholders(text: &str) -> Vec<(usize, usize, String)> {
+    let mut result = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = text[offset..].find("{{") {
+        let start = offset + start;
+        match text[start + 2..].find("}}") {
+            Some(length) => {
+                let end = start + 2 + length + 2;
+                let key = text[start + 2..end - 2].trim();
+                if !key.is_empty() {
+                    result.push((start, end, String::from(key)));
+                }
+                offset = end;
+            },
+            None => break,
+        }
+    }
+    result
+}
+
+/// Fill a xlsx template and write it, the parts spsheet doesn't model are kept
+#[cfg(feature = "xlsx")]
+pub fn fill_xlsx(template: &Path, output: &Path, data: &Data) -> Result<()> {
+    let mut book = xlsx::open(template)?;
+    fill_book(&mut book, data)?;
+    xlsx::write(&book, output)?;
+    Ok(())
+}
+
+/// Fill a ods template and write it, the parts spsheet doesn't model are kept
+#[cfg(feature = "ods")]
+pub fn fill_ods(template: &Path, output: &Path, data: &Data) -> Result<()> {
+    let mut book = ods::open(template)?;
+    fill_book(&mut book, data)?;
+    ods::write(&book, output)?;
+    Ok(())
+}
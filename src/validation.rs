@@ -0,0 +1,316 @@
+//! Data validation rules of cells, the drop-down lists of the allowed values and the alerts of invalid values
+use std::borrow::Cow;
+use chrono::prelude::*;
+use range::{self, Range};
+use Value;
+
+/// Comparison of a value with its bounds, the bounds of `Between` and `NotBetween` are included
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Operator<T> {
+    Between(T, T),
+    NotBetween(T, T),
+    Equal(T),
+    NotEqual(T),
+    GreaterThan(T),
+    LessThan(T),
+    GreaterThanOrEqual(T),
+    LessThanOrEqual(T),
+}
+
+impl<T: PartialOrd> Operator<T> {
+    pub fn contains(&self, value: &T) -> bool {
+        match *self {
+            Operator::Between(ref min, ref max) => min <= value && value <= max,
+            Operator::NotBetween(ref min, ref max) => value < min || max < value,
+            Operator::Equal(ref bound) => value == bound,
+            Operator::NotEqual(ref bound) => value != bound,
+            Operator::GreaterThan(ref bound) => value > bound,
+            Operator::LessThan(ref bound) => value < bound,
+            Operator::GreaterThanOrEqual(ref bound) => value >= bound,
+            Operator::LessThanOrEqual(ref bound) => value <= bound,
+        }
+    }
+}
+
+impl<T> Operator<T> {
+    /// Name of the operator in xlsx like "notBetween"
+    pub(crate) fn name(&self) -> &'static str {
+        match *self {
+            Operator::Between(..) => "between",
+            Operator::NotBetween(..) => "notBetween",
+            Operator::Equal(_) => "equal",
+            Operator::NotEqual(_) => "notEqual",
+            Operator::GreaterThan(_) => "greaterThan",
+            Operator::LessThan(_) => "lessThan",
+            Operator::GreaterThanOrEqual(_) => "greaterThanOrEqual",
+            Operator::LessThanOrEqual(_) => "lessThanOrEqual",
+        }
+    }
+
+    /// The bound and the upper bound of `Between` and `NotBetween`
+    pub(crate) fn bounds(&self) -> (&T, Option<&T>) {
+        match *self {
+            Operator::Between(ref min, ref max) | Operator::NotBetween(ref min, ref max) => (min, Some(max)),
+            Operator::Equal(ref bound) | Operator::NotEqual(ref bound)
+            | Operator::GreaterThan(ref bound) | Operator::LessThan(ref bound)
+            | Operator::GreaterThanOrEqual(ref bound) | Operator::LessThanOrEqual(ref bound) => (bound, None),
+        }
+    }
+
+    /// Operator of a xlsx name, `Between` and `NotBetween` need the upper bound
+    pub(crate) fn from_name(name: &str, bound: T, upper: Option<T>) -> Option<Operator<T>> {
+        match (name, upper) {
+            ("between", Some(upper)) => Some(Operator::Between(bound, upper)),
+            ("notBetween", Some(upper)) => Some(Operator::NotBetween(bound, upper)),
+            ("equal", _) => Some(Operator::Equal(bound)),
+            ("notEqual", _) => Some(Operator::NotEqual(bound)),
+            ("greaterThan", _) => Some(Operator::GreaterThan(bound)),
+            ("lessThan", _) => Some(Operator::LessThan(bound)),
+            ("greaterThanOrEqual", _) => Some(Operator::GreaterThanOrEqual(bound)),
+            ("lessThanOrEqual", _) => Some(Operator::LessThanOrEqual(bound)),
+            _ => None,
+        }
+    }
+}
+
+/// Values allowed in the cells
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Rule {
+    /// One of the texts, the texts can't have commas in xlsx
+    List(Vec<String>),
+    /// One of the values of the cells
+    ListRange(Range),
+    /// Number without decimals
+    WholeNumber(Operator<i64>),
+    Decimal(Operator<f64>),
+    Date(Operator<NaiveDate>),
+    /// Number of the characters of the text
+    TextLength(Operator<usize>),
+    /// Formula without "=" which is true for a valid value like "ISNUMBER(A1)",
+    /// the formula isn't computed so any value is valid in `validate`
+    Custom(String),
+}
+
+/// Style of the alert of an invalid value, only `Stop` refuses the value
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ErrorStyle {
+    #[default]
+    Stop,
+    Warning,
+    Information,
+}
+
+/// Title and text of the prompt of a selected cell or the alert of an invalid value
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Message {
+    title: String,
+    text: String,
+}
+
+impl Message {
+    pub fn new<'a, S>(title: S, text: S) -> Message
+        where S: Into<Cow<'a, str>>
+    {
+        Message {
+            title: title.into().into_owned(),
+            text: text.into().into_owned(),
+        }
+    }
+
+    pub fn get_title(&self) -> &String {
+        &self.title
+    }
+
+    pub fn get_text(&self) -> &String {
+        &self.text
+    }
+}
+
+/// DataValidation is the rule of the values of the cells of its ranges,
+/// the ranges are in the sheet of the validation.
+/// An empty cell is valid by default and a list is shown as a drop-down.
+///
+/// ```
+/// use spsheet::{Book, Sheet, Value};
+/// use spsheet::range::Range;
+/// use spsheet::validation::{DataValidation, Message, Operator, Rule};
+///
+/// let mut sheet = Sheet::new("input");
+/// let mut validation = DataValidation::new(Range::new("input", 1, 1, 10, 1), Rule::WholeNumber(Operator::Between(1, 10)));
+/// validation.set_error(Some(Message::new("Quantity", "Enter 1 to 10")));
+/// sheet.add_validation(validation);
+/// sheet.add_validation(DataValidation::new(Range::new("input", 1, 2, 10, 2), Rule::List(vec![String::from("Yes"), String::from("No")])));
+/// let mut book = Book::new();
+/// book.add_sheet(sheet);
+/// assert!(book.validate_cell(0, 1, 1, &Value::Float(3.0)).is_ok());
+/// assert!(book.validate_cell(0, 1, 1, &Value::Float(11.0)).is_err());
+/// assert!(book.validate_cell(0, 1, 2, &Value::Str(String::from("Maybe"))).is_err());
+/// assert!(book.validate_cell(0, 0, 2, &Value::Str(String::from("Maybe"))).is_ok());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DataValidation {
+    ranges: Vec<Range>,
+    rule: Rule,
+    #[cfg_attr(feature = "serde", serde(default = "default_true"))]
+    allow_blank: bool,
+    #[cfg_attr(feature = "serde", serde(default = "default_true"))]
+    drop_down: bool,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    prompt: Option<Message>,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    error: Option<Message>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    error_style: ErrorStyle,
+}
+
+#[cfg(feature = "serde")]
+fn default_true() -> bool {
+    true
+}
+
+impl DataValidation {
+    pub fn new(range: Range, rule: Rule) -> DataValidation {
+        DataValidation {
+            ranges: vec![range],
+            rule,
+            allow_blank: true,
+            drop_down: true,
+            prompt: None,
+            error: None,
+            error_style: ErrorStyle::Stop,
+        }
+    }
+
+    pub fn get_ranges(&self) -> &Vec<Range> {
+        &self.ranges
+    }
+
+    pub fn set_ranges(&mut self, ranges: Vec<Range>) {
+        self.ranges = ranges;
+    }
+
+    pub fn add_range(&mut self, range: Range) {
+        self.ranges.push(range);
+    }
+
+    pub fn get_rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    pub fn set_rule(&mut self, rule: Rule) {
+        self.rule = rule;
+    }
+
+    /// An empty value is valid
+    pub fn is_allow_blank(&self) -> bool {
+        self.allow_blank
+    }
+
+    pub fn set_allow_blank(&mut self, allow_blank: bool) {
+        self.allow_blank = allow_blank;
+    }
+
+    /// The values of a list are shown in a drop-down of the cell, only a list has it in ods
+    pub fn is_drop_down(&self) -> bool {
+        self.drop_down
+    }
+
+    pub fn set_drop_down(&mut self, drop_down: bool) {
+        self.drop_down = drop_down;
+    }
+
+    /// Message shown while a cell of the ranges is selected
+    pub fn get_prompt(&self) -> Option<&Message> {
+        self.prompt.as_ref()
+    }
+
+    pub fn set_prompt(&mut self, prompt: Option<Message>) {
+        self.prompt = prompt;
+    }
+
+    /// Message of the alert of an invalid value, None for the default message of the application
+    pub fn get_error(&self) -> Option<&Message> {
+        self.error.as_ref()
+    }
+
+    pub fn set_error(&mut self, error: Option<Message>) {
+        self.error = error;
+    }
+
+    pub fn get_error_style(&self) -> ErrorStyle {
+        self.error_style
+    }
+
+    pub fn set_error_style(&mut self, error_style: ErrorStyle) {
+        self.error_style = error_style;
+    }
+
+    pub fn contains(&self, row: usize, column: usize) -> bool {
+        self.ranges.iter().any(|range| range.contains(row, column))
+    }
+
+    /// Check a value by the rule, the day of a date is the day in UTC.
+    /// The values of the cells of a `ListRange` are only known by the book, see `Book::validate_cell`,
+    /// so any value is valid here.
+    pub fn validate(&self, value: &Value) -> bool {
+        self.check(value, FixedOffset::east_opt(0).unwrap(), None)
+    }
+
+    /// Check a value with the texts of the cells of a `ListRange`, the day of a date is the day in the timezone
+    pub(crate) fn check(&self, value: &Value, timezone: FixedOffset, range_texts: Option<Vec<String>>) -> bool {
+        let text = value_text(value);
+        if text.as_ref().is_some_and(|text| text.is_empty()) {
+            return self.allow_blank;
+        }
+        let number = match *value {
+            Value::Float(number) => Some(number),
+            Value::Currency(ref currency) => Some(currency.get_amount()),
+            _ => None,
+        };
+        match self.rule {
+            Rule::List(ref items) => text.is_some_and(|text| items.contains(&text)),
+            Rule::ListRange(_) => match (text, range_texts) {
+                (Some(text), Some(items)) => items.contains(&text),
+                (None, Some(_)) => false,
+                (_, None) => true,
+            },
+            Rule::WholeNumber(ref operator) => number
+                .filter(|number| number.fract() == 0.0)
+                .is_some_and(|number| operator.contains(&(number as i64))),
+            Rule::Decimal(ref operator) => number.is_some_and(|number| operator.contains(&number)),
+            Rule::Date(ref operator) => {
+                let date = match *value {
+                    Value::Date(ref value) => Some(value.with_timezone(&timezone).naive_local().date()),
+                    Value::DateOnly(value) => Some(value),
+                    _ => None,
+                };
+                date.is_some_and(|date| operator.contains(&date))
+            },
+            Rule::TextLength(ref operator) => text.is_some_and(|text| operator.contains(&text.chars().count())),
+            Rule::Custom(_) => true,
+        }
+    }
+
+    /// Move the ranges like the rows inserted at `row_index`
+    pub(crate) fn insert_rows(&mut self, row_index: usize, count: usize) {
+        range::insert_rows(&mut self.ranges, row_index, count);
+    }
+
+    /// Move the ranges like the rows removed at `row_index`, the ranges only on the removed rows are dropped
+    pub(crate) fn remove_rows(&mut self, row_index: usize, count: usize) {
+        range::remove_rows(&mut self.ranges, row_index, count);
+    }
+}
+
+/// Text of a value compared with the texts of a list, None for a date or a time
+pub(crate) fn value_text(value: &Value) -> Option<String> {
+    match *value {
+        Value::Date(_) | Value::DateOnly(_) | Value::Time(_) | Value::Duration(_) => None,
+        _ => Some(value.get_text()),
+    }
+}
@@ -15,21 +15,53 @@ use std::fs::File;
 use std::string::FromUtf8Error;
 use self::chrono::prelude::*;
 use self::tempdir::TempDir;
-use super::{Book,Sheet,Cell,Value,column_and_row_to_index,index_to_column};
+use super::{Book,Sheet,Cell,Value,Package,column_and_row_to_index,index_to_column,index_to_column_and_row};
+use defined_name;
 
+mod read_comments;
+mod read_drawing;
 mod read_sheet;
 mod read_shared_strings;
 mod read_styles;
+mod read_table;
 mod read_workbook_xml_rels;
 mod read_workbook;
+mod relationships;
+mod write_chart;
+mod write_comments;
+mod write_drawing;
 mod write_sheet;
 mod write_shared_strings;
 mod write_styles;
+mod write_table;
+mod write_content_types;
+mod write_package;
 mod write_workbook;
 mod write_workbook_xml_rels;
 
+const WORKSHEET_TYPE: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet";
+const STYLES_TYPE: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles";
+const SHARED_STRINGS_TYPE: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships/sharedStrings";
+const CALC_CHAIN_TYPE: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships/calcChain";
+const COMMENTS_TYPE: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships/comments";
+const VML_DRAWING_TYPE: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships/vmlDrawing";
+const THREADED_COMMENT_TYPE: &str = "http://schemas.microsoft.com/office/2017/10/relationships/threadedComment";
+const HYPERLINK_TYPE: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships/hyperlink";
+const DRAWING_TYPE: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships/drawing";
+const IMAGE_TYPE: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships/image";
+const CHART_TYPE: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships/chart";
+const TABLE_TYPE: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships/table";
+const PERSON_TYPE: &str = "http://schemas.microsoft.com/office/2017/10/relationships/person";
+const WORKSHEET_CONTENT_TYPE: &str = "application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml";
+const STYLES_CONTENT_TYPE: &str = "application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml";
+const SHARED_STRINGS_CONTENT_TYPE: &str = "application/vnd.openxmlformats-officedocument.spreadsheetml.sharedStrings+xml";
+const COMMENTS_CONTENT_TYPE: &str = "application/vnd.openxmlformats-officedocument.spreadsheetml.comments+xml";
+const VML_DRAWING_CONTENT_TYPE: &str = "application/vnd.openxmlformats-officedocument.vmlDrawing";
+const DRAWING_CONTENT_TYPE: &str = "application/vnd.openxmlformats-officedocument.drawing+xml";
+const CHART_CONTENT_TYPE: &str = "application/vnd.openxmlformats-officedocument.drawingml.chart+xml";
+const TABLE_CONTENT_TYPE: &str = "application/vnd.openxmlformats-officedocument.spreadsheetml.table+xml";
 const CONTENT_TYPE_XML: &'static str = r#"<?xml version="1.0" encoding="UTF-8"?>
-<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Override PartName="/_rels/.rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Override PartName="/docProps/app.xml" ContentType="application/vnd.openxmlformats-officedocument.extended-properties+xml"/><Override PartName="/docProps/core.xml" ContentType="application/vnd.openxmlformats-package.core-properties+xml"/><Override PartName="/xl/sharedStrings.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sharedStrings+xml"/><Override PartName="/xl/_rels/workbook.xml.rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Override PartName="/xl/worksheets/sheet4.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/><Override PartName="/xl/worksheets/sheet3.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/><Override PartName="/xl/worksheets/sheet2.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/><Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/><Override PartName="/xl/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/_rels/.rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Override PartName="/docProps/app.xml" ContentType="application/vnd.openxmlformats-officedocument.extended-properties+xml"/><Override PartName="/docProps/core.xml" ContentType="application/vnd.openxmlformats-package.core-properties+xml"/><Override PartName="/xl/sharedStrings.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sharedStrings+xml"/><Override PartName="/xl/_rels/workbook.xml.rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Override PartName="/xl/worksheets/sheet4.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/><Override PartName="/xl/worksheets/sheet3.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/><Override PartName="/xl/worksheets/sheet2.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/><Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/><Override PartName="/xl/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/>
 </Types>"#;
 const APP_XML: &'static str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
 <Properties xmlns="http://schemas.openxmlformats.org/officeDocument/2006/extended-properties" xmlns:vt="http://schemas.openxmlformats.org/officeDocument/2006/docPropsVTypes"><Template></Template><TotalTime>11</TotalTime><Application>spreadsheet-rs/0.0.1</Application></Properties>"#;
@@ -74,7 +106,61 @@ impl From<FromUtf8Error> for XlsxError {
 
 type Result<T> = result::Result<T, XlsxError>;
 
+/// Error of a value of a part which isn't valid, like the index of a missing shared string
+fn invalid_data(message: String) -> XlsxError {
+    XlsxError::Io(io::Error::new(io::ErrorKind::InvalidData, message))
+}
+
+/// Options for `write_with_options`.
+///
+/// ```
+/// use spsheet::xlsx::XlsxOptions;
+/// let mut options = XlsxOptions::new();
+/// options.set_inline_strings(true);
+/// assert!(options.get_inline_strings());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct XlsxOptions {
+    inline_strings: bool,
+}
+
+impl XlsxOptions {
+    pub fn new() -> XlsxOptions {
+        XlsxOptions {
+            inline_strings: false,
+        }
+    }
+
+    /// Write the texts in the cells instead of sharedStrings.xml
+    pub fn set_inline_strings(&mut self, inline_strings: bool) {
+        self.inline_strings = inline_strings;
+    }
+
+    pub fn get_inline_strings(&self) -> bool {
+        self.inline_strings
+    }
+}
+
+impl Default for XlsxOptions {
+    fn default() -> XlsxOptions {
+        XlsxOptions::new()
+    }
+}
+
 pub fn read(path: &Path) -> Result<Book> {
+    read_book(path, false)
+}
+
+/// Read a book keeping the original file.
+///
+/// `write` then makes only the sheet data, shared strings, comments, pictures and the styles it needs,
+/// and the tables, and adds the charts of the sheets. Every other part like the original charts or column widths is copied as is.
+/// A copy of a sheet gets copies of the parts of its original sheet, like the drawing with the charts.
+pub fn open(path: &Path) -> Result<Book> {
+    read_book(path, true)
+}
+
+fn read_book(path: &Path, keep_package: bool) -> Result<Book> {
     let file = File::open(path)?;
     let dir = TempDir::new("shreadsheet")?;
     match unzip(&file, &dir) {
@@ -87,6 +173,7 @@ pub fn read(path: &Path) -> Result<Book> {
     let mut book = Book::new();
     {
         let styles = read_styles::read(&dir)?;
+        let dxfs = read_styles::read_dxfs(&dir)?;
         let rels = read_workbook_xml_rels::read(&dir)?;
         let mut rels_map = HashMap::new();
         for r in &rels {
@@ -94,21 +181,75 @@ pub fn read(path: &Path) -> Result<Book> {
         }
         let sheets = read_workbook::read(&dir)?;
         let shared_strings = read_shared_strings::read(&dir)?;
+        let date_system = read_workbook::read_date_system(&dir)?;
+        book.set_date_system(date_system);
+        let persons = read_comments::read_persons(&dir)?;
+        let parts = read_sheet::BookParts {
+            shared_strings: &shared_strings,
+            styles: &styles,
+            dxfs: &dxfs,
+            date_system,
+        };
         for s in &sheets {
             let sheet_target = rels_map.get(s.get("rid").unwrap()).unwrap();
-            book.add_sheet(
-                read_sheet::read(
-                    &dir, s.get("name").unwrap(),
-                    sheet_target,
-                    &shared_strings,
-                    &styles)?);
+            let part_name = resolve_part_name("xl/", sheet_target);
+            let mut sheet = read_sheet::read(
+                &dir, s.get("name").unwrap(),
+                &part_name,
+                &parts,
+                keep_package)?;
+            for (row, column, comment) in read_comments::read(&dir, &part_name, &persons)? {
+                let mut cell = sheet.get_cell(row, column).cloned().unwrap_or_else(|| Cell::new(Value::Empty, ""));
+                cell.set_comment(Some(comment));
+                sheet.add_cell(cell, row, column);
+            }
+            for image in read_drawing::read(&dir, &part_name)? {
+                sheet.add_image(image);
+            }
+            for table in read_table::read(&dir, &part_name, s.get("name").unwrap())? {
+                sheet.add_table(table);
+            }
+            if keep_package {
+                sheet.set_part_name(Some(part_name));
+            }
+            book.add_sheet(sheet);
+        }
+        // the list of an auto filter is read from the autoFilter of the sheet
+        for (sheet_index, defined_name) in read_workbook::read_defined_names(&dir)? {
+            let filter_database = defined_name.get_name().to_lowercase() == defined_name::FILTER_DATABASE.to_lowercase();
+            match sheet_index {
+                Some(index) if filter_database && index < book.get_sheet_size() && book.get_sheet(index).get_auto_filter().is_some() => {},
+                Some(index) if index < book.get_sheet_size() => book.get_sheet_mut(index).add_defined_name(defined_name),
+                Some(_) => {},
+                None => book.add_defined_name(defined_name),
+            }
+        }
+        let active_tab = read_workbook::read_active_tab(&dir)?;
+        if active_tab < book.get_sheet_size() {
+            book.set_active_sheet(active_tab);
         }
     }
     dir.close()?;
+    if keep_package {
+        let mut package = Package::new();
+        for (name, data) in read_package(&File::open(path)?)? {
+            package.set_part(name, data);
+        }
+        book.set_package(package);
+    }
     Ok(book)
 }
 
 pub fn write(book: &Book, path: &Path) -> result::Result<(), XlsxError> {
+    write_with_options(book, path, &XlsxOptions::new())
+}
+
+pub fn write_with_options(book: &Book, path: &Path, options: &XlsxOptions) -> result::Result<(), XlsxError> {
+    if let Some(package) = book.get_package() {
+        if package.is_xlsx() {
+            return write_package::write(book, package, path, options);
+        }
+    }
     let dir = TempDir::new("shreadsheet")?;
     let now = Utc::now();
     let now_str = now.format("%Y-%m-%dT%H:%M:%SZ").to_string();
@@ -128,16 +269,57 @@ pub fn write(book: &Book, path: &Path) -> result::Result<(), XlsxError> {
         &dir, "docProps/core.xml",
         CORE_XML.replace("XXXXXXXXXX", now_str.as_str()).as_str(),
         Some("docProps"))?;
-    let format_map = write_styles::write(book, &dir)?;
-    let shared_strings = write_shared_strings::write(book, &dir)?;
+    let cell_styles = write_styles::write(book, &dir)?;
+    let shared_strings = write_shared_strings::write(book, &dir, options.get_inline_strings())?;
     let _ = write_workbook_xml_rels::write(book, &dir)?;
     let _ = write_workbook::write(book, &dir)?;
+    let mut added_content_types = Vec::new();
     let mut index = 1;
+    let mut table_id = 0;
+    let parts = write_sheet::BookParts {
+        shared_strings: &shared_strings,
+        cell_styles: &cell_styles,
+        date_system: book.get_date_system(),
+        timezone: book.get_timezone(),
+    };
     for sheet in book.get_sheet_vec() {
-        let _ = write_sheet::write(sheet, &dir, &shared_strings, index, &format_map)?;
+        let part_name = format!("xl/worksheets/sheet{}.xml", index);
+        let mut sheet_relationships = Vec::new();
+        let ids = write_sheet::RelationshipIds {
+            drawing: write_drawing::write(sheet, &dir, &part_name, &mut sheet_relationships, &mut added_content_types, &mut Vec::new())?,
+            legacy_drawing: write_comments::write(sheet, &dir, &part_name, &mut sheet_relationships, &mut added_content_types)?,
+            hyperlinks: write_sheet::add_hyperlinks(sheet, &mut sheet_relationships),
+            tables: write_table::write(sheet, &dir, &part_name, &mut sheet_relationships, &mut added_content_types, &mut table_id)?,
+        };
+        write_sheet::write(sheet, &dir, &part_name, &parts, None, index - 1 == book.get_active_sheet(), &ids)?;
+        if !sheet_relationships.is_empty() {
+            relationships::write(&dir, &part_name, &sheet_relationships)?;
+        }
         index = index + 1;
     }
+    if !added_content_types.is_empty() {
+        write_content_types::write(&dir, CONTENT_TYPE_XML.as_bytes(), &Vec::new(), &added_content_types)?;
+    }
     write_to_file(path, &dir)?;
     dir.close()?;
     Ok(())
 }
+
+/// Part name of a relationship target, "worksheets/sheet1.xml" from "xl/" is "xl/worksheets/sheet1.xml"
+fn resolve_part_name(base_dir: &str, target: &str) -> String {
+    let mut segments: Vec<&str> = if target.starts_with('/') {
+        Vec::new()
+    } else {
+        base_dir.split('/').filter(|s| !s.is_empty()).collect()
+    };
+    for segment in target.split('/') {
+        match segment {
+            "" | "." => {},
+            ".." => {
+                segments.pop();
+            },
+            _ => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
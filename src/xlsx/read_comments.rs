@@ -0,0 +1,299 @@
+use file_common::*;
+use std::collections::HashMap;
+use std::result;
+use super::quick_xml::Reader;
+use super::quick_xml::events::{Event, BytesStart};
+use super::tempdir::TempDir;
+use super::XlsxError;
+use super::read_shared_strings::read_string_item;
+use super::relationships;
+use super::{resolve_part_name, column_and_row_to_index, Value};
+use super::{COMMENTS_TYPE, VML_DRAWING_TYPE, THREADED_COMMENT_TYPE, PERSON_TYPE};
+use comment::Comment;
+use rich_text::TextRun;
+
+const WORKBOOK_XML: &str = "xl/workbook.xml";
+// the size Excel gives a new note
+pub const DEFAULT_WIDTH: f64 = 108.0;
+pub const DEFAULT_HEIGHT: f64 = 59.25;
+
+/// Width, height and visibility of a note
+type NoteShape = (Option<f64>, Option<f64>, bool);
+
+/// Names of the persons of threaded comments by their id
+pub fn read_persons(dir: &TempDir) -> result::Result<HashMap<String, String>, XlsxError> {
+    let mut persons = HashMap::new();
+    for r in relationships::read_part(dir, WORKBOOK_XML)? {
+        if r.rel_type != PERSON_TYPE {
+            continue;
+        }
+        let path = dir.path().join(resolve_part_name("xl/", &r.target));
+        if !path.is_file() {
+            continue;
+        }
+        let mut reader = Reader::from_file(path)?;
+        reader.trim_text(true);
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event(&mut buf) {
+                Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) if e.name() == b"person" => {
+                    if let (Some(id), Some(name)) = (read_attribute(e, b"id")?, read_attribute(e, b"displayName")?) {
+                        persons.insert(id, name);
+                    }
+                },
+                Ok(Event::Eof) => break,
+                Err(e) => return Err(XlsxError::Xml(e)),
+                _ => (),
+            }
+            buf.clear();
+        }
+    }
+    Ok(persons)
+}
+
+/// Comments of a worksheet part with their (row, column).
+/// The notes take the size and the visibility of their shape in the VML drawing,
+/// a threaded comment replaces the note of its cell and keeps the replies as lines.
+pub fn read(dir: &TempDir, part_name: &str, persons: &HashMap<String, String>) -> result::Result<Vec<(usize, usize, Comment)>, XlsxError> {
+    let base_dir = match part_name.rfind('/') {
+        Some(index) => &part_name[..index + 1],
+        None => "",
+    };
+    let mut comments: Vec<(usize, usize, Comment)> = Vec::new();
+    let mut shapes: HashMap<(usize, usize), NoteShape> = HashMap::new();
+    let mut threads: Vec<(usize, usize, Comment)> = Vec::new();
+    for r in relationships::read_part(dir, part_name)? {
+        if r.target_mode.is_some() {
+            continue;
+        }
+        let path = resolve_part_name(base_dir, &r.target);
+        if !dir.path().join(&path).is_file() {
+            continue;
+        }
+        if r.rel_type == COMMENTS_TYPE {
+            comments.extend(read_comments(dir, &path)?);
+        } else if r.rel_type == VML_DRAWING_TYPE {
+            shapes.extend(read_vml_drawing(dir, &path)?);
+        } else if r.rel_type == THREADED_COMMENT_TYPE {
+            threads.extend(read_threaded_comments(dir, &path, persons)?);
+        }
+    }
+    for &mut (row, column, ref mut comment) in comments.iter_mut() {
+        if let Some(&(width, height, visible)) = shapes.get(&(row, column)) {
+            comment.set_size(width, height);
+            comment.set_visible(visible);
+        }
+    }
+    for (row, column, mut thread) in threads {
+        match comments.iter_mut().find(|c| c.0 == row && c.1 == column) {
+            Some(c) => {
+                thread.set_size(c.2.get_width(), c.2.get_height());
+                thread.set_visible(c.2.is_visible());
+                c.2 = thread;
+            },
+            None => comments.push((row, column, thread)),
+        }
+    }
+    Ok(comments)
+}
+
+fn read_comments(dir: &TempDir, path: &str) -> result::Result<Vec<(usize, usize, Comment)>, XlsxError> {
+    let mut reader = Reader::from_file(dir.path().join(path))?;
+    reader.trim_text(false);
+    let mut buf = Vec::new();
+    let mut authors: Vec<String> = Vec::new();
+    let mut author_flag = false;
+    let mut position: Option<(usize, usize)> = None;
+    let mut author_id: usize = 0;
+    let mut result = Vec::new();
+    loop {
+        match reader.read_event(&mut buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"author" => {
+                        author_flag = true;
+                        authors.push(String::new());
+                    },
+                    b"comment" => {
+                        position = read_attribute(e, b"ref")?.and_then(column_and_row_to_index)
+                            .map(|(column, row)| (row, column));
+                        author_id = read_attribute(e, b"authorId")?.and_then(|id| id.parse::<usize>().ok()).unwrap_or(0);
+                    },
+                    b"text" => {
+                        let text = match read_string_item(&mut reader, b"text")?.0 {
+                            Value::RichText(runs) => TextRun::concat(&runs),
+                            Value::Str(text) => text,
+                            _ => String::new(),
+                        };
+                        if let Some((row, column)) = position {
+                            let author = authors.get(author_id).cloned().unwrap_or_default();
+                            let text = strip_author(text, &author);
+                            result.push((row, column, Comment::new(author, text)));
+                        }
+                    },
+                    _ => (),
+                }
+            },
+            Ok(Event::Text(e)) if author_flag => {
+                if let Some(author) = authors.last_mut() {
+                    author.push_str(&e.unescape_and_decode(&reader)?);
+                }
+            },
+            Ok(Event::Empty(ref e)) if e.name() == b"author" => authors.push(String::new()),
+            Ok(Event::End(ref e)) if e.name() == b"author" => author_flag = false,
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(XlsxError::Xml(e)),
+            _ => (),
+        }
+        buf.clear();
+    }
+    Ok(result)
+}
+
+// Excel starts the text of a note with a bold "author:" line
+fn strip_author(text: String, author: &str) -> String {
+    let prefix = format!("{}:\n", author);
+    if !author.is_empty() && text.starts_with(&prefix) {
+        text[prefix.len()..].to_string()
+    } else {
+        text
+    }
+}
+
+/// Size and visibility of the notes by their cell, the drawing may be no well formed xml
+fn read_vml_drawing(dir: &TempDir, path: &str) -> result::Result<HashMap<(usize, usize), NoteShape>, XlsxError> {
+    let mut reader = Reader::from_file(dir.path().join(path))?;
+    reader.trim_text(true);
+    reader.check_end_names(false);
+    let mut buf = Vec::new();
+    let mut result = HashMap::new();
+    let mut style = String::new();
+    let mut note_flag = false;
+    let mut visible = false;
+    let mut row: Option<usize> = None;
+    let mut column: Option<usize> = None;
+    let mut text = String::new();
+    loop {
+        match reader.read_event(&mut buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"v:shape" => {
+                        style = read_attribute(e, b"style")?.unwrap_or_default();
+                        note_flag = false;
+                        visible = false;
+                        row = None;
+                        column = None;
+                    },
+                    b"x:ClientData" => note_flag = read_attribute(e, b"ObjectType")?.is_some_and(|t| t == "Note"),
+                    b"x:Visible" => visible = true,
+                    _ => (),
+                }
+                text = String::new();
+            },
+            Ok(Event::Empty(ref e)) if e.name() == b"x:Visible" => visible = true,
+            Ok(Event::Text(e)) => text = e.unescape_and_decode(&reader)?,
+            Ok(Event::End(ref e)) => {
+                match e.name() {
+                    b"x:Row" => row = text.trim().parse::<usize>().ok(),
+                    b"x:Column" => column = text.trim().parse::<usize>().ok(),
+                    b"v:shape" => {
+                        if let (true, Some(row), Some(column)) = (note_flag, row, column) {
+                            let (width, height) = read_shape_size(&style);
+                            result.insert((row, column), (width, height, visible));
+                        }
+                    },
+                    _ => (),
+                }
+            },
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(XlsxError::Xml(e)),
+            _ => (),
+        }
+        buf.clear();
+    }
+    Ok(result)
+}
+
+// "position:absolute;width:108pt;height:59.25pt" -> the size, the default size is None
+fn read_shape_size(style: &str) -> (Option<f64>, Option<f64>) {
+    let mut width = None;
+    let mut height = None;
+    for declaration in style.split(';') {
+        let mut pair = declaration.splitn(2, ':');
+        match (pair.next().map(|k| k.trim()), pair.next()) {
+            (Some("width"), Some(value)) => width = length_to_points(value),
+            (Some("height"), Some(value)) => height = length_to_points(value),
+            _ => (),
+        }
+    }
+    if width == Some(DEFAULT_WIDTH) && height == Some(DEFAULT_HEIGHT) {
+        (None, None)
+    } else {
+        (width, height)
+    }
+}
+
+fn read_threaded_comments(dir: &TempDir, path: &str, persons: &HashMap<String, String>) -> result::Result<Vec<(usize, usize, Comment)>, XlsxError> {
+    let mut reader = Reader::from_file(dir.path().join(path))?;
+    reader.trim_text(false);
+    let mut buf = Vec::new();
+    let mut result: Vec<(usize, usize, Comment)> = Vec::new();
+    let mut position: Option<(usize, usize)> = None;
+    let mut person: String = String::new();
+    let mut text_flag = false;
+    let mut text = String::new();
+    loop {
+        match reader.read_event(&mut buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"threadedComment" => {
+                        position = read_attribute(e, b"ref")?.and_then(column_and_row_to_index)
+                            .map(|(column, row)| (row, column));
+                        person = read_attribute(e, b"personId")?.and_then(|id| persons.get(&id).cloned()).unwrap_or_default();
+                    },
+                    b"text" => {
+                        text_flag = true;
+                        text = String::new();
+                    },
+                    _ => (),
+                }
+            },
+            Ok(Event::Text(e)) if text_flag => {
+                text.push_str(&e.unescape_and_decode(&reader)?);
+            },
+            Ok(Event::End(ref e)) => {
+                match e.name() {
+                    b"text" => text_flag = false,
+                    b"threadedComment" => {
+                        if let Some((row, column)) = position {
+                            match result.iter_mut().find(|c| c.0 == row && c.1 == column) {
+                                Some(c) => {
+                                    let thread = format!("{}\n{}", c.2.get_text(), text);
+                                    c.2.set_text(thread);
+                                },
+                                None => result.push((row, column, Comment::new(person.clone(), text.clone()))),
+                            }
+                        }
+                    },
+                    _ => (),
+                }
+            },
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(XlsxError::Xml(e)),
+            _ => (),
+        }
+        buf.clear();
+    }
+    Ok(result)
+}
+
+fn read_attribute(e: &BytesStart, key: &[u8]) -> result::Result<Option<String>, XlsxError> {
+    for a in e.attributes().with_checks(false) {
+        match a {
+            Ok(ref attr) if attr.key == key => return Ok(Some(get_attribute_value(attr)?)),
+            Ok(_) => {},
+            Err(_) => {},
+        }
+    }
+    Ok(None)
+}
@@ -0,0 +1,131 @@
+use file_common::*;
+use std::fs;
+use std::result;
+use super::quick_xml::Reader;
+use super::quick_xml::events::{Event, BytesStart};
+use super::tempdir::TempDir;
+use super::{XlsxError, resolve_part_name};
+use super::relationships;
+use super::DRAWING_TYPE;
+use super::write_drawing::EMU_PER_POINT;
+use image::{Anchor, Image, Marker};
+
+/// Pictures in the drawing of a worksheet part.
+/// An absolute anchor is read as a one cell anchor at A1 with the position as the offsets,
+/// the other anchors like charts or shapes aren't pictures and are skipped.
+pub fn read(dir: &TempDir, part_name: &str) -> result::Result<Vec<Image>, XlsxError> {
+    let mut images = Vec::new();
+    for r in relationships::read_part(dir, part_name)? {
+        if r.rel_type != DRAWING_TYPE || r.target_mode.is_some() {
+            continue;
+        }
+        let drawing = resolve_part_name(base_dir(part_name), &r.target);
+        if dir.path().join(&drawing).is_file() {
+            images.extend(read_drawing(dir, &drawing)?);
+        }
+    }
+    Ok(images)
+}
+
+fn base_dir(part_name: &str) -> &str {
+    match part_name.rfind('/') {
+        Some(index) => &part_name[..index + 1],
+        None => "",
+    }
+}
+
+fn read_drawing(dir: &TempDir, part_name: &str) -> result::Result<Vec<Image>, XlsxError> {
+    let drawing_relationships = relationships::read_part(dir, part_name)?;
+    let mut reader = Reader::from_file(dir.path().join(part_name))?;
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+    let mut images = Vec::new();
+    let mut markers: Vec<Marker> = Vec::new();
+    // column, column offset, row, row offset of the marker being read
+    let mut marker = [0.0; 4];
+    let mut field: Option<usize> = None;
+    let mut position = (0.0, 0.0);
+    let mut size = (0.0, 0.0);
+    let mut name = String::new();
+    let mut embed: Option<String> = None;
+    let mut is_picture = false;
+    loop {
+        match reader.read_event(&mut buf) {
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => {
+                match e.name() {
+                    b"xdr:oneCellAnchor" | b"xdr:twoCellAnchor" | b"xdr:absoluteAnchor" => {
+                        markers.clear();
+                        position = (0.0, 0.0);
+                        size = (0.0, 0.0);
+                        name = String::new();
+                        embed = None;
+                        is_picture = false;
+                    },
+                    b"xdr:from" | b"xdr:to" => marker = [0.0; 4],
+                    b"xdr:col" => field = Some(0),
+                    b"xdr:colOff" => field = Some(1),
+                    b"xdr:row" => field = Some(2),
+                    b"xdr:rowOff" => field = Some(3),
+                    b"xdr:pos" => position = (read_emu(e, b"x")?, read_emu(e, b"y")?),
+                    b"xdr:ext" => size = (read_emu(e, b"cx")?, read_emu(e, b"cy")?),
+                    b"xdr:pic" => is_picture = true,
+                    b"xdr:cNvPr" if is_picture => name = read_attribute(e, b"name")?.unwrap_or_default(),
+                    b"a:blip" if is_picture => embed = read_attribute(e, b"r:embed")?,
+                    _ => (),
+                }
+            },
+            Ok(Event::Text(e)) => {
+                if let Some(index) = field {
+                    let value = e.unescape_and_decode(&reader)?.trim().parse::<f64>().unwrap_or(0.0);
+                    marker[index] = if index % 2 == 1 { value / EMU_PER_POINT } else { value };
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                match e.name() {
+                    b"xdr:col" | b"xdr:colOff" | b"xdr:row" | b"xdr:rowOff" => field = None,
+                    b"xdr:from" | b"xdr:to" => {
+                        markers.push(Marker::new(marker[2] as usize, marker[0] as usize, marker[1], marker[3]));
+                    },
+                    b"xdr:oneCellAnchor" | b"xdr:twoCellAnchor" | b"xdr:absoluteAnchor" => {
+                        let anchor = match (e.name(), markers.len()) {
+                            (b"xdr:oneCellAnchor", 1) => Some(Anchor::OneCell(markers[0].clone(), size.0, size.1)),
+                            (b"xdr:twoCellAnchor", 2) => Some(Anchor::TwoCell(markers[0].clone(), markers[1].clone())),
+                            (b"xdr:absoluteAnchor", _) => Some(Anchor::OneCell(Marker::new(0, 0, position.0, position.1), size.0, size.1)),
+                            _ => None,
+                        };
+                        let target = embed.as_ref()
+                            .and_then(|id| drawing_relationships.iter().find(|r| &r.id == id && r.target_mode.is_none()))
+                            .map(|r| resolve_part_name(base_dir(part_name), &r.target));
+                        if let (true, Some(anchor), Some(target)) = (is_picture, anchor, target) {
+                            let path = dir.path().join(&target);
+                            if path.is_file() {
+                                images.push(Image::new(name.clone(), fs::read(path)?, anchor));
+                            }
+                        }
+                    },
+                    _ => (),
+                }
+            },
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(XlsxError::Xml(e)),
+            _ => (),
+        }
+        buf.clear();
+    }
+    Ok(images)
+}
+
+fn read_emu(e: &BytesStart, key: &[u8]) -> result::Result<f64, XlsxError> {
+    Ok(read_attribute(e, key)?.and_then(|v| v.parse::<f64>().ok()).unwrap_or(0.0) / EMU_PER_POINT)
+}
+
+fn read_attribute(e: &BytesStart, key: &[u8]) -> result::Result<Option<String>, XlsxError> {
+    for a in e.attributes().with_checks(false) {
+        match a {
+            Ok(ref attr) if attr.key == key => return Ok(Some(condvert_character_reference(&get_attribute_value(attr)?))),
+            Ok(_) => {},
+            Err(_) => {},
+        }
+    }
+    Ok(None)
+}
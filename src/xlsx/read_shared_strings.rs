@@ -1,25 +1,171 @@
+use file_common::*;
+use std::fs::File;
+use std::io::BufReader;
 use std::result;
 use super::quick_xml::Reader;
-use super::quick_xml::events::{Event};
+use super::quick_xml::events::{Event, BytesStart};
 use super::tempdir::TempDir;
-use super::XlsxError;
+use rich_text::{Font, Phonetic, PhoneticRun, TextRun};
+use super::{Value, XlsxError};
 
 const SHARED_STRINGS: &'static str = "xl/sharedStrings.xml";
 
-pub fn read(dir: &TempDir) -> result::Result<Vec<String>, XlsxError> {
+/// One value and its reading for each si, a text with runs is a rich text
+pub fn read(dir: &TempDir) -> result::Result<Vec<(Value, Option<Phonetic>)>, XlsxError> {
     let path = dir.path().join(SHARED_STRINGS);
+    let mut res: Vec<(Value, Option<Phonetic>)> = Vec::new();
+    // a book with inline strings may have no shared strings
+    if !path.exists() {
+        return Ok(res);
+    }
     let mut reader = Reader::from_file(path)?;
-    reader.trim_text(true);
+    reader.trim_text(false);
     let mut buf = Vec::new();
-    let mut res: Vec<String> = Vec::new();
     loop {
         match reader.read_event(&mut buf) {
-            Ok(Event::Text(e)) => res.push(e.unescape_and_decode(&reader).unwrap()),
+            Ok(Event::Start(ref e)) if e.name() == b"si" => res.push(read_string_item(&mut reader, b"si")?),
+            Ok(Event::Empty(ref e)) if e.name() == b"si" => res.push((Value::Str(String::new()), None)),
             Ok(Event::Eof) => break,
-            Err(e) => panic!("Error at position {}: {:?}", reader.buffer_position(), e),
+            Err(e) => return Err(XlsxError::Xml(e)),
             _ => (),
         }
         buf.clear();
     }
     Ok(res)
 }
+
+/// Read the content of a si or an inline is until its end tag,
+/// the reader must not trim the text
+pub fn read_string_item(reader: &mut Reader<BufReader<File>>, end_name: &[u8]) -> result::Result<(Value, Option<Phonetic>), XlsxError> {
+    let mut buf = Vec::new();
+    let mut text = String::new();
+    let mut runs: Vec<TextRun> = Vec::new();
+    let mut font: Option<Font> = None;
+    let mut text_flag = false;
+    let mut phonetic_flag = false;
+    let mut phonetic: Option<Phonetic> = None;
+    let mut phonetic_text = String::new();
+    let mut phonetic_range: (usize, usize) = (0, 0);
+    loop {
+        match reader.read_event(&mut buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"r" if !phonetic_flag => {
+                        text = String::new();
+                        font = None;
+                    },
+                    b"rPr" => font = Some(Font::new()),
+                    b"rPh" => {
+                        phonetic_flag = true;
+                        phonetic_text = String::new();
+                        phonetic_range = read_phonetic_range(e)?;
+                    },
+                    b"t" => text_flag = true,
+                    _ => (),
+                }
+            },
+            Ok(Event::Empty(ref e)) if e.name() == b"phoneticPr" => {
+                read_phonetic_properties(e, phonetic.get_or_insert_with(Phonetic::new))?;
+            },
+            Ok(Event::Empty(ref e)) => {
+                if let Some(ref mut font) = font {
+                    read_font_property(e, font)?;
+                }
+            },
+            Ok(Event::Text(e)) => {
+                if text_flag && phonetic_flag {
+                    phonetic_text.push_str(&e.unescape_and_decode(reader)?);
+                } else if text_flag {
+                    text.push_str(&e.unescape_and_decode(reader)?);
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                match e.name() {
+                    b"t" => text_flag = false,
+                    b"rPh" => {
+                        phonetic_flag = false;
+                        let run = PhoneticRun::new(phonetic_text.clone(), phonetic_range.0, phonetic_range.1);
+                        phonetic.get_or_insert_with(Phonetic::new).add_run(run);
+                    },
+                    b"r" if !phonetic_flag => {
+                        runs.push(TextRun::new(text.clone(), font.take()));
+                    },
+                    name if name == end_name => break,
+                    _ => (),
+                }
+            },
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(XlsxError::Xml(e)),
+            _ => (),
+        }
+        buf.clear();
+    }
+    let value = if runs.is_empty() {
+        Value::Str(text)
+    } else {
+        Value::RichText(runs)
+    };
+    Ok((value, phonetic))
+}
+
+/// Characters of the text which the reading is for, (sb, eb)
+fn read_phonetic_range(e: &BytesStart) -> result::Result<(usize, usize), XlsxError> {
+    let mut range = (0, 0);
+    for attr in e.attributes().with_checks(false) {
+        match attr {
+            Ok(ref attr) if attr.key == b"sb" => range.0 = get_attribute_value(attr)?.parse::<usize>().unwrap_or(0),
+            Ok(ref attr) if attr.key == b"eb" => range.1 = get_attribute_value(attr)?.parse::<usize>().unwrap_or(0),
+            Ok(_) => {},
+            Err(_) => {},
+        }
+    }
+    Ok(range)
+}
+
+fn read_phonetic_properties(e: &BytesStart, phonetic: &mut Phonetic) -> result::Result<(), XlsxError> {
+    for attr in e.attributes().with_checks(false) {
+        match attr {
+            Ok(ref attr) if attr.key == b"fontId" => {
+                phonetic.set_font_id(get_attribute_value(attr)?.parse::<usize>().unwrap_or(0));
+            },
+            Ok(ref attr) if attr.key == b"type" => phonetic.set_kind(Some(get_attribute_value(attr)?)),
+            Ok(ref attr) if attr.key == b"alignment" => phonetic.set_alignment(Some(get_attribute_value(attr)?)),
+            Ok(_) => {},
+            Err(_) => {},
+        }
+    }
+    Ok(())
+}
+
+/// Property of a run font or a font of the styles
+pub fn read_font_property(e: &BytesStart, font: &mut Font) -> result::Result<(), XlsxError> {
+    let mut val: Option<String> = None;
+    let mut rgb: Option<String> = None;
+    for attr in e.attributes().with_checks(false) {
+        match attr {
+            Ok(ref attr) if attr.key == b"val" => val = Some(get_attribute_value(attr)?),
+            Ok(ref attr) if attr.key == b"rgb" => rgb = Some(get_attribute_value(attr)?),
+            Ok(_) => {},
+            Err(_) => {},
+        }
+    }
+    // b, i and strike are on without val, and off with val="0" or "false"
+    let flag = match val {
+        Some(ref val) => val != "0" && val != "false",
+        None => true,
+    };
+    match e.name() {
+        b"rFont" | b"name" => font.set_name(val.map(|v| condvert_character_reference(&v))),
+        b"sz" => font.set_size(val.and_then(|v| v.parse::<f64>().ok())),
+        b"b" => font.set_bold(flag),
+        b"i" => font.set_italic(flag),
+        b"strike" => font.set_strike(flag),
+        b"u" => font.set_underline(val.map(|v| v != "none").unwrap_or(true)),
+        // ARGB like "FFFF0000"
+        b"color" => font.set_color(rgb.map(|v| {
+            if v.len() == 8 { String::from(&v[2..]) } else { v }
+        })),
+        _ => (),
+    }
+    Ok(())
+}
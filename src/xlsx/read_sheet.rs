@@ -1,18 +1,40 @@
 use file_common::*;
 use std::collections::HashMap;
+use std::mem;
 use std::result;
-use super::time::Duration;
 use super::chrono::prelude::*;
 use super::quick_xml::Reader;
-use super::quick_xml::events::{Event};
+use super::quick_xml::events::{BytesStart, Event};
 use super::tempdir::TempDir;
-use super::XlsxError;
+use super::{XlsxError, invalid_data};
+use super::read_shared_strings::read_string_item;
+use super::relationships;
 use super::{Sheet,Cell,Value,column_and_row_to_index};
+use rich_text::Phonetic;
+use date::{self, DateSystem};
+use formula;
+use currency::Currency;
+use format::Format;
+use hyperlink::Hyperlink;
+use range::{self, Range};
+use validation::{DataValidation, ErrorStyle, Message, Operator, Rule};
+use conditional_format::{self, ConditionalFormat, DifferentialStyle, IconStyle, Threshold};
+use sheet_view::{Pane, SheetView};
+use auto_filter::{AutoFilter, Comparison, Filter, SortKey};
 
-pub fn read(dir: &TempDir, name: &String, target: &String, shared_strings: &Vec<String>, styles: &Vec<HashMap<String, String>>) -> result::Result<Sheet, XlsxError> {
-    let mut sheet = Sheet::new(name.as_str());
+/// Parts of the workbook which the sheets refer to
+pub struct BookParts<'a> {
+    pub shared_strings: &'a [(Value, Option<Phonetic>)],
+    pub styles: &'a [HashMap<String, String>],
+    pub dxfs: &'a [DifferentialStyle],
+    pub date_system: DateSystem,
+}
+
+pub fn read(dir: &TempDir, name: &str, part_name: &str, parts: &BookParts, keep_style: bool) -> result::Result<Sheet, XlsxError> {
+    let BookParts { shared_strings, styles, dxfs, date_system } = *parts;
+    let mut sheet = Sheet::new(name);
 
-    let path = dir.path().join("xl/".to_string() + target);
+    let path = dir.path().join(part_name);
     let mut reader = Reader::from_file(path)?;
     reader.trim_text(true);
     let mut buf = Vec::new();
@@ -22,6 +44,32 @@ pub fn read(dir: &TempDir, name: &String, target: &String, shared_strings: &Vec<
     let mut string_value: String = String::from("");
     let mut type_value: String = String::from("");
     let mut style_index: usize = 0;
+    let mut value_flag = false;
+    let mut phonetic_flag = false;
+    // formula of the cell being read, the shared formulas by their index with the cell of their text
+    let mut formula: Option<String> = None;
+    let mut formula_attributes: HashMap<String, String> = HashMap::new();
+    let mut shared_formulas: HashMap<String, (String, usize, usize)> = HashMap::new();
+    let mut hyperlinks: Vec<(String, Hyperlink)> = Vec::new();
+    // attributes and formulas of the data validation being read
+    let mut validation_attributes: HashMap<String, String> = HashMap::new();
+    let mut formulas: Vec<String> = Vec::new();
+    // ranges and rules of the conditional format being read, and the thresholds, colors and icons of its rule
+    let mut format_ranges: Vec<Range> = Vec::new();
+    let mut format_rules: Vec<conditional_format::Rule> = Vec::new();
+    let mut rule_attributes: HashMap<String, String> = HashMap::new();
+    let mut thresholds: Vec<Threshold> = Vec::new();
+    let mut colors: Vec<String> = Vec::new();
+    let mut icons: Option<String> = None;
+    // only the first sheetView is read, its selection is the one of the pane with the cursor
+    let mut sheet_views = 0;
+    let mut active_pane = String::from("topLeft");
+    // column, attributes, values and conditions of the filter column being read
+    let mut filter_column: usize = 0;
+    let mut filter_attributes: HashMap<String, String> = HashMap::new();
+    let mut filter_values: Vec<String> = Vec::new();
+    let mut filter_conditions: Vec<(Comparison, String)> = Vec::new();
+    let sheet_relationships = relationships::read_part(dir, part_name)?;
 
     loop {
         match reader.read_event(&mut buf) {
@@ -38,8 +86,16 @@ pub fn read(dir: &TempDir, name: &String, target: &String, shared_strings: &Vec<
                                 Err(_) => {},
                             }
                         }
+                        if keep_style {
+                            sheet.set_row_source(row_index, row_index);
+                        }
                     },
                     b"c" => {
+                        type_value = String::from("");
+                        style_index = 0;
+                        value_flag = false;
+                        phonetic_flag = false;
+                        formula = None;
                         for a in e.attributes().with_checks(false) {
                             match a {
                                 Ok(ref attr) if attr.key == b"r" => {
@@ -54,36 +110,290 @@ pub fn read(dir: &TempDir, name: &String, target: &String, shared_strings: &Vec<
                                 Ok(ref attr) if attr.key == b"t" => {
                                     type_value = get_attribute_value(attr)?;
                                 },
+                                Ok(ref attr) if attr.key == b"ph" => {
+                                    phonetic_flag = get_attribute_value(attr)? == "1";
+                                },
                                 Ok(_) => {},
                                 Err(_) => {},
                             }
                         }
                     },
+                    b"v" | b"formula1" | b"formula2" | b"formula" => string_value = String::from(""),
+                    b"conditionalFormatting" => {
+                        format_ranges = read_attributes(e)?.get("sqref").map(|sqref| parse_sqref(name, sqref)).unwrap_or_default();
+                        format_rules = Vec::new();
+                    },
+                    b"cfRule" => {
+                        rule_attributes = read_attributes(e)?;
+                        formulas = Vec::new();
+                        thresholds = Vec::new();
+                        colors = Vec::new();
+                        icons = None;
+                    },
+                    b"iconSet" => icons = read_attributes(e)?.remove("iconSet"),
+                    b"autoFilter" => read_auto_filter(name, &read_attributes(e)?, &mut sheet),
+                    b"filterColumn" => {
+                        filter_column = read_attributes(e)?.get("colId").and_then(|id| id.parse::<usize>().ok()).unwrap_or(0);
+                    },
+                    b"filters" | b"customFilters" => {
+                        filter_attributes = read_attributes(e)?;
+                        filter_values = Vec::new();
+                        filter_conditions = Vec::new();
+                    },
+                    b"sheetView" => {
+                        sheet_views += 1;
+                        if sheet_views == 1 {
+                            read_sheet_view(&read_attributes(e)?, sheet.get_view_mut());
+                        }
+                    },
+                    b"dataValidation" => {
+                        validation_attributes = read_attributes(e)?;
+                        formulas = Vec::new();
+                    },
+                    // an inline string has the text in the cell like a shared string
+                    b"is" => {
+                        reader.trim_text(false);
+                        let (val, phonetic) = read_string_item(&mut reader, b"is")?;
+                        reader.trim_text(true);
+                        let mut cell = Cell::new(val, "");
+                        cell.set_phonetic(phonetic.map(|mut phonetic| {
+                            phonetic.set_visible(phonetic_flag);
+                            phonetic
+                        }));
+                        cell.set_formula(formula.take());
+                        if keep_style {
+                            cell.set_style_id(Some(style_index.to_string()));
+                        }
+                        sheet.add_cell(cell, row_index, column_index);
+                        value_flag = true;
+                    },
+                    b"f" => {
+                        string_value = String::from("");
+                        formula_attributes = read_attributes(e)?;
+                    },
                     _ => (),
                 }
             },
             Ok(Event::End(ref e)) => {
                 match e.name() {
                     b"v" => {
-                        let cell = if type_value == "s" {
-                            let index = string_value.parse::<usize>().unwrap();
-                            let val = shared_strings.get(index).unwrap();
-                            Cell::str((*val).clone(), String::from(""))
-                        } else {
-                            let hash = &styles[style_index];
-                            match hash.get("formatCode") {
-                                Some(format_code) => {
-                                    Cell::new(
-                                        Value::Date(number_to_date(&string_value)),
-                                        format_code.to_string()
-                                    )
-                                },
-                                None => {
-                                    Cell::float(string_value.parse::<f64>().unwrap(), "")
+                        let format_code = styles.get(style_index).and_then(|hash| hash.get("formatCode"));
+                        let mut cell = match type_value.as_str() {
+                            "s" => {
+                                let (val, phonetic) = string_value.trim().parse::<usize>().ok()
+                                    .and_then(|index| shared_strings.get(index))
+                                    .ok_or_else(|| invalid_data(format!("invalid shared string index: {}", string_value)))?;
+                                let mut cell = Cell::new(val.clone(), "");
+                                cell.set_phonetic(phonetic.clone().map(|mut phonetic| {
+                                    phonetic.set_visible(phonetic_flag);
+                                    phonetic
+                                }));
+                                cell
+                            },
+                            // the string result of a formula
+                            "str" | "inlineStr" => Cell::str(string_value.clone(), String::from("")),
+                            "b" => Cell::new(Value::Bool(string_value.trim() == "1"), ""),
+                            "e" => Cell::new(Value::Error(string_value.clone()), ""),
+                            "d" => {
+                                let val = iso_to_value(&string_value);
+                                let format_code = match format_code {
+                                    Some(format_code) if !format_code.is_empty() && !format_code.eq_ignore_ascii_case("General") => format_code.clone(),
+                                    _ => String::from(iso_format_code(&val)),
+                                };
+                                Cell::new(val, format_code)
+                            },
+                            _ => {
+                                let number = string_value.trim().parse::<f64>()
+                                    .map_err(|_| invalid_data(format!("invalid number: {}", string_value)))?;
+                                match format_code.and_then(|format_code| number_to_value(number, format_code, date_system).map(|val| (format_code, val))) {
+                                    Some((format_code, val)) => Cell::new(val, format_code.to_string()),
+                                    None => Cell::float(number, ""),
                                 }
-                            }
+                            },
                         };
+                        cell.set_formula(formula.take());
+                        if keep_style {
+                            cell.set_style_id(Some(style_index.to_string()));
+                        }
                         sheet.add_cell(cell, row_index, column_index);
+                        value_flag = true;
+                    },
+                    b"f" => {
+                        formula = Some(string_value.clone());
+                        if let (Some("shared"), Some(index)) = (formula_attributes.get("t").map(|t| t.as_str()), formula_attributes.get("si")) {
+                            shared_formulas.insert(index.clone(), (string_value.clone(), row_index, column_index));
+                        }
+                    },
+                    // a formula without its result
+                    b"c" if formula.is_some() && !value_flag => {
+                        let mut cell = Cell::new(Value::Empty, "");
+                        cell.set_formula(formula.take());
+                        if keep_style {
+                            cell.set_style_id(Some(style_index.to_string()));
+                        }
+                        sheet.add_cell(cell, row_index, column_index);
+                    },
+                    b"c" if keep_style && !value_flag => sheet.add_cell(empty_cell(style_index), row_index, column_index),
+                    b"formula1" | b"formula2" | b"formula" => formulas.push(string_value.clone()),
+                    b"cfRule" => {
+                        format_rules.extend(read_conditional_rule(&rule_attributes, &formulas, &thresholds, &colors, icons.take(), dxfs));
+                    },
+                    b"conditionalFormatting" => {
+                        let mut ranges = format_ranges.drain(..);
+                        let mut rules = format_rules.drain(..);
+                        if let (Some(range), Some(rule)) = (ranges.next(), rules.next()) {
+                            let mut format = ConditionalFormat::new(range, rule);
+                            for rule in rules {
+                                format.add_rule(rule);
+                            }
+                            for range in ranges {
+                                format.add_range(range);
+                            }
+                            sheet.add_conditional_format(format);
+                        }
+                    },
+                    b"dataValidation" => {
+                        if let Some(validation) = read_validation(name, &validation_attributes, &formulas, date_system) {
+                            sheet.add_validation(validation);
+                        }
+                    },
+                    b"filters" => {
+                        let filter = Filter::Values {
+                            values: mem::take(&mut filter_values),
+                            blank: filter_attributes.get("blank").is_some_and(|value| value == "1" || value == "true"),
+                        };
+                        set_filter(&mut sheet, filter_column, filter);
+                    },
+                    b"customFilters" => {
+                        let filter = Filter::Custom {
+                            conditions: mem::take(&mut filter_conditions),
+                            and: filter_attributes.get("and").is_some_and(|value| value == "1" || value == "true"),
+                        };
+                        set_filter(&mut sheet, filter_column, filter);
+                    },
+                    _ => (),
+                }
+            },
+            Ok(Event::Empty(ref e)) => {
+                match e.name() {
+                    b"cfRule" => {
+                        let attributes = read_attributes(e)?;
+                        format_rules.extend(read_conditional_rule(&attributes, &[], &[], &[], None, dxfs));
+                    },
+                    b"cfvo" => thresholds.extend(read_threshold(&read_attributes(e)?)),
+                    b"autoFilter" => read_auto_filter(name, &read_attributes(e)?, &mut sheet),
+                    b"filters" => {
+                        let attributes = read_attributes(e)?;
+                        let filter = Filter::Values {
+                            values: Vec::new(),
+                            blank: attributes.get("blank").is_some_and(|value| value == "1" || value == "true"),
+                        };
+                        set_filter(&mut sheet, filter_column, filter);
+                    },
+                    b"filter" => filter_values.extend(read_attributes(e)?.remove("val")),
+                    b"customFilter" => {
+                        let mut attributes = read_attributes(e)?;
+                        let comparison = attributes.get("operator").map_or(Some(Comparison::Equal), |operator| Comparison::from_name(operator));
+                        if let Some(comparison) = comparison {
+                            filter_conditions.push((comparison, attributes.remove("val").unwrap_or_default()));
+                        }
+                    },
+                    b"top10" => {
+                        let attributes = read_attributes(e)?;
+                        let flag = |key: &str, default: bool| attributes.get(key).map_or(default, |value| value == "1" || value == "true");
+                        let filter = Filter::Top {
+                            count: attributes.get("val").and_then(|value| value.trim().parse::<f64>().ok()).unwrap_or(10.0),
+                            bottom: !flag("top", true),
+                            percent: flag("percent", false),
+                        };
+                        set_filter(&mut sheet, filter_column, filter);
+                    },
+                    b"sortCondition" => read_sort_condition(name, &read_attributes(e)?, &mut sheet),
+                    b"sheetView" => {
+                        sheet_views += 1;
+                        if sheet_views == 1 {
+                            read_sheet_view(&read_attributes(e)?, sheet.get_view_mut());
+                        }
+                    },
+                    b"pane" if sheet_views == 1 => active_pane = read_pane(&read_attributes(e)?, sheet.get_view_mut()),
+                    b"selection" if sheet_views == 1 => read_selection(name, &read_attributes(e)?, &active_pane, sheet.get_view_mut()),
+                    b"color" => colors.extend(read_attributes(e)?.remove("rgb").map(|rgb| {
+                        if rgb.len() == 8 { String::from(&rgb[2..]) } else { rgb }
+                    })),
+                    // a row without cells only keeps its height and style
+                    b"row" if keep_style => {
+                        for a in e.attributes().with_checks(false) {
+                            match a {
+                                Ok(ref attr) if attr.key == b"r" => {
+                                    let value = get_attribute_value(attr)?;
+                                    row_index = value.parse::<usize>().unwrap() - 1;
+                                    sheet.set_row_source(row_index, row_index);
+                                },
+                                Ok(_) => {},
+                                Err(_) => {},
+                            }
+                        }
+                    },
+                    // a cell without value only keeps its style
+                    b"c" if keep_style => {
+                        style_index = 0;
+                        for a in e.attributes().with_checks(false) {
+                            match a {
+                                Ok(ref attr) if attr.key == b"r" => {
+                                    let value = get_attribute_value(attr)?;
+                                    column_index = column_and_row_to_index(value).unwrap().0;
+                                },
+                                Ok(ref attr) if attr.key == b"s" => {
+                                    let value = get_attribute_value(attr)?;
+                                    style_index = value.parse::<usize>().unwrap();
+                                },
+                                Ok(_) => {},
+                                Err(_) => {},
+                            }
+                        }
+                        sheet.add_cell(empty_cell(style_index), row_index, column_index);
+                    },
+                    // a cell of a shared formula has the formula of its first cell moved to it
+                    b"f" => {
+                        let attributes = read_attributes(e)?;
+                        formula = attributes.get("si")
+                            .and_then(|index| shared_formulas.get(index))
+                            .map(|&(ref text, row, column)| formula::offset(text, row_index as isize - row as isize, column_index as isize - column as isize));
+                    },
+                    // the url is the target of the relationship, the location may be its fragment
+                    b"hyperlink" => {
+                        let mut reference = String::from("");
+                        let mut url: Option<String> = None;
+                        let mut location: Option<String> = None;
+                        let mut tooltip: Option<String> = None;
+                        for a in e.attributes().with_checks(false) {
+                            match a {
+                                Ok(ref attr) if attr.key == b"ref" => reference = get_attribute_value(attr)?,
+                                Ok(ref attr) if attr.key == b"r:id" => {
+                                    let id = get_attribute_value(attr)?;
+                                    url = sheet_relationships.iter().find(|r| r.id == id).map(|r| r.target.clone());
+                                },
+                                Ok(ref attr) if attr.key == b"location" => {
+                                    location = Some(condvert_character_reference(&get_attribute_value(attr)?));
+                                },
+                                Ok(ref attr) if attr.key == b"tooltip" => {
+                                    tooltip = Some(condvert_character_reference(&get_attribute_value(attr)?));
+                                },
+                                Ok(_) => {},
+                                Err(_) => {},
+                            }
+                        }
+                        let target = match (url, location) {
+                            (Some(url), Some(location)) => Some(format!("{}#{}", url, location)),
+                            (Some(url), None) => Some(url),
+                            (None, Some(location)) => Some(format!("#{}", location)),
+                            (None, None) => None,
+                        };
+                        if let Some(target) = target {
+                            let mut hyperlink = Hyperlink::new(target);
+                            hyperlink.set_tooltip(tooltip);
+                            hyperlinks.push((reference, hyperlink));
+                        }
                     },
                     _ => (),
                 }
@@ -95,17 +405,288 @@ pub fn read(dir: &TempDir, name: &String, target: &String, shared_strings: &Vec<
         }
         buf.clear();
     }
+    for (reference, hyperlink) in hyperlinks {
+        add_hyperlink(&mut sheet, &reference, hyperlink);
+    }
 
     Ok(sheet)
 }
 
-// 1900年からのepoch
-// 43071.5625 -> 2017-12-02T13:30:00
-fn number_to_date(src: &String) -> DateTime<Utc> {
-    let num = src.parse::<f64>().unwrap();
-    let timestamp = ((((num - num.floor()) * 86400.0) as f64).round()) as i64;
-    let hms = NaiveDateTime::from_timestamp(timestamp, 0);
+/// Attributes by their names
+fn read_attributes(e: &BytesStart) -> result::Result<HashMap<String, String>, XlsxError> {
+    let mut attributes = HashMap::new();
+    for attr in e.attributes().with_checks(false).flatten() {
+        let key = String::from_utf8_lossy(attr.key).into_owned();
+        attributes.insert(key, condvert_character_reference(&get_attribute_value(&attr)?));
+    }
+    Ok(attributes)
+}
+
+/// Auto filter of the range of an autoFilter, the filters and the sort keys are added to it
+fn read_auto_filter(sheet_name: &str, attributes: &HashMap<String, String>, sheet: &mut Sheet) {
+    let range = attributes.get("ref").and_then(|reference| parse_sqref(sheet_name, reference).into_iter().next());
+    if let Some(range) = range {
+        sheet.set_auto_filter(Some(AutoFilter::new(range)));
+    }
+}
 
-    let dt = (Utc.ymd(1900, 1, 1) + Duration::days(num.floor() as i64 - 2)).and_hms(hms.hour(), hms.minute(), hms.second());
-    dt
+fn set_filter(sheet: &mut Sheet, column: usize, filter: Filter) {
+    if let Some(auto_filter) = sheet.get_auto_filter_mut() {
+        auto_filter.set_filter(column, filter);
+    }
+}
+
+/// Sort key of a sortCondition of a column of the auto filter,
+/// the sort state may be in the autoFilter or after it in the worksheet
+fn read_sort_condition(sheet_name: &str, attributes: &HashMap<String, String>, sheet: &mut Sheet) {
+    let cells = attributes.get("ref").and_then(|reference| parse_sqref(sheet_name, reference).into_iter().next());
+    let descending = attributes.get("descending").is_some_and(|value| value == "1" || value == "true");
+    if let (Some(cells), Some(auto_filter)) = (cells, sheet.get_auto_filter_mut()) {
+        let range = auto_filter.get_range().clone();
+        if range.get_first_column() <= cells.get_first_column() && cells.get_first_column() <= range.get_last_column() {
+            auto_filter.add_sort_key(SortKey::new(cells.get_first_column() - range.get_first_column(), descending));
+        }
+    }
+}
+
+/// Grid lines, headers, direction and zoom of a sheetView
+fn read_sheet_view(attributes: &HashMap<String, String>, view: &mut SheetView) {
+    let flag = |key: &str, default: bool| attributes.get(key).map_or(default, |value| value == "1" || value == "true");
+    view.set_show_grid_lines(flag("showGridLines", true));
+    view.set_show_headers(flag("showRowColHeaders", true));
+    view.set_right_to_left(flag("rightToLeft", false));
+    match attributes.get("zoomScale").and_then(|zoom| zoom.parse::<u32>().ok()) {
+        Some(zoom) if zoom > 0 => view.set_zoom(zoom),
+        _ => {},
+    }
+}
+
+/// Frozen rows and columns or the split in twentieths of a point of a pane, the name of the pane with the cursor is returned
+fn read_pane(attributes: &HashMap<String, String>, view: &mut SheetView) -> String {
+    let split = |key: &str| attributes.get(key).and_then(|value| value.parse::<f64>().ok()).unwrap_or(0.0);
+    let (x, y) = (split("xSplit"), split("ySplit"));
+    if x > 0.0 || y > 0.0 {
+        view.set_pane(Some(match attributes.get("state").map(|state| state.as_str()) {
+            Some("frozen") | Some("frozenSplit") => Pane::Frozen { rows: y as usize, columns: x as usize },
+            _ => Pane::Split { x: x / 20.0, y: y / 20.0 },
+        }));
+    }
+    attributes.get("activePane").cloned().unwrap_or_else(|| String::from("topLeft"))
+}
+
+/// Active cell and selected ranges of the selection of the active pane, a selection of only the active cell has no ranges
+fn read_selection(sheet_name: &str, attributes: &HashMap<String, String>, active_pane: &str, view: &mut SheetView) {
+    if attributes.get("pane").map_or("topLeft", |pane| pane.as_str()) != active_pane {
+        return;
+    }
+    let active_cell = attributes.get("activeCell").and_then(|cell| column_and_row_to_index(cell.as_str()));
+    if let Some((column, row)) = active_cell {
+        view.set_active_cell(row, column);
+    }
+    let ranges = attributes.get("sqref").map(|sqref| parse_sqref(sheet_name, sqref)).unwrap_or_default();
+    let only_active_cell = match (ranges.as_slice(), active_cell) {
+        ([range], Some((column, row))) => *range == Range::cell(sheet_name, row, column),
+        (&[], _) => true,
+        _ => false,
+    };
+    view.set_selection(if only_active_cell { Vec::new() } else { ranges });
+}
+
+/// Ranges of the sheet of a sqref like "B2:B10 D2"
+fn parse_sqref(sheet_name: &str, sqref: &str) -> Vec<Range> {
+    sqref.split_whitespace()
+        .filter_map(|reference| Range::parse(&format!("{}!{}", range::quote_sheet_name(sheet_name), reference)))
+        .collect()
+}
+
+/// Rule of the attributes and the children of a cfRule, None for a type which isn't a rule like "containsText".
+/// A rule without its dxf has an empty style.
+fn read_conditional_rule(attributes: &HashMap<String, String>, formulas: &[String], thresholds: &[Threshold], colors: &[String], icons: Option<String>, dxfs: &[DifferentialStyle]) -> Option<conditional_format::Rule> {
+    use conditional_format::Rule;
+    let style = attributes.get("dxfId").and_then(|id| id.parse::<usize>().ok())
+        .and_then(|id| dxfs.get(id).cloned())
+        .unwrap_or_default();
+    let flag = |key: &str| attributes.get(key).is_some_and(|value| value == "1" || value == "true");
+    let rule = match attributes.get("type").map(|t| t.as_str()).unwrap_or("") {
+        "cellIs" => {
+            let operator = attributes.get("operator").map(|operator| operator.as_str()).unwrap_or("between");
+            Rule::CellIs(Operator::from_name(operator, formulas.first()?.clone(), formulas.get(1).cloned())?, style)
+        },
+        "expression" => Rule::Expression(formulas.first()?.clone(), style),
+        "colorScale" if thresholds.len() >= 2 && thresholds.len() == colors.len() => {
+            Rule::ColorScale(thresholds.iter().cloned().zip(colors.iter().cloned()).collect())
+        },
+        "dataBar" if thresholds.len() == 2 => Rule::DataBar {
+            min: thresholds[0].clone(),
+            max: thresholds[1].clone(),
+            color: colors.first()?.clone(),
+        },
+        // the default icons are traffic lights
+        "iconSet" => Rule::IconSet {
+            icons: icons.map_or(Some(IconStyle::TrafficLights3), |name| IconStyle::from_name(&name))?,
+            thresholds: thresholds.to_vec(),
+        },
+        "top10" => Rule::Top {
+            rank: attributes.get("rank").and_then(|rank| rank.parse::<u32>().ok()).unwrap_or(10),
+            bottom: flag("bottom"),
+            percent: flag("percent"),
+            style,
+        },
+        "duplicateValues" => Rule::Duplicate(style),
+        "uniqueValues" => Rule::Unique(style),
+        _ => return None,
+    };
+    Some(rule)
+}
+
+/// Threshold of the attributes of a cfvo
+fn read_threshold(attributes: &HashMap<String, String>) -> Option<Threshold> {
+    let value = || attributes.get("val").and_then(|value| value.trim().parse::<f64>().ok());
+    match attributes.get("type").map(|t| t.as_str()).unwrap_or("") {
+        "min" => Some(Threshold::Min),
+        "max" => Some(Threshold::Max),
+        "num" => value().map(Threshold::Number),
+        "percent" => value().map(Threshold::Percent),
+        "percentile" => value().map(Threshold::Percentile),
+        "formula" => attributes.get("val").cloned().map(Threshold::Formula),
+        _ => None,
+    }
+}
+
+/// Validation of the attributes and the formulas of a dataValidation,
+/// None for a rule without formulas or a list of a name which isn't a range
+fn read_validation(sheet_name: &str, attributes: &HashMap<String, String>, formulas: &[String], date_system: DateSystem) -> Option<DataValidation> {
+    let ranges = attributes.get("sqref").map(|sqref| parse_sqref(sheet_name, sqref)).unwrap_or_default();
+    let formula = formulas.first()?;
+    let operator = attributes.get("operator").map(|operator| operator.as_str()).unwrap_or("between");
+    fn read_operator<T, F>(operator: &str, formulas: &[String], parse: F) -> Option<Operator<T>>
+        where F: Fn(&str) -> Option<T>
+    {
+        let upper = match formulas.get(1) {
+            Some(formula) => Some(parse(formula)?),
+            None => None,
+        };
+        Operator::from_name(operator, parse(&formulas[0])?, upper)
+    }
+    let rule = match attributes.get("type").map(|t| t.as_str()).unwrap_or("none") {
+        "list" if formula.len() >= 2 && formula.starts_with('"') && formula.ends_with('"') => {
+            let items = formula[1..formula.len() - 1].replace("\"\"", "\"");
+            Rule::List(items.split(',').map(|item| item.to_string()).collect())
+        },
+        // a range of the sheet has no sheet name
+        "list" => Range::parse(formula)
+            .or_else(|| Range::parse(&format!("{}!{}", range::quote_sheet_name(sheet_name), formula)))
+            .map(Rule::ListRange)?,
+        "whole" => Rule::WholeNumber(read_operator(operator, formulas, |f| f.trim().parse::<f64>().ok().map(|n| n as i64))?),
+        "decimal" => Rule::Decimal(read_operator(operator, formulas, |f| f.trim().parse::<f64>().ok())?),
+        "date" => Rule::Date(read_operator(operator, formulas, |f| {
+            f.trim().parse::<f64>().ok().and_then(|serial| date::serial_to_datetime(serial, date_system)).map(|dt| dt.date())
+        })?),
+        "textLength" => Rule::TextLength(read_operator(operator, formulas, |f| f.trim().parse::<usize>().ok())?),
+        "custom" => Rule::Custom(formula.clone()),
+        _ => return None,
+    };
+    let mut ranges = ranges.into_iter();
+    let mut validation = DataValidation::new(ranges.next()?, rule);
+    for range in ranges {
+        validation.add_range(range);
+    }
+    let flag = |key: &str| attributes.get(key).map(|value| value == "1" || value == "true");
+    validation.set_allow_blank(flag("allowBlank").unwrap_or(false));
+    // showDropDown hides the drop-down
+    validation.set_drop_down(!flag("showDropDown").unwrap_or(false));
+    let message = |title: &str, text: &str| match (attributes.get(title), attributes.get(text)) {
+        (None, None) => None,
+        (title, text) => Some(Message::new(title.cloned().unwrap_or_default(), text.cloned().unwrap_or_default())),
+    };
+    if flag("showInputMessage").unwrap_or(false) {
+        validation.set_prompt(message("promptTitle", "prompt"));
+    }
+    validation.set_error(message("errorTitle", "error"));
+    validation.set_error_style(match attributes.get("errorStyle").map(|style| style.as_str()) {
+        Some("warning") => ErrorStyle::Warning,
+        Some("information") => ErrorStyle::Information,
+        _ => ErrorStyle::Stop,
+    });
+    Some(validation)
+}
+
+/// Link the first cell of the range and the other cells of it having a value
+fn add_hyperlink(sheet: &mut Sheet, reference: &str, hyperlink: Hyperlink) {
+    let mut corners = reference.split(':').filter_map(column_and_row_to_index);
+    let (first_column, first_row) = match corners.next() {
+        Some(first) => first,
+        None => return,
+    };
+    let (last_column, last_row) = corners.next().unwrap_or((first_column, first_row));
+    let mut linked = Vec::new();
+    for (&row_index, row) in sheet.get_rows() {
+        for &column_index in row.keys() {
+            if row_index >= first_row && row_index <= last_row && column_index >= first_column && column_index <= last_column {
+                linked.push((row_index, column_index));
+            }
+        }
+    }
+    if !linked.contains(&(first_row, first_column)) {
+        linked.push((first_row, first_column));
+    }
+    for (row_index, column_index) in linked {
+        let mut cell = sheet.get_cell(row_index, column_index).cloned().unwrap_or_else(|| Cell::new(Value::Empty, ""));
+        cell.set_hyperlink(Some(hyperlink.clone()));
+        sheet.add_cell(cell, row_index, column_index);
+    }
+}
+
+fn empty_cell(style_index: usize) -> Cell {
+    let mut cell = Cell::new(Value::Empty, "");
+    cell.set_style_id(Some(style_index.to_string()));
+    cell
+}
+
+// 2017-12-02T13:30:00+09:00 is the date in UTC, 2017-12-02T13:30:00 and 2017-12-02 are dates in UTC too,
+// 13:30:00 is a time of day, a text which isn't any of them is kept as it is
+fn iso_to_value(src: &str) -> Value {
+    let src = src.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(src) {
+        return Value::Date(dt.with_timezone(&Utc));
+    }
+    let naive = src.trim_end_matches('Z');
+    if let Ok(dt) = NaiveDateTime::parse_from_str(naive, "%Y-%m-%dT%H:%M:%S%.f") {
+        Value::Date(DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc))
+    } else if let Ok(date) = NaiveDate::parse_from_str(naive, "%Y-%m-%d") {
+        Value::Date(DateTime::<Utc>::from_naive_utc_and_offset(date.and_hms_opt(0, 0, 0).unwrap(), Utc))
+    } else if let Ok(time) = NaiveTime::parse_from_str(naive, "%H:%M:%S%.f") {
+        Value::Time(time)
+    } else {
+        Value::Str(String::from(src))
+    }
+}
+
+// a date cell without a number format keeps a date or a time format to be written back as a date
+fn iso_format_code(value: &Value) -> &'static str {
+    match *value {
+        Value::Date(_) => "yyyy-mm-dd hh:mm:ss",
+        Value::Time(_) => "hh:mm:ss",
+        _ => "",
+    }
+}
+
+// 43071.5625 -> 2017-12-02T13:30:00
+// "[$¥-411]#,##0" is a currency, "[h]:mm" is an elapsed time, "hh:mm" is a time of day,
+// the other dates and times are dates and the rest are numbers keeping the format but "General"
+fn number_to_value(number: f64, format_code: &str, date_system: DateSystem) -> Option<Value> {
+    let format = Format::new(format_code);
+    if let Some(code) = Currency::code_from_format(format_code) {
+        Some(Value::Currency(Currency::new(number, code)))
+    } else if format_code.is_empty() || format_code.eq_ignore_ascii_case("General") {
+        None
+    } else if !format.is_date() {
+        Some(Value::Float(number))
+    } else if format.is_elapsed() {
+        date::serial_to_duration(number).map(Value::Duration)
+    } else if format.is_time_only() && number < 1.0 {
+        date::serial_to_time(number).map(Value::Time)
+    } else {
+        date::serial_to_datetime(number, date_system).map(|dt| Value::Date(DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc)))
+    }
 }
@@ -1,15 +1,22 @@
 use file_common::*;
 use std::collections::HashMap;
+use std::io::BufRead;
 use std::result;
+use conditional_format::DifferentialStyle;
+use rich_text::Font;
 use super::quick_xml::Reader;
 use super::quick_xml::events::{Event};
 use super::tempdir::TempDir;
 use super::XlsxError;
+use super::read_shared_strings::read_font_property;
 
 const STYLE_XML: &'static str = "xl/styles.xml";
 
 pub fn read(dir: &TempDir) -> result::Result<Vec<HashMap<String, String>>, XlsxError> {
     let path = dir.path().join(STYLE_XML);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
     let mut reader = Reader::from_file(path)?;
     reader.trim_text(true);
     let mut buf = Vec::new();
@@ -77,3 +84,89 @@ pub fn read(dir: &TempDir) -> result::Result<Vec<HashMap<String, String>>, XlsxE
     }
     Ok(cell_xfs)
 }
+
+/// Differential styles of the conditional formats
+pub fn read_dxfs(dir: &TempDir) -> result::Result<Vec<DifferentialStyle>, XlsxError> {
+    let path = dir.path().join(STYLE_XML);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    read_dxf_list(&mut Reader::from_file(path)?)
+}
+
+/// Differential styles of the dxfs of an original styles.xml
+pub fn read_dxfs_of(data: &[u8]) -> result::Result<Vec<DifferentialStyle>, XlsxError> {
+    read_dxf_list(&mut Reader::from_reader(data))
+}
+
+/// The font and the fill color of each dxf, a theme or an indexed color isn't read
+fn read_dxf_list<B: BufRead>(reader: &mut Reader<B>) -> result::Result<Vec<DifferentialStyle>, XlsxError> {
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+    let mut dxfs: Vec<DifferentialStyle> = Vec::new();
+    let mut dxf: Option<DifferentialStyle> = None;
+    let mut font: Option<Font> = None;
+    let mut fill_flag = false;
+    loop {
+        match reader.read_event(&mut buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"dxf" => dxf = Some(DifferentialStyle::new()),
+                    b"font" if dxf.is_some() => font = Some(Font::new()),
+                    b"fill" if dxf.is_some() => fill_flag = true,
+                    _ => {
+                        if let Some(ref mut font) = font {
+                            read_font_property(e, font)?;
+                        }
+                    },
+                }
+            },
+            Ok(Event::Empty(ref e)) => {
+                match e.name() {
+                    b"dxf" => dxfs.push(DifferentialStyle::new()),
+                    // the color of a solid fill of a dxf is its bgColor
+                    b"bgColor" | b"fgColor" if fill_flag => {
+                        if let Some(ref mut dxf) = dxf {
+                            for a in e.attributes().with_checks(false) {
+                                match a {
+                                    Ok(ref attr) if attr.key == b"rgb" => {
+                                        let rgb = get_attribute_value(attr)?;
+                                        let rgb = if rgb.len() == 8 { String::from(&rgb[2..]) } else { rgb };
+                                        if e.name() == b"bgColor" || dxf.get_fill_color().is_none() {
+                                            dxf.set_fill_color(Some(rgb));
+                                        }
+                                    },
+                                    Ok(_) => {},
+                                    Err(_) => {},
+                                }
+                            }
+                        }
+                    },
+                    _ => {
+                        if let Some(ref mut font) = font {
+                            read_font_property(e, font)?;
+                        }
+                    },
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                match e.name() {
+                    b"font" => {
+                        if let (Some(dxf), Some(font)) = (dxf.as_mut(), font.take()) {
+                            dxf.set_font(font);
+                        }
+                    },
+                    b"fill" => fill_flag = false,
+                    b"dxf" => dxfs.extend(dxf.take()),
+                    b"dxfs" => break,
+                    _ => (),
+                }
+            },
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(XlsxError::Xml(e)),
+            _ => (),
+        }
+        buf.clear();
+    }
+    Ok(dxfs)
+}
@@ -0,0 +1,102 @@
+use file_common::*;
+use std::result;
+use super::quick_xml::Reader;
+use super::quick_xml::events::{Event, BytesStart};
+use super::tempdir::TempDir;
+use super::{XlsxError, resolve_part_name};
+use super::relationships;
+use super::TABLE_TYPE;
+use range::{self, Range};
+use table::{Table, TableColumn, TableStyle, Totals, TotalsFunction};
+
+/// Tables of the table parts of a worksheet part, in the order of its relationships
+pub fn read(dir: &TempDir, part_name: &str, sheet_name: &str) -> result::Result<Vec<Table>, XlsxError> {
+    let base_dir = match part_name.rfind('/') {
+        Some(index) => &part_name[..index + 1],
+        None => "",
+    };
+    let mut tables = Vec::new();
+    for r in relationships::read_part(dir, part_name)? {
+        if r.rel_type != TABLE_TYPE || r.target_mode.is_some() {
+            continue;
+        }
+        let table_part_name = resolve_part_name(base_dir, &r.target);
+        if dir.path().join(&table_part_name).is_file() {
+            if let Some(table) = read_table(dir, &table_part_name, sheet_name)? {
+                tables.push(table);
+            }
+        }
+    }
+    Ok(tables)
+}
+
+/// The name is the displayName used in the formulas, a table without its name or range is None
+fn read_table(dir: &TempDir, part_name: &str, sheet_name: &str) -> result::Result<Option<Table>, XlsxError> {
+    let mut reader = Reader::from_file(dir.path().join(part_name))?;
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+    let mut table: Option<Table> = None;
+    loop {
+        match reader.read_event(&mut buf) {
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => {
+                match e.name() {
+                    b"table" => {
+                        let name = read_attribute(e, b"displayName")?.or(read_attribute(e, b"name")?);
+                        let range = read_attribute(e, b"ref")?
+                            .and_then(|reference| Range::parse(&format!("{}!{}", range::quote_sheet_name(sheet_name), reference)));
+                        if let (Some(name), Some(range)) = (name, range) {
+                            let mut t = Table::new(&name, range);
+                            t.set_header_row(!matches!(read_attribute(e, b"headerRowCount")?, Some(ref count) if count == "0"));
+                            t.set_totals_row(read_attribute(e, b"totalsRowCount")?.is_some_and(|count| count != "0"));
+                            t.set_style(None);
+                            table = Some(t);
+                        }
+                    },
+                    b"tableColumn" => {
+                        if let Some(ref mut table) = table {
+                            let mut column = TableColumn::new(&read_attribute(e, b"name")?.unwrap_or_default());
+                            let function = read_attribute(e, b"totalsRowFunction")?.and_then(|name| TotalsFunction::from_name(&name));
+                            match (read_attribute(e, b"totalsRowLabel")?, function) {
+                                (_, Some(function)) => column.set_totals(Some(Totals::Function(function))),
+                                (Some(label), None) => column.set_totals(Some(Totals::Label(label))),
+                                (None, None) => {},
+                            }
+                            table.add_column(column);
+                        }
+                    },
+                    b"tableStyleInfo" => {
+                        if let Some(ref mut table) = table {
+                            let mut style = TableStyle::new(&read_attribute(e, b"name")?.unwrap_or_default());
+                            style.set_show_first_column(read_flag(e, b"showFirstColumn")?);
+                            style.set_show_last_column(read_flag(e, b"showLastColumn")?);
+                            style.set_show_row_stripes(read_flag(e, b"showRowStripes")?);
+                            style.set_show_column_stripes(read_flag(e, b"showColumnStripes")?);
+                            table.set_style(Some(style));
+                        }
+                    },
+                    _ => (),
+                }
+            },
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(XlsxError::Xml(e)),
+            _ => (),
+        }
+        buf.clear();
+    }
+    Ok(table)
+}
+
+fn read_flag(e: &BytesStart, key: &[u8]) -> result::Result<bool, XlsxError> {
+    Ok(read_attribute(e, key)?.is_some_and(|value| value == "1" || value == "true"))
+}
+
+fn read_attribute(e: &BytesStart, key: &[u8]) -> result::Result<Option<String>, XlsxError> {
+    for a in e.attributes().with_checks(false) {
+        match a {
+            Ok(ref attr) if attr.key == key => return Ok(Some(condvert_character_reference(&get_attribute_value(attr)?))),
+            Ok(_) => {},
+            Err(_) => {},
+        }
+    }
+    Ok(None)
+}
@@ -5,6 +5,8 @@ use super::quick_xml::Reader;
 use super::quick_xml::events::{Event};
 use super::tempdir::TempDir;
 use super::XlsxError;
+use date::DateSystem;
+use defined_name::DefinedName;
 
 const WORKBOOK_XML: &'static str = "xl/workbook.xml";
 
@@ -51,3 +53,120 @@ pub fn read(dir: &TempDir) -> result::Result<Vec<HashMap<&str, String>>, XlsxErr
     }
     Ok(res)
 }
+
+/// Date system of workbookPr, 1904 with date1904="1"
+pub fn read_date_system(dir: &TempDir) -> result::Result<DateSystem, XlsxError> {
+    let path = dir.path().join(WORKBOOK_XML);
+    let mut reader = Reader::from_file(path)?;
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+    let mut res = DateSystem::Date1900;
+    loop {
+        match reader.read_event(&mut buf) {
+            Ok(Event::Empty(ref e)) | Ok(Event::Start(ref e)) if e.name() == b"workbookPr" => {
+                for a in e.attributes().with_checks(false) {
+                    match a {
+                        Ok(ref attr) if attr.key == b"date1904" => {
+                            let value = get_attribute_value(attr)?;
+                            if value == "1" || value == "true" {
+                                res = DateSystem::Date1904;
+                            }
+                        },
+                        Ok(_) => {},
+                        Err(_) => {},
+                    }
+                }
+                break;
+            },
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(XlsxError::Xml(e)),
+            _ => (),
+        }
+        buf.clear();
+    }
+    Ok(res)
+}
+
+/// Index of the active sheet of the first workbookView
+pub fn read_active_tab(dir: &TempDir) -> result::Result<usize, XlsxError> {
+    let path = dir.path().join(WORKBOOK_XML);
+    let mut reader = Reader::from_file(path)?;
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+    let mut res = 0;
+    loop {
+        match reader.read_event(&mut buf) {
+            Ok(Event::Empty(ref e)) | Ok(Event::Start(ref e)) if e.name() == b"workbookView" => {
+                for a in e.attributes().with_checks(false) {
+                    match a {
+                        Ok(ref attr) if attr.key == b"activeTab" => {
+                            res = get_attribute_value(attr)?.parse::<usize>().unwrap_or(0);
+                        },
+                        Ok(_) => {},
+                        Err(_) => {},
+                    }
+                }
+                break;
+            },
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(XlsxError::Xml(e)),
+            _ => (),
+        }
+        buf.clear();
+    }
+    Ok(res)
+}
+
+/// Names of definedNames with the index of their sheet, None for a name of the workbook
+pub fn read_defined_names(dir: &TempDir) -> result::Result<Vec<(Option<usize>, DefinedName)>, XlsxError> {
+    let path = dir.path().join(WORKBOOK_XML);
+    let mut reader = Reader::from_file(path)?;
+    reader.trim_text(false);
+    let mut buf = Vec::new();
+    let mut res = Vec::new();
+    let mut current: Option<(Option<usize>, DefinedName)> = None;
+    loop {
+        match reader.read_event(&mut buf) {
+            Ok(Event::Start(ref e)) if e.name() == b"definedName" => {
+                let mut defined_name = DefinedName::new("", "");
+                let mut sheet_index = None;
+                for a in e.attributes().with_checks(false) {
+                    match a {
+                        Ok(ref attr) if attr.key == b"name" => {
+                            defined_name.set_name(condvert_character_reference(&get_attribute_value(attr)?));
+                        },
+                        Ok(ref attr) if attr.key == b"localSheetId" => {
+                            sheet_index = get_attribute_value(attr)?.parse::<usize>().ok();
+                        },
+                        Ok(ref attr) if attr.key == b"hidden" => {
+                            let value = get_attribute_value(attr)?;
+                            defined_name.set_hidden(value == "1" || value == "true");
+                        },
+                        Ok(_) => {},
+                        Err(_) => {},
+                    }
+                }
+                current = Some((sheet_index, defined_name));
+            },
+            Ok(Event::Text(e)) => {
+                if let Some((_, ref mut defined_name)) = current {
+                    let text = e.unescape_and_decode(&reader)?;
+                    let formula = format!("{}{}", defined_name.get_formula(), text);
+                    defined_name.set_formula(formula);
+                }
+            },
+            Ok(Event::End(ref e)) if e.name() == b"definedName" => {
+                if let Some((sheet_index, mut defined_name)) = current.take() {
+                    let formula = defined_name.get_formula().trim().to_string();
+                    defined_name.set_formula(formula);
+                    res.push((sheet_index, defined_name));
+                }
+            },
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(XlsxError::Xml(e)),
+            _ => (),
+        }
+        buf.clear();
+    }
+    Ok(res)
+}
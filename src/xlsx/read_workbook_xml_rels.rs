@@ -31,6 +31,9 @@ pub fn read(dir: &TempDir) -> result::Result<Vec<HashMap<&str, String>>, XlsxErr
                                 Ok(ref attr) if attr.key == b"Target" => {
                                     map.insert("target", get_attribute_value(attr)?);
                                 },
+                                Ok(ref attr) if attr.key == b"TargetMode" => {
+                                    map.insert("target_mode", get_attribute_value(attr)?);
+                                },
                                 Ok(_) => {},
                                 Err(_) => {},
                             }
@@ -0,0 +1,108 @@
+use file_common::*;
+use std::fs;
+use std::io::Cursor;
+use std::result;
+use super::quick_xml::events::{Event, BytesDecl};
+use super::quick_xml::Writer;
+use super::tempdir::TempDir;
+use super::XlsxError;
+
+/// Relationship of a part, the target is relative to the folder of the part
+#[derive(Debug, Clone, PartialEq)]
+pub struct Relationship {
+    pub id: String,
+    pub rel_type: String,
+    pub target: String,
+    pub target_mode: Option<String>,
+}
+
+/// Relationships of a .rels part
+pub fn read(data: &[u8]) -> result::Result<Vec<Relationship>, XlsxError> {
+    let mut result = Vec::new();
+    for child in read_children(data)?.1 {
+        if child.name != "Relationship" {
+            continue;
+        }
+        result.push(Relationship {
+            id: child.get_attribute("Id").unwrap_or(String::from("")),
+            rel_type: child.get_attribute("Type").unwrap_or(String::from("")),
+            target: child.get_attribute("Target").unwrap_or(String::from("")),
+            target_mode: child.get_attribute("TargetMode"),
+        });
+    }
+    Ok(result)
+}
+
+/// Relationships of a part in the folder, empty without the .rels part
+pub fn read_part(dir: &TempDir, part_name: &str) -> result::Result<Vec<Relationship>, XlsxError> {
+    let path = dir.path().join(rels_part_name(part_name));
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    read(&fs::read(path)?)
+}
+
+/// Write the .rels part of a part
+pub fn write(dir: &TempDir, part_name: &str, relationships: &Vec<Relationship>) -> result::Result<(), XlsxError> {
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    let _ = writer.write_event(Event::Decl(
+        BytesDecl::new(b"1.0", Some(b"UTF-8"), None)));
+    write_text_node(&mut writer, "\n");
+    write_start_tag(&mut writer, "Relationships", vec![
+        ("xmlns", "http://schemas.openxmlformats.org/package/2006/relationships")
+    ], false);
+    for r in relationships {
+        let mut attributes = vec![
+            ("Id", r.id.as_str()),
+            ("Type", r.rel_type.as_str()),
+            ("Target", r.target.as_str()),
+        ];
+        if let Some(ref target_mode) = r.target_mode {
+            attributes.push(("TargetMode", target_mode.as_str()));
+        }
+        write_start_tag(&mut writer, "Relationship", attributes, true);
+    }
+    write_end_tag(&mut writer, "Relationships");
+    let rels_part_name = rels_part_name(part_name);
+    let folder = rels_part_name.rfind('/').map(|index| rels_part_name[..index].to_string());
+    make_file_from_writer(&rels_part_name, dir, writer, folder.as_deref())?;
+    Ok(())
+}
+
+/// Add a relationship with a new id like "rId3", the id is returned
+pub fn add(relationships: &mut Vec<Relationship>, rel_type: &str, target: &str, target_mode: Option<&str>) -> String {
+    let mut index = relationships.len();
+    let id = loop {
+        index += 1;
+        let id = format!("rId{}", index);
+        if !relationships.iter().any(|r| r.id == id) {
+            break id;
+        }
+    };
+    relationships.push(Relationship {
+        id: id.clone(),
+        rel_type: String::from(rel_type),
+        target: String::from(target),
+        target_mode: target_mode.map(String::from),
+    });
+    id
+}
+
+/// "xl/worksheets/_rels/sheet1.xml.rels" of "xl/worksheets/sheet1.xml"
+pub fn rels_part_name(part_name: &str) -> String {
+    match part_name.rfind('/') {
+        Some(index) => format!("{}/_rels/{}.rels", &part_name[..index], &part_name[index + 1..]),
+        None => format!("_rels/{}.rels", part_name),
+    }
+}
+
+/// Target of a part from another part, "../comments1.xml" of "xl/comments1.xml" from "xl/worksheets/sheet1.xml"
+pub fn relative_target(from_part_name: &str, part_name: &str) -> String {
+    let from: Vec<&str> = from_part_name.split('/').collect();
+    let to: Vec<&str> = part_name.split('/').collect();
+    let from_dir = &from[..from.len() - 1];
+    let common = from_dir.iter().zip(to.iter()).take_while(|&(a, b)| a == b).count();
+    let mut segments: Vec<&str> = vec![".."; from_dir.len() - common];
+    segments.extend(&to[common..]);
+    segments.join("/")
+}
@@ -0,0 +1,186 @@
+use file_common::*;
+use std::io::Cursor;
+use std::result;
+use super::quick_xml::events::{Event, BytesDecl};
+use super::quick_xml::Writer;
+use super::tempdir::TempDir;
+use super::XlsxError;
+use chart::{Axis, Chart, ChartType, LegendPosition};
+
+// ids of the category or x axis and of the value axis
+const X_AXIS_ID: &str = "500000001";
+const Y_AXIS_ID: &str = "500000002";
+
+/// Write a chart part, the series refer to the cells so the chart is drawn from the current values
+pub fn write(dir: &TempDir, part_name: &str, chart: &Chart) -> result::Result<(), XlsxError> {
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    let _ = writer.write_event(Event::Decl(
+        BytesDecl::new(b"1.0", Some(b"UTF-8"), Some(b"yes"))));
+    write_text_node(&mut writer, "\n");
+    write_start_tag(&mut writer, "c:chartSpace", vec![
+        ("xmlns:c", "http://schemas.openxmlformats.org/drawingml/2006/chart"),
+        ("xmlns:a", "http://schemas.openxmlformats.org/drawingml/2006/main"),
+        ("xmlns:r", "http://schemas.openxmlformats.org/officeDocument/2006/relationships"),
+    ], false);
+    write_start_tag(&mut writer, "c:roundedCorners", vec![("val", "0")], true);
+    write_start_tag(&mut writer, "c:chart", vec![], false);
+    match chart.get_title() {
+        Some(title) => write_title(&mut writer, title),
+        None => write_start_tag(&mut writer, "c:autoTitleDeleted", vec![("val", "1")], true),
+    }
+    write_start_tag(&mut writer, "c:plotArea", vec![], false);
+    write_start_tag(&mut writer, "c:layout", vec![], true);
+    let element = match chart.get_chart_type() {
+        ChartType::Bar => "c:barChart",
+        ChartType::Line => "c:lineChart",
+        ChartType::Pie => "c:pieChart",
+        ChartType::Scatter => "c:scatterChart",
+    };
+    write_start_tag(&mut writer, element, vec![], false);
+    match chart.get_chart_type() {
+        ChartType::Bar => {
+            write_start_tag(&mut writer, "c:barDir", vec![("val", "col")], true);
+            write_start_tag(&mut writer, "c:grouping", vec![("val", "clustered")], true);
+        },
+        ChartType::Line => write_start_tag(&mut writer, "c:grouping", vec![("val", "standard")], true),
+        ChartType::Scatter => write_start_tag(&mut writer, "c:scatterStyle", vec![("val", "lineMarker")], true),
+        ChartType::Pie => {},
+    }
+    let vary_colors = if chart.get_chart_type() == ChartType::Pie { "1" } else { "0" };
+    write_start_tag(&mut writer, "c:varyColors", vec![("val", vary_colors)], true);
+    for (index, series) in chart.get_series().iter().enumerate() {
+        let index = index.to_string();
+        write_start_tag(&mut writer, "c:ser", vec![], false);
+        write_start_tag(&mut writer, "c:idx", vec![("val", index.as_str())], true);
+        write_start_tag(&mut writer, "c:order", vec![("val", index.as_str())], true);
+        if let Some(name) = series.get_name() {
+            write_start_tag(&mut writer, "c:tx", vec![], false);
+            write_reference(&mut writer, "c:strRef", &name.to_string());
+            write_end_tag(&mut writer, "c:tx");
+        }
+        if chart.get_chart_type() == ChartType::Scatter {
+            write_start_tag(&mut writer, "c:spPr", vec![], false);
+            write_start_tag(&mut writer, "a:ln", vec![("w", "19050")], false);
+            write_start_tag(&mut writer, "a:noFill", vec![], true);
+            write_end_tag(&mut writer, "a:ln");
+            write_end_tag(&mut writer, "c:spPr");
+        }
+        if chart.get_chart_type() == ChartType::Bar {
+            write_start_tag(&mut writer, "c:invertIfNegative", vec![("val", "0")], true);
+        }
+        let (categories_element, values_element) = match chart.get_chart_type() {
+            ChartType::Scatter => ("c:xVal", "c:yVal"),
+            _ => ("c:cat", "c:val"),
+        };
+        if let Some(categories) = chart.get_categories() {
+            write_start_tag(&mut writer, categories_element, vec![], false);
+            let reference = if chart.get_chart_type() == ChartType::Scatter { "c:numRef" } else { "c:strRef" };
+            write_reference(&mut writer, reference, &categories.to_string());
+            write_end_tag(&mut writer, categories_element);
+        }
+        write_start_tag(&mut writer, values_element, vec![], false);
+        write_reference(&mut writer, "c:numRef", &series.get_values().to_string());
+        write_end_tag(&mut writer, values_element);
+        if chart.get_chart_type() == ChartType::Line || chart.get_chart_type() == ChartType::Scatter {
+            write_start_tag(&mut writer, "c:smooth", vec![("val", "0")], true);
+        }
+        write_end_tag(&mut writer, "c:ser");
+    }
+    match chart.get_chart_type() {
+        ChartType::Bar => write_start_tag(&mut writer, "c:gapWidth", vec![("val", "150")], true),
+        ChartType::Line => write_start_tag(&mut writer, "c:marker", vec![("val", "1")], true),
+        ChartType::Pie => write_start_tag(&mut writer, "c:firstSliceAng", vec![("val", "0")], true),
+        ChartType::Scatter => {},
+    }
+    if chart.get_chart_type() != ChartType::Pie {
+        write_start_tag(&mut writer, "c:axId", vec![("val", X_AXIS_ID)], true);
+        write_start_tag(&mut writer, "c:axId", vec![("val", Y_AXIS_ID)], true);
+    }
+    write_end_tag(&mut writer, element);
+    match chart.get_chart_type() {
+        ChartType::Pie => {},
+        ChartType::Scatter => {
+            write_axis(&mut writer, "c:valAx", X_AXIS_ID, Y_AXIS_ID, "b", chart.get_x_axis(), false);
+            write_axis(&mut writer, "c:valAx", Y_AXIS_ID, X_AXIS_ID, "l", chart.get_y_axis(), true);
+        },
+        _ => {
+            write_axis(&mut writer, "c:catAx", X_AXIS_ID, Y_AXIS_ID, "b", chart.get_x_axis(), false);
+            write_axis(&mut writer, "c:valAx", Y_AXIS_ID, X_AXIS_ID, "l", chart.get_y_axis(), true);
+        },
+    }
+    write_end_tag(&mut writer, "c:plotArea");
+    if let Some(legend) = chart.get_legend() {
+        let position = match legend {
+            LegendPosition::Top => "t",
+            LegendPosition::Bottom => "b",
+            LegendPosition::Left => "l",
+            LegendPosition::Right => "r",
+        };
+        write_start_tag(&mut writer, "c:legend", vec![], false);
+        write_start_tag(&mut writer, "c:legendPos", vec![("val", position)], true);
+        write_start_tag(&mut writer, "c:overlay", vec![("val", "0")], true);
+        write_end_tag(&mut writer, "c:legend");
+    }
+    write_start_tag(&mut writer, "c:plotVisOnly", vec![("val", "1")], true);
+    write_start_tag(&mut writer, "c:dispBlanksAs", vec![("val", "gap")], true);
+    write_end_tag(&mut writer, "c:chart");
+    write_end_tag(&mut writer, "c:chartSpace");
+    make_file_from_writer(part_name, dir, writer, Some("xl/charts"))?;
+    Ok(())
+}
+
+fn write_title(writer: &mut Writer<Cursor<Vec<u8>>>, title: &str) {
+    write_start_tag(writer, "c:title", vec![], false);
+    write_start_tag(writer, "c:tx", vec![], false);
+    write_start_tag(writer, "c:rich", vec![], false);
+    write_start_tag(writer, "a:bodyPr", vec![], true);
+    for line in title.split('\n') {
+        write_start_tag(writer, "a:p", vec![], false);
+        write_start_tag(writer, "a:r", vec![], false);
+        write_start_tag(writer, "a:t", vec![], false);
+        write_text_node(writer, line);
+        write_end_tag(writer, "a:t");
+        write_end_tag(writer, "a:r");
+        write_end_tag(writer, "a:p");
+    }
+    write_end_tag(writer, "c:rich");
+    write_end_tag(writer, "c:tx");
+    write_start_tag(writer, "c:overlay", vec![("val", "0")], true);
+    write_end_tag(writer, "c:title");
+}
+
+fn write_reference(writer: &mut Writer<Cursor<Vec<u8>>>, element: &str, formula: &str) {
+    write_start_tag(writer, element, vec![], false);
+    write_start_tag(writer, "c:f", vec![], false);
+    write_text_node(writer, formula);
+    write_end_tag(writer, "c:f");
+    write_end_tag(writer, element);
+}
+
+fn write_axis(writer: &mut Writer<Cursor<Vec<u8>>>, element: &str, id: &str, cross_id: &str, position: &str, axis: &Axis, grid: bool) {
+    write_start_tag(writer, element, vec![], false);
+    write_start_tag(writer, "c:axId", vec![("val", id)], true);
+    write_start_tag(writer, "c:scaling", vec![], false);
+    write_start_tag(writer, "c:orientation", vec![("val", "minMax")], true);
+    if let Some(max) = axis.get_max() {
+        write_start_tag(writer, "c:max", vec![("val", max.to_string().as_str())], true);
+    }
+    if let Some(min) = axis.get_min() {
+        write_start_tag(writer, "c:min", vec![("val", min.to_string().as_str())], true);
+    }
+    write_end_tag(writer, "c:scaling");
+    write_start_tag(writer, "c:delete", vec![("val", "0")], true);
+    write_start_tag(writer, "c:axPos", vec![("val", position)], true);
+    if grid {
+        write_start_tag(writer, "c:majorGridlines", vec![], true);
+    }
+    if let Some(title) = axis.get_title() {
+        write_title(writer, title);
+    }
+    write_start_tag(writer, "c:majorTickMark", vec![("val", "out")], true);
+    write_start_tag(writer, "c:minorTickMark", vec![("val", "none")], true);
+    write_start_tag(writer, "c:tickLblPos", vec![("val", "nextTo")], true);
+    write_start_tag(writer, "c:crossAx", vec![("val", cross_id)], true);
+    write_start_tag(writer, "c:crosses", vec![("val", "autoZero")], true);
+    write_end_tag(writer, element);
+}
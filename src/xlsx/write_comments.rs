@@ -0,0 +1,161 @@
+use file_common::*;
+use std::io::Cursor;
+use std::result;
+use super::quick_xml::events::{Event, BytesDecl};
+use super::quick_xml::Writer;
+use super::tempdir::TempDir;
+use super::{Sheet, XlsxError, index_to_column_and_row};
+use super::relationships::{self, Relationship, relative_target};
+use super::read_comments::{DEFAULT_WIDTH, DEFAULT_HEIGHT};
+use super::{COMMENTS_TYPE, VML_DRAWING_TYPE, COMMENTS_CONTENT_TYPE, VML_DRAWING_CONTENT_TYPE};
+use comment::Comment;
+
+// default width of a column and height of a row in points
+const COLUMN_WIDTH: f64 = 48.0;
+const ROW_HEIGHT: f64 = 15.0;
+
+/// Write the comments of the sheet to a new comments part and the VML drawing showing them.
+/// Their relationships are added to the ones of the sheet and their content types to `content_types`,
+/// the id of the drawing relationship for legacyDrawing is returned.
+pub fn write(sheet: &Sheet, dir: &TempDir, sheet_part_name: &str, sheet_relationships: &mut Vec<Relationship>, content_types: &mut Vec<(String, String)>) -> result::Result<Option<String>, XlsxError> {
+    let mut comments: Vec<(usize, usize, &Comment)> = Vec::new();
+    for (row_index, row) in sheet.get_rows() {
+        for (column_index, cell) in row {
+            if let Some(comment) = cell.get_comment() {
+                comments.push((*row_index, *column_index, comment));
+            }
+        }
+    }
+    comments.sort_by_key(|&(row, column, _)| (row, column));
+    if comments.is_empty() {
+        return Ok(None);
+    }
+    let mut index = 0;
+    let (comments_part, drawing_part) = loop {
+        index += 1;
+        let comments_part = format!("xl/comments{}.xml", index);
+        let drawing_part = format!("xl/drawings/vmlDrawing{}.vml", index);
+        if !dir.path().join(&comments_part).exists() && !dir.path().join(&drawing_part).exists() {
+            break (comments_part, drawing_part);
+        }
+    };
+    write_comments(dir, &comments_part, &comments)?;
+    write_vml_drawing(dir, &drawing_part, index, &comments)?;
+    content_types.push((comments_part.clone(), String::from(COMMENTS_CONTENT_TYPE)));
+    content_types.push((drawing_part.clone(), String::from(VML_DRAWING_CONTENT_TYPE)));
+    relationships::add(sheet_relationships, COMMENTS_TYPE, &relative_target(sheet_part_name, &comments_part), None);
+    let id = relationships::add(sheet_relationships, VML_DRAWING_TYPE, &relative_target(sheet_part_name, &drawing_part), None);
+    Ok(Some(id))
+}
+
+fn write_comments(dir: &TempDir, part_name: &str, comments: &Vec<(usize, usize, &Comment)>) -> result::Result<(), XlsxError> {
+    let mut authors: Vec<&str> = Vec::new();
+    for &(_, _, comment) in comments {
+        if !authors.contains(&comment.get_author().as_str()) {
+            authors.push(comment.get_author());
+        }
+    }
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    let _ = writer.write_event(Event::Decl(
+        BytesDecl::new(b"1.0", Some(b"UTF-8"), Some(b"yes"))));
+    write_text_node(&mut writer, "\n");
+    write_start_tag(&mut writer, "comments", vec![
+        ("xmlns", "http://schemas.openxmlformats.org/spreadsheetml/2006/main"),
+    ], false);
+    write_start_tag(&mut writer, "authors", vec![], false);
+    for author in &authors {
+        write_start_tag(&mut writer, "author", vec![], false);
+        write_text_node(&mut writer, *author);
+        write_end_tag(&mut writer, "author");
+    }
+    write_end_tag(&mut writer, "authors");
+    write_start_tag(&mut writer, "commentList", vec![], false);
+    for &(row, column, comment) in comments {
+        let reference = index_to_column_and_row(column, row);
+        let author_id = authors.iter().position(|a| *a == comment.get_author().as_str()).unwrap_or(0).to_string();
+        write_start_tag(&mut writer, "comment", vec![
+            ("ref", reference.as_str()),
+            ("authorId", author_id.as_str()),
+        ], false);
+        write_start_tag(&mut writer, "text", vec![], false);
+        write_start_tag(&mut writer, "t", vec![("xml:space", "preserve")], false);
+        write_text_node(&mut writer, comment.get_text().as_str());
+        write_end_tag(&mut writer, "t");
+        write_end_tag(&mut writer, "text");
+        write_end_tag(&mut writer, "comment");
+    }
+    write_end_tag(&mut writer, "commentList");
+    write_end_tag(&mut writer, "comments");
+    make_file_from_writer(part_name, dir, writer, Some("xl"))?;
+    Ok(())
+}
+
+/// Excel only shows the notes having a shape in the legacy drawing of the sheet
+fn write_vml_drawing(dir: &TempDir, part_name: &str, index: usize, comments: &Vec<(usize, usize, &Comment)>) -> result::Result<(), XlsxError> {
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    write_start_tag(&mut writer, "xml", vec![
+        ("xmlns:v", "urn:schemas-microsoft-com:vml"),
+        ("xmlns:o", "urn:schemas-microsoft-com:office:office"),
+        ("xmlns:x", "urn:schemas-microsoft-com:office:excel"),
+    ], false);
+    write_start_tag(&mut writer, "o:shapelayout", vec![("v:ext", "edit")], false);
+    write_start_tag(&mut writer, "o:idmap", vec![("v:ext", "edit"), ("data", index.to_string().as_str())], true);
+    write_end_tag(&mut writer, "o:shapelayout");
+    write_start_tag(&mut writer, "v:shapetype", vec![
+        ("id", "_x0000_t202"),
+        ("coordsize", "21600,21600"),
+        ("o:spt", "202"),
+        ("path", "m,l,21600r21600,l21600,xe"),
+    ], false);
+    write_start_tag(&mut writer, "v:stroke", vec![("joinstyle", "miter")], true);
+    write_start_tag(&mut writer, "v:path", vec![("gradientshapeok", "t"), ("o:connecttype", "rect")], true);
+    write_end_tag(&mut writer, "v:shapetype");
+    for (n, &(row, column, comment)) in comments.iter().enumerate() {
+        let width = comment.get_width().unwrap_or(DEFAULT_WIDTH);
+        let height = comment.get_height().unwrap_or(DEFAULT_HEIGHT);
+        let style = format!(
+            "position:absolute;margin-left:{}pt;margin-top:{}pt;width:{}pt;height:{}pt;z-index:{};visibility:{}",
+            (column + 1) as f64 * COLUMN_WIDTH + 11.25, row as f64 * ROW_HEIGHT + 1.5, width, height, n + 1,
+            if comment.is_visible() { "visible" } else { "hidden" });
+        // left column, offset, top row, offset, right column, offset, bottom row, offset
+        let anchor = format!("{}, 15, {}, 2, {}, 15, {}, 4",
+            column + 1, row,
+            column + 1 + (width / COLUMN_WIDTH).ceil() as usize, row + (height / ROW_HEIGHT).ceil() as usize);
+        write_start_tag(&mut writer, "v:shape", vec![
+            ("id", format!("_x0000_s{}", 1024 * index + n + 1).as_str()),
+            ("type", "#_x0000_t202"),
+            ("style", style.as_str()),
+            ("fillcolor", "#ffffe1"),
+            ("o:insetmode", "auto"),
+        ], false);
+        write_start_tag(&mut writer, "v:fill", vec![("color2", "#ffffe1")], true);
+        write_start_tag(&mut writer, "v:shadow", vec![("on", "t"), ("color", "black"), ("obscured", "t")], true);
+        write_start_tag(&mut writer, "v:path", vec![("o:connecttype", "none")], true);
+        write_start_tag(&mut writer, "v:textbox", vec![("style", "mso-direction-alt:auto")], false);
+        write_start_tag(&mut writer, "div", vec![("style", "text-align:left")], true);
+        write_end_tag(&mut writer, "v:textbox");
+        write_start_tag(&mut writer, "x:ClientData", vec![("ObjectType", "Note")], false);
+        write_start_tag(&mut writer, "x:MoveWithCells", vec![], true);
+        write_start_tag(&mut writer, "x:SizeWithCells", vec![], true);
+        write_start_tag(&mut writer, "x:Anchor", vec![], false);
+        write_text_node(&mut writer, anchor.as_str());
+        write_end_tag(&mut writer, "x:Anchor");
+        write_start_tag(&mut writer, "x:AutoFill", vec![], false);
+        write_text_node(&mut writer, "False");
+        write_end_tag(&mut writer, "x:AutoFill");
+        write_start_tag(&mut writer, "x:Row", vec![], false);
+        write_text_node(&mut writer, row.to_string().as_str());
+        write_end_tag(&mut writer, "x:Row");
+        write_start_tag(&mut writer, "x:Column", vec![], false);
+        write_text_node(&mut writer, column.to_string().as_str());
+        write_end_tag(&mut writer, "x:Column");
+        if comment.is_visible() {
+            write_start_tag(&mut writer, "x:Visible", vec![], true);
+        }
+        write_end_tag(&mut writer, "x:ClientData");
+        write_end_tag(&mut writer, "v:shape");
+    }
+    write_end_tag(&mut writer, "xml");
+    make_file_from_writer(part_name, dir, writer, Some("xl/drawings"))?;
+    Ok(())
+}
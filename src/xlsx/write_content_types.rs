@@ -0,0 +1,53 @@
+use file_common::*;
+use std::result;
+use super::tempdir::TempDir;
+use super::XlsxError;
+
+const CONTENT_TYPES_XML: &str = "[Content_Types].xml";
+/// Content types of the extensions every package needs, the relationship parts and the xml parts without an override
+const DEFAULTS: [(&str, &str); 2] = [
+    ("rels", "application/vnd.openxmlformats-package.relationships+xml"),
+    ("xml", "application/xml"),
+];
+
+/// Patch [Content_Types].xml of the original package.
+/// `removed` are part names without an override any more, `added` are (part name, content type).
+/// The defaults of the rels and xml extensions are added when the original doesn't have them.
+pub fn write(dir: &TempDir, original: &[u8], removed: &[String], added: &Vec<(String, String)>) -> result::Result<(), XlsxError> {
+    let (root, children) = read_children(original)?;
+    let root = match root {
+        Some(root) => root,
+        None => return Ok(()),
+    };
+    let mut result = Vec::new();
+    for &(extension, content_type) in DEFAULTS.iter() {
+        let found = children.iter().any(|child| {
+            child.name == "Default" && child.get_attribute("Extension").filter(|e| e.eq_ignore_ascii_case(extension)).is_some()
+        });
+        if !found {
+            result.push(RawElement::new("Default", |writer| {
+                write_start_tag(writer, "Default", vec![("Extension", extension), ("ContentType", content_type)], true);
+            }));
+        }
+    }
+    for child in children {
+        if child.name == "Override" {
+            let part_name = child.get_attribute("PartName").unwrap_or(String::from(""));
+            let part_name = part_name.trim_start_matches('/');
+            if removed.iter().any(|r| r == part_name) || added.iter().any(|a| a.0 == part_name) {
+                continue;
+            }
+        }
+        result.push(child);
+    }
+    for (part_name, content_type) in added {
+        result.push(RawElement::new("Override", |writer| {
+            write_start_tag(writer, "Override", vec![
+                ("PartName", format!("/{}", part_name).as_str()),
+                ("ContentType", content_type.as_str()),
+            ], true);
+        }));
+    }
+    make_file_from_elements(CONTENT_TYPES_XML, dir, &root, &result)?;
+    Ok(())
+}
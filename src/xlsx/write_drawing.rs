@@ -0,0 +1,278 @@
+use file_common::*;
+use std::fs;
+use std::io::Cursor;
+use std::result;
+use super::quick_xml::events::{Event, BytesDecl, BytesEnd};
+use super::quick_xml::{Reader, Writer};
+use super::tempdir::TempDir;
+use super::{Sheet, XlsxError, resolve_part_name};
+use super::relationships::{self, Relationship, rels_part_name, relative_target};
+use super::write_chart;
+use super::{DRAWING_TYPE, IMAGE_TYPE, CHART_TYPE, DRAWING_CONTENT_TYPE, CHART_CONTENT_TYPE};
+use chart::Chart;
+use image::{Anchor, Image, Marker};
+
+// DrawingML lengths are in English Metric Units
+pub const EMU_PER_POINT: f64 = 12700.0;
+
+/// Write the pictures and the charts of the sheet to its drawing part.
+/// The anchors of an existing drawing which aren't pictures, like its charts or shapes, are kept,
+/// its pictures are replaced by the ones of the sheet and the charts of the sheet are added.
+/// The relationship id of the drawing for the drawing element is returned, None without any anchor.
+pub fn write(sheet: &Sheet, dir: &TempDir, sheet_part_name: &str, sheet_relationships: &mut Vec<Relationship>, content_types: &mut Vec<(String, String)>, removed_parts: &mut Vec<String>) -> result::Result<Option<String>, XlsxError> {
+    let images: Vec<&Image> = sheet.get_images().iter().filter(|image| image.get_extension().is_some()).collect();
+    let base_dir = match sheet_part_name.rfind('/') {
+        Some(index) => &sheet_part_name[..index + 1],
+        None => "",
+    };
+    let existing = sheet_relationships.iter()
+        .find(|r| r.rel_type == DRAWING_TYPE && r.target_mode.is_none())
+        .map(|r| (r.id.clone(), resolve_part_name(base_dir, &r.target)))
+        .filter(|(_, part_name)| dir.path().join(part_name).is_file());
+
+    let mut root = None;
+    let mut kept = Vec::new();
+    let mut drawing_relationships = Vec::new();
+    let mut max_id = 0;
+    if let Some((_, ref part_name)) = existing {
+        let (original_root, anchors) = read_children(&fs::read(dir.path().join(part_name))?)?;
+        root = original_root;
+        drawing_relationships = relationships::read_part(dir, part_name)?;
+        let mut removed_ids = Vec::new();
+        for anchor in anchors {
+            let (is_picture, embeds, id) = read_anchor(&anchor.data)?;
+            if is_picture {
+                removed_ids.extend(embeds);
+            } else {
+                max_id = max_id.max(id);
+                kept.push(anchor);
+            }
+        }
+        drawing_relationships.retain(|r| !(r.rel_type == IMAGE_TYPE && removed_ids.contains(&r.id)));
+    }
+
+    if images.is_empty() && sheet.get_charts().is_empty() && kept.is_empty() {
+        if let Some((id, part_name)) = existing {
+            for removed_part in [rels_part_name(&part_name), part_name] {
+                let file_path = dir.path().join(&removed_part);
+                if file_path.is_file() {
+                    fs::remove_file(file_path)?;
+                }
+                removed_parts.push(removed_part);
+            }
+            sheet_relationships.retain(|r| r.id != id);
+        }
+        return Ok(None);
+    }
+
+    let (id, part_name) = match existing {
+        Some(existing) => existing,
+        None => {
+            let mut index = 0;
+            let part_name = loop {
+                index += 1;
+                let part_name = format!("xl/drawings/drawing{}.xml", index);
+                if !dir.path().join(&part_name).exists() {
+                    break part_name;
+                }
+            };
+            content_types.push((part_name.clone(), String::from(DRAWING_CONTENT_TYPE)));
+            let id = relationships::add(sheet_relationships, DRAWING_TYPE, &relative_target(sheet_part_name, &part_name), None);
+            (id, part_name)
+        },
+    };
+
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    let _ = writer.write_event(Event::Decl(
+        BytesDecl::new(b"1.0", Some(b"UTF-8"), Some(b"yes"))));
+    write_text_node(&mut writer, "\n");
+    match root {
+        Some(ref root) => write_start_tag_with_replaced_attributes(&mut writer, root, vec![], false),
+        None => write_start_tag(&mut writer, "xdr:wsDr", vec![
+            ("xmlns:xdr", "http://schemas.openxmlformats.org/drawingml/2006/spreadsheetDrawing"),
+            ("xmlns:a", "http://schemas.openxmlformats.org/drawingml/2006/main"),
+            ("xmlns:r", "http://schemas.openxmlformats.org/officeDocument/2006/relationships"),
+        ], false),
+    }
+    for anchor in &kept {
+        write_raw(&mut writer, &anchor.data);
+    }
+    for (n, image) in images.iter().enumerate() {
+        let media_part_name = write_media(dir, image, content_types)?;
+        let embed = relationships::add(&mut drawing_relationships, IMAGE_TYPE, &relative_target(&part_name, &media_part_name), None);
+        write_anchor(&mut writer, image.get_anchor(), |writer| write_picture(writer, image, max_id + n + 1, &embed));
+    }
+    for (n, chart) in sheet.get_charts().iter().enumerate() {
+        let mut index = 0;
+        let chart_part_name = loop {
+            index += 1;
+            let chart_part_name = format!("xl/charts/chart{}.xml", index);
+            if !dir.path().join(&chart_part_name).exists() {
+                break chart_part_name;
+            }
+        };
+        write_chart::write(dir, &chart_part_name, chart)?;
+        content_types.push((chart_part_name.clone(), String::from(CHART_CONTENT_TYPE)));
+        let chart_id = relationships::add(&mut drawing_relationships, CHART_TYPE, &relative_target(&part_name, &chart_part_name), None);
+        let id = max_id + images.len() + n + 1;
+        write_anchor(&mut writer, chart.get_anchor(), |writer| write_graphic_frame(writer, chart, id, &chart_id));
+    }
+    match root {
+        Some(ref root) => {
+            let _ = writer.write_event(Event::End(BytesEnd::borrowed(root.name())));
+        },
+        None => write_end_tag(&mut writer, "xdr:wsDr"),
+    }
+    make_file_from_writer(&part_name, dir, writer, Some("xl/drawings"))?;
+    relationships::write(dir, &part_name, &drawing_relationships)?;
+    Ok(Some(id))
+}
+
+/// Whether the anchor has a picture, the relationship ids of its pictures and its largest shape id
+fn read_anchor(data: &[u8]) -> result::Result<(bool, Vec<String>, usize), XlsxError> {
+    let mut reader = Reader::from_reader(data);
+    let mut buf = Vec::new();
+    let mut is_picture = false;
+    let mut embeds = Vec::new();
+    let mut max_id = 0;
+    loop {
+        match reader.read_event(&mut buf) {
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => {
+                if e.name() == b"xdr:pic" {
+                    is_picture = true;
+                }
+                for attr in e.attributes().with_checks(false).flatten() {
+                    if attr.key == b"r:embed" {
+                        embeds.push(get_attribute_value(&attr)?);
+                    } else if attr.key == b"id" && e.name() == b"xdr:cNvPr" {
+                        max_id = max_id.max(get_attribute_value(&attr)?.parse::<usize>().unwrap_or(0));
+                    }
+                }
+            },
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(XlsxError::Xml(e)),
+            _ => (),
+        }
+        buf.clear();
+    }
+    Ok((is_picture, embeds, max_id))
+}
+
+/// Write the picture to xl/media, a media part with the same bytes is shared
+fn write_media(dir: &TempDir, image: &Image, content_types: &mut Vec<(String, String)>) -> result::Result<String, XlsxError> {
+    let extension = image.get_extension().unwrap_or("png");
+    let mut index = 0;
+    loop {
+        index += 1;
+        let part_name = format!("xl/media/image{}.{}", index, extension);
+        let path = dir.path().join(&part_name);
+        if !path.exists() {
+            make_file_from_bytes(dir, &part_name, image.get_data())?;
+            content_types.push((part_name.clone(), String::from(image.get_media_type().unwrap_or("image/png"))));
+            return Ok(part_name);
+        }
+        if &fs::read(&path)? == image.get_data() {
+            return Ok(part_name);
+        }
+    }
+}
+
+fn write_anchor<F>(writer: &mut Writer<Cursor<Vec<u8>>>, anchor: &Anchor, content: F)
+    where F: FnOnce(&mut Writer<Cursor<Vec<u8>>>)
+{
+    match *anchor {
+        Anchor::OneCell(ref from, width, height) => {
+            write_start_tag(writer, "xdr:oneCellAnchor", vec![], false);
+            write_marker(writer, "xdr:from", from);
+            write_start_tag(writer, "xdr:ext", vec![
+                ("cx", to_emu(width).as_str()),
+                ("cy", to_emu(height).as_str()),
+            ], true);
+        },
+        Anchor::TwoCell(ref from, ref to) => {
+            write_start_tag(writer, "xdr:twoCellAnchor", vec![("editAs", "twoCell")], false);
+            write_marker(writer, "xdr:from", from);
+            write_marker(writer, "xdr:to", to);
+        },
+    }
+    content(writer);
+    write_start_tag(writer, "xdr:clientData", vec![], true);
+    match *anchor {
+        Anchor::OneCell(..) => write_end_tag(writer, "xdr:oneCellAnchor"),
+        Anchor::TwoCell(..) => write_end_tag(writer, "xdr:twoCellAnchor"),
+    }
+}
+
+fn write_picture(writer: &mut Writer<Cursor<Vec<u8>>>, image: &Image, id: usize, embed: &str) {
+    write_start_tag(writer, "xdr:pic", vec![], false);
+    write_start_tag(writer, "xdr:nvPicPr", vec![], false);
+    write_start_tag(writer, "xdr:cNvPr", vec![
+        ("id", id.to_string().as_str()),
+        ("name", image.get_name().as_str()),
+    ], true);
+    write_start_tag(writer, "xdr:cNvPicPr", vec![], false);
+    write_start_tag(writer, "a:picLocks", vec![("noChangeAspect", "1")], true);
+    write_end_tag(writer, "xdr:cNvPicPr");
+    write_end_tag(writer, "xdr:nvPicPr");
+    write_start_tag(writer, "xdr:blipFill", vec![], false);
+    write_start_tag(writer, "a:blip", vec![
+        ("xmlns:r", "http://schemas.openxmlformats.org/officeDocument/2006/relationships"),
+        ("r:embed", embed),
+    ], true);
+    write_start_tag(writer, "a:stretch", vec![], false);
+    write_start_tag(writer, "a:fillRect", vec![], true);
+    write_end_tag(writer, "a:stretch");
+    write_end_tag(writer, "xdr:blipFill");
+    write_start_tag(writer, "xdr:spPr", vec![], false);
+    write_start_tag(writer, "a:prstGeom", vec![("prst", "rect")], false);
+    write_start_tag(writer, "a:avLst", vec![], true);
+    write_end_tag(writer, "a:prstGeom");
+    write_end_tag(writer, "xdr:spPr");
+    write_end_tag(writer, "xdr:pic");
+}
+
+fn write_graphic_frame(writer: &mut Writer<Cursor<Vec<u8>>>, chart: &Chart, id: usize, chart_id: &str) {
+    let name = format!("Chart {}", id);
+    write_start_tag(writer, "xdr:graphicFrame", vec![("macro", "")], false);
+    write_start_tag(writer, "xdr:nvGraphicFramePr", vec![], false);
+    write_start_tag(writer, "xdr:cNvPr", vec![
+        ("id", id.to_string().as_str()),
+        ("name", chart.get_title().unwrap_or(&name).as_str()),
+    ], true);
+    write_start_tag(writer, "xdr:cNvGraphicFramePr", vec![], true);
+    write_end_tag(writer, "xdr:nvGraphicFramePr");
+    write_start_tag(writer, "xdr:xfrm", vec![], false);
+    write_start_tag(writer, "a:off", vec![("x", "0"), ("y", "0")], true);
+    write_start_tag(writer, "a:ext", vec![("cx", "0"), ("cy", "0")], true);
+    write_end_tag(writer, "xdr:xfrm");
+    write_start_tag(writer, "a:graphic", vec![], false);
+    write_start_tag(writer, "a:graphicData", vec![("uri", "http://schemas.openxmlformats.org/drawingml/2006/chart")], false);
+    write_start_tag(writer, "c:chart", vec![
+        ("xmlns:c", "http://schemas.openxmlformats.org/drawingml/2006/chart"),
+        ("xmlns:r", "http://schemas.openxmlformats.org/officeDocument/2006/relationships"),
+        ("r:id", chart_id),
+    ], true);
+    write_end_tag(writer, "a:graphicData");
+    write_end_tag(writer, "a:graphic");
+    write_end_tag(writer, "xdr:graphicFrame");
+}
+
+fn write_marker(writer: &mut Writer<Cursor<Vec<u8>>>, name: &str, marker: &Marker) {
+    write_start_tag(writer, name, vec![], false);
+    let values = [
+        ("xdr:col", marker.get_column().to_string()),
+        ("xdr:colOff", to_emu(marker.get_offset_x())),
+        ("xdr:row", marker.get_row().to_string()),
+        ("xdr:rowOff", to_emu(marker.get_offset_y())),
+    ];
+    for (element, value) in values.iter() {
+        write_start_tag(writer, *element, vec![], false);
+        write_text_node(writer, value.as_str());
+        write_end_tag(writer, *element);
+    }
+    write_end_tag(writer, name);
+}
+
+fn to_emu(points: f64) -> String {
+    ((points * EMU_PER_POINT).round() as i64).to_string()
+}
@@ -0,0 +1,454 @@
+use file_common::*;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::result;
+use super::quick_xml::events::BytesStart;
+use super::tempdir::TempDir;
+use super::{Book, Package};
+use super::{XlsxError, XlsxOptions};
+use date::DateSystem;
+use super::{read_workbook, read_workbook_xml_rels, write_comments, write_content_types, write_drawing, write_shared_strings, write_sheet, write_styles, write_table, write_workbook};
+use super::relationships::{self, Relationship, rels_part_name, relative_target};
+use super::{resolve_part_name, WORKSHEET_TYPE, STYLES_TYPE, SHARED_STRINGS_TYPE, CALC_CHAIN_TYPE};
+use super::{COMMENTS_TYPE, VML_DRAWING_TYPE, THREADED_COMMENT_TYPE, HYPERLINK_TYPE, IMAGE_TYPE};
+use super::{WORKSHEET_CONTENT_TYPE, STYLES_CONTENT_TYPE, SHARED_STRINGS_CONTENT_TYPE};
+
+const WORKBOOK_XML: &str = "xl/workbook.xml";
+const CONTENT_TYPES_XML: &str = "[Content_Types].xml";
+const STYLE_XML: &str = "xl/styles.xml";
+const SHARED_STRINGS: &str = "xl/sharedStrings.xml";
+const WORKBOOK_ELEMENTS: [&str; 19] = [
+    "fileVersion", "fileSharing", "workbookPr", "workbookProtection", "bookViews",
+    "sheets", "functionGroups", "externalReferences", "definedNames", "calcPr",
+    "oleSize", "customWorkbookViews", "pivotCaches", "smartTagPr", "smartTagTypes",
+    "webPublishing", "fileRecoveryPr", "webPublishObjects", "extLst",
+];
+
+struct SheetEntry {
+    rid: String,
+    sheet_id: String,
+    part_name: String,
+    original: bool,
+    // part a copied sheet is made from
+    source: Option<String>,
+}
+
+/// Write a book read by `open`, copying every part which isn't made from the book
+pub fn write(book: &Book, package: &Package, path: &Path, options: &XlsxOptions) -> result::Result<(), XlsxError> {
+    let dir = TempDir::new("shreadsheet")?;
+    for (name, data) in package.get_parts() {
+        make_file_from_bytes(&dir, name, data)?;
+    }
+
+    let mut relationships: Vec<Relationship> = read_workbook_xml_rels::read(&dir)?.into_iter().map(|r| {
+        Relationship {
+            id: r.get("id").cloned().unwrap_or(String::from("")),
+            rel_type: r.get("type").cloned().unwrap_or(String::from("")),
+            target: r.get("target").cloned().unwrap_or(String::from("")),
+            target_mode: r.get("target_mode").cloned(),
+        }
+    }).collect();
+    let mut original_sheets = Vec::new();
+    for s in read_workbook::read(&dir)? {
+        let rid = s.get("rid").cloned().unwrap_or(String::from(""));
+        if let Some(r) = relationships.iter().find(|r| r.id == rid) {
+            original_sheets.push(SheetEntry {
+                rid: rid.clone(),
+                sheet_id: s.get("sheet_id").cloned().unwrap_or(String::from("")),
+                part_name: resolve_part_name("xl/", &r.target),
+                original: true,
+                source: None,
+            });
+        }
+    }
+
+    // the sheet keeps its original part, a new or copied sheet gets a new one
+    let mut max_sheet_id = original_sheets.iter().filter_map(|s| s.sheet_id.parse::<usize>().ok()).max().unwrap_or(0);
+    let mut rid_index = 0;
+    let mut part_index = 0;
+    let mut sheet_entries: Vec<SheetEntry> = Vec::new();
+    for sheet in book.get_sheet_vec() {
+        let found = sheet.get_part_name().and_then(|part_name| {
+            original_sheets.iter().position(|s| &s.part_name == part_name)
+        });
+        match found {
+            Some(position) => sheet_entries.push(original_sheets.remove(position)),
+            None => {
+                let rid = loop {
+                    rid_index += 1;
+                    let rid = format!("rId{}", rid_index);
+                    if !relationships.iter().any(|r| r.id == rid) && !sheet_entries.iter().any(|s| s.rid == rid) {
+                        break rid;
+                    }
+                };
+                let part_name = loop {
+                    part_index += 1;
+                    let part_name = format!("xl/worksheets/sheet{}.xml", part_index);
+                    if package.get_part(&part_name).is_none() {
+                        break part_name;
+                    }
+                };
+                max_sheet_id += 1;
+                sheet_entries.push(SheetEntry {
+                    rid,
+                    sheet_id: max_sheet_id.to_string(),
+                    part_name,
+                    original: false,
+                    source: sheet.get_part_name()
+                        .filter(|part_name| package.get_part(part_name).is_some())
+                        .cloned(),
+                });
+            },
+        }
+    }
+
+    // parts which are removed or made again
+    let mut removed_parts: Vec<String> = Vec::new();
+    let mut removed_ids: Vec<String> = Vec::new();
+    for s in &original_sheets {
+        removed_parts.push(s.part_name.clone());
+        removed_parts.push(rels_part_name(&s.part_name));
+        removed_ids.push(s.rid.clone());
+    }
+    let mut has_styles = false;
+    let mut has_shared_strings = false;
+    for r in &relationships {
+        let part_name = resolve_part_name("xl/", &r.target);
+        if r.rel_type == CALC_CHAIN_TYPE {
+            removed_parts.push(part_name);
+            removed_ids.push(r.id.clone());
+        } else if r.rel_type == STYLES_TYPE {
+            if part_name == STYLE_XML {
+                has_styles = true;
+            } else {
+                removed_parts.push(part_name);
+                removed_ids.push(r.id.clone());
+            }
+        } else if r.rel_type == SHARED_STRINGS_TYPE {
+            if part_name == SHARED_STRINGS {
+                has_shared_strings = true;
+            } else {
+                removed_parts.push(part_name);
+                removed_ids.push(r.id.clone());
+            }
+        }
+    }
+    for part_name in &removed_parts {
+        let file_path = dir.path().join(part_name);
+        if file_path.is_file() {
+            fs::remove_file(file_path)?;
+        }
+    }
+    relationships.retain(|r| !removed_ids.contains(&r.id));
+
+    let cell_styles = match package.get_part(STYLE_XML) {
+        Some(original) if has_styles => write_styles::write_with_package(book, &dir, original)?,
+        _ => write_styles::write(book, &dir)?,
+    };
+    let shared_strings = write_shared_strings::write(book, &dir, options.get_inline_strings())?;
+    let mut added_content_types = vec![
+        (String::from(SHARED_STRINGS), String::from(SHARED_STRINGS_CONTENT_TYPE)),
+    ];
+    let mut table_id = 0;
+    let overrides = read_overrides(package)?;
+    let parts = write_sheet::BookParts {
+        shared_strings: &shared_strings,
+        cell_styles: &cell_styles,
+        date_system: book.get_date_system(),
+        timezone: book.get_timezone(),
+    };
+    for (index, (sheet, entry)) in book.get_sheet_vec().iter().zip(sheet_entries.iter()).enumerate() {
+        let original = if entry.original {
+            package.get_part(&entry.part_name)
+        } else {
+            entry.source.as_ref().and_then(|source| package.get_part(source))
+        };
+        let mut sheet_relationships = Vec::new();
+        let mut kept_drawing = None;
+        let mut copies = PartCopies::new(package, &dir, &overrides);
+        if let Some(original) = original {
+            sheet_relationships = match entry.source {
+                Some(ref source) => copies.copy_relationships(source, &entry.part_name)?,
+                None => relationships::read_part(&dir, &entry.part_name)?,
+            };
+            let has_comments = sheet.get_rows().values().any(|row| row.values().any(|cell| cell.get_comment().is_some()));
+            kept_drawing = remove_comments(&dir, &entry.part_name, original, &mut sheet_relationships, &mut removed_parts, has_comments)?;
+            // the hyperlinks are made again from the cells
+            sheet_relationships.retain(|r| r.rel_type != HYPERLINK_TYPE);
+            write_table::remove(&dir, &entry.part_name, &mut sheet_relationships, &mut removed_parts)?;
+        }
+        let ids = write_sheet::RelationshipIds {
+            drawing: write_drawing::write(sheet, &dir, &entry.part_name, &mut sheet_relationships, &mut added_content_types, &mut removed_parts)?,
+            legacy_drawing: write_comments::write(sheet, &dir, &entry.part_name, &mut sheet_relationships, &mut added_content_types)?.or(kept_drawing),
+            hyperlinks: write_sheet::add_hyperlinks(sheet, &mut sheet_relationships),
+            tables: write_table::write(sheet, &dir, &entry.part_name, &mut sheet_relationships, &mut added_content_types, &mut table_id)?,
+        };
+        // the copied parts which are made again from the sheet are removed like the original ones
+        added_content_types.extend(copies.content_types.into_iter().filter(|(part_name, _)| !removed_parts.contains(part_name)));
+        write_sheet::write(sheet, &dir, &entry.part_name, &parts, original.map(|data| &data[..]), index == book.get_active_sheet(), &ids)?;
+        if !sheet_relationships.is_empty() || dir.path().join(rels_part_name(&entry.part_name)).is_file() {
+            relationships::write(&dir, &entry.part_name, &sheet_relationships)?;
+        }
+    }
+
+    if !has_styles {
+        added_content_types.push((String::from(STYLE_XML), String::from(STYLES_CONTENT_TYPE)));
+        relationships.push(new_relationship(&relationships, &sheet_entries, STYLES_TYPE, "styles.xml"));
+    }
+    if !has_shared_strings {
+        relationships.push(new_relationship(&relationships, &sheet_entries, SHARED_STRINGS_TYPE, "sharedStrings.xml"));
+    }
+    for entry in sheet_entries.iter().filter(|e| !e.original) {
+        added_content_types.push((entry.part_name.clone(), String::from(WORKSHEET_CONTENT_TYPE)));
+        relationships.push(Relationship {
+            id: entry.rid.clone(),
+            rel_type: String::from(WORKSHEET_TYPE),
+            target: entry.part_name.trim_start_matches("xl/").to_string(),
+            target_mode: None,
+        });
+    }
+    relationships::write(&dir, WORKBOOK_XML, &relationships)?;
+    if let Some(original) = package.get_part(WORKBOOK_XML) {
+        write_workbook(book, &dir, original, &sheet_entries)?;
+    }
+    if let Some(original) = package.get_part(CONTENT_TYPES_XML) {
+        write_content_types::write(&dir, original, &removed_parts, &added_content_types)?;
+    }
+    write_to_file(path, &dir)?;
+    dir.close()?;
+    Ok(())
+}
+
+fn new_relationship(relationships: &[Relationship], sheet_entries: &[SheetEntry], rel_type: &str, target: &str) -> Relationship {
+    let mut index = relationships.len();
+    let id = loop {
+        index += 1;
+        let id = format!("rId{}", index);
+        if !relationships.iter().any(|r| r.id == id) && !sheet_entries.iter().any(|s| s.rid == id) {
+            break id;
+        }
+    };
+    Relationship {
+        id,
+        rel_type: String::from(rel_type),
+        target: String::from(target),
+        target_mode: None,
+    }
+}
+
+/// Content types of the parts with an override in [Content_Types].xml by their names
+fn read_overrides(package: &Package) -> result::Result<HashMap<String, String>, XlsxError> {
+    let mut overrides = HashMap::new();
+    if let Some(data) = package.get_part(CONTENT_TYPES_XML) {
+        for child in read_children(data)?.1 {
+            if child.name != "Override" {
+                continue;
+            }
+            if let (Some(part_name), Some(content_type)) = (child.get_attribute("PartName"), child.get_attribute("ContentType")) {
+                overrides.insert(part_name.trim_start_matches('/').to_string(), content_type);
+            }
+        }
+    }
+    Ok(overrides)
+}
+
+/// Copies of the parts of the package a copied sheet refers to, like its drawing with the charts,
+/// so the copy doesn't share them with the sheet it is made from. The pictures are shared.
+struct PartCopies<'a> {
+    package: &'a Package,
+    dir: &'a TempDir,
+    overrides: &'a HashMap<String, String>,
+    // part names of the copies by the parts they are made from
+    copied: HashMap<String, String>,
+    content_types: Vec<(String, String)>,
+}
+
+impl<'a> PartCopies<'a> {
+    fn new(package: &'a Package, dir: &'a TempDir, overrides: &'a HashMap<String, String>) -> PartCopies<'a> {
+        PartCopies {
+            package,
+            dir,
+            overrides,
+            copied: HashMap::new(),
+            content_types: Vec::new(),
+        }
+    }
+
+    /// Relationships of the part `source` for its copy `part_name`, with the ids of the original ones
+    fn copy_relationships(&mut self, source: &str, part_name: &str) -> result::Result<Vec<Relationship>, XlsxError> {
+        let data = match self.package.get_part(&rels_part_name(source)) {
+            Some(data) => data,
+            None => return Ok(Vec::new()),
+        };
+        let base_dir = match source.rfind('/') {
+            Some(index) => &source[..index + 1],
+            None => "",
+        };
+        let mut result = relationships::read(data)?;
+        for r in result.iter_mut().filter(|r| r.target_mode.is_none() && r.rel_type != IMAGE_TYPE) {
+            let target = resolve_part_name(base_dir, &r.target);
+            if self.package.get_part(&target).is_some() {
+                let copy = self.copy_part(&target)?;
+                r.target = relative_target(part_name, &copy);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Copy a part with the parts it refers to, the name of the copy is returned
+    fn copy_part(&mut self, source: &str) -> result::Result<String, XlsxError> {
+        if let Some(part_name) = self.copied.get(source) {
+            return Ok(part_name.clone());
+        }
+        let part_name = self.new_part_name(source);
+        self.copied.insert(source.to_string(), part_name.clone());
+        if let Some(data) = self.package.get_part(source) {
+            make_file_from_bytes(self.dir, &part_name, data)?;
+        }
+        if let Some(content_type) = self.overrides.get(source) {
+            self.content_types.push((part_name.clone(), content_type.clone()));
+        }
+        let part_relationships = self.copy_relationships(source, &part_name)?;
+        if !part_relationships.is_empty() {
+            relationships::write(self.dir, &part_name, &part_relationships)?;
+        }
+        Ok(part_name)
+    }
+
+    /// "xl/drawings/drawing2.xml" for a copy of "xl/drawings/drawing1.xml", a name which neither the package nor the folder has
+    fn new_part_name(&self, source: &str) -> String {
+        let file_index = source.rfind('/').map(|index| index + 1).unwrap_or(0);
+        let (stem, extension) = match source[file_index..].rfind('.') {
+            Some(index) => source.split_at(file_index + index),
+            None => (source, ""),
+        };
+        let stem = stem.trim_end_matches(|c: char| c.is_ascii_digit());
+        let mut index = 0;
+        loop {
+            index += 1;
+            let part_name = format!("{}{}{}", stem, index, extension);
+            if self.package.get_part(&part_name).is_none() && !self.dir.path().join(&part_name).exists() {
+                return part_name;
+            }
+        }
+    }
+}
+
+/// Remove the comments of an original sheet, which are made again from the book.
+/// Threaded comments go too, their text is kept in the notes.
+/// The VML drawing of the notes may also have form controls, it is only removed with the comments
+/// or when the notes of the book replace it, otherwise its relationship id is returned to keep it.
+fn remove_comments(dir: &TempDir, part_name: &str, original: &[u8], sheet_relationships: &mut Vec<Relationship>, removed_parts: &mut Vec<String>, replace_drawing: bool) -> result::Result<Option<String>, XlsxError> {
+    let legacy_drawing = read_children(original)?.1.iter()
+        .find(|child| child.name == "legacyDrawing")
+        .and_then(|child| child.get_attribute("r:id"));
+    let has_comments = sheet_relationships.iter().any(|r| r.rel_type == COMMENTS_TYPE);
+    let remove_drawing = has_comments || replace_drawing;
+    let base_dir = match part_name.rfind('/') {
+        Some(index) => &part_name[..index + 1],
+        None => "",
+    };
+    let mut kept = Vec::new();
+    for r in sheet_relationships.drain(..) {
+        let removed = r.rel_type == COMMENTS_TYPE
+            || r.rel_type == THREADED_COMMENT_TYPE
+            || (remove_drawing && r.rel_type == VML_DRAWING_TYPE && Some(&r.id) == legacy_drawing.as_ref());
+        if !removed {
+            kept.push(r);
+            continue;
+        }
+        let removed_part = resolve_part_name(base_dir, &r.target);
+        let file_path = dir.path().join(&removed_part);
+        if file_path.is_file() {
+            fs::remove_file(file_path)?;
+        }
+        removed_parts.push(removed_part);
+    }
+    *sheet_relationships = kept;
+    Ok(if remove_drawing { None } else { legacy_drawing })
+}
+
+/// Patch the sheets, the names, the date system and the active sheet of workbook.xml and let the application recalculate without calcChain
+fn write_workbook(book: &Book, dir: &TempDir, original: &[u8], sheet_entries: &[SheetEntry]) -> result::Result<(), XlsxError> {
+    let (root, children) = read_children(original)?;
+    let root = match root {
+        Some(root) => root,
+        None => return Ok(()),
+    };
+    let mut original_tags: HashMap<String, BytesStart> = HashMap::new();
+    let mut calc_pr = None;
+    let date1904 = if book.get_date_system() == DateSystem::Date1904 { "1" } else { "0" };
+    let mut workbook_pr = None;
+    let mut book_views = None;
+    for child in &children {
+        match child.name.as_str() {
+            "sheets" => {
+                for sheet in read_children(&child.data)?.1 {
+                    if let (Some(rid), Some(tag)) = (sheet.get_attribute("r:id"), sheet.start_tag()) {
+                        original_tags.insert(rid, tag);
+                    }
+                }
+            },
+            "calcPr" => calc_pr = Some(child.with_replaced_attributes(vec![("fullCalcOnLoad", "1")])),
+            "workbookPr" => workbook_pr = Some(child.with_replaced_attributes(vec![("date1904", date1904)])),
+            "bookViews" => book_views = Some((child.start_tag(), read_children(&child.data)?.1)),
+            _ => {},
+        }
+    }
+    let sheets = RawElement::new("sheets", |writer| {
+        write_start_tag(writer, "sheets", vec![], false);
+        for (sheet, entry) in book.get_sheet_vec().iter().zip(sheet_entries.iter()) {
+            match original_tags.get(&entry.rid) {
+                Some(tag) if entry.original => {
+                    write_start_tag_with_replaced_attributes(writer, tag, vec![
+                        ("name", sheet.get_name()),
+                    ], true);
+                },
+                _ => {
+                    write_start_tag(writer, "sheet", vec![
+                        ("name", sheet.get_name()),
+                        ("sheetId", entry.sheet_id.as_str()),
+                        ("r:id", entry.rid.as_str()),
+                    ], true);
+                },
+            }
+        }
+        write_end_tag(writer, "sheets");
+    });
+    let calc_pr = calc_pr.unwrap_or_else(|| RawElement::new("calcPr", |writer| {
+        write_start_tag(writer, "calcPr", vec![("fullCalcOnLoad", "1")], true);
+    }));
+    let mut generated = vec![sheets, calc_pr];
+    // the first window shows the active sheet
+    if let Some((Some(tag), views)) = book_views {
+        let active_tab = book.get_active_sheet().to_string();
+        generated.push(RawElement::new("bookViews", |writer| {
+            write_start_tag_with_replaced_attributes(writer, &tag, vec![], false);
+            for (index, view) in views.iter().enumerate() {
+                if index == 0 {
+                    write_raw(writer, &view.with_replaced_attributes(vec![("activeTab", active_tab.as_str())]).data);
+                } else {
+                    write_raw(writer, &view.data);
+                }
+            }
+            write_end_tag(writer, "bookViews");
+        }));
+    }
+    // the names are made again from the book
+    let children: Vec<RawElement> = children.into_iter().filter(|child| child.name != "definedNames").collect();
+    if write_workbook::has_defined_names(book) {
+        generated.push(RawElement::new("definedNames", |writer| write_workbook::write_defined_names(writer, book)));
+    }
+    if workbook_pr.is_none() && book.get_date_system() == DateSystem::Date1904 {
+        workbook_pr = Some(RawElement::new("workbookPr", |writer| {
+            write_start_tag(writer, "workbookPr", vec![("date1904", "1")], true);
+        }));
+    }
+    if let Some(workbook_pr) = workbook_pr {
+        generated.push(workbook_pr);
+    }
+    let children = merge_elements(&WORKBOOK_ELEMENTS, children, generated);
+    make_file_from_elements(WORKBOOK_XML, dir, &root, &children)?;
+    Ok(())
+}
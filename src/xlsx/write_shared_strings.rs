@@ -5,21 +5,50 @@ use std::result;
 use super::quick_xml::events::{Event, BytesDecl};
 use super::quick_xml::Writer;
 use super::tempdir::TempDir;
-use super::{Book,Value};
+use rich_text::{Phonetic, TextRun};
+use super::{Book,Cell,Value};
 use super::XlsxError;
 
 const SHARED_STRINGS: &'static str = "xl/sharedStrings.xml";
 
-pub fn write(book: &Book, dir: &TempDir) -> result::Result<HashMap<String, usize>, XlsxError> {
-    let mut shared_strings: Vec<String> = Vec::new();
+/// Index of the texts in sharedStrings.xml,
+/// the rich texts and the texts with reading are looked up by their value and reading.
+/// Without index the text is written inline.
+pub struct SharedStrings {
+    strings: HashMap<String, usize>,
+    others: Vec<(Value, Option<Phonetic>, usize)>,
+}
+
+impl SharedStrings {
+    pub fn get(&self, cell: &Cell) -> Option<usize> {
+        match (cell.get_value(), cell.get_phonetic()) {
+            (Value::Str(val), None) => self.strings.get(val).cloned(),
+            (value, phonetic) => {
+                self.others.iter()
+                    .find(|&(v, p, _)| v == value && p.as_ref() == phonetic)
+                    .map(|&(_, _, index)| index)
+            },
+        }
+    }
+}
+
+/// Write sharedStrings.xml, with `inline_strings` it has no text
+pub fn write(book: &Book, dir: &TempDir, inline_strings: bool) -> result::Result<SharedStrings, XlsxError> {
+    let mut shared_strings: Vec<(Value, Option<Phonetic>)> = Vec::new();
     let mut count: usize = 0;
-    for sheet in book.get_sheet_vec() {
+    let sheets = if inline_strings { &[][..] } else { &book.get_sheet_vec()[..] };
+    for sheet in sheets {
         sheet.sorted_access(|_, _, cell| {
+            // the text result of a formula is in its cell
+            if cell.get_formula().is_some() {
+                return;
+            }
             match cell.get_value() {
-                &Value::Str(ref val) => {
+                &Value::Str(_) | &Value::RichText(_) => {
                     count = count + 1;
-                    if !shared_strings.contains(val) {
-                        shared_strings.push(val.clone());
+                    let entry = (cell.get_value().clone(), cell.get_phonetic().cloned());
+                    if !shared_strings.contains(&entry) {
+                        shared_strings.push(entry);
                     }
                 },
                 _ => {}
@@ -34,18 +63,99 @@ pub fn write(book: &Book, dir: &TempDir) -> result::Result<HashMap<String, usize
         ("xmlns", "http://schemas.openxmlformats.org/spreadsheetml/2006/main"),
         ("count", count.to_string().as_str()),
         ("uniqueCount", shared_strings.len().to_string().as_str())], false);
-    let mut map: HashMap<String, usize> = HashMap::new();
+    let mut result = SharedStrings {
+        strings: HashMap::new(),
+        others: Vec::new(),
+    };
     let mut index = 0;
-    for st in shared_strings {
+    for (st, phonetic) in shared_strings {
          write_start_tag(&mut writer, "si", vec![], false);
-         write_start_tag(&mut writer, "t", vec![("xml:space", "preserve")], false);
-         write_text_node(&mut writer, st.clone());
-         write_end_tag(&mut writer, "t");
+         write_string_item(&mut writer, &st, phonetic.as_ref());
          write_end_tag(&mut writer, "si");
-         map.insert(st, index);
+         match (st, phonetic) {
+             (Value::Str(val), None) => {
+                 result.strings.insert(val, index);
+             },
+             (st, phonetic) => result.others.push((st, phonetic, index)),
+         }
          index = index + 1;
     }
     write_end_tag(&mut writer, "sst");
     let _ = make_file_from_writer(SHARED_STRINGS, dir, writer, Some("xl"))?;
-    Ok(map)
+    Ok(result)
+}
+
+/// Write the content of a si or an inline is, the text or the runs and the reading
+pub fn write_string_item(writer: &mut Writer<Cursor<Vec<u8>>>, value: &Value, phonetic: Option<&Phonetic>) {
+    match *value {
+        Value::RichText(ref runs) => {
+            for run in runs.iter() {
+                write_run(writer, run);
+            }
+        },
+        Value::Str(ref val) => {
+            write_start_tag(writer, "t", vec![("xml:space", "preserve")], false);
+            write_text_node(writer, val.as_str());
+            write_end_tag(writer, "t");
+        },
+        _ => {},
+    }
+    if let Some(phonetic) = phonetic {
+        write_phonetic(writer, phonetic);
+    }
+}
+
+fn write_run(writer: &mut Writer<Cursor<Vec<u8>>>, run: &TextRun) {
+    write_start_tag(writer, "r", vec![], false);
+    if let Some(font) = run.get_font() {
+        write_start_tag(writer, "rPr", vec![], false);
+        if let Some(name) = font.get_name() {
+            write_start_tag(writer, "rFont", vec![("val", name.as_str())], true);
+        }
+        if font.is_bold() {
+            write_start_tag(writer, "b", vec![], true);
+        }
+        if font.is_italic() {
+            write_start_tag(writer, "i", vec![], true);
+        }
+        if font.is_strike() {
+            write_start_tag(writer, "strike", vec![], true);
+        }
+        if let Some(color) = font.get_color() {
+            write_start_tag(writer, "color", vec![("rgb", format!("FF{}", color).as_str())], true);
+        }
+        if let Some(size) = font.get_size() {
+            write_start_tag(writer, "sz", vec![("val", size.to_string().as_str())], true);
+        }
+        if font.is_underline() {
+            write_start_tag(writer, "u", vec![], true);
+        }
+        write_end_tag(writer, "rPr");
+    }
+    write_start_tag(writer, "t", vec![("xml:space", "preserve")], false);
+    write_text_node(writer, run.get_text().as_str());
+    write_end_tag(writer, "t");
+    write_end_tag(writer, "r");
+}
+
+fn write_phonetic(writer: &mut Writer<Cursor<Vec<u8>>>, phonetic: &Phonetic) {
+    for run in phonetic.get_runs() {
+        write_start_tag(writer, "rPh", vec![
+            ("sb", run.get_start().to_string().as_str()),
+            ("eb", run.get_end().to_string().as_str()),
+        ], false);
+        write_start_tag(writer, "t", vec![], false);
+        write_text_node(writer, run.get_text().as_str());
+        write_end_tag(writer, "t");
+        write_end_tag(writer, "rPh");
+    }
+    let font_id = phonetic.get_font_id().to_string();
+    let mut attributes = vec![("fontId", font_id.as_str())];
+    if let Some(kind) = phonetic.get_kind() {
+        attributes.push(("type", kind.as_str()));
+    }
+    if let Some(alignment) = phonetic.get_alignment() {
+        attributes.push(("alignment", alignment.as_str()));
+    }
+    write_start_tag(writer, "phoneticPr", attributes, true);
 }
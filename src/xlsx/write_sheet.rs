@@ -1,15 +1,80 @@
 use file_common::*;
+use std::collections::{BTreeMap, HashMap};
 use std::io::Cursor;
 use std::result;
-use super::chrono::prelude::*;
-use super::quick_xml::events::{Event, BytesDecl};
+use std::slice;
+use super::quick_xml::events::{Event, BytesDecl, BytesStart};
 use super::quick_xml::Writer;
 use super::tempdir::TempDir;
-use super::{Sheet, Value, index_to_column};
+use super::chrono::FixedOffset;
+use super::{Sheet, Cell, Value, index_to_column, index_to_column_and_row};
 use super::XlsxError;
-use std::collections::HashMap;
+use auto_filter::{AutoFilter, Filter};
+use rich_text::TextRun;
+use date::{self, DateSystem};
+use conditional_format::{ConditionalFormat, Threshold};
+use range::Range;
+use sheet_view::{Pane, SheetView};
+use validation::{DataValidation, ErrorStyle, Operator, Rule};
+use super::write_shared_strings::{SharedStrings, write_string_item};
+use super::write_styles::CellStyles;
+use super::relationships::{self, Relationship};
+use super::HYPERLINK_TYPE;
 
-pub fn write(sheet: &Sheet, dir: &TempDir, shared_strings: &HashMap<String, usize>, index: usize, format_map: &HashMap<String, usize>) -> result::Result<(), XlsxError> {
+const WORKSHEET_ELEMENTS: [&str; 39] = [
+    "sheetPr", "dimension", "sheetViews", "sheetFormatPr", "cols", "sheetData",
+    "sheetCalcPr", "sheetProtection", "protectedRanges", "scenarios", "autoFilter",
+    "sortState", "dataConsolidate", "customSheetViews", "mergeCells", "phoneticPr",
+    "conditionalFormatting", "dataValidations", "hyperlinks", "printOptions",
+    "pageMargins", "pageSetup", "headerFooter", "rowBreaks", "colBreaks",
+    "customProperties", "cellWatches", "ignoredErrors", "smartTags", "drawing",
+    "legacyDrawing", "legacyDrawingHF", "drawingHF", "picture", "oleObjects",
+    "controls", "webPublishItems", "tableParts", "extLst",
+];
+
+/// Relationship ids of the parts and links the worksheet refers to
+#[derive(Debug, Default)]
+pub struct RelationshipIds {
+    /// drawing with the pictures
+    pub drawing: Option<String>,
+    /// VML drawing with the notes
+    pub legacy_drawing: Option<String>,
+    /// external hyperlinks by (row, column)
+    pub hyperlinks: HashMap<(usize, usize), String>,
+    /// table parts in the order of the tables
+    pub tables: Vec<String>,
+}
+
+/// Parts of the workbook which the cells refer to
+pub struct BookParts<'a> {
+    pub shared_strings: &'a SharedStrings,
+    pub cell_styles: &'a CellStyles,
+    pub date_system: DateSystem,
+    pub timezone: FixedOffset,
+}
+
+/// Add a relationship for each hyperlink to an url, a location in the book needs none
+pub fn add_hyperlinks(sheet: &Sheet, sheet_relationships: &mut Vec<Relationship>) -> HashMap<(usize, usize), String> {
+    let mut ids = HashMap::new();
+    sheet.sorted_access(|row, column, cell| {
+        if let Some(hyperlink) = cell.get_hyperlink() {
+            if hyperlink.get_location().is_none() {
+                let id = relationships::add(sheet_relationships, HYPERLINK_TYPE, hyperlink.get_target(), Some("External"));
+                ids.insert((row, column), id);
+            }
+        }
+    });
+    ids
+}
+
+/// Write the worksheet part.
+/// With the original part only dimension and sheetData are made,
+/// the other elements and the attributes of the rows are kept.
+/// The view of the sheet replaces the attributes it has of the original sheetView, the panes and the selection.
+/// The auto filter with its sort state, the conditional formats, the data validations, the hyperlinks, the legacy drawing with the notes
+/// and the table parts replace the original ones.
+pub fn write(sheet: &Sheet, dir: &TempDir, part_name: &str, parts: &BookParts, original: Option<&[u8]>, selected: bool, ids: &RelationshipIds) -> result::Result<(), XlsxError> {
+    let BookParts { shared_strings, cell_styles, date_system, timezone } = *parts;
     let dimension = match sheet.get_max_index() {
         Some((max_row_index, max_column_index)) => {
             if max_row_index == 0 && max_column_index == 0 {
@@ -20,6 +85,81 @@ pub fn write(sheet: &Sheet, dir: &TempDir, shared_strings: &HashMap<String, usiz
         },
         None => String::from("A1")
     };
+    if let Some(data) = original {
+        let (root, mut children) = read_children(data)?;
+        let mut original_view = None;
+        if let Some(child) = children.iter().find(|child| child.name == "sheetViews") {
+            original_view = read_children(&child.data)?.1.into_iter().next().and_then(|view| view.start_tag());
+        }
+        children.retain(|child| !["sheetViews", "autoFilter", "drawing", "legacyDrawing", "hyperlinks", "conditionalFormatting", "dataValidations", "tableParts"].contains(&child.name.as_str()));
+        // the sort state of the auto filter is written in it
+        if sheet.get_auto_filter().is_some() {
+            children.retain(|child| child.name != "sortState");
+        }
+        if let Some(root) = root {
+            let mut original_rows = BTreeMap::new();
+            for child in &children {
+                if child.name == "sheetData" {
+                    for row in read_children(&child.data)?.1 {
+                        let index = row.get_attribute("r").and_then(|r| r.parse::<usize>().ok());
+                        if let (Some(index), Some(tag)) = (index, row.start_tag()) {
+                            original_rows.insert(index - 1, tag);
+                        }
+                    }
+                }
+            }
+            let mut generated = vec![
+                RawElement::new("dimension", |writer| {
+                    write_start_tag(writer, "dimension", vec![("ref", dimension.as_str())], true);
+                }),
+                RawElement::new("sheetViews", |writer| {
+                    write_sheet_views(writer, sheet.get_view(), selected, original_view.as_ref());
+                }),
+                RawElement::new("sheetData", |writer| {
+                    write_sheet_data(writer, sheet, shared_strings, cell_styles, date_system, timezone, &original_rows);
+                }),
+            ];
+            if let Some(auto_filter) = sheet.get_auto_filter() {
+                generated.push(RawElement::new("autoFilter", |writer| {
+                    write_auto_filter(writer, auto_filter);
+                }));
+            }
+            let mut priority = 1;
+            for format in sheet.get_conditional_formats() {
+                generated.push(RawElement::new("conditionalFormatting", |writer| {
+                    write_conditional_format(writer, format, cell_styles, &mut priority);
+                }));
+            }
+            if !sheet.get_validations().is_empty() {
+                generated.push(RawElement::new("dataValidations", |writer| {
+                    write_data_validations(writer, sheet, date_system);
+                }));
+            }
+            if has_hyperlinks(sheet) {
+                generated.push(RawElement::new("hyperlinks", |writer| {
+                    write_hyperlinks(writer, sheet, &ids.hyperlinks);
+                }));
+            }
+            if let Some(ref id) = ids.drawing {
+                generated.push(RawElement::new("drawing", |writer| {
+                    write_start_tag(writer, "drawing", vec![("r:id", id.as_str())], true);
+                }));
+            }
+            if let Some(ref id) = ids.legacy_drawing {
+                generated.push(RawElement::new("legacyDrawing", |writer| {
+                    write_start_tag(writer, "legacyDrawing", vec![("r:id", id.as_str())], true);
+                }));
+            }
+            if !ids.tables.is_empty() {
+                generated.push(RawElement::new("tableParts", |writer| {
+                    write_table_parts(writer, &ids.tables);
+                }));
+            }
+            let children = merge_elements(&WORKSHEET_ELEMENTS, children, generated);
+            make_file_from_elements(part_name, dir, &root, &children)?;
+            return Ok(());
+        }
+    }
     let mut writer = Writer::new(Cursor::new(Vec::new()));
     let _ = writer.write_event(Event::Decl(
         BytesDecl::new(b"1.0", Some(b"UTF-8"), Some(b"yes"))));
@@ -32,85 +172,24 @@ pub fn write(sheet: &Sheet, dir: &TempDir, shared_strings: &HashMap<String, usiz
     write_start_tag(&mut writer, "pageSetUpPr", vec![("fitToPage", "false")], true);
     write_end_tag(&mut writer, "sheetPr");
     write_start_tag(&mut writer, "dimension", vec![("ref", dimension.as_str())], true);
-    write_start_tag(&mut writer, "sheetViews", vec![], false);
-    write_start_tag(&mut writer, "sheetView", vec![("showFormulas", "false"),("showGridLines", "true"),("showRowColHeaders", "true"),("showZeros", "true"),("rightToLeft", "false"),("tabSelected", "true"),("showOutlineSymbols", "true"),("defaultGridColor", "true"),("view", "normal"),("topLeftCell", "A1"),("colorId", "64"),("zoomScale", "100"),("zoomScaleNormal", "100"),("zoomScalePageLayoutView", "100"),("workbookViewId", "0")], false);
-    write_start_tag(&mut writer, "selection", vec![("pane", "topLeft"),("activeCell", "A1"),("activeCellId", "0"),("sqref", "A1")], true);
-    write_end_tag(&mut writer, "sheetView");
-    write_end_tag(&mut writer, "sheetViews");
+    write_sheet_views(&mut writer, sheet.get_view(), selected, None);
     write_start_tag(&mut writer, "sheetFormatPr", vec![("defaultRowHeight", "12.8"),("zeroHeight", "false"),("outlineLevelRow", "0"),("outlineLevelCol", "0")], true);
     write_start_tag(&mut writer, "cols", vec![], false);
     write_start_tag(&mut writer, "col", vec![("collapsed", "false"),("customWidth", "true"),("hidden", "false"),("outlineLevel", "0"),("max", "1025"),("min", "1"),("style", "0"),("width", "10.86")], true);
     write_end_tag(&mut writer, "cols");
-    if sheet.get_rows().len() == 0 {
-        write_start_tag(&mut writer, "sheetData", vec![], true);
-    } else {
-        write_start_tag(&mut writer, "sheetData", vec![], false);
-        let mut current_row_index = ::std::usize::MAX;
-        sheet.sorted_access(|row_index, column_index, cell| {
-            let row_str = (row_index + 1).to_string();
-            if current_row_index != row_index {
-                if current_row_index != ::std::usize::MAX {
-                    write_end_tag(&mut writer, "row");
-                }
-                current_row_index = row_index;
-                write_start_tag(&mut writer, "row", vec![
-                    ("r", &row_str),
-                    ("customFormat", "false"),
-                    ("hidden", "false"),
-                    ("customHeight", "false"),
-                    ("outlineLevel", "0"),
-                    ("collapsed", "false"),
-                ], false);
-            }
-            let col_str = format!(
-                "{}{}", index_to_column(column_index), row_str);
-            let format = cell.get_format().get_content();
-            match cell.get_value() {
-                &Value::Str(ref val) => {
-                    write_start_tag(&mut writer, "c", vec![
-                        ("r", &col_str),
-                        ("s", "0"),
-                        ("t", "s"),
-                    ], false);
-                    write_start_tag(&mut writer, "v", vec![], false);
-                    let val_index = shared_strings.get(val).unwrap().to_string();
-                    write_text_node(&mut writer, val_index.as_str());
-                },
-                &Value::Float(ref val) => {
-                    write_start_tag(&mut writer, "c", vec![
-                        ("r", &col_str),
-                        ("s", "0"),
-                        ("t", "n"),
-                    ], false);
-                    write_start_tag(&mut writer, "v", vec![], false);
-                    write_text_node(&mut writer, val.to_string().as_str());
-                },
-                &Value::Date(ref val) => {
-                    let s_value = format_map.get(format).unwrap();
-                    write_start_tag(&mut writer, "c", vec![
-                        ("r", &col_str),
-                        ("s", s_value.to_string().as_str()),
-                        ("t", "n"),
-                    ], false);
-                    write_start_tag(&mut writer, "v", vec![], false);
-                    write_text_node(&mut writer, datetime_to_serail(val).to_string().as_str());
-                },
-                &Value::Currency(ref val) => {
-                    let s_value = format_map.get(format).unwrap();
-                    write_start_tag(&mut writer, "c", vec![
-                        ("r", &col_str),
-                        ("s", s_value.to_string().as_str()),
-                        ("t", "n"),
-                    ], false);
-                    write_start_tag(&mut writer, "v", vec![], false);
-                    write_text_node(&mut writer, val.to_string().as_str());
-                },
-            }
-            write_end_tag(&mut writer, "v");
-            write_end_tag(&mut writer, "c");
-        });
-        write_end_tag(&mut writer, "row");
-        write_end_tag(&mut writer, "sheetData");
+    write_sheet_data(&mut writer, sheet, shared_strings, cell_styles, date_system, timezone, &BTreeMap::new());
+    if let Some(auto_filter) = sheet.get_auto_filter() {
+        write_auto_filter(&mut writer, auto_filter);
+    }
+    let mut priority = 1;
+    for format in sheet.get_conditional_formats() {
+        write_conditional_format(&mut writer, format, cell_styles, &mut priority);
+    }
+    if !sheet.get_validations().is_empty() {
+        write_data_validations(&mut writer, sheet, date_system);
+    }
+    if has_hyperlinks(sheet) {
+        write_hyperlinks(&mut writer, sheet, &ids.hyperlinks);
     }
     write_start_tag(&mut writer, "printOptions", vec![("headings", "false"),("gridLines", "false"),("gridLinesSet", "true"),("horizontalCentered", "false"),("verticalCentered", "false")], true);
     write_start_tag(&mut writer, "pageMargins", vec![("left", "0.7875"),("right", "0.7875"),("top", "1.025"),("bottom", "1.025"),("header", "0.7875"),("footer", "0.7875")], true);
@@ -123,14 +202,521 @@ pub fn write(sheet: &Sheet, dir: &TempDir, shared_strings: &HashMap<String, usiz
     write_text_node(&mut writer, "&amp;C&amp;&quot;Arial,標準&quot;ページ &amp;P");
     write_end_tag(&mut writer, "oddFooter");
     write_end_tag(&mut writer, "headerFooter");
+    if let Some(ref id) = ids.drawing {
+        write_start_tag(&mut writer, "drawing", vec![("r:id", id.as_str())], true);
+    }
+    if let Some(ref id) = ids.legacy_drawing {
+        write_start_tag(&mut writer, "legacyDrawing", vec![("r:id", id.as_str())], true);
+    }
+    if !ids.tables.is_empty() {
+        write_table_parts(&mut writer, &ids.tables);
+    }
     write_end_tag(&mut writer, "worksheet");
-    let _ = make_file_from_writer(format!("xl/worksheets/sheet{}.xml", index).as_str(), dir, writer, Some("xl/worksheets"))?;
+    make_file_from_writer(part_name, dir, writer, Some("xl/worksheets"))?;
     Ok(())
 }
 
-fn datetime_to_serail(src: &DateTime<Utc>) -> f64 {
-    let seconds = src.hour() * 3600 + src.minute() * 60 + src.second();
-    let base = Utc.ymd(1900, 1, 1).and_hms(0, 0, 0);
-    let days = src.num_days_from_ce() - base.num_days_from_ce() + 2;
-    days as f64 + seconds as f64 / 86400.0
+fn write_table_parts(writer: &mut Writer<Cursor<Vec<u8>>>, ids: &[String]) {
+    write_start_tag(writer, "tableParts", vec![("count", ids.len().to_string().as_str())], false);
+    for id in ids {
+        write_start_tag(writer, "tablePart", vec![("r:id", id.as_str())], true);
+    }
+    write_end_tag(writer, "tableParts");
+}
+
+fn has_hyperlinks(sheet: &Sheet) -> bool {
+    sheet.get_rows().values().any(|row| row.values().any(|cell| cell.get_hyperlink().is_some()))
+}
+
+/// A link to an url refers to its relationship, a link in the book has the location without "#"
+fn write_hyperlinks(writer: &mut Writer<Cursor<Vec<u8>>>, sheet: &Sheet, ids: &HashMap<(usize, usize), String>) {
+    write_start_tag(writer, "hyperlinks", vec![], false);
+    sheet.sorted_access(|row, column, cell| {
+        if let Some(hyperlink) = cell.get_hyperlink() {
+            let reference = format!("{}{}", index_to_column(column), row + 1);
+            let mut attributes = vec![("ref", reference.as_str())];
+            match (hyperlink.get_location(), ids.get(&(row, column))) {
+                (Some(location), _) => attributes.push(("location", location)),
+                (None, Some(id)) => attributes.push(("r:id", id.as_str())),
+                (None, None) => return,
+            }
+            if let Some(tooltip) = hyperlink.get_tooltip() {
+                attributes.push(("tooltip", tooltip.as_str()));
+            }
+            write_start_tag(writer, "hyperlink", attributes, true);
+        }
+    });
+    write_end_tag(writer, "hyperlinks");
+}
+
+/// Write the sheetView of the view, a view of an original sheet keeps the attributes of the original sheetView
+/// which aren't in the view like the view mode
+fn write_sheet_views(writer: &mut Writer<Cursor<Vec<u8>>>, view: &SheetView, selected: bool, original: Option<&BytesStart>) {
+    let flag = |value: bool| if value { "true" } else { "false" };
+    let zoom = view.get_zoom().to_string();
+    write_start_tag(writer, "sheetViews", vec![], false);
+    match original {
+        Some(tag) => {
+            write_start_tag_with_replaced_attributes(writer, tag, vec![
+                ("showGridLines", flag(view.is_show_grid_lines())),
+                ("showRowColHeaders", flag(view.is_show_headers())),
+                ("rightToLeft", flag(view.is_right_to_left())),
+                ("tabSelected", flag(selected)),
+                ("zoomScale", zoom.as_str()),
+                ("zoomScaleNormal", zoom.as_str()),
+            ], false);
+        },
+        None => {
+            write_start_tag(writer, "sheetView", vec![("showFormulas", "false"),("showGridLines", flag(view.is_show_grid_lines())),("showRowColHeaders", flag(view.is_show_headers())),("showZeros", "true"),("rightToLeft", flag(view.is_right_to_left())),("tabSelected", flag(selected)),("showOutlineSymbols", "true"),("defaultGridColor", "true"),("view", "normal"),("topLeftCell", "A1"),("colorId", "64"),("zoomScale", zoom.as_str()),("zoomScaleNormal", zoom.as_str()),("zoomScalePageLayoutView", "100"),("workbookViewId", "0")], false);
+        },
+    }
+    let active_pane = write_pane(writer, view.get_pane());
+    let (row, column) = view.get_active_cell();
+    let active_cell = index_to_column_and_row(column, row);
+    let selection = if view.get_selection().is_empty() { active_cell.clone() } else { sqref(view.get_selection()) };
+    write_start_tag(writer, "selection", vec![("pane", active_pane),("activeCell", active_cell.as_str()),("activeCellId", "0"),("sqref", selection.as_str())], true);
+    write_end_tag(writer, "sheetView");
+    write_end_tag(writer, "sheetViews");
+}
+
+/// A frozen pane splits at the number of the rows and columns, a split pane at twentieths of a point.
+/// The pane with the cursor is the one right and below the split which is returned.
+fn write_pane(writer: &mut Writer<Cursor<Vec<u8>>>, pane: Option<&Pane>) -> &'static str {
+    let (x, y, frozen) = match pane {
+        Some(&Pane::Frozen { rows, columns }) => (columns as f64, rows as f64, true),
+        Some(&Pane::Split { x, y }) => ((x * 20.0).round(), (y * 20.0).round(), false),
+        None => (0.0, 0.0, false),
+    };
+    let active_pane = match (x > 0.0, y > 0.0) {
+        (true, true) => "bottomRight",
+        (false, true) => "bottomLeft",
+        (true, false) => "topRight",
+        (false, false) => return "topLeft",
+    };
+    let (x_split, y_split) = (x.to_string(), y.to_string());
+    let mut attributes = Vec::new();
+    if x > 0.0 {
+        attributes.push(("xSplit", x_split.as_str()));
+    }
+    if y > 0.0 {
+        attributes.push(("ySplit", y_split.as_str()));
+    }
+    let top_left_cell = index_to_column_and_row(x as usize, y as usize);
+    if frozen {
+        attributes.push(("topLeftCell", top_left_cell.as_str()));
+    }
+    attributes.push(("activePane", active_pane));
+    if frozen {
+        attributes.push(("state", "frozen"));
+    }
+    write_start_tag(writer, "pane", attributes, true);
+    active_pane
+}
+
+/// Cells of the ranges without the sheet name like "B2:B10 D2"
+fn sqref(ranges: &[Range]) -> String {
+    ranges.iter().map(|range| {
+        let first = format!("{}{}", index_to_column(range.get_first_column()), range.get_first_row() + 1);
+        if range.get_first_row() == range.get_last_row() && range.get_first_column() == range.get_last_column() {
+            first
+        } else {
+            format!("{}:{}{}", first, index_to_column(range.get_last_column()), range.get_last_row() + 1)
+        }
+    }).collect::<Vec<String>>().join(" ")
+}
+
+/// The columns of the filters are numbered from the first column of the range.
+/// The sort state is the rows below the header row, a list of only the header row has none.
+fn write_auto_filter(writer: &mut Writer<Cursor<Vec<u8>>>, auto_filter: &AutoFilter) {
+    let range = auto_filter.get_range();
+    let reference = sqref(slice::from_ref(range));
+    let has_sort_state = !auto_filter.get_sort_keys().is_empty() && range.get_first_row() < range.get_last_row();
+    let empty = auto_filter.get_filters().is_empty() && !has_sort_state;
+    write_start_tag(writer, "autoFilter", vec![("ref", reference.as_str())], empty);
+    if empty {
+        return;
+    }
+    for (column, filter) in auto_filter.get_filters() {
+        write_start_tag(writer, "filterColumn", vec![("colId", column.to_string().as_str())], false);
+        match *filter {
+            Filter::Values { ref values, blank } => {
+                let attributes = if blank { vec![("blank", "1")] } else { vec![] };
+                write_start_tag(writer, "filters", attributes, values.is_empty());
+                if !values.is_empty() {
+                    for value in values {
+                        write_start_tag(writer, "filter", vec![("val", value.as_str())], true);
+                    }
+                    write_end_tag(writer, "filters");
+                }
+            },
+            Filter::Custom { ref conditions, and } => {
+                let attributes = if and { vec![("and", "1")] } else { vec![] };
+                write_start_tag(writer, "customFilters", attributes, false);
+                for &(comparison, ref value) in conditions {
+                    write_start_tag(writer, "customFilter", vec![("operator", comparison.name()),("val", value.as_str())], true);
+                }
+                write_end_tag(writer, "customFilters");
+            },
+            Filter::Top { count, bottom, percent } => {
+                write_start_tag(writer, "top10", vec![
+                    ("top", if bottom { "0" } else { "1" }),
+                    ("percent", if percent { "1" } else { "0" }),
+                    ("val", count.to_string().as_str()),
+                ], true);
+            },
+        }
+        write_end_tag(writer, "filterColumn");
+    }
+    if has_sort_state {
+        let sheet_name = range.get_sheet_name().as_str();
+        let rows = Range::new(sheet_name, range.get_first_row() + 1, range.get_first_column(), range.get_last_row(), range.get_last_column());
+        write_start_tag(writer, "sortState", vec![("ref", sqref(&[rows]).as_str())], false);
+        for key in auto_filter.get_sort_keys() {
+            let column = range.get_first_column() + key.get_column();
+            let cells = Range::new(sheet_name, range.get_first_row() + 1, column, range.get_last_row(), column);
+            let reference = sqref(&[cells]);
+            let mut attributes = vec![];
+            if key.is_descending() {
+                attributes.push(("descending", "1"));
+            }
+            attributes.push(("ref", reference.as_str()));
+            write_start_tag(writer, "sortCondition", attributes, true);
+        }
+        write_end_tag(writer, "sortState");
+    }
+    write_end_tag(writer, "autoFilter");
+}
+
+/// The rules of the sheet have priorities from 1 in their order,
+/// a rule with a style refers to its dxf in styles.xml
+fn write_conditional_format(writer: &mut Writer<Cursor<Vec<u8>>>, format: &ConditionalFormat, cell_styles: &CellStyles, priority: &mut usize) {
+    use conditional_format::Rule;
+    write_start_tag(writer, "conditionalFormatting", vec![("sqref", sqref(format.get_ranges()).as_str())], false);
+    for rule in format.get_rules() {
+        let rule_type = match *rule {
+            Rule::CellIs(..) => "cellIs",
+            Rule::Expression(..) => "expression",
+            Rule::ColorScale(_) => "colorScale",
+            Rule::DataBar { .. } => "dataBar",
+            Rule::IconSet { .. } => "iconSet",
+            Rule::Top { .. } => "top10",
+            Rule::Duplicate(_) => "duplicateValues",
+            Rule::Unique(_) => "uniqueValues",
+        };
+        let dxf_id = rule.get_style().and_then(|style| cell_styles.get_dxf(style)).map(|id| id.to_string());
+        let priority_text = priority.to_string();
+        *priority += 1;
+        let mut attributes = vec![("type", rule_type)];
+        if let Some(ref id) = dxf_id {
+            attributes.push(("dxfId", id.as_str()));
+        }
+        attributes.push(("priority", priority_text.as_str()));
+        let rank;
+        match *rule {
+            Rule::CellIs(ref operator, _) => attributes.push(("operator", operator.name())),
+            Rule::Top { rank: top_rank, bottom, percent, .. } => {
+                rank = top_rank.to_string();
+                attributes.push(("rank", rank.as_str()));
+                if bottom {
+                    attributes.push(("bottom", "1"));
+                }
+                if percent {
+                    attributes.push(("percent", "1"));
+                }
+            },
+            _ => {},
+        }
+        let empty = matches!(*rule, Rule::Top { .. } | Rule::Duplicate(_) | Rule::Unique(_));
+        write_start_tag(writer, "cfRule", attributes, empty);
+        if empty {
+            continue;
+        }
+        match *rule {
+            Rule::CellIs(ref operator, _) => {
+                let (bound, upper) = operator.bounds();
+                for formula in Some(bound).into_iter().chain(upper) {
+                    write_start_tag(writer, "formula", vec![], false);
+                    write_text_node(writer, formula.as_str());
+                    write_end_tag(writer, "formula");
+                }
+            },
+            Rule::Expression(ref formula, _) => {
+                write_start_tag(writer, "formula", vec![], false);
+                write_text_node(writer, formula.as_str());
+                write_end_tag(writer, "formula");
+            },
+            Rule::ColorScale(ref stops) => {
+                write_start_tag(writer, "colorScale", vec![], false);
+                for (threshold, _) in stops {
+                    write_cfvo(writer, threshold);
+                }
+                for (_, color) in stops {
+                    write_start_tag(writer, "color", vec![("rgb", format!("FF{}", color).as_str())], true);
+                }
+                write_end_tag(writer, "colorScale");
+            },
+            Rule::DataBar { ref min, ref max, ref color } => {
+                write_start_tag(writer, "dataBar", vec![], false);
+                write_cfvo(writer, min);
+                write_cfvo(writer, max);
+                write_start_tag(writer, "color", vec![("rgb", format!("FF{}", color).as_str())], true);
+                write_end_tag(writer, "dataBar");
+            },
+            Rule::IconSet { icons, ref thresholds } => {
+                write_start_tag(writer, "iconSet", vec![("iconSet", icons.name())], false);
+                for threshold in thresholds {
+                    write_cfvo(writer, threshold);
+                }
+                write_end_tag(writer, "iconSet");
+            },
+            _ => {},
+        }
+        write_end_tag(writer, "cfRule");
+    }
+    write_end_tag(writer, "conditionalFormatting");
+}
+
+fn write_cfvo(writer: &mut Writer<Cursor<Vec<u8>>>, threshold: &Threshold) {
+    let (cfvo_type, value) = match *threshold {
+        Threshold::Min => ("min", None),
+        Threshold::Max => ("max", None),
+        Threshold::Number(value) => ("num", Some(value.to_string())),
+        Threshold::Percent(value) => ("percent", Some(value.to_string())),
+        Threshold::Percentile(value) => ("percentile", Some(value.to_string())),
+        Threshold::Formula(ref formula) => ("formula", Some(formula.clone())),
+    };
+    let mut attributes = vec![("type", cfvo_type)];
+    if let Some(ref value) = value {
+        attributes.push(("val", value.as_str()));
+    }
+    write_start_tag(writer, "cfvo", attributes, true);
+}
+
+/// The ranges are the cells of the sheet without the sheet name like "B2:B10 D2",
+/// a list of texts is a string like "Yes,No" and a date is its serial
+fn write_data_validations(writer: &mut Writer<Cursor<Vec<u8>>>, sheet: &Sheet, date_system: DateSystem) {
+    write_start_tag(writer, "dataValidations", vec![("count", sheet.get_validations().len().to_string().as_str())], false);
+    for validation in sheet.get_validations() {
+        let (validation_type, operator, formulas) = validation_formulas(validation, date_system);
+        let sqref = sqref(validation.get_ranges());
+        let error_style = match validation.get_error_style() {
+            ErrorStyle::Stop => "stop",
+            ErrorStyle::Warning => "warning",
+            ErrorStyle::Information => "information",
+        };
+        let mut attributes = vec![("type", validation_type)];
+        if let Some(operator) = operator {
+            attributes.push(("operator", operator));
+        }
+        attributes.push(("allowBlank", if validation.is_allow_blank() { "1" } else { "0" }));
+        // showDropDown hides the drop-down
+        attributes.push(("showDropDown", if validation.is_drop_down() { "0" } else { "1" }));
+        attributes.push(("showInputMessage", if validation.get_prompt().is_some() { "1" } else { "0" }));
+        attributes.push(("showErrorMessage", "1"));
+        attributes.push(("errorStyle", error_style));
+        if let Some(error) = validation.get_error() {
+            attributes.push(("errorTitle", error.get_title().as_str()));
+            attributes.push(("error", error.get_text().as_str()));
+        }
+        if let Some(prompt) = validation.get_prompt() {
+            attributes.push(("promptTitle", prompt.get_title().as_str()));
+            attributes.push(("prompt", prompt.get_text().as_str()));
+        }
+        attributes.push(("sqref", sqref.as_str()));
+        write_start_tag(writer, "dataValidation", attributes, false);
+        for (index, formula) in formulas.iter().enumerate() {
+            let name = if index == 0 { "formula1" } else { "formula2" };
+            write_start_tag(writer, name, vec![], false);
+            write_text_node(writer, formula.as_str());
+            write_end_tag(writer, name);
+        }
+        write_end_tag(writer, "dataValidation");
+    }
+    write_end_tag(writer, "dataValidations");
+}
+
+/// Type, operator and formulas of the rule of a validation
+fn validation_formulas(validation: &DataValidation, date_system: DateSystem) -> (&'static str, Option<&'static str>, Vec<String>) {
+    fn operator_formulas<T, F>(operator: &Operator<T>, formula: F) -> (Option<&'static str>, Vec<String>)
+        where F: Fn(&T) -> String
+    {
+        let (bound, upper) = operator.bounds();
+        (Some(operator.name()), Some(bound).into_iter().chain(upper).map(formula).collect())
+    }
+    match *validation.get_rule() {
+        Rule::List(ref items) => ("list", None, vec![format!("\"{}\"", items.join(",").replace('"', "\"\""))]),
+        Rule::ListRange(ref range) => ("list", None, vec![range.to_string()]),
+        Rule::WholeNumber(ref operator) => {
+            let (operator, formulas) = operator_formulas(operator, |value| value.to_string());
+            ("whole", operator, formulas)
+        },
+        Rule::Decimal(ref operator) => {
+            let (operator, formulas) = operator_formulas(operator, |value| value.to_string());
+            ("decimal", operator, formulas)
+        },
+        Rule::Date(ref operator) => {
+            let (operator, formulas) = operator_formulas(operator, |value| date::datetime_to_serial(&value.and_hms_opt(0, 0, 0).unwrap(), date_system).to_string());
+            ("date", operator, formulas)
+        },
+        Rule::TextLength(ref operator) => {
+            let (operator, formulas) = operator_formulas(operator, |value| value.to_string());
+            ("textLength", operator, formulas)
+        },
+        Rule::Custom(ref formula) => ("custom", None, vec![formula.clone()]),
+    }
+}
+
+/// A formula cell has its last result after the formula, a text result is in the cell instead of sharedStrings.xml
+fn write_formula_cell(writer: &mut Writer<Cursor<Vec<u8>>>, col_str: &str, s_value: &str, cell: &Cell, formula: &str, date_system: DateSystem, timezone: FixedOffset) {
+    let (cell_type, value) = match *cell.get_value() {
+        Value::Str(ref val) => (Some("str"), Some(val.clone())),
+        Value::RichText(ref runs) => (Some("str"), Some(TextRun::concat(runs))),
+        Value::Bool(val) => (Some("b"), Some(String::from(if val { "1" } else { "0" }))),
+        Value::Error(ref val) => (Some("e"), Some(val.clone())),
+        Value::Empty => (None, None),
+        ref val => (Some("n"), number_value(val, date_system, timezone)),
+    };
+    let mut attributes = vec![("r", col_str), ("s", s_value)];
+    if let Some(cell_type) = cell_type {
+        attributes.push(("t", cell_type));
+    }
+    write_start_tag(writer, "c", attributes, false);
+    write_start_tag(writer, "f", vec![], false);
+    write_text_node(writer, formula);
+    write_end_tag(writer, "f");
+    if let Some(value) = value {
+        write_start_tag(writer, "v", vec![], false);
+        write_text_node(writer, value.as_str());
+        write_end_tag(writer, "v");
+    }
+    write_end_tag(writer, "c");
+}
+
+/// Number of a numeric value, a date or a time is its serial
+fn number_value(value: &Value, date_system: DateSystem, timezone: FixedOffset) -> Option<String> {
+    match *value {
+        Value::Float(val) => Some(val.to_string()),
+        Value::Date(ref val) => Some(date::datetime_to_serial(&val.with_timezone(&timezone).naive_local(), date_system).to_string()),
+        Value::Currency(ref val) => Some(val.get_amount().to_string()),
+        Value::Time(ref val) => Some(date::time_to_serial(val).to_string()),
+        Value::Duration(ref val) => Some(date::duration_to_serial(val).to_string()),
+        Value::DateOnly(ref val) => Some(date::datetime_to_serial(&val.and_hms_opt(0, 0, 0).unwrap(), date_system).to_string()),
+        _ => None,
+    }
+}
+
+/// Rows keep the attributes like the height of their source row in the original part, even without cells
+fn write_sheet_data(writer: &mut Writer<Cursor<Vec<u8>>>, sheet: &Sheet, shared_strings: &SharedStrings, cell_styles: &CellStyles, date_system: DateSystem, timezone: FixedOffset, original_rows: &BTreeMap<usize, BytesStart>) {
+    let mut rows: BTreeMap<usize, Vec<(usize, &Cell)>> = BTreeMap::new();
+    for (row_index, row) in sheet.get_rows() {
+        let mut cells: Vec<(usize, &Cell)> = row.iter().map(|(column_index, cell)| (*column_index, cell)).collect();
+        cells.sort_by_key(|&(column_index, _)| column_index);
+        rows.insert(*row_index, cells);
+    }
+    for (row_index, source) in sheet.get_row_sources() {
+        if original_rows.contains_key(source) {
+            rows.entry(*row_index).or_default();
+        }
+    }
+    if rows.is_empty() {
+        write_start_tag(writer, "sheetData", vec![], true);
+        return;
+    }
+    write_start_tag(writer, "sheetData", vec![], false);
+    for (row_index, cells) in &rows {
+        let row_str = (row_index + 1).to_string();
+        let empty_flag = cells.is_empty();
+        match sheet.get_row_source(*row_index).and_then(|source| original_rows.get(&source)) {
+            Some(tag) => write_start_tag_with_replaced_attributes(writer, tag, vec![("r", &row_str)], empty_flag),
+            None => write_start_tag(writer, "row", vec![
+                ("r", &row_str),
+                ("customFormat", "false"),
+                ("hidden", "false"),
+                ("customHeight", "false"),
+                ("outlineLevel", "0"),
+                ("collapsed", "false"),
+            ], empty_flag),
+        }
+        if empty_flag {
+            continue;
+        }
+        for &(column_index, cell) in cells {
+            let col_str = format!("{}{}", index_to_column(column_index), row_str);
+            write_cell(writer, &col_str, cell, shared_strings, cell_styles, date_system, timezone);
+        }
+        write_end_tag(writer, "row");
+    }
+    write_end_tag(writer, "sheetData");
+}
+
+fn write_cell(writer: &mut Writer<Cursor<Vec<u8>>>, col_str: &str, cell: &Cell, shared_strings: &SharedStrings, cell_styles: &CellStyles, date_system: DateSystem, timezone: FixedOffset) {
+    let s_value = cell_styles.get(cell).to_string();
+    if let Some(formula) = cell.get_formula() {
+        write_formula_cell(writer, col_str, &s_value, cell, formula, date_system, timezone);
+        return;
+    }
+    match cell.get_value() {
+        &Value::Str(_) | &Value::RichText(_) => {
+            let mut attributes = vec![
+                ("r", col_str),
+                ("s", s_value.as_str()),
+                ("t", "s"),
+            ];
+            if cell.get_phonetic().map(|phonetic| phonetic.is_visible()).unwrap_or(false) {
+                attributes.push(("ph", "1"));
+            }
+            match shared_strings.get(cell) {
+                Some(val_index) => {
+                    write_start_tag(writer, "c", attributes, false);
+                    write_start_tag(writer, "v", vec![], false);
+                    write_text_node(writer, val_index.to_string().as_str());
+                },
+                None => {
+                    attributes[2] = ("t", "inlineStr");
+                    write_start_tag(writer, "c", attributes, false);
+                    write_start_tag(writer, "is", vec![], false);
+                    write_string_item(writer, cell.get_value(), cell.get_phonetic());
+                    write_end_tag(writer, "is");
+                    write_end_tag(writer, "c");
+                    return;
+                },
+            }
+        },
+        Value::Bool(val) => {
+            write_start_tag(writer, "c", vec![
+                ("r", col_str),
+                ("s", s_value.as_str()),
+                ("t", "b"),
+            ], false);
+            write_start_tag(writer, "v", vec![], false);
+            write_text_node(writer, if *val { "1" } else { "0" });
+        },
+        Value::Error(val) => {
+            write_start_tag(writer, "c", vec![
+                ("r", col_str),
+                ("s", s_value.as_str()),
+                ("t", "e"),
+            ], false);
+            write_start_tag(writer, "v", vec![], false);
+            write_text_node(writer, val.as_str());
+        },
+        &Value::Empty => {
+            write_start_tag(writer, "c", vec![
+                ("r", col_str),
+                ("s", s_value.as_str()),
+            ], true);
+            return;
+        },
+        val => {
+            write_start_tag(writer, "c", vec![
+                ("r", col_str),
+                ("s", s_value.as_str()),
+                ("t", "n"),
+            ], false);
+            write_start_tag(writer, "v", vec![], false);
+            write_text_node(writer, number_value(val, date_system, timezone).unwrap_or_default().as_str());
+        },
+    }
+    write_end_tag(writer, "v");
+    write_end_tag(writer, "c");
 }
@@ -5,10 +5,55 @@ use std::result;
 use super::quick_xml::events::{Event, BytesDecl};
 use super::quick_xml::Writer;
 use super::tempdir::TempDir;
-use super::{Book, Value};
+use super::{Book, Cell, Value};
 use super::XlsxError;
+use super::read_styles;
+use conditional_format::{self, DifferentialStyle};
+use rich_text::Font;
 
 const STYLE_XML: &'static str = "xl/styles.xml";
+const STYLE_SHEET_ELEMENTS: [&str; 11] = [
+    "numFmts", "fonts", "fills", "borders", "cellStyleXfs", "cellXfs",
+    "cellStyles", "dxfs", "tableStyles", "colors", "extLst",
+];
+
+/// cellXfs index of each cell and dxfs index of each differential style of the conditional formats
+pub struct CellStyles {
+    keep_style_id: bool,
+    format_map: HashMap<(Option<usize>, String), usize>,
+    dxf_ids: Vec<(DifferentialStyle, usize)>,
+}
+
+impl CellStyles {
+    pub fn get(&self, cell: &Cell) -> usize {
+        let base = if self.keep_style_id {
+            Some(cell.get_style_id().and_then(|id| id.parse::<usize>().ok()).unwrap_or(0))
+        } else {
+            None
+        };
+        if !has_number_format(cell) {
+            return base.unwrap_or(0);
+        }
+        let key = (base, cell.get_format().get_content().clone());
+        match self.format_map.get(&key) {
+            Some(index) => *index,
+            None => base.unwrap_or(0),
+        }
+    }
+
+    pub fn get_dxf(&self, style: &DifferentialStyle) -> Option<usize> {
+        self.dxf_ids.iter().find(|&(s, _)| s == style).map(|&(_, id)| id)
+    }
+}
+
+/// A date, time or currency cell, or a number cell with a format code
+fn has_number_format(cell: &Cell) -> bool {
+    match cell.get_value() {
+        &Value::Date(_) | &Value::Currency(_) | &Value::Time(_) | &Value::Duration(_) | &Value::DateOnly(_) => true,
+        &Value::Float(_) => cell.get_format().get_content() != "",
+        _ => false,
+    }
+}
 
 fn make_num_fmts(writer: &mut Writer<Cursor<Vec<u8>>>, book: &Book) -> Vec<HashMap<String, String>> {
     let mut result = vec![];
@@ -16,19 +61,16 @@ fn make_num_fmts(writer: &mut Writer<Cursor<Vec<u8>>>, book: &Book) -> Vec<HashM
     let mut key_map = HashMap::new();
     for sheet in book.get_sheet_vec() {
         sheet.walk_through(|_, _, cell| {
-            match cell.get_value() {
-                &Value::Date(_) => {
-                    let format = cell.get_format().get_content();
-                    if !key_map.contains_key(format) {
-                        let mut map = HashMap::new();
-                        map.insert(String::from("numFmtId"), num_fmot_id.to_string());
-                        map.insert(String::from("format"), format.clone());
-                        result.push(map);
-                        num_fmot_id = num_fmot_id + 1;
-                        key_map.insert(format.clone(), ());
-                    }
-                },
-                _ => {}
+            if has_number_format(cell) {
+                let format = cell.get_format().get_content();
+                if !key_map.contains_key(format) {
+                    let mut map = HashMap::new();
+                    map.insert(String::from("numFmtId"), num_fmot_id.to_string());
+                    map.insert(String::from("format"), format.clone());
+                    result.push(map);
+                    num_fmot_id += 1;
+                    key_map.insert(format.clone(), ());
+                }
             }
         });
     }
@@ -96,7 +138,7 @@ fn make_cell_xfs(writer: &mut Writer<Cursor<Vec<u8>>>, num_fmts: &Vec<HashMap<St
     result
 }
 
-pub fn write(book: &Book, dir: &TempDir) -> result::Result<HashMap<String, usize>, XlsxError> {
+pub fn write(book: &Book, dir: &TempDir) -> result::Result<CellStyles, XlsxError> {
     let mut writer = Writer::new(Cursor::new(Vec::new()));
     let _ = writer.write_event(Event::Decl(
         BytesDecl::new(b"1.0", Some(b"UTF-8"), Some(b"yes"))));
@@ -142,10 +184,200 @@ pub fn write(book: &Book, dir: &TempDir) -> result::Result<HashMap<String, usize
     write_start_tag(&mut writer, "cellStyle", vec![("xfId", "0"),("name", "Normal"),("builtinId", "0"),], false);
     write_end_tag(&mut writer, "cellStyle");
     write_end_tag(&mut writer, "cellStyles");
-    write_start_tag(&mut writer, "dxfs", vec![("count", "0"),], false);
+    let styles = conditional_format::differential_styles(book);
+    write_start_tag(&mut writer, "dxfs", vec![("count", styles.len().to_string().as_str()),], false);
+    for style in &styles {
+        write_dxf(&mut writer, style);
+    }
     write_end_tag(&mut writer, "dxfs");
     write_end_tag(&mut writer, "styleSheet");
 
     let _ = make_file_from_writer(STYLE_XML, dir, writer, Some("xl"))?;
-    Ok(result)
+    let mut format_map = HashMap::new();
+    for (format, index) in result {
+        format_map.insert((None, format), index);
+    }
+    Ok(CellStyles {
+        keep_style_id: false,
+        format_map,
+        dxf_ids: styles.into_iter().cloned().enumerate().map(|(id, style)| (style, id)).collect(),
+    })
+}
+
+/// Font and solid fill of a differential style, the color of the fill is its bgColor
+fn write_dxf(writer: &mut Writer<Cursor<Vec<u8>>>, style: &DifferentialStyle) {
+    write_start_tag(writer, "dxf", vec![], false);
+    let font = style.get_font();
+    if *font != Font::new() {
+        write_start_tag(writer, "font", vec![], false);
+        if font.is_bold() {
+            write_start_tag(writer, "b", vec![], true);
+        }
+        if font.is_italic() {
+            write_start_tag(writer, "i", vec![], true);
+        }
+        if font.is_strike() {
+            write_start_tag(writer, "strike", vec![], true);
+        }
+        if font.is_underline() {
+            write_start_tag(writer, "u", vec![], true);
+        }
+        if let Some(size) = font.get_size() {
+            write_start_tag(writer, "sz", vec![("val", size.to_string().as_str())], true);
+        }
+        if let Some(color) = font.get_color() {
+            write_start_tag(writer, "color", vec![("rgb", format!("FF{}", color).as_str())], true);
+        }
+        if let Some(name) = font.get_name() {
+            write_start_tag(writer, "name", vec![("val", name.as_str())], true);
+        }
+        write_end_tag(writer, "font");
+    }
+    if let Some(color) = style.get_fill_color() {
+        write_start_tag(writer, "fill", vec![], false);
+        write_start_tag(writer, "patternFill", vec![], false);
+        write_start_tag(writer, "bgColor", vec![("rgb", format!("FF{}", color).as_str())], true);
+        write_end_tag(writer, "patternFill");
+        write_end_tag(writer, "fill");
+    }
+    write_end_tag(writer, "dxf");
+}
+
+/// Patch the original styles.xml, adding the cell formats of dates, currencies and numbers
+/// which the original style of the cell doesn't have and the differential styles which aren't in the original dxfs
+pub fn write_with_package(book: &Book, dir: &TempDir, original: &[u8]) -> result::Result<CellStyles, XlsxError> {
+    let (root, children) = read_children(original)?;
+    let root = match root {
+        Some(root) => root,
+        None => return write(book, dir),
+    };
+    let mut num_fmts: Vec<RawElement> = Vec::new();
+    let mut xfs: Vec<RawElement> = Vec::new();
+    let mut cell_xfs_tag = None;
+    let mut dxfs: Vec<RawElement> = Vec::new();
+    let mut dxf_styles: Vec<DifferentialStyle> = Vec::new();
+    for child in &children {
+        match child.name.as_str() {
+            "numFmts" => num_fmts = read_children(&child.data)?.1,
+            "dxfs" => {
+                dxfs = read_children(&child.data)?.1;
+                dxf_styles = read_styles::read_dxfs_of(&child.data)?;
+            },
+            "cellXfs" => {
+                let (tag, elements) = read_children(&child.data)?;
+                cell_xfs_tag = tag;
+                xfs = elements;
+            },
+            _ => {},
+        }
+    }
+    let mut codes: HashMap<String, String> = HashMap::new();
+    let mut max_num_fmt_id = 163;
+    for num_fmt in &num_fmts {
+        if let (Some(id), Some(code)) = (num_fmt.get_attribute("numFmtId"), num_fmt.get_attribute("formatCode")) {
+            if let Ok(value) = id.parse::<usize>() {
+                if value > max_num_fmt_id {
+                    max_num_fmt_id = value;
+                }
+            }
+            codes.insert(id, code);
+        }
+    }
+
+    let original_xfs_len = xfs.len();
+    let mut format_map = HashMap::new();
+    for sheet in book.get_sheet_vec() {
+        sheet.sorted_access(|_, _, cell| {
+            if !has_number_format(cell) {
+                return;
+            }
+            let base = cell.get_style_id().and_then(|id| id.parse::<usize>().ok()).unwrap_or(0);
+            let format = cell.get_format().get_content().clone();
+            let key = (Some(base), format.clone());
+            // a date without format is shown by the original style
+            if format.is_empty() || format_map.contains_key(&key) || base >= original_xfs_len {
+                return;
+            }
+            let base_code = xfs[base].get_attribute("numFmtId").and_then(|id| codes.get(&id).cloned());
+            if base_code.as_ref() == Some(&format) {
+                format_map.insert(key, base);
+                return;
+            }
+            let num_fmt_id = match codes.iter().find(|&(_, code)| *code == format) {
+                Some((id, _)) => id.clone(),
+                None => {
+                    max_num_fmt_id += 1;
+                    let id = max_num_fmt_id.to_string();
+                    num_fmts.push(RawElement::new("numFmt", |writer| {
+                        write_start_tag(writer, "numFmt", vec![
+                            ("numFmtId", id.as_str()),
+                            ("formatCode", format.as_str()),
+                        ], true);
+                    }));
+                    codes.insert(id.clone(), format.clone());
+                    id
+                },
+            };
+            let xf = xfs[base].with_replaced_attributes(vec![
+                ("numFmtId", num_fmt_id.as_str()),
+                ("applyNumberFormat", "1"),
+            ]);
+            format_map.insert(key, xfs.len());
+            xfs.push(xf);
+        });
+    }
+
+    let mut generated = Vec::new();
+    if !num_fmts.is_empty() {
+        generated.push(RawElement::new("numFmts", |writer| {
+            write_start_tag(writer, "numFmts", vec![
+                ("count", num_fmts.len().to_string().as_str()),
+            ], false);
+            for num_fmt in &num_fmts {
+                write_raw(writer, &num_fmt.data);
+            }
+            write_end_tag(writer, "numFmts");
+        }));
+    }
+    let original_dxfs_len = dxfs.len();
+    let mut dxf_ids = Vec::new();
+    for style in conditional_format::differential_styles(book) {
+        let id = match dxf_styles.iter().position(|s| s == style) {
+            Some(id) if id < original_dxfs_len => id,
+            _ => {
+                dxfs.push(RawElement::new("dxf", |writer| write_dxf(writer, style)));
+                dxfs.len() - 1
+            },
+        };
+        dxf_ids.push((style.clone(), id));
+    }
+    if dxfs.len() > original_dxfs_len {
+        generated.push(RawElement::new("dxfs", |writer| {
+            write_start_tag(writer, "dxfs", vec![
+                ("count", dxfs.len().to_string().as_str()),
+            ], false);
+            for dxf in &dxfs {
+                write_raw(writer, &dxf.data);
+            }
+            write_end_tag(writer, "dxfs");
+        }));
+    }
+    if let Some(tag) = cell_xfs_tag {
+        generated.push(RawElement::new("cellXfs", |writer| {
+            write_start_tag_with_replaced_attributes(writer, &tag, vec![
+                ("count", xfs.len().to_string().as_str()),
+            ], false);
+            for xf in &xfs {
+                write_raw(writer, &xf.data);
+            }
+            write_end_tag(writer, "cellXfs");
+        }));
+    }
+    let children = merge_elements(&STYLE_SHEET_ELEMENTS, children, generated);
+    make_file_from_elements(STYLE_XML, dir, &root, &children)?;
+    Ok(CellStyles {
+        keep_style_id: true,
+        format_map,
+        dxf_ids,
+    })
 }
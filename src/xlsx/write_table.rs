@@ -0,0 +1,121 @@
+use file_common::*;
+use std::fs;
+use std::io::Cursor;
+use std::result;
+use super::quick_xml::events::{Event, BytesDecl};
+use super::quick_xml::Writer;
+use super::tempdir::TempDir;
+use super::{Sheet, Value, XlsxError, index_to_column, resolve_part_name};
+use super::relationships::{self, Relationship, rels_part_name, relative_target};
+use super::{TABLE_TYPE, TABLE_CONTENT_TYPE};
+use table::{Table, Totals};
+
+/// Write each table of the sheet to a new table part, `table_id` is the last id of the tables of the book.
+/// The relationship ids of the tables for the tableParts element are returned.
+pub fn write(sheet: &Sheet, dir: &TempDir, sheet_part_name: &str, sheet_relationships: &mut Vec<Relationship>, content_types: &mut Vec<(String, String)>, table_id: &mut usize) -> result::Result<Vec<String>, XlsxError> {
+    let mut ids = Vec::new();
+    for table in sheet.get_tables() {
+        let mut index = 0;
+        let part_name = loop {
+            index += 1;
+            let part_name = format!("xl/tables/table{}.xml", index);
+            if !dir.path().join(&part_name).exists() {
+                break part_name;
+            }
+        };
+        *table_id += 1;
+        write_table(sheet, table, dir, &part_name, *table_id)?;
+        content_types.push((part_name.clone(), String::from(TABLE_CONTENT_TYPE)));
+        ids.push(relationships::add(sheet_relationships, TABLE_TYPE, &relative_target(sheet_part_name, &part_name), None));
+    }
+    Ok(ids)
+}
+
+/// Remove the table parts of an original sheet, which are made again from the book
+pub fn remove(dir: &TempDir, sheet_part_name: &str, sheet_relationships: &mut Vec<Relationship>, removed_parts: &mut Vec<String>) -> result::Result<(), XlsxError> {
+    let base_dir = match sheet_part_name.rfind('/') {
+        Some(index) => &sheet_part_name[..index + 1],
+        None => "",
+    };
+    for r in sheet_relationships.iter().filter(|r| r.rel_type == TABLE_TYPE && r.target_mode.is_none()) {
+        let part_name = resolve_part_name(base_dir, &r.target);
+        for removed_part in [rels_part_name(&part_name), part_name] {
+            let file_path = dir.path().join(&removed_part);
+            if file_path.is_file() {
+                fs::remove_file(file_path)?;
+            }
+            removed_parts.push(removed_part);
+        }
+    }
+    sheet_relationships.retain(|r| r.rel_type != TABLE_TYPE);
+    Ok(())
+}
+
+/// A column without a name is named by the text of its header cell, "Column1" for the first column without it.
+/// The filter buttons are on the header row, a table without it has no autoFilter.
+fn write_table(sheet: &Sheet, table: &Table, dir: &TempDir, part_name: &str, id: usize) -> result::Result<(), XlsxError> {
+    let range = table.get_range();
+    let reference = |first_row: usize, last_row: usize| {
+        format!("{}{}:{}{}", index_to_column(range.get_first_column()), first_row + 1, index_to_column(range.get_last_column()), last_row + 1)
+    };
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    let _ = writer.write_event(Event::Decl(
+        BytesDecl::new(b"1.0", Some(b"UTF-8"), Some(b"yes"))));
+    write_text_node(&mut writer, "\n");
+    let id = id.to_string();
+    let table_reference = reference(range.get_first_row(), range.get_last_row());
+    let mut attributes = vec![
+        ("xmlns", "http://schemas.openxmlformats.org/spreadsheetml/2006/main"),
+        ("id", id.as_str()),
+        ("name", table.get_name().as_str()),
+        ("displayName", table.get_name().as_str()),
+        ("ref", table_reference.as_str()),
+    ];
+    if !table.has_header_row() {
+        attributes.push(("headerRowCount", "0"));
+    }
+    if table.has_totals_row() {
+        attributes.push(("totalsRowCount", "1"));
+    } else {
+        attributes.push(("totalsRowShown", "0"));
+    }
+    write_start_tag(&mut writer, "table", attributes, false);
+    if table.has_header_row() {
+        let last_row = if table.has_totals_row() && range.get_last_row() > range.get_first_row() { range.get_last_row() - 1 } else { range.get_last_row() };
+        write_start_tag(&mut writer, "autoFilter", vec![("ref", reference(range.get_first_row(), last_row).as_str())], true);
+    }
+    let count = range.get_last_column() - range.get_first_column() + 1;
+    write_start_tag(&mut writer, "tableColumns", vec![("count", count.to_string().as_str())], false);
+    for index in 0..count {
+        let column = table.get_columns().get(index);
+        let name = match column {
+            Some(column) => column.get_name().clone(),
+            None => match sheet.get_cell(range.get_first_row(), range.get_first_column() + index).map(|cell| cell.get_value()) {
+                Some(Value::Str(text)) if table.has_header_row() && !text.is_empty() => text.clone(),
+                _ => format!("Column{}", index + 1),
+            },
+        };
+        let column_id = (index + 1).to_string();
+        let mut attributes = vec![("id", column_id.as_str()), ("name", name.as_str())];
+        match column.and_then(|column| column.get_totals()) {
+            Some(Totals::Label(label)) => attributes.push(("totalsRowLabel", label.as_str())),
+            Some(&Totals::Function(function)) => attributes.push(("totalsRowFunction", function.name())),
+            None => {},
+        }
+        write_start_tag(&mut writer, "tableColumn", attributes, true);
+    }
+    write_end_tag(&mut writer, "tableColumns");
+    if let Some(style) = table.get_style() {
+        let flag = |value: bool| if value { "1" } else { "0" };
+        write_start_tag(&mut writer, "tableStyleInfo", vec![
+            ("name", style.get_name().as_str()),
+            ("showFirstColumn", flag(style.is_show_first_column())),
+            ("showLastColumn", flag(style.is_show_last_column())),
+            ("showRowStripes", flag(style.is_show_row_stripes())),
+            ("showColumnStripes", flag(style.is_show_column_stripes())),
+        ], true);
+    }
+    write_end_tag(&mut writer, "table");
+    make_file_from_writer(part_name, dir, writer, Some("xl/tables"))?;
+    Ok(())
+}
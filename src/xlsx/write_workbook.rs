@@ -6,10 +6,13 @@ use super::quick_xml::Writer;
 use super::tempdir::TempDir;
 use super::{Book};
 use super::XlsxError;
+use date::DateSystem;
+use defined_name::{self, DefinedName};
 
 const WORKBOOK_XML: &'static str = "xl/workbook.xml";
 
 pub fn write(book: &Book, dir: &TempDir) -> result::Result<(), XlsxError> {
+    let active_tab = book.get_active_sheet().to_string();
     let mut writer = Writer::new(Cursor::new(Vec::new()));
     let _ = writer.write_event(Event::Decl(
         BytesDecl::new(b"1.0", Some(b"UTF-8"), Some(b"yes"))));
@@ -24,7 +27,7 @@ pub fn write(book: &Book, dir: &TempDir) -> result::Result<(), XlsxError> {
     write_start_tag(&mut writer, "workbookPr", vec![
         ("backupFile", "false"),
         ("showObjects", "all"),
-        ("date1904", "false")
+        ("date1904", if book.get_date_system() == DateSystem::Date1904 { "true" } else { "false" })
     ], true);
     write_start_tag(&mut writer, "workbookProtection", vec![
     ], true);
@@ -40,7 +43,7 @@ pub fn write(book: &Book, dir: &TempDir) -> result::Result<(), XlsxError> {
         ("windowHeight", "8192"),
         ("tabRatio", "500"),
         ("firstSheet", "0"),
-        ("activeTab", "0")
+        ("activeTab", active_tab.as_str())
     ], true);
     write_end_tag(&mut writer, "bookViews");
     write_start_tag(&mut writer, "sheets", vec![], false);
@@ -55,7 +58,12 @@ pub fn write(book: &Book, dir: &TempDir) -> result::Result<(), XlsxError> {
         index = index + 1;
     }
     write_end_tag(&mut writer, "sheets");
+    if has_defined_names(book) {
+        write_defined_names(&mut writer, book);
+    }
+    // the formulas are calculated when the file is opened
     write_start_tag(&mut writer, "calcPr", vec![
+        ("fullCalcOnLoad", "1"),
         ("iterateCount", "100"),
         ("refMode", "A1"),
         ("iterate", "false"),
@@ -76,3 +84,42 @@ pub fn write(book: &Book, dir: &TempDir) -> result::Result<(), XlsxError> {
     let _ = make_file_from_writer(WORKBOOK_XML, dir, writer, Some("xl"))?;
     Ok(())
 }
+
+pub fn has_defined_names(book: &Book) -> bool {
+    !book.get_defined_names().is_empty()
+        || book.get_sheet_vec().iter().any(|sheet| !sheet.get_defined_names().is_empty() || sheet.get_auto_filter().is_some())
+}
+
+/// Write the names of the workbook and of the sheets, the names of a sheet have the index of the sheet.
+/// The hidden name of the list of the auto filter of a sheet is made from the auto filter instead of the names of the sheet.
+pub fn write_defined_names(writer: &mut Writer<Cursor<Vec<u8>>>, book: &Book) {
+    write_start_tag(writer, "definedNames", vec![], false);
+    let filter_databases: Vec<Option<DefinedName>> = book.get_sheet_vec().iter().map(|sheet| {
+        sheet.get_auto_filter().map(|auto_filter| {
+            let mut defined_name = DefinedName::range(defined_name::FILTER_DATABASE, auto_filter.get_range());
+            defined_name.set_hidden(true);
+            defined_name
+        })
+    }).collect();
+    let names = book.get_defined_names().iter().map(|name| (None, name));
+    let sheet_names = book.get_sheet_vec().iter().zip(filter_databases.iter()).enumerate()
+        .flat_map(|(index, (sheet, filter_database))| {
+            sheet.get_defined_names().iter()
+                .filter(move |name| filter_database.is_none() || name.get_name().to_lowercase() != defined_name::FILTER_DATABASE.to_lowercase())
+                .chain(filter_database.iter())
+                .map(move |name| (Some(index.to_string()), name))
+        });
+    for (sheet_index, defined_name) in names.chain(sheet_names) {
+        let mut attributes = vec![("name", defined_name.get_name().as_str())];
+        if let Some(ref sheet_index) = sheet_index {
+            attributes.push(("localSheetId", sheet_index.as_str()));
+        }
+        if defined_name.is_hidden() {
+            attributes.push(("hidden", "1"));
+        }
+        write_start_tag(writer, "definedName", attributes, false);
+        write_text_node(writer, defined_name.get_formula().as_str());
+        write_end_tag(writer, "definedName");
+    }
+    write_end_tag(writer, "definedNames");
+}
@@ -1,10 +1,22 @@
 // cargo test --all-features -- --nocapture
 
+extern crate chrono;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde_derive;
+#[cfg(feature = "serde")]
+extern crate serde_json;
 extern crate spsheet;
+extern crate tempdir;
 use spsheet::{Book,Sheet,Cell,column_to_index,index_to_column,column_and_row_to_index};
 use spsheet::format::Format;
+#[cfg(any(feature = "ods", feature = "xlsx"))]
+use spsheet::currency::Currency;
+use spsheet::date::{self, DateSystem};
 
 use std::path::Path;
+#[cfg(any(feature = "ods", feature = "xlsx"))]
+use tempdir::TempDir;
 
 #[cfg(feature = "ods")]
 use spsheet::ods;
@@ -12,6 +24,31 @@ use spsheet::ods;
 #[cfg(feature = "xlsx")]
 use spsheet::xlsx;
 
+#[cfg(feature = "csv")]
+use spsheet::csv::{CsvOptions, Encoding, Header};
+
+#[cfg(any(feature = "ods", feature = "xlsx"))]
+use spsheet::Value;
+#[cfg(any(feature = "ods", feature = "xlsx"))]
+use spsheet::template::{self, Data};
+#[cfg(any(feature = "ods", feature = "xlsx"))]
+use spsheet::rich_text::{Font, TextRun};
+#[cfg(feature = "xlsx")]
+use spsheet::rich_text::{Phonetic, PhoneticRun};
+#[cfg(any(feature = "ods", feature = "xlsx"))]
+use spsheet::comment::Comment;
+#[cfg(any(feature = "ods", feature = "xlsx"))]
+use spsheet::hyperlink::Hyperlink;
+use spsheet::image::{Anchor, Image, Marker};
+use spsheet::chart::{Axis, Chart, ChartType, LegendPosition, Series};
+use spsheet::range::Range;
+use spsheet::defined_name::{self, DefinedName};
+use spsheet::validation::{DataValidation, ErrorStyle, Message, Operator, Rule};
+use spsheet::conditional_format::{self, ConditionalFormat, DifferentialStyle, IconStyle, Threshold};
+use spsheet::sheet_view::{Pane, SheetView};
+use spsheet::auto_filter::{AutoFilter, Comparison, Filter, SortKey};
+use spsheet::table::{Table, TableColumn, TableStyle, Totals, TotalsFunction};
+
 fn make_sheet1() -> Sheet {
     let mut sheet = Sheet::new("シート1");
     sheet.add_cell(Cell::str("a", ""), 0, 0);
@@ -85,6 +122,1999 @@ fn xlsx_test() {
     assert_eq!(book, res);
 }
 
+#[test]
+#[cfg(feature = "ods")]
+fn ods_open_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let mut expected = ods::read(Path::new("./data/test.ods")).unwrap();
+    let mut book = ods::open(Path::new("./data/test.ods")).unwrap();
+    for b in [&mut expected, &mut book] {
+        b.get_sheet_mut(0).add_cell(Cell::str("x", ""), 0, 0);
+        b.get_sheet_mut(0).add_cell(Cell::date("2018-01-02", "YYYY/MM/DD"), 5, 0);
+        b.remove_sheet(3);
+        b.add_sheet(make_sheet2());
+    }
+    ods::write(&book, &dir.path().join("open_test.ods")).unwrap();
+    let res = ods::read(&dir.path().join("open_test.ods")).unwrap();
+    assert_eq!(expected, res);
+
+    let res = ods::open(&dir.path().join("open_test.ods")).unwrap();
+    let package = res.get_package().unwrap();
+    assert_eq!(book.get_package().unwrap().get_part("settings.xml"), package.get_part("settings.xml"));
+
+    // the mimetype is the first entry of the zip and isn't compressed
+    let bytes = std::fs::read(dir.path().join("open_test.ods")).unwrap();
+    assert_eq!(b"mimetype", &bytes[30..38]);
+    assert_eq!([0, 0], bytes[8..10]);
+}
+
+#[test]
+#[cfg(feature = "xlsx")]
+fn xlsx_open_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let mut expected = xlsx::read(Path::new("./data/test.xlsx")).unwrap();
+    let mut book = xlsx::open(Path::new("./data/test.xlsx")).unwrap();
+    for b in [&mut expected, &mut book] {
+        b.get_sheet_mut(0).add_cell(Cell::str("x", ""), 0, 0);
+        b.get_sheet_mut(0).add_cell(Cell::date("2018-01-02", "YYYY/MM/DD"), 5, 0);
+        b.remove_sheet(3);
+        b.add_sheet(make_sheet2());
+    }
+    xlsx::write(&book, &dir.path().join("open_test.xlsx")).unwrap();
+    let res = xlsx::read(&dir.path().join("open_test.xlsx")).unwrap();
+    assert_eq!(expected, res);
+
+    // column widths are kept
+    let res = xlsx::open(&dir.path().join("open_test.xlsx")).unwrap();
+    let sheet_xml = String::from_utf8(res.get_package().unwrap().get_part("xl/worksheets/sheet1.xml").unwrap().clone()).unwrap();
+    assert!(sheet_xml.contains(r#"width="23.75""#));
+    assert_eq!(4, res.get_sheet_size());
+}
+
+#[cfg(feature = "xlsx")]
+fn make_formula_book() -> Book {
+    let mut book = Book::new();
+    let mut sheet = Sheet::new("totals");
+    sheet.add_cell(Cell::float(1.0, ""), 0, 0);
+    sheet.add_cell(Cell::float(2.0, ""), 1, 0);
+    let formulas = vec![
+        (Cell::float(3.0, ""), "SUM(A1:A2)"),
+        (Cell::str("total 3", ""), "\"total \"&A3"),
+        (Cell::new(Value::Bool(true), ""), "A3>2"),
+        (Cell::new(Value::Empty, ""), "A3*2"),
+    ];
+    for (column, (mut cell, formula)) in formulas.into_iter().enumerate() {
+        cell.set_formula(Some(String::from(formula)));
+        sheet.add_cell(cell, 2, column);
+    }
+    book.add_sheet(sheet);
+    book
+}
+
+#[test]
+#[cfg(feature = "xlsx")]
+fn xlsx_formula_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let book = make_formula_book();
+    xlsx::write(&book, &dir.path().join("formula.xlsx")).unwrap();
+    let res = xlsx::read(&dir.path().join("formula.xlsx")).unwrap();
+    assert_eq!(book, res);
+
+    // the formulas of an opened book are written again with their results, a text result isn't a shared string
+    let mut book = xlsx::open(&dir.path().join("formula.xlsx")).unwrap();
+    book.get_sheet_mut(0).add_cell(Cell::float(5.0, ""), 1, 0);
+    xlsx::write(&book, &dir.path().join("formula_open.xlsx")).unwrap();
+    let res = xlsx::open(&dir.path().join("formula_open.xlsx")).unwrap();
+    assert_eq!(Some(&String::from("SUM(A1:A2)")), res.get_sheet(0).get_cell(2, 0).unwrap().get_formula());
+    let package = res.get_package().unwrap();
+    let sheet = String::from_utf8(package.get_part("xl/worksheets/sheet1.xml").unwrap().clone()).unwrap();
+    assert!(sheet.contains("<c r=\"A3\" s=\"0\" t=\"n\"><f>SUM(A1:A2)</f><v>3</v></c>"));
+    assert!(sheet.contains("t=\"str\"><f>&quot;total &quot;&amp;A3</f><v>total 3</v></c>"));
+    assert!(sheet.contains("<c r=\"D3\" s=\"0\"><f>A3*2</f></c>"));
+    let shared_strings = String::from_utf8(package.get_part("xl/sharedStrings.xml").unwrap().clone()).unwrap();
+    assert!(!shared_strings.contains("total 3"));
+    let workbook = String::from_utf8(package.get_part("xl/workbook.xml").unwrap().clone()).unwrap();
+    assert!(workbook.contains("fullCalcOnLoad=\"1\""));
+
+    // a cell of a shared formula has the formula of its first cell moved to it
+    let res = xlsx::read(Path::new("./data/shared_formula.xlsx")).unwrap();
+    let formulas: Vec<Option<&String>> = (0..3).map(|row| res.get_sheet(0).get_cell(row, 1).and_then(|cell| cell.get_formula())).collect();
+    assert_eq!(vec![Some(&String::from("A1*$A$1")), Some(&String::from("A2*$A$1")), Some(&String::from("A3*$A$1"))], formulas);
+}
+
+#[test]
+fn formula_test() {
+    let mut sheet = Sheet::new("totals");
+    let mut cell = Cell::float(0.0, "");
+    cell.set_formula(Some(String::from("SUM(B2:B3)+'other sheet'!B2+totals!$B$3+\"B2\"+LOG10(B3)+Sales[B2]")));
+    sheet.add_cell(cell, 4, 1);
+    let mut cell = Cell::float(0.0, "");
+    cell.set_formula(Some(String::from("B2*$C$2")));
+    sheet.add_cell(cell, 1, 3);
+    let formula = |sheet: &Sheet, row: usize, column: usize| sheet.get_cell(row, column).unwrap().get_formula().unwrap().clone();
+
+    sheet.insert_rows(2, 2);
+    assert_eq!("SUM(B2:B5)+'other sheet'!B2+totals!$B$5+\"B2\"+LOG10(B5)+Sales[B2]", formula(&sheet, 6, 1));
+    sheet.copy_row(1, 3);
+    assert_eq!("B4*$C$2", formula(&sheet, 3, 3));
+    sheet.remove_rows(1, 1);
+    assert_eq!("SUM(B2:B4)+'other sheet'!B2+totals!$B$4+\"B2\"+LOG10(B4)+Sales[B2]", formula(&sheet, 5, 1));
+    assert_eq!("B3*#REF!", formula(&sheet, 2, 3));
+}
+
+#[cfg(any(feature = "ods", feature = "xlsx"))]
+fn make_rich_text_sheet() -> Sheet {
+    let mut bold = Font::new();
+    bold.set_bold(true);
+    bold.set_color(Some(String::from("FF0000")));
+    let mut italic = Font::new();
+    italic.set_italic(true);
+    italic.set_underline(true);
+    italic.set_size(Some(14.0));
+    let mut sheet = Sheet::new("rich");
+    sheet.add_cell(Cell::new(Value::RichText(vec![
+        TextRun::new("赤い", Some(bold.clone())),
+        TextRun::new(" text ", None),
+        TextRun::new("italic", Some(italic)),
+    ]), ""), 0, 0);
+    sheet.add_cell(Cell::new(Value::RichText(vec![
+        TextRun::new("bold", Some(bold)),
+        TextRun::new(" only", None),
+    ]), ""), 1, 0);
+    sheet.add_cell(Cell::str("plain", ""), 1, 1);
+    sheet
+}
+
+#[test]
+#[cfg(feature = "xlsx")]
+fn xlsx_rich_text_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let mut book = Book::new();
+    book.add_sheet(make_rich_text_sheet());
+    xlsx::write(&book, &dir.path().join("rich_text.xlsx")).unwrap();
+    let res = xlsx::read(&dir.path().join("rich_text.xlsx")).unwrap();
+    assert_eq!(book, res);
+
+    let mut res = xlsx::open(Path::new("./data/test.xlsx")).unwrap();
+    res.add_sheet(make_rich_text_sheet());
+    xlsx::write(&res, &dir.path().join("rich_text_open.xlsx")).unwrap();
+    let res = xlsx::read(&dir.path().join("rich_text_open.xlsx")).unwrap();
+    assert_eq!(&make_rich_text_sheet(), res.get_sheet(4));
+}
+
+#[test]
+#[cfg(feature = "xlsx")]
+fn xlsx_phonetic_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let mut phonetic = Phonetic::new();
+    phonetic.add_run(PhoneticRun::new("ヤマダ", 0, 2));
+    phonetic.add_run(PhoneticRun::new("タロウ", 2, 4));
+    phonetic.set_kind(Some(String::from("Hiragana")));
+    phonetic.set_visible(true);
+    let mut name = Cell::str("山田太郎", "");
+    name.set_phonetic(Some(phonetic));
+    let mut sheet = Sheet::new("氏名");
+    sheet.add_cell(name.clone(), 0, 0);
+    // the same text without reading is another shared string
+    sheet.add_cell(Cell::str("山田太郎", ""), 1, 0);
+    sheet.add_cell(Cell::str("次", ""), 2, 0);
+    let mut book = Book::new();
+    book.add_sheet(sheet);
+    xlsx::write(&book, &dir.path().join("phonetic.xlsx")).unwrap();
+    let res = xlsx::read(&dir.path().join("phonetic.xlsx")).unwrap();
+    assert_eq!(book, res);
+    assert_eq!("ヤマダタロウ", res.get_sheet(0).get_cell(0, 0).unwrap().get_phonetic().unwrap().get_text());
+    assert_eq!(Some(&Cell::str("次", "")), res.get_sheet(0).get_cell(2, 0));
+}
+
+#[cfg(any(feature = "ods", feature = "xlsx"))]
+fn make_cell_types_sheet() -> Sheet {
+    let mut sheet = make_rich_text_sheet();
+    sheet.add_cell(Cell::new(Value::Bool(true), ""), 2, 0);
+    sheet.add_cell(Cell::new(Value::Bool(false), ""), 2, 1);
+    sheet.add_cell(Cell::new(Value::Error(String::from("#DIV/0!")), ""), 3, 0);
+    sheet.add_cell(Cell::str(" spaced ", ""), 3, 1);
+    sheet
+}
+
+#[test]
+#[cfg(feature = "xlsx")]
+fn xlsx_inline_strings_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let mut book = Book::new();
+    book.add_sheet(make_cell_types_sheet());
+    let mut options = xlsx::XlsxOptions::new();
+    options.set_inline_strings(true);
+    xlsx::write_with_options(&book, &dir.path().join("inline_strings.xlsx"), &options).unwrap();
+    let res = xlsx::read(&dir.path().join("inline_strings.xlsx")).unwrap();
+    assert_eq!(book, res);
+    let res = xlsx::open(&dir.path().join("inline_strings.xlsx")).unwrap();
+    let package = res.get_package().unwrap();
+    let sheet = String::from_utf8(package.get_part("xl/worksheets/sheet1.xml").unwrap().clone()).unwrap();
+    assert!(sheet.contains("t=\"inlineStr\""));
+    let shared_strings = String::from_utf8(package.get_part("xl/sharedStrings.xml").unwrap().clone()).unwrap();
+    assert!(!shared_strings.contains("<si>"));
+
+    xlsx::write(&book, &dir.path().join("cell_types.xlsx")).unwrap();
+    let res = xlsx::read(&dir.path().join("cell_types.xlsx")).unwrap();
+    assert_eq!(book, res);
+}
+
+#[test]
+#[cfg(feature = "xlsx")]
+fn xlsx_iso_date_test() {
+    use chrono::{NaiveTime, TimeZone, Utc};
+    // the ISO 8601 values of date cells with or without an offset, a time of day and a text which isn't a date
+    let res = xlsx::read(Path::new("./data/iso_dates.xlsx")).unwrap();
+    let expected = vec![
+        Value::Date(Utc.with_ymd_and_hms(2017, 12, 2, 4, 30, 0).unwrap()),
+        Value::Date(Utc.with_ymd_and_hms(2017, 12, 2, 13, 30, 0).unwrap()),
+        Value::Date(Utc.with_ymd_and_hms(2017, 12, 2, 0, 0, 0).unwrap()),
+        Value::Time(NaiveTime::from_hms_opt(13, 30, 0).unwrap()),
+        Value::Str(String::from("not a date")),
+    ];
+    let values: Vec<Value> = (0..5).map(|row| res.get_sheet(0).get_cell(row, 0).unwrap().get_value().clone()).collect();
+    assert_eq!(expected, values);
+
+    // the date cells without a number format are written back as dates
+    let dir = TempDir::new("spsheet").unwrap();
+    let path = dir.path().join("iso_dates.xlsx");
+    xlsx::write(&res, &path).unwrap();
+    let written = xlsx::read(&path).unwrap();
+    let values: Vec<Value> = (0..5).map(|row| written.get_sheet(0).get_cell(row, 0).unwrap().get_value().clone()).collect();
+    assert_eq!(expected, values);
+}
+
+#[test]
+#[cfg(feature = "ods")]
+fn ods_rich_text_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let mut book = Book::new();
+    book.add_sheet(make_cell_types_sheet());
+    ods::write(&book, &dir.path().join("rich_text.ods")).unwrap();
+    let res = ods::read(&dir.path().join("rich_text.ods")).unwrap();
+    assert_eq!(book, res);
+
+    let mut res = ods::open(Path::new("./data/test.ods")).unwrap();
+    res.add_sheet(make_rich_text_sheet());
+    ods::write(&res, &dir.path().join("rich_text_open.ods")).unwrap();
+    let res = ods::read(&dir.path().join("rich_text_open.ods")).unwrap();
+    assert_eq!(&make_rich_text_sheet(), res.get_sheet(4));
+}
+
+#[cfg(any(feature = "ods", feature = "xlsx"))]
+fn make_template() -> Book {
+    let mut sheet = Sheet::new("invoice");
+    sheet.add_cell(Cell::str("{{customer}} 様", ""), 0, 0);
+    sheet.add_cell(Cell::str("{{items.name}}", ""), 1, 0);
+    sheet.add_cell(Cell::str("{{items.price}}", ""), 1, 1);
+    sheet.add_cell(Cell::str("合計", ""), 2, 0);
+    sheet.add_cell(Cell::str("{{total}}", ""), 2, 1);
+    let mut book = Book::new();
+    book.add_sheet(sheet);
+    book
+}
+
+#[cfg(any(feature = "ods", feature = "xlsx"))]
+fn make_template_data(customer: &str) -> Data {
+    let mut data = Data::new();
+    data.set_value("customer", Value::Str(String::from(customer)));
+    data.set_value("total", Value::Float(300.0));
+    for (name, price) in [("りんご", 100.0), ("みかん", 200.0)] {
+        let mut record = Data::new();
+        record.set_value("name", Value::Str(String::from(name)));
+        record.set_value("price", Value::Float(price));
+        data.add_record("items", record);
+    }
+    data
+}
+
+#[cfg(any(feature = "ods", feature = "xlsx"))]
+fn make_filled_sheet(name: &str, customer: &str) -> Sheet {
+    let mut sheet = Sheet::new(name);
+    sheet.add_cell(Cell::str(format!("{} 様", customer).as_str(), ""), 0, 0);
+    sheet.add_cell(Cell::str("りんご", ""), 1, 0);
+    sheet.add_cell(Cell::float(100.0, ""), 1, 1);
+    sheet.add_cell(Cell::str("みかん", ""), 2, 0);
+    sheet.add_cell(Cell::float(200.0, ""), 2, 1);
+    sheet.add_cell(Cell::str("合計", ""), 3, 0);
+    sheet.add_cell(Cell::float(300.0, ""), 3, 1);
+    sheet
+}
+
+#[test]
+#[cfg(feature = "xlsx")]
+fn xlsx_template_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    xlsx::write(&make_template(), &dir.path().join("template.xlsx")).unwrap();
+    template::fill_xlsx(&dir.path().join("template.xlsx"), &dir.path().join("filled.xlsx"), &make_template_data("田中")).unwrap();
+    let res = xlsx::read(&dir.path().join("filled.xlsx")).unwrap();
+    assert_eq!(&make_filled_sheet("invoice", "田中"), res.get_sheet(0));
+
+    let mut book = xlsx::open(&dir.path().join("template.xlsx")).unwrap();
+    template::fill_sheets(&mut book, 0, vec![
+        (String::from("田中"), make_template_data("田中")),
+        (String::from("鈴木"), make_template_data("鈴木")),
+    ]).unwrap();
+    xlsx::write(&book, &dir.path().join("filled.xlsx")).unwrap();
+    let res = xlsx::read(&dir.path().join("filled.xlsx")).unwrap();
+    assert_eq!(2, res.get_sheet_size());
+    assert_eq!(&make_filled_sheet("鈴木", "鈴木"), res.get_sheet(1));
+}
+
+#[test]
+#[cfg(feature = "ods")]
+fn ods_template_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    ods::write(&make_template(), &dir.path().join("template.ods")).unwrap();
+    template::fill_ods(&dir.path().join("template.ods"), &dir.path().join("filled.ods"), &make_template_data("田中")).unwrap();
+    let res = ods::read(&dir.path().join("filled.ods")).unwrap();
+    assert_eq!(&make_filled_sheet("invoice", "田中"), res.get_sheet(0));
+
+    let mut book = ods::open(&dir.path().join("template.ods")).unwrap();
+    match template::fill_book(&mut book, &Data::new()) {
+        Err(template::TemplateError::Missing(key, reference)) => {
+            assert_eq!("customer", key);
+            assert_eq!("A1", reference);
+        },
+        _ => panic!("missing value is not an error"),
+    }
+}
+
+#[test]
+#[cfg(any(feature = "ods", feature = "xlsx"))]
+fn template_formula_test() {
+    let mut sheet = Sheet::new("invoice");
+    sheet.add_cell(Cell::str("{{items.name}}", ""), 1, 0);
+    sheet.add_cell(Cell::str("{{items.price}}", ""), 1, 1);
+    let mut cell = Cell::float(0.0, "");
+    cell.set_formula(Some(String::from("B2*1.1")));
+    sheet.add_cell(cell, 1, 2);
+    let mut cell = Cell::float(0.0, "");
+    cell.set_formula(Some(String::from("SUM(B2:B2)")));
+    sheet.add_cell(cell, 2, 1);
+    sheet.add_cell(Cell::str("{{total}}", ""), 2, 2);
+
+    let mut data = Data::new();
+    data.set_value("total", Value::Float(300.0));
+    for name in ["{{total}}", "みかん", "ぶどう"] {
+        let mut record = Data::new();
+        record.set_value("name", Value::Str(String::from(name)));
+        record.set_value("price", Value::Float(100.0));
+        data.add_record("items", record);
+    }
+    template::fill_sheet(&mut sheet, &data).unwrap();
+
+    // a record value like a placeholder isn't filled again
+    assert_eq!(Some(&Cell::str("{{total}}", "")), sheet.get_cell(1, 0));
+    let formula = |row: usize, column: usize| sheet.get_cell(row, column).unwrap().get_formula().unwrap().clone();
+    assert_eq!("B3*1.1", formula(2, 2));
+    assert_eq!("B4*1.1", formula(3, 2));
+    assert_eq!("SUM(B2:B4)", formula(4, 1));
+    assert_eq!(&Value::Float(300.0), sheet.get_cell(4, 2).unwrap().get_value());
+}
+
+#[test]
+#[cfg(feature = "csv")]
+fn csv_test() {
+    let mut sheet = Sheet::new("Sheet1");
+    sheet.add_cell(Cell::str("名前", ""), 0, 0);
+    sheet.add_cell(Cell::str("日付", ""), 0, 1);
+    sheet.add_cell(Cell::str("007", ""), 1, 0);
+    sheet.add_cell(Cell::date("2017-12-02", "YYYY/MM/DD"), 1, 1);
+    sheet.add_cell(Cell::float(1.5, ""), 2, 0);
+    sheet.add_cell(Cell::date("2017-12-02T13:30:00", "YYYY/MM/DD\\ HH:MM:SS"), 2, 1);
+
+    let mut options = CsvOptions::new();
+    options.set_header(Header::Keep);
+    for encoding in [Encoding::Utf8, Encoding::Utf8Bom, Encoding::ShiftJis] {
+        options.set_encoding(encoding);
+        let mut out = Vec::new();
+        sheet.to_csv(&mut out, &options).unwrap();
+        let res = Sheet::from_csv(out.as_slice(), &options).unwrap();
+        assert_eq!(sheet, res);
+    }
+
+    let mut options = CsvOptions::tsv();
+    options.set_header(Header::Skip);
+    let res = Sheet::from_csv("a\tb\n1\t\"x\ty\"\n".as_bytes(), &options).unwrap();
+    assert_eq!(Some(&Cell::float(1.0, "")), res.get_cell(0, 0));
+    assert_eq!(Some(&Cell::str("x\ty", "")), res.get_cell(0, 1));
+
+    // the skipped header isn't in the sheet, every row of it is written back
+    let mut options = CsvOptions::new();
+    options.set_header(Header::Skip);
+    let res = Sheet::from_csv("h1,h2\na,1\nb,2\n".as_bytes(), &options).unwrap();
+    let mut out = Vec::new();
+    res.to_csv(&mut out, &options).unwrap();
+    assert_eq!("a,1\nb,2\n", String::from_utf8(out).unwrap());
+}
+
+#[cfg(feature = "serde")]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Rank {
+    Gold,
+    Silver,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Member {
+    name: String,
+    age: u32,
+    #[serde(with = "spsheet::date::naive_date")]
+    joined: chrono::NaiveDate,
+    rank: Rank,
+    memo: Option<String>,
+    active: bool,
+    #[serde(with = "spsheet::date::datetime")]
+    updated: chrono::DateTime<chrono::Utc>,
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn serde_test() {
+    use spsheet::Value;
+    let members = vec![
+        Member {
+            name: String::from("青柳"),
+            age: 40,
+            joined: chrono::NaiveDate::from_ymd_opt(2017, 12, 2).unwrap(),
+            rank: Rank::Gold,
+            memo: None,
+            active: true,
+            updated: chrono::DateTime::parse_from_rfc3339("2017-12-02T13:30:00+09:00").unwrap().with_timezone(&chrono::Utc),
+        },
+        Member {
+            name: String::from("aoyagi"),
+            age: 20,
+            joined: chrono::NaiveDate::from_ymd_opt(2018, 1, 31).unwrap(),
+            rank: Rank::Silver,
+            memo: Some(String::from("new")),
+            active: false,
+            updated: chrono::DateTime::parse_from_rfc3339("2018-01-31T09:00:00Z").unwrap().with_timezone(&chrono::Utc),
+        },
+    ];
+    let mut sheet = spsheet::ser::to_sheet("members", &members).unwrap();
+    assert_eq!(Some(&Cell::str("joined", "")), sheet.get_cell(0, 2));
+    assert_eq!(&Value::DateOnly(chrono::NaiveDate::from_ymd_opt(2017, 12, 2).unwrap()), sheet.get_cell(1, 2).unwrap().get_value());
+    assert_eq!(&Value::Bool(true), sheet.get_cell(1, 5).unwrap().get_value());
+    assert_eq!(&Value::Date(members[0].updated), sheet.get_cell(1, 6).unwrap().get_value());
+    let res: Vec<Member> = spsheet::de::from_sheet(&sheet).unwrap();
+    assert_eq!(members, res);
+
+    // date cells are mapped to chrono fields as well
+    sheet.add_cell(Cell::date("2017-12-03", "YYYY/MM/DD"), 1, 2);
+    let res: Vec<Member> = spsheet::de::from_sheet(&sheet).unwrap();
+    assert_eq!(chrono::NaiveDate::from_ymd_opt(2017, 12, 3).unwrap(), res[0].joined);
+
+    sheet.add_cell(Cell::str("forty", ""), 1, 1);
+    let err = spsheet::de::from_sheet::<Member>(&sheet).unwrap_err();
+    assert_eq!(Some(&String::from("B2")), err.get_reference());
+
+    // a text like a date is a string without the date helper
+    let mut row = std::collections::BTreeMap::new();
+    row.insert("memo", "2017-12-02T13:30:00+09:00");
+    let rows = spsheet::ser::to_sheet("rows", &[row]).unwrap();
+    assert_eq!(Some(&Cell::str("2017-12-02T13:30:00+09:00", "")), rows.get_cell(1, 0));
+
+    // a row which fails adds no cells and no header
+    let mut serializer = spsheet::ser::Serializer::new("rows");
+    let mut row = std::collections::BTreeMap::new();
+    row.insert("a", vec![1]);
+    assert!(serializer.serialize_row(&row).is_err());
+    let mut row = std::collections::BTreeMap::new();
+    row.insert("b", 2);
+    serializer.serialize_row(&row).unwrap();
+    let rows = serializer.into_sheet();
+    assert_eq!(Some(&Cell::str("b", "")), rows.get_cell(0, 0));
+    assert_eq!(Some(&Cell::float(2.0, "")), rows.get_cell(1, 0));
+    assert_eq!(2, rows.get_rows().len());
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn serde_model_test() {
+    use spsheet::Value;
+    let book = make_book();
+    let json = serde_json::to_string(&book).unwrap();
+    let res: Book = serde_json::from_str(&json).unwrap();
+    assert_eq!(book, res);
+    assert_eq!(json, serde_json::to_string(&res).unwrap());
+
+    let mut sheet = Sheet::new("s");
+    sheet.add_cell(Cell::date("2017-12-02", "YYYY/MM/DD"), 1, 0);
+    sheet.add_cell(Cell::float(1.5, ""), 0, 1);
+    sheet.add_cell(Cell::str("a", ""), 0, 0);
+    assert_eq!(
+        r#"{"name":"s","cells":[{"row":0,"column":0,"value":{"type":"str","value":"a"},"format":""},{"row":0,"column":1,"value":{"type":"float","value":1.5},"format":""},{"row":1,"column":0,"value":{"type":"date","value":"2017-12-02T00:00:00Z"},"format":"YYYY/MM/DD"}]}"#,
+        serde_json::to_string(&sheet).unwrap());
+
+    // a duration is an ISO 8601 text
+    let cell = Cell::new(Value::Duration(chrono::Duration::minutes(90)), "[h]:mm");
+    let json = serde_json::to_string(&cell).unwrap();
+    assert_eq!(r#"{"value":{"type":"duration","value":"PT01H30M00S"},"format":"[h]:mm"}"#, json);
+    assert_eq!(cell, serde_json::from_str::<Cell>(&json).unwrap());
+}
+
+#[test]
+fn date_serial_test() {
+    use chrono::NaiveDate;
+    let cases = vec![
+        (-2.0, NaiveDate::from_ymd_opt(1899, 12, 29).unwrap().and_hms_opt(0, 0, 0).unwrap()),
+        (-1.0, NaiveDate::from_ymd_opt(1899, 12, 30).unwrap().and_hms_opt(0, 0, 0).unwrap()),
+        (-0.75, NaiveDate::from_ymd_opt(1899, 12, 30).unwrap().and_hms_opt(6, 0, 0).unwrap()),
+        (0.0, NaiveDate::from_ymd_opt(1899, 12, 31).unwrap().and_hms_opt(0, 0, 0).unwrap()),
+        (1.0, NaiveDate::from_ymd_opt(1900, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap()),
+        (59.5, NaiveDate::from_ymd_opt(1900, 2, 28).unwrap().and_hms_opt(12, 0, 0).unwrap()),
+        (61.0, NaiveDate::from_ymd_opt(1900, 3, 1).unwrap().and_hms_opt(0, 0, 0).unwrap()),
+        (43071.5625, NaiveDate::from_ymd_opt(2017, 12, 2).unwrap().and_hms_opt(13, 30, 0).unwrap()),
+        (43071.0 + 1.0 / 86_400_000.0, NaiveDate::from_ymd_opt(2017, 12, 2).unwrap().and_hms_milli_opt(0, 0, 0, 1).unwrap()),
+    ];
+    for (serial, dt) in cases {
+        assert_eq!(Some(dt), date::serial_to_datetime(serial, DateSystem::Date1900));
+        assert_eq!(serial, date::datetime_to_serial(&dt, DateSystem::Date1900));
+    }
+    // the leap day of 1900 which doesn't exist
+    assert_eq!(Some(NaiveDate::from_ymd_opt(1900, 3, 1).unwrap().and_hms_opt(0, 0, 0).unwrap()), date::serial_to_datetime(60.0, DateSystem::Date1900));
+    // a serial a little less than a day is rounded to the next day
+    assert_eq!(Some(NaiveDate::from_ymd_opt(2017, 12, 3).unwrap().and_hms_opt(0, 0, 0).unwrap()), date::serial_to_datetime(43071.9999999999, DateSystem::Date1900));
+    assert_eq!(Some(NaiveDate::from_ymd_opt(1904, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap()), date::serial_to_datetime(0.0, DateSystem::Date1904));
+    assert_eq!(None, date::serial_to_datetime(f64::NAN, DateSystem::Date1900));
+}
+
+#[test]
+#[cfg(feature = "xlsx")]
+fn xlsx_date1904_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let mut book = make_book();
+    book.set_date_system(DateSystem::Date1904);
+    xlsx::write(&book, &dir.path().join("date1904.xlsx")).unwrap();
+    let res = xlsx::read(&dir.path().join("date1904.xlsx")).unwrap();
+    assert_eq!(book, res);
+
+    // the date system of an opened book is kept
+    let mut res = xlsx::open(&dir.path().join("date1904.xlsx")).unwrap();
+    res.get_sheet_mut(0).add_cell(Cell::date("1904-01-02", "YYYY/MM/DD"), 5, 0);
+    xlsx::write(&res, &dir.path().join("date1904_open.xlsx")).unwrap();
+    let res = xlsx::read(&dir.path().join("date1904_open.xlsx")).unwrap();
+    assert_eq!(DateSystem::Date1904, res.get_date_system());
+    assert_eq!(Some(&Cell::date("1904-01-02", "YYYY/MM/DD")), res.get_sheet(0).get_cell(5, 0));
+}
+
+#[cfg(any(feature = "ods", feature = "xlsx"))]
+fn make_time_sheet() -> Sheet {
+    use chrono::{Duration, NaiveDate, NaiveTime};
+    let mut sheet = Sheet::new("timesheet");
+    sheet.add_cell(Cell::new(Value::DateOnly(NaiveDate::from_ymd_opt(2017, 12, 2).unwrap()), "YYYY/MM/DD"), 0, 0);
+    sheet.add_cell(Cell::new(Value::Time(NaiveTime::from_hms_opt(9, 30, 0).unwrap()), "HH:MM"), 0, 1);
+    sheet.add_cell(Cell::new(Value::Time(NaiveTime::from_hms_opt(18, 15, 30).unwrap()), "HH:MM:SS"), 0, 2);
+    sheet.add_cell(Cell::new(Value::Duration(Duration::hours(37) + Duration::minutes(30)), "[HH]:MM"), 0, 3);
+    sheet
+}
+
+#[test]
+#[cfg(feature = "xlsx")]
+fn xlsx_time_test() {
+    use chrono::{Duration, NaiveTime};
+    let dir = TempDir::new("spsheet").unwrap();
+    let mut book = Book::new();
+    book.add_sheet(make_time_sheet());
+    xlsx::write(&book, &dir.path().join("time.xlsx")).unwrap();
+    let res = xlsx::read(&dir.path().join("time.xlsx")).unwrap();
+    let sheet = res.get_sheet(0);
+    // xlsx has no date only value, it is a date at midnight showing the same text
+    assert_eq!(Some(&Cell::date("2017-12-02", "YYYY/MM/DD")), sheet.get_cell(0, 0));
+    assert_eq!(make_time_sheet().get_cell(0, 0).unwrap().get_formated_value(), sheet.get_cell(0, 0).unwrap().get_formated_value());
+    assert_eq!(Some(&Cell::new(Value::Time(NaiveTime::from_hms_opt(9, 30, 0).unwrap()), "HH:MM")), sheet.get_cell(0, 1));
+    assert_eq!(Some(&Cell::new(Value::Time(NaiveTime::from_hms_opt(18, 15, 30).unwrap()), "HH:MM:SS")), sheet.get_cell(0, 2));
+    let duration = sheet.get_cell(0, 3).unwrap();
+    assert_eq!(&Value::Duration(Duration::hours(37) + Duration::minutes(30)), duration.get_value());
+    assert_eq!(Some(String::from("37:30")), duration.get_formated_value());
+}
+
+#[test]
+#[cfg(feature = "ods")]
+fn ods_time_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let mut book = Book::new();
+    book.add_sheet(make_time_sheet());
+    ods::write(&book, &dir.path().join("time.ods")).unwrap();
+    let res = ods::read(&dir.path().join("time.ods")).unwrap();
+    assert_eq!(book, res);
+
+    let mut res = ods::open(Path::new("./data/test.ods")).unwrap();
+    res.add_sheet(make_time_sheet());
+    ods::write(&res, &dir.path().join("time_open.ods")).unwrap();
+    let res = ods::read(&dir.path().join("time_open.ods")).unwrap();
+    assert_eq!(&make_time_sheet(), res.get_sheet(4));
+}
+
+#[cfg(any(feature = "ods", feature = "xlsx"))]
+fn make_timezone_book() -> Book {
+    use chrono::{FixedOffset, NaiveDate, TimeZone};
+    let jst = FixedOffset::east_opt(9 * 3600).unwrap();
+    let mut sheet = Sheet::new("jst");
+    sheet.add_cell(Cell::datetime(jst.with_ymd_and_hms(2017, 12, 2, 13, 30, 0).unwrap(), r"YYYY/MM/DD\ HH:MM"), 0, 0);
+    sheet.add_cell(Cell::naive_datetime(NaiveDate::from_ymd_opt(2017, 12, 3).unwrap().and_hms_opt(0, 15, 0).unwrap(), &jst, r"YYYY/MM/DD\ HH:MM").unwrap(), 1, 0);
+    let mut book = Book::new();
+    book.set_timezone(jst);
+    book.add_sheet(sheet);
+    book
+}
+
+#[test]
+#[cfg(feature = "xlsx")]
+fn xlsx_timezone_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let book = make_timezone_book();
+    xlsx::write(&book, &dir.path().join("timezone.xlsx")).unwrap();
+    // the file has the wall-clock times
+    let mut res = xlsx::read(&dir.path().join("timezone.xlsx")).unwrap();
+    assert_eq!(Some(&Cell::date("2017-12-02T13:30:00", r"YYYY/MM/DD\ HH:MM")), res.get_sheet(0).get_cell(0, 0));
+    res.localize(book.get_timezone());
+    assert_eq!(book, res);
+}
+
+#[test]
+#[cfg(feature = "ods")]
+fn ods_timezone_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let book = make_timezone_book();
+    ods::write(&book, &dir.path().join("timezone.ods")).unwrap();
+    let mut res = ods::read(&dir.path().join("timezone.ods")).unwrap();
+    assert_eq!(Some(&Cell::date("2017-12-03T00:15:00", r"YYYY/MM/DD\ HH:MM")), res.get_sheet(0).get_cell(1, 0));
+    res.localize(book.get_timezone());
+    assert_eq!(book, res);
+}
+
+#[cfg(any(feature = "ods", feature = "xlsx"))]
+fn make_currency_sheet() -> Sheet {
+    let mut sheet = Sheet::new("currency");
+    sheet.add_cell(Cell::currency(Currency::new(1500.0, "JPY"), ""), 0, 0);
+    sheet.add_cell(Cell::currency(Currency::new(12.5, "USD"), ""), 1, 0);
+    sheet.add_cell(Cell::currency(Currency::new(-3.25, "EUR"), "#,##0.00\\ [$€-407]"), 2, 0);
+    sheet
+}
+
+#[test]
+#[cfg(feature = "xlsx")]
+fn xlsx_currency_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let mut book = Book::new();
+    book.add_sheet(make_currency_sheet());
+    xlsx::write(&book, &dir.path().join("currency.xlsx")).unwrap();
+    let res = xlsx::read(&dir.path().join("currency.xlsx")).unwrap();
+    assert_eq!(book, res);
+    let sheet = res.get_sheet(0);
+    assert_eq!("[$¥-411]#,##0", sheet.get_cell(0, 0).unwrap().get_format().get_content());
+    assert_eq!(Some(String::from("$12.50")), sheet.get_cell(1, 0).unwrap().get_formated_value());
+    assert_eq!(Some(String::from("-3.25 €")), sheet.get_cell(2, 0).unwrap().get_formated_value());
+}
+
+#[test]
+#[cfg(feature = "ods")]
+fn ods_currency_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let mut book = Book::new();
+    book.add_sheet(make_currency_sheet());
+    ods::write(&book, &dir.path().join("currency.ods")).unwrap();
+    let res = ods::read(&dir.path().join("currency.ods")).unwrap();
+    assert_eq!(book, res);
+
+    let mut res = ods::open(Path::new("./data/test.ods")).unwrap();
+    res.add_sheet(make_currency_sheet());
+    ods::write(&res, &dir.path().join("currency_open.ods")).unwrap();
+    let res = ods::read(&dir.path().join("currency_open.ods")).unwrap();
+    assert_eq!(&make_currency_sheet(), res.get_sheet(4));
+}
+
+#[cfg(any(feature = "ods", feature = "xlsx"))]
+fn make_number_sheet() -> Sheet {
+    let mut sheet = Sheet::new("number");
+    sheet.add_cell(Cell::float(1234.5, "#,##0.00"), 0, 0);
+    sheet.add_cell(Cell::float(0.125, "0.0%"), 1, 0);
+    sheet.add_cell(Cell::float(12345.0, "0.00E+00"), 2, 0);
+    sheet.add_cell(Cell::float(1.25, "# ?/?"), 3, 0);
+    sheet.add_cell(Cell::float(-1500.0, "#,##0;[Red]\\-#,##0"), 4, 0);
+    sheet.add_cell(Cell::float(0.0, "0.00;[Red]\\-0.00;\"zero\""), 5, 0);
+    sheet.add_cell(Cell::float(3.0, ""), 6, 0);
+    sheet
+}
+
+#[cfg(any(feature = "ods", feature = "xlsx"))]
+fn assert_number_sheet(sheet: &Sheet) {
+    assert_eq!(&make_number_sheet(), sheet);
+    let values: Vec<Option<String>> = (0..7).map(|row| sheet.get_cell(row, 0).unwrap().get_formated_value()).collect();
+    assert_eq!(vec![
+        Some(String::from("1,234.50")),
+        Some(String::from("12.5%")),
+        Some(String::from("1.23E+04")),
+        Some(String::from("1 1/4")),
+        Some(String::from("-1,500")),
+        Some(String::from("zero")),
+        None,
+    ], values);
+}
+
+#[test]
+#[cfg(feature = "xlsx")]
+fn xlsx_number_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let mut book = Book::new();
+    book.add_sheet(make_number_sheet());
+    xlsx::write(&book, &dir.path().join("number.xlsx")).unwrap();
+    let res = xlsx::read(&dir.path().join("number.xlsx")).unwrap();
+    assert_number_sheet(res.get_sheet(0));
+}
+
+#[test]
+#[cfg(feature = "ods")]
+fn ods_number_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let mut book = Book::new();
+    book.add_sheet(make_number_sheet());
+    ods::write(&book, &dir.path().join("number.ods")).unwrap();
+    let res = ods::read(&dir.path().join("number.ods")).unwrap();
+    assert_number_sheet(res.get_sheet(0));
+
+    let mut res = ods::open(Path::new("./data/test.ods")).unwrap();
+    res.add_sheet(make_number_sheet());
+    ods::write(&res, &dir.path().join("number_open.ods")).unwrap();
+    let res = ods::read(&dir.path().join("number_open.ods")).unwrap();
+    assert_number_sheet(res.get_sheet(4));
+}
+
+#[test]
+#[cfg(feature = "ods")]
+fn ods_repeated_rows_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let mut sheet = Sheet::new("rows");
+    sheet.add_cell(Cell::str("name", ""), 0, 0);
+    sheet.add_cell(Cell::str("value", ""), 0, 1);
+    for row in 4..6 {
+        sheet.add_cell(Cell::str("same", ""), row, 0);
+        sheet.add_cell(Cell::float(7.0, ""), row, 1);
+        sheet.add_cell(Cell::float(7.0, ""), row, 2);
+    }
+    sheet.add_cell(Cell::str("merged", ""), 6, 0);
+    sheet.add_cell(Cell::float(1.0, ""), 6, 2);
+
+    let res = ods::read(Path::new("./data/rows.ods")).unwrap();
+    assert_eq!(&sheet, res.get_sheet(0));
+
+    let res = ods::open(Path::new("./data/rows.ods")).unwrap();
+    ods::write(&res, &dir.path().join("rows_open.ods")).unwrap();
+    let res = ods::read(&dir.path().join("rows_open.ods")).unwrap();
+    assert_eq!(&sheet, res.get_sheet(0));
+}
+
+#[test]
+#[cfg(feature = "ods")]
+fn ods_paragraph_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let mut bold = Font::new();
+    bold.set_bold(true);
+    let mut sheet = Sheet::new("text");
+    sheet.add_cell(Cell::str("first\nsecond", ""), 0, 0);
+    sheet.add_cell(Cell::str("a b c   d\te\nf", ""), 1, 0);
+    sheet.add_cell(Cell::new(Value::RichText(vec![
+        TextRun::new("x", None),
+        TextRun::new("bold still  bold", Some(bold.clone())),
+        TextRun::new(" plain\n", None),
+    ]), ""), 2, 0);
+    let res = ods::read(Path::new("./data/text.ods")).unwrap();
+    assert_eq!(&sheet, res.get_sheet(0));
+
+    sheet.add_cell(Cell::str("  indented\t tab  \n\nlast ", ""), 3, 0);
+    sheet.add_cell(Cell::new(Value::RichText(vec![
+        TextRun::new("line ", Some(bold)),
+        TextRun::new(" one\n two", None),
+    ]), ""), 4, 0);
+    let mut book = Book::new();
+    book.add_sheet(sheet);
+    ods::write(&book, &dir.path().join("paragraph.ods")).unwrap();
+    let res = ods::read(&dir.path().join("paragraph.ods")).unwrap();
+    assert_eq!(book, res);
+    let res = ods::open(&dir.path().join("paragraph.ods")).unwrap();
+    let content = String::from_utf8(res.get_package().unwrap().get_part("content.xml").unwrap().clone()).unwrap();
+    assert!(content.contains("<text:p>first</text:p><text:p>second</text:p>"));
+    assert!(content.contains("<text:tab/>"));
+}
+
+#[cfg(any(feature = "ods", feature = "xlsx"))]
+fn make_comment_sheet() -> Sheet {
+    let mut sheet = Sheet::new("comments");
+    let mut cell = Cell::float(-1.0, "");
+    cell.set_comment(Some(Comment::new("Reviewer", "must be positive")));
+    sheet.add_cell(cell, 0, 0);
+    let mut comment = Comment::new("Checker", "first line\n  second line");
+    comment.set_size(Some(144.0), Some(72.0));
+    comment.set_visible(true);
+    let mut cell = Cell::new(Value::Empty, "");
+    cell.set_comment(Some(comment));
+    sheet.add_cell(cell, 2, 3);
+    sheet.add_cell(Cell::str("no comment", ""), 3, 0);
+    sheet
+}
+
+#[cfg(any(feature = "ods", feature = "xlsx"))]
+fn collect_comments(sheet: &Sheet) -> Vec<(usize, usize, Comment)> {
+    let mut comments = Vec::new();
+    sheet.sorted_access(|row, column, cell| {
+        if let Some(comment) = cell.get_comment() {
+            comments.push((row, column, comment.clone()));
+        }
+    });
+    comments
+}
+
+#[test]
+#[cfg(feature = "xlsx")]
+fn xlsx_comment_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let mut book = Book::new();
+    book.add_sheet(make_sheet1());
+    book.add_sheet(make_comment_sheet());
+    xlsx::write(&book, &dir.path().join("comment.xlsx")).unwrap();
+    let res = xlsx::read(&dir.path().join("comment.xlsx")).unwrap();
+    assert_eq!(book, res);
+
+    // the comments replace the ones of the original file
+    let mut book = xlsx::open(&dir.path().join("comment.xlsx")).unwrap();
+    let mut cell = Cell::str("changed", "");
+    cell.set_comment(Some(Comment::new("Reviewer", "another")));
+    book.get_sheet_mut(1).add_cell(cell.clone(), 5, 0);
+    book.get_sheet_mut(1).add_cell(Cell::new(Value::Empty, ""), 2, 3);
+    book.get_sheet_mut(0).add_cell(cell, 0, 0);
+    xlsx::write(&book, &dir.path().join("comment_open.xlsx")).unwrap();
+    let res = xlsx::open(&dir.path().join("comment_open.xlsx")).unwrap();
+    for index in 0..2 {
+        assert_eq!(collect_comments(book.get_sheet(index)), collect_comments(res.get_sheet(index)));
+    }
+    let package = res.get_package().unwrap();
+    assert!(package.get_part("xl/comments3.xml").is_none());
+    assert!(package.get_part("xl/drawings/vmlDrawing3.vml").is_none());
+    let content_types = String::from_utf8(package.get_part("[Content_Types].xml").unwrap().clone()).unwrap();
+    assert_eq!(1, content_types.matches("<Default Extension=\"rels\" ContentType=\"application/vnd.openxmlformats-package.relationships+xml\"/>").count());
+    assert_eq!(1, content_types.matches("<Default Extension=\"xml\" ContentType=\"application/xml\"/>").count());
+
+    // the defaults are added to the content types of an original file without them
+    let mut book = xlsx::open(Path::new("./data/test.xlsx")).unwrap();
+    let mut cell = Cell::str("x", "");
+    cell.set_comment(Some(Comment::new("Reviewer", "check")));
+    book.get_sheet_mut(0).add_cell(cell, 0, 0);
+    xlsx::write(&book, &dir.path().join("comment_open.xlsx")).unwrap();
+    let res = xlsx::open(&dir.path().join("comment_open.xlsx")).unwrap();
+    let content_types = String::from_utf8(res.get_package().unwrap().get_part("[Content_Types].xml").unwrap().clone()).unwrap();
+    assert!(content_types.contains("<Default Extension=\"rels\" ContentType=\"application/vnd.openxmlformats-package.relationships+xml\"/>"));
+    assert!(content_types.contains("<Default Extension=\"xml\" ContentType=\"application/xml\"/>"));
+}
+
+#[test]
+#[cfg(feature = "xlsx")]
+fn xlsx_threaded_comment_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let book = xlsx::read(Path::new("./data/comments.xlsx")).unwrap();
+    let sheet = book.get_sheet(0);
+    let mut note = Comment::new("Author A", "Check the total\nagain");
+    note.set_size(Some(144.0), Some(72.0));
+    note.set_visible(true);
+    assert_eq!(Some(&note), sheet.get_cell(1, 1).and_then(|cell| cell.get_comment()));
+    let thread = Comment::new("Reviewer", "Is this right?\nYes");
+    assert_eq!(Some(&thread), sheet.get_cell(2, 2).and_then(|cell| cell.get_comment()));
+
+    // the threaded comments are written as notes
+    let book = xlsx::open(Path::new("./data/comments.xlsx")).unwrap();
+    xlsx::write(&book, &dir.path().join("threaded_comment.xlsx")).unwrap();
+    let res = xlsx::open(&dir.path().join("threaded_comment.xlsx")).unwrap();
+    assert_eq!(Some(&thread), res.get_sheet(0).get_cell(2, 2).and_then(|cell| cell.get_comment()));
+    assert!(res.get_package().unwrap().get_part("xl/threadedComments/threadedComment1.xml").is_none());
+}
+
+#[test]
+#[cfg(feature = "ods")]
+fn ods_comment_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let mut book = Book::new();
+    book.add_sheet(make_sheet1());
+    book.add_sheet(make_comment_sheet());
+    ods::write(&book, &dir.path().join("comment.ods")).unwrap();
+    let res = ods::read(&dir.path().join("comment.ods")).unwrap();
+    assert_eq!(book, res);
+
+    let mut book = ods::open(&dir.path().join("comment.ods")).unwrap();
+    book.get_sheet_mut(1).add_cell(Cell::new(Value::Empty, ""), 2, 3);
+    let mut cell = Cell::str("changed", "");
+    cell.set_comment(Some(Comment::new("Reviewer", "another")));
+    book.get_sheet_mut(0).add_cell(cell, 0, 0);
+    ods::write(&book, &dir.path().join("comment_open.ods")).unwrap();
+    let res = ods::open(&dir.path().join("comment_open.ods")).unwrap();
+    for index in 0..2 {
+        assert_eq!(collect_comments(book.get_sheet(index)), collect_comments(res.get_sheet(index)));
+    }
+}
+
+#[cfg(any(feature = "ods", feature = "xlsx"))]
+fn make_hyperlink_book() -> Book {
+    let mut index = Sheet::new("index");
+    let mut cell = Cell::str("web", "");
+    cell.set_hyperlink(Some(Hyperlink::new("https://example.com/report?id=1&page=2")));
+    index.add_cell(cell, 0, 0);
+    let mut link = Hyperlink::new("mailto:info@example.com");
+    link.set_tooltip(Some(String::from("write to us")));
+    let mut cell = Cell::str("mail", "");
+    cell.set_hyperlink(Some(link));
+    index.add_cell(cell, 1, 0);
+    let mut link = Hyperlink::new("#detail!B3");
+    link.set_tooltip(Some(String::from("the total & more")));
+    let mut cell = Cell::float(10.0, "");
+    cell.set_hyperlink(Some(link));
+    index.add_cell(cell, 2, 1);
+    let mut cell = Cell::new(Value::Empty, "");
+    cell.set_hyperlink(Some(Hyperlink::new("#detail!A1")));
+    index.add_cell(cell, 3, 0);
+    let mut detail = Sheet::new("detail");
+    detail.add_cell(Cell::float(10.0, ""), 2, 1);
+    let mut book = Book::new();
+    book.add_sheet(index);
+    book.add_sheet(detail);
+    book
+}
+
+#[cfg(any(feature = "ods", feature = "xlsx"))]
+fn collect_hyperlinks(sheet: &Sheet) -> Vec<(usize, usize, Hyperlink)> {
+    let mut hyperlinks = Vec::new();
+    sheet.sorted_access(|row, column, cell| {
+        if let Some(hyperlink) = cell.get_hyperlink() {
+            hyperlinks.push((row, column, hyperlink.clone()));
+        }
+    });
+    hyperlinks
+}
+
+#[test]
+#[cfg(feature = "xlsx")]
+fn xlsx_hyperlink_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let book = make_hyperlink_book();
+    xlsx::write(&book, &dir.path().join("hyperlink.xlsx")).unwrap();
+    let res = xlsx::read(&dir.path().join("hyperlink.xlsx")).unwrap();
+    assert_eq!(book, res);
+
+    // the links replace the ones of the original file
+    let mut book = xlsx::open(&dir.path().join("hyperlink.xlsx")).unwrap();
+    let mut cell = Cell::str("changed", "");
+    cell.set_hyperlink(Some(Hyperlink::new("https://example.com/changed")));
+    book.get_sheet_mut(0).add_cell(cell, 0, 0);
+    book.get_sheet_mut(0).add_cell(Cell::str("mail", ""), 1, 0);
+    xlsx::write(&book, &dir.path().join("hyperlink_open.xlsx")).unwrap();
+    let res = xlsx::open(&dir.path().join("hyperlink_open.xlsx")).unwrap();
+    assert_eq!(collect_hyperlinks(book.get_sheet(0)), collect_hyperlinks(res.get_sheet(0)));
+    let rels = String::from_utf8(res.get_package().unwrap().get_part("xl/worksheets/_rels/sheet1.xml.rels").unwrap().clone()).unwrap();
+    assert!(!rels.contains("mailto:"));
+}
+
+#[test]
+#[cfg(feature = "ods")]
+fn ods_hyperlink_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let book = make_hyperlink_book();
+    ods::write(&book, &dir.path().join("hyperlink.ods")).unwrap();
+    let res = ods::read(&dir.path().join("hyperlink.ods")).unwrap();
+    assert_eq!(book, res);
+
+    let mut book = ods::open(&dir.path().join("hyperlink.ods")).unwrap();
+    book.get_sheet_mut(0).add_cell(Cell::str("mail", ""), 1, 0);
+    ods::write(&book, &dir.path().join("hyperlink_open.ods")).unwrap();
+    let res = ods::open(&dir.path().join("hyperlink_open.ods")).unwrap();
+    assert_eq!(collect_hyperlinks(book.get_sheet(0)), collect_hyperlinks(res.get_sheet(0)));
+}
+
+// 1x1 PNG pictures
+const RED_PNG: [u8; 69] = [
+    0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00, 0x00, 0x0D,
+    0x49, 0x48, 0x44, 0x52, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01,
+    0x08, 0x02, 0x00, 0x00, 0x00, 0x90, 0x77, 0x53, 0xDE, 0x00, 0x00, 0x00,
+    0x0C, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9C, 0x63, 0xF8, 0xCF, 0xC0, 0x00,
+    0x00, 0x03, 0x01, 0x01, 0x00, 0xC9, 0xFE, 0x92, 0xEF, 0x00, 0x00, 0x00,
+    0x00, 0x49, 0x45, 0x4E, 0x44, 0xAE, 0x42, 0x60, 0x82,
+];
+const BLUE_PNG: [u8; 69] = [
+    0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00, 0x00, 0x0D,
+    0x49, 0x48, 0x44, 0x52, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01,
+    0x08, 0x02, 0x00, 0x00, 0x00, 0x90, 0x77, 0x53, 0xDE, 0x00, 0x00, 0x00,
+    0x0C, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9C, 0x63, 0x60, 0x60, 0xF8, 0x0F,
+    0x00, 0x01, 0x03, 0x01, 0x00, 0x08, 0x89, 0xC2, 0xEC, 0x00, 0x00, 0x00,
+    0x00, 0x49, 0x45, 0x4E, 0x44, 0xAE, 0x42, 0x60, 0x82,
+];
+
+#[cfg(any(feature = "ods", feature = "xlsx"))]
+fn make_image_book() -> Book {
+    let mut sheet = Sheet::new("pictures");
+    sheet.add_cell(Cell::str("logo", ""), 0, 0);
+    sheet.add_cell(Cell::float(1.0, ""), 3, 0);
+    sheet.add_image(Image::new("logo", RED_PNG.to_vec(), Anchor::OneCell(Marker::new(1, 1, 5.0, 2.5), 120.0, 40.0)));
+    sheet.add_image(Image::new("graph", BLUE_PNG.to_vec(), Anchor::TwoCell(Marker::new(3, 0, 0.0, 0.0), Marker::new(10, 4, 12.5, 6.0))));
+    let mut other = Sheet::new("other");
+    other.add_image(Image::new("logo", RED_PNG.to_vec(), Anchor::OneCell(Marker::new(0, 0, 0.0, 0.0), 60.0, 20.0)));
+    let mut book = Book::new();
+    book.add_sheet(sheet);
+    book.add_sheet(other);
+    book
+}
+
+#[test]
+fn image_rows_test() {
+    let mut sheet = Sheet::new("pictures");
+    sheet.add_image(Image::new("logo", RED_PNG.to_vec(), Anchor::TwoCell(Marker::new(2, 0, 0.0, 0.0), Marker::new(5, 2, 0.0, 0.0))));
+    sheet.insert_rows(1, 2);
+    assert_eq!(&Anchor::TwoCell(Marker::new(4, 0, 0.0, 0.0), Marker::new(7, 2, 0.0, 0.0)), sheet.get_images()[0].get_anchor());
+    sheet.remove_rows(3, 2);
+    assert_eq!(&Anchor::TwoCell(Marker::new(3, 0, 0.0, 0.0), Marker::new(5, 2, 0.0, 0.0)), sheet.get_images()[0].get_anchor());
+    assert_eq!(Some("image/png"), sheet.remove_image(0).get_media_type());
+}
+
+#[test]
+#[cfg(feature = "xlsx")]
+fn xlsx_image_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let book = make_image_book();
+    xlsx::write(&book, &dir.path().join("image.xlsx")).unwrap();
+    let res = xlsx::read(&dir.path().join("image.xlsx")).unwrap();
+    assert_eq!(book, res);
+
+    // the pictures replace the ones of the original file, the same picture is one media part
+    let mut book = xlsx::open(&dir.path().join("image.xlsx")).unwrap();
+    book.get_sheet_mut(0).remove_image(0);
+    book.get_sheet_mut(1).add_image(Image::new("copy", BLUE_PNG.to_vec(), Anchor::OneCell(Marker::new(2, 2, 0.0, 0.0), 30.0, 30.0)));
+    xlsx::write(&book, &dir.path().join("image_open.xlsx")).unwrap();
+    let res = xlsx::open(&dir.path().join("image_open.xlsx")).unwrap();
+    assert_eq!(book.get_sheet(0).get_images(), res.get_sheet(0).get_images());
+    assert_eq!(book.get_sheet(1).get_images(), res.get_sheet(1).get_images());
+    let package = res.get_package().unwrap();
+    assert_eq!(2, package.get_parts().keys().filter(|name| name.starts_with("xl/media/")).count());
+
+    // a sheet without pictures has no drawing
+    let mut book = res;
+    book.get_sheet_mut(0).remove_image(0);
+    xlsx::write(&book, &dir.path().join("image_removed.xlsx")).unwrap();
+    let res = xlsx::open(&dir.path().join("image_removed.xlsx")).unwrap();
+    assert!(res.get_sheet(0).get_images().is_empty());
+    let sheet = String::from_utf8(res.get_package().unwrap().get_part("xl/worksheets/sheet1.xml").unwrap().clone()).unwrap();
+    assert!(!sheet.contains("<drawing"));
+}
+
+#[test]
+#[cfg(feature = "ods")]
+fn ods_image_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let book = make_image_book();
+    ods::write(&book, &dir.path().join("image.ods")).unwrap();
+    let res = ods::read(&dir.path().join("image.ods")).unwrap();
+    assert_eq!(book, res);
+
+    let mut book = ods::open(&dir.path().join("image.ods")).unwrap();
+    book.get_sheet_mut(0).remove_image(0);
+    book.get_sheet_mut(1).add_image(Image::new("copy", BLUE_PNG.to_vec(), Anchor::OneCell(Marker::new(2, 2, 0.0, 0.0), 30.0, 30.0)));
+    ods::write(&book, &dir.path().join("image_open.ods")).unwrap();
+    let res = ods::open(&dir.path().join("image_open.ods")).unwrap();
+    assert_eq!(book.get_sheet(0).get_images(), res.get_sheet(0).get_images());
+    assert_eq!(book.get_sheet(1).get_images(), res.get_sheet(1).get_images());
+    let package = res.get_package().unwrap();
+    assert_eq!(2, package.get_parts().keys().filter(|name| name.starts_with("Pictures/")).count());
+    let manifest = String::from_utf8(package.get_part("META-INF/manifest.xml").unwrap().clone()).unwrap();
+    assert!(manifest.contains("Pictures/image2.png"));
+}
+
+#[test]
+fn range_test() {
+    let range = Range::parse("'Sales ''17'!$C$5:A2").unwrap();
+    assert_eq!(Range::new("Sales '17", 1, 0, 4, 2), range);
+    assert_eq!("'Sales ''17'!$A$2:$C$5", range.to_string());
+    assert!(range.contains(3, 1));
+    assert!(!range.contains(5, 1));
+    assert_eq!("Sheet1!$B$1", Range::parse("Sheet1!B1").unwrap().to_string());
+    assert_eq!(Some(Range::new("Sheet1", 0, 1, 1048575, 1)), Range::parse("Sheet1!B:B"));
+    assert_eq!(None, Range::parse("B1:B5"));
+    assert_eq!(None, Range::parse("Sheet1!5B"));
+}
+
+fn make_chart_book() -> Book {
+    let mut sheet = Sheet::new("Sales 2017");
+    sheet.add_cell(Cell::str("month", ""), 0, 0);
+    sheet.add_cell(Cell::str("amount", ""), 0, 1);
+    for row in 1..5 {
+        sheet.add_cell(Cell::str(format!("M{}", row).as_str(), ""), row, 0);
+        sheet.add_cell(Cell::float(row as f64 * 10.0, ""), row, 1);
+    }
+    let mut chart = Chart::new(ChartType::Bar, Anchor::TwoCell(Marker::new(1, 3, 0.0, 0.0), Marker::new(15, 9, 0.0, 0.0)));
+    chart.set_title(Some(String::from("Sales")));
+    chart.set_categories(Range::parse("'Sales 2017'!A2:A5"));
+    let mut series = Series::new(Range::parse("'Sales 2017'!B2:B5").unwrap());
+    series.set_name(Range::parse("'Sales 2017'!B1"));
+    chart.add_series(series);
+    let mut axis = Axis::new();
+    axis.set_title(Some(String::from("amount")));
+    axis.set_max(Some(50.0));
+    chart.set_y_axis(axis);
+    chart.set_legend(Some(LegendPosition::Bottom));
+    sheet.add_chart(chart);
+    let mut pie = Chart::new(ChartType::Pie, Anchor::OneCell(Marker::new(16, 3, 0.0, 0.0), 240.0, 160.0));
+    pie.set_categories(Range::parse("'Sales 2017'!A2:A5"));
+    pie.add_series(Series::new(Range::parse("'Sales 2017'!B2:B5").unwrap()));
+    pie.set_legend(None);
+    sheet.add_chart(pie);
+    let mut book = Book::new();
+    book.add_sheet(sheet);
+    book
+}
+
+#[test]
+fn chart_rows_test() {
+    let mut book = make_chart_book();
+    let sheet = book.get_sheet_mut(0);
+    sheet.insert_rows(0, 2);
+    assert_eq!(&Marker::new(3, 3, 0.0, 0.0), sheet.get_charts()[0].get_anchor().get_from());
+    sheet.remove_rows(0, 2);
+    assert_eq!(&Marker::new(1, 3, 0.0, 0.0), sheet.get_charts()[0].get_anchor().get_from());
+    assert_eq!(ChartType::Pie, sheet.remove_chart(1).get_chart_type());
+    assert_eq!(1, sheet.get_charts().len());
+}
+
+#[test]
+#[cfg(feature = "xlsx")]
+fn xlsx_chart_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let book = make_chart_book();
+    xlsx::write(&book, &dir.path().join("chart.xlsx")).unwrap();
+    let res = xlsx::open(&dir.path().join("chart.xlsx")).unwrap();
+    let package = res.get_package().unwrap();
+    let chart = String::from_utf8(package.get_part("xl/charts/chart1.xml").unwrap().clone()).unwrap();
+    assert!(chart.contains("<c:barChart>"));
+    assert!(chart.contains("<c:f>&apos;Sales 2017&apos;!$B$2:$B$5</c:f>"));
+    assert!(chart.contains("<c:f>&apos;Sales 2017&apos;!$B$1</c:f>"));
+    assert!(chart.contains("<c:max val=\"50\"/>"));
+    assert!(chart.contains("<c:legendPos val=\"b\"/>"));
+    let pie = String::from_utf8(package.get_part("xl/charts/chart2.xml").unwrap().clone()).unwrap();
+    assert!(pie.contains("<c:pieChart>"));
+    assert!(!pie.contains("<c:legend>"));
+    let drawing = String::from_utf8(package.get_part("xl/drawings/drawing1.xml").unwrap().clone()).unwrap();
+    assert_eq!(2, drawing.matches("<xdr:graphicFrame").count());
+
+    // the charts of the original file aren't read and stay with the added ones
+    let mut book = res;
+    let chart = make_chart_book().get_sheet(0).get_charts()[0].clone();
+    book.get_sheet_mut(0).add_chart(chart);
+    xlsx::write(&book, &dir.path().join("chart_open.xlsx")).unwrap();
+    let res = xlsx::open(&dir.path().join("chart_open.xlsx")).unwrap();
+    let package = res.get_package().unwrap();
+    assert_eq!(3, package.get_parts().keys().filter(|name| name.starts_with("xl/charts/")).count());
+}
+
+#[test]
+#[cfg(feature = "xlsx")]
+fn xlsx_copied_sheet_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let mut book = make_chart_book();
+    {
+        let sheet = book.get_sheet_mut(0);
+        sheet.add_image(Image::new("logo", RED_PNG.to_vec(), Anchor::OneCell(Marker::new(0, 3, 0.0, 0.0), 60.0, 20.0)));
+        let mut cell = Cell::str("report", "");
+        cell.set_hyperlink(Some(Hyperlink::new("https://example.com/report")));
+        sheet.add_cell(cell, 6, 0);
+    }
+    xlsx::write(&book, &dir.path().join("copied.xlsx")).unwrap();
+
+    // a copied sheet gets its own drawing with copies of the original charts and shares the picture
+    let mut book = xlsx::open(&dir.path().join("copied.xlsx")).unwrap();
+    let mut copy = book.get_sheet(0).clone();
+    copy.set_name("copy");
+    book.add_sheet(copy);
+    xlsx::write(&book, &dir.path().join("copied_open.xlsx")).unwrap();
+    let res = xlsx::open(&dir.path().join("copied_open.xlsx")).unwrap();
+    assert_eq!(book.get_sheet(0).get_images(), res.get_sheet(1).get_images());
+    assert_eq!(Some(&Hyperlink::new("https://example.com/report")), res.get_sheet(1).get_cell(6, 0).unwrap().get_hyperlink());
+    let package = res.get_package().unwrap();
+    assert_eq!(4, package.get_parts().keys().filter(|name| name.starts_with("xl/charts/")).count());
+    assert_eq!(1, package.get_parts().keys().filter(|name| name.starts_with("xl/media/")).count());
+    let rels = String::from_utf8(package.get_part("xl/worksheets/_rels/sheet2.xml.rels").unwrap().clone()).unwrap();
+    assert!(rels.contains("Target=\"../drawings/drawing2.xml\""));
+    let drawing = String::from_utf8(package.get_part("xl/drawings/drawing2.xml").unwrap().clone()).unwrap();
+    assert_eq!(2, drawing.matches("<xdr:graphicFrame").count());
+    let drawing_rels = String::from_utf8(package.get_part("xl/drawings/_rels/drawing2.xml.rels").unwrap().clone()).unwrap();
+    assert!(drawing_rels.contains("Target=\"../charts/chart3.xml\""));
+    assert!(drawing_rels.contains("Target=\"../charts/chart4.xml\""));
+    let content_types = String::from_utf8(package.get_part("[Content_Types].xml").unwrap().clone()).unwrap();
+    assert!(content_types.contains("PartName=\"/xl/charts/chart4.xml\""));
+}
+
+#[test]
+#[cfg(feature = "ods")]
+fn ods_chart_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let book = make_chart_book();
+    ods::write(&book, &dir.path().join("chart.ods")).unwrap();
+    let res = ods::open(&dir.path().join("chart.ods")).unwrap();
+    let package = res.get_package().unwrap();
+    let chart = String::from_utf8(package.get_part("Object 1/content.xml").unwrap().clone()).unwrap();
+    assert!(chart.contains("chart:class=\"chart:bar\""));
+    assert!(chart.contains("chart:values-cell-range-address=\"&apos;Sales 2017&apos;.B2:&apos;Sales 2017&apos;.B5\""));
+    assert!(chart.contains("chart:label-cell-address=\"&apos;Sales 2017&apos;.B1\""));
+    assert!(chart.contains("chart:maximum=\"50\""));
+    let pie = String::from_utf8(package.get_part("Object 2/content.xml").unwrap().clone()).unwrap();
+    assert!(pie.contains("chart:class=\"chart:circle\""));
+    assert!(!pie.contains("<chart:legend"));
+    let content = String::from_utf8(package.get_part("content.xml").unwrap().clone()).unwrap();
+    assert!(content.contains("xlink:href=\"./Object 1\""));
+    assert!(content.contains("draw:notify-on-update-of-ranges=\"&apos;Sales 2017&apos;.A2:&apos;Sales 2017&apos;.A5"));
+    let manifest = String::from_utf8(package.get_part("META-INF/manifest.xml").unwrap().clone()).unwrap();
+    assert!(manifest.contains("manifest:full-path=\"Object 2/\" manifest:media-type=\"application/vnd.oasis.opendocument.chart\""));
+}
+
+fn make_name_book() -> Book {
+    let mut input = Sheet::new("input data");
+    input.add_cell(Cell::str("amount", ""), 0, 0);
+    input.add_cell(Cell::float(100.0, ""), 1, 0);
+    input.add_cell(Cell::float(200.0, ""), 2, 0);
+    input.add_defined_name(DefinedName::new(defined_name::PRINT_AREA, "'input data'!$A$1:$A$3"));
+    input.add_defined_name(DefinedName::range("Total", &Range::cell("input data", 3, 0)));
+    let mut report = Sheet::new("report");
+    report.add_cell(Cell::str("total", ""), 0, 0);
+    report.add_defined_name(DefinedName::range("Total", &Range::cell("report", 0, 1)));
+    let mut book = Book::new();
+    book.add_sheet(input);
+    book.add_sheet(report);
+    book.add_defined_name(DefinedName::range("Amounts", &Range::new("input data", 1, 0, 2, 0)));
+    book.add_defined_name(DefinedName::new("TaxRate", "0.08"));
+    book.add_defined_name(DefinedName::new("Areas", "'input data'!$A$1,report!$A$1:$B$1"));
+    book
+}
+
+#[test]
+fn defined_name_test() {
+    let mut book = make_name_book();
+    assert_eq!(Some(Range::new("input data", 1, 0, 2, 0)), book.get_named_range("AMOUNTS", Some(1)));
+    assert_eq!(Some(Range::cell("input data", 3, 0)), book.get_named_range("Total", Some(0)));
+    assert_eq!(Some(Range::cell("report", 0, 1)), book.get_named_range("Total", Some(1)));
+    assert_eq!(None, book.get_named_range("Total", None));
+    assert_eq!(None, book.get_named_range("TaxRate", None));
+    assert_eq!(2, book.get_defined_name("areas").unwrap().get_ranges().len());
+    assert_eq!(Some(Range::new("input data", 0, 0, 2, 0)), book.get_named_range(defined_name::PRINT_AREA, Some(0)));
+    assert_eq!(Some(Range::new("report", 0, 0, 0, 16383)), DefinedName::new("Titles", "report!$1:$1").get_range());
+
+    book.add_defined_name(DefinedName::new("taxrate", "0.1"));
+    assert_eq!(3, book.get_defined_names().len());
+    assert_eq!("0.1", book.remove_defined_name("TAXRATE").unwrap().get_formula());
+}
+
+#[test]
+fn defined_name_rows_test() {
+    let mut book = make_name_book();
+    book.get_sheet_mut(0).insert_rows(1, 3);
+    assert_eq!(Some(Range::new("input data", 0, 0, 5, 0)), book.get_named_range(defined_name::PRINT_AREA, Some(0)));
+    assert_eq!(Some(Range::cell("input data", 6, 0)), book.get_named_range("Total", Some(0)));
+    assert_eq!(Some(Range::new("input data", 1, 0, 2, 0)), book.get_named_range("Amounts", None));
+
+    book.insert_rows(0, 1, 3);
+    assert_eq!(Some(Range::new("input data", 4, 0, 5, 0)), book.get_named_range("Amounts", None));
+    assert_eq!(Some(Range::new("input data", 0, 0, 8, 0)), book.get_named_range(defined_name::PRINT_AREA, Some(0)));
+    assert_eq!(Some(Range::cell("report", 0, 1)), book.get_named_range("Total", Some(1)));
+    assert_eq!("0.08", book.get_defined_name("TaxRate").unwrap().get_formula());
+
+    book.remove_rows(0, 4, 2);
+    assert_eq!(None, book.get_defined_name("Amounts"));
+    assert_eq!(Some(Range::new("input data", 0, 0, 6, 0)), book.get_named_range(defined_name::PRINT_AREA, Some(0)));
+    assert_eq!(Some(Range::cell("input data", 7, 0)), book.get_named_range("Total", Some(0)));
+
+    book.remove_rows(0, 0, 1);
+    assert_eq!("report!$A$1:$B$1", book.get_defined_name("Areas").unwrap().get_formula());
+}
+
+#[test]
+fn defined_name_rename_test() {
+    let mut book = make_name_book();
+    book.get_sheet_mut(0).set_name("input");
+    assert_eq!("input!$A$1:$A$3", book.get_sheet(0).get_defined_name(defined_name::PRINT_AREA).unwrap().get_formula());
+    assert_eq!(Some(Range::cell("input", 3, 0)), book.get_named_range("Total", Some(0)));
+    assert_eq!(Some(Range::new("input data", 1, 0, 2, 0)), book.get_named_range("Amounts", None));
+
+    let mut book = make_name_book();
+    let mut cell = Cell::float(300.0, "");
+    cell.set_formula(Some(String::from("SUM('input data'!A2:A3)")));
+    book.get_sheet_mut(1).add_cell(cell, 0, 1);
+    book.rename_sheet(0, "data");
+    assert_eq!("data", book.get_sheet(0).get_name());
+    assert_eq!(Some(Range::cell("data", 3, 0)), book.get_named_range("Total", Some(0)));
+    assert_eq!(Some(Range::new("data", 1, 0, 2, 0)), book.get_named_range("Amounts", None));
+    assert_eq!("data!$A$1,report!$A$1:$B$1", book.get_defined_name("Areas").unwrap().get_formula());
+    assert_eq!(Some(&String::from("SUM(data!A2:A3)")), book.get_sheet(1).get_cell(0, 1).unwrap().get_formula());
+
+    book.rename_sheet(1, "my report");
+    assert_eq!("data!$A$1,'my report'!$A$1:$B$1", book.get_defined_name("Areas").unwrap().get_formula());
+    assert_eq!(Some(Range::cell("my report", 0, 1)), book.get_named_range("Total", Some(1)));
+}
+
+#[test]
+#[cfg(feature = "xlsx")]
+fn xlsx_defined_name_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let mut book = make_name_book();
+    let mut hidden = DefinedName::new("_xlnm._FilterDatabase", "report!$A$1:$A$1");
+    hidden.set_hidden(true);
+    book.get_sheet_mut(1).add_defined_name(hidden);
+    xlsx::write(&book, &dir.path().join("name.xlsx")).unwrap();
+    let res = xlsx::read(&dir.path().join("name.xlsx")).unwrap();
+    assert_eq!(book, res);
+
+    // the names of an opened file are written again from the book
+    let mut book = xlsx::open(&dir.path().join("name.xlsx")).unwrap();
+    book.remove_defined_name("TaxRate");
+    book.get_sheet_mut(1).remove_defined_name("Total");
+    book.add_defined_name(DefinedName::range("Report", &Range::new("report", 0, 0, 0, 1)));
+    xlsx::write(&book, &dir.path().join("name_open.xlsx")).unwrap();
+    let res = xlsx::open(&dir.path().join("name_open.xlsx")).unwrap();
+    assert_eq!(book.get_defined_names(), res.get_defined_names());
+    assert_eq!(book.get_sheet(0).get_defined_names(), res.get_sheet(0).get_defined_names());
+    assert_eq!(book.get_sheet(1).get_defined_names(), res.get_sheet(1).get_defined_names());
+    let workbook = String::from_utf8(res.get_package().unwrap().get_part("xl/workbook.xml").unwrap().clone()).unwrap();
+    assert!(workbook.contains("<definedName name=\"_xlnm.Print_Area\" localSheetId=\"0\">&apos;input data&apos;!$A$1:$A$3</definedName>"));
+    assert_eq!(1, workbook.matches("<definedNames>").count());
+}
+
+#[test]
+#[cfg(feature = "ods")]
+fn ods_defined_name_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let book = make_name_book();
+    ods::write(&book, &dir.path().join("name.ods")).unwrap();
+    let res = ods::read(&dir.path().join("name.ods")).unwrap();
+    assert_eq!(book, res);
+
+    let mut book = ods::open(&dir.path().join("name.ods")).unwrap();
+    book.remove_defined_name("Areas");
+    book.get_sheet_mut(0).remove_defined_name(defined_name::PRINT_AREA);
+    book.get_sheet_mut(1).add_defined_name(DefinedName::new(defined_name::PRINT_AREA, "report!$A$1:$B$2"));
+    ods::write(&book, &dir.path().join("name_open.ods")).unwrap();
+    let res = ods::open(&dir.path().join("name_open.ods")).unwrap();
+    assert_eq!(book.get_defined_names(), res.get_defined_names());
+    assert_eq!(book.get_sheet(0).get_defined_names(), res.get_sheet(0).get_defined_names());
+    // the print area of a table is read before its names
+    assert_eq!(2, res.get_sheet(1).get_defined_names().len());
+    assert_eq!(book.get_named_range(defined_name::PRINT_AREA, Some(1)), res.get_named_range(defined_name::PRINT_AREA, Some(1)));
+    let content = String::from_utf8(res.get_package().unwrap().get_part("content.xml").unwrap().clone()).unwrap();
+    assert!(content.contains("table:print-ranges=\"$report.$A$1:$report.$B$2\""));
+    assert!(content.contains("<table:named-expression table:name=\"TaxRate\" table:expression=\"of:=0.08\"/>"));
+}
+
+fn make_validation_book() -> Book {
+    let mut order = Sheet::new("order");
+    order.add_cell(Cell::str("paid", ""), 0, 1);
+    order.add_cell(Cell::str("Yes", ""), 1, 1);
+    order.add_cell(Cell::float(5.0, ""), 1, 3);
+    let mut paid = DataValidation::new(Range::new("order", 1, 1, 4, 1), Rule::List(vec![String::from("Yes"), String::from("No")]));
+    paid.set_prompt(Some(Message::new("Paid", "Select \"Yes\" or \"No\"")));
+    paid.set_error(Some(Message::new("Paid", "Not in the list\nSelect from the list")));
+    order.add_validation(paid);
+    let mut code = DataValidation::new(Range::new("order", 1, 2, 4, 2), Rule::ListRange(Range::new("codes", 0, 0, 2, 0)));
+    code.set_drop_down(false);
+    order.add_validation(code);
+    let mut quantity = DataValidation::new(Range::new("order", 1, 3, 4, 3), Rule::WholeNumber(Operator::Between(1, 10)));
+    quantity.add_range(Range::cell("order", 1, 5));
+    quantity.set_allow_blank(false);
+    quantity.set_error_style(ErrorStyle::Warning);
+    order.add_validation(quantity);
+    order.add_validation(DataValidation::new(Range::new("order", 1, 4, 1048575, 4), Rule::Decimal(Operator::GreaterThan(0.5))));
+    order.add_validation(DataValidation::new(Range::new("order", 1, 6, 4, 6), Rule::Date(Operator::NotBetween(
+        chrono::NaiveDate::from_ymd_opt(2018, 12, 29).unwrap(), chrono::NaiveDate::from_ymd_opt(2019, 1, 3).unwrap()))));
+    order.add_validation(DataValidation::new(Range::cell("order", 1, 7), Rule::TextLength(Operator::LessThanOrEqual(10))));
+    let mut note = DataValidation::new(Range::new("order", 1, 8, 4, 8), Rule::Custom(String::from("ISNUMBER(D2)")));
+    note.set_error_style(ErrorStyle::Information);
+    order.add_validation(note);
+    let mut codes = Sheet::new("codes");
+    codes.add_cell(Cell::str("A", ""), 0, 0);
+    codes.add_cell(Cell::str("B", ""), 1, 0);
+    codes.add_cell(Cell::str("C", ""), 2, 0);
+    let mut book = Book::new();
+    book.add_sheet(order);
+    book.add_sheet(codes);
+    book
+}
+
+#[test]
+fn validation_test() {
+    use chrono::prelude::*;
+    use spsheet::Value;
+    let book = make_validation_book();
+    let text = |text: &str| Value::Str(String::from(text));
+    assert!(book.validate_cell(0, 1, 1, &text("Yes")).is_ok());
+    assert!(book.validate_cell(0, 1, 1, &text("Maybe")).is_err());
+    assert!(book.validate_cell(0, 0, 1, &text("Maybe")).is_ok());
+    assert!(book.validate_cell(0, 1, 1, &Value::Empty).is_ok());
+    assert!(book.validate_cell(0, 2, 2, &text("B")).is_ok());
+    assert!(book.validate_cell(0, 2, 2, &text("D")).is_err());
+    assert!(book.validate_cell(0, 1, 3, &Value::Float(10.0)).is_ok());
+    assert!(book.validate_cell(0, 1, 5, &Value::Float(2.5)).is_err());
+    assert_eq!(ErrorStyle::Warning, book.validate_cell(0, 1, 3, &Value::Empty).unwrap_err().get_error_style());
+    assert!(book.validate_cell(0, 1000, 4, &Value::Float(0.6)).is_ok());
+    assert!(book.validate_cell(0, 1000, 4, &text("0.6")).is_err());
+    assert!(book.validate_cell(0, 1, 6, &Value::DateOnly(NaiveDate::from_ymd_opt(2019, 1, 1).unwrap())).is_err());
+    assert!(book.validate_cell(0, 1, 6, &Value::Date(Utc.with_ymd_and_hms(2019, 1, 4, 0, 0, 0).unwrap())).is_ok());
+    assert!(book.validate_cell(0, 1, 7, &text("0123456789")).is_ok());
+    assert!(book.validate_cell(0, 1, 7, &text("0123456789A")).is_err());
+    assert!(book.validate_cell(0, 1, 8, &text("any")).is_ok());
+    // a list from a range is only checked by the book
+    assert!(book.get_sheet(0).get_validation(2, 2).unwrap().validate(&text("D")));
+
+    let mut sheet = book.get_sheet(0).clone();
+    sheet.insert_rows(0, 1);
+    assert_eq!(&vec![Range::new("order", 2, 3, 5, 3), Range::cell("order", 2, 5)], sheet.get_validation(2, 3).unwrap().get_ranges());
+    assert_eq!(&Range::new("order", 2, 4, 1048575, 4), &sheet.get_validation(2, 4).unwrap().get_ranges()[0]);
+    sheet.remove_rows(1, 2);
+    assert_eq!(&vec![Range::new("order", 1, 3, 3, 3)], sheet.get_validation(1, 3).unwrap().get_ranges());
+    // the text length of the removed row has no range left
+    assert_eq!(6, sheet.get_validations().len());
+}
+
+#[test]
+#[cfg(feature = "xlsx")]
+fn xlsx_validation_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let book = make_validation_book();
+    xlsx::write(&book, &dir.path().join("validation.xlsx")).unwrap();
+    let res = xlsx::read(&dir.path().join("validation.xlsx")).unwrap();
+    assert_eq!(book, res);
+
+    // the validations of an opened file are written again from the book
+    let mut book = xlsx::open(&dir.path().join("validation.xlsx")).unwrap();
+    book.get_sheet_mut(0).remove_validation(0);
+    book.get_sheet_mut(1).add_validation(DataValidation::new(Range::new("codes", 0, 0, 2, 0), Rule::TextLength(Operator::Equal(1))));
+    xlsx::write(&book, &dir.path().join("validation_open.xlsx")).unwrap();
+    let res = xlsx::open(&dir.path().join("validation_open.xlsx")).unwrap();
+    assert_eq!(book.get_sheet(0).get_validations(), res.get_sheet(0).get_validations());
+    assert_eq!(book.get_sheet(1).get_validations(), res.get_sheet(1).get_validations());
+    let sheet = String::from_utf8(res.get_package().unwrap().get_part("xl/worksheets/sheet1.xml").unwrap().clone()).unwrap();
+    assert_eq!(1, sheet.matches("<dataValidations").count());
+    assert!(sheet.contains("sqref=\"D2:D5 F2\"><formula1>1</formula1><formula2>10</formula2></dataValidation>"));
+    assert!(sheet.contains("<formula1>codes!$A$1:$A$3</formula1>"));
+}
+
+#[test]
+#[cfg(feature = "ods")]
+fn ods_validation_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let book = make_validation_book();
+    ods::write(&book, &dir.path().join("validation.ods")).unwrap();
+    let res = ods::read(&dir.path().join("validation.ods")).unwrap();
+    assert_eq!(book, res);
+
+    let mut book = ods::open(&dir.path().join("validation.ods")).unwrap();
+    book.get_sheet_mut(0).remove_validation(0);
+    book.get_sheet_mut(1).add_validation(DataValidation::new(Range::new("codes", 0, 0, 2, 0), Rule::TextLength(Operator::Equal(1))));
+    ods::write(&book, &dir.path().join("validation_open.ods")).unwrap();
+    let res = ods::open(&dir.path().join("validation_open.ods")).unwrap();
+    assert_eq!(book.get_sheet(0).get_validations(), res.get_sheet(0).get_validations());
+    assert_eq!(book.get_sheet(1).get_validations(), res.get_sheet(1).get_validations());
+    let content = String::from_utf8(res.get_package().unwrap().get_part("content.xml").unwrap().clone()).unwrap();
+    assert_eq!(1, content.matches("<table:content-validations>").count());
+    assert!(content.contains("table:condition=\"of:cell-content-is-whole-number() and cell-content-is-between(1;10)\""));
+    assert!(content.contains("table:condition=\"of:cell-content-is-in-list([$codes.$A$1:$codes.$A$3])\""));
+}
+
+fn make_conditional_format_book() -> Book {
+    let mut bad = DifferentialStyle::new();
+    let mut font = spsheet::rich_text::Font::new();
+    font.set_color(Some(String::from("9C0006")));
+    font.set_bold(true);
+    bad.set_font(font);
+    bad.set_fill_color(Some(String::from("FFC7CE")));
+    let mut good = DifferentialStyle::new();
+    good.set_fill_color(Some(String::from("C6EFCE")));
+    let mut kpi = Sheet::new("kpi");
+    kpi.add_cell(Cell::str("sales", ""), 0, 1);
+    kpi.add_cell(Cell::float(120.0, ""), 1, 1);
+    kpi.add_cell(Cell::float(-5.0, ""), 2, 1);
+    let mut sales = ConditionalFormat::new(Range::new("kpi", 1, 1, 10, 1), conditional_format::Rule::CellIs(Operator::LessThan(String::from("0")), bad.clone()));
+    sales.add_rule(conditional_format::Rule::CellIs(Operator::Between(String::from("100"), String::from("200")), good.clone()));
+    sales.add_range(Range::new("kpi", 1, 3, 10, 3));
+    kpi.add_conditional_format(sales);
+    kpi.add_conditional_format(ConditionalFormat::new(Range::new("kpi", 1, 2, 10, 2),
+        conditional_format::Rule::CellIs(Operator::Equal(String::from("\"done\"")), good.clone())));
+    kpi.add_conditional_format(ConditionalFormat::new(Range::new("kpi", 1, 0, 10, 0),
+        conditional_format::Rule::Expression(String::from("$C2=\"late\""), bad.clone())));
+    kpi.add_conditional_format(ConditionalFormat::new(Range::new("kpi", 1, 4, 10, 4), conditional_format::Rule::ColorScale(vec![
+        (Threshold::Min, String::from("F8696B")), (Threshold::Percentile(50.0), String::from("FFEB84")), (Threshold::Max, String::from("63BE7B")),
+    ])));
+    kpi.add_conditional_format(ConditionalFormat::new(Range::new("kpi", 1, 5, 10, 5), conditional_format::Rule::DataBar {
+        min: Threshold::Number(0.0),
+        max: Threshold::Max,
+        color: String::from("638EC6"),
+    }));
+    kpi.add_conditional_format(ConditionalFormat::new(Range::new("kpi", 1, 6, 10, 6), conditional_format::Rule::IconSet {
+        icons: IconStyle::Arrows3,
+        thresholds: vec![Threshold::Percent(0.0), Threshold::Percent(33.0), Threshold::Percent(67.0)],
+    }));
+    let mut top = ConditionalFormat::new(Range::new("kpi", 1, 7, 10, 7), conditional_format::Rule::Top {
+        rank: 3,
+        bottom: false,
+        percent: false,
+        style: good.clone(),
+    });
+    top.add_rule(conditional_format::Rule::Top {
+        rank: 10,
+        bottom: true,
+        percent: true,
+        style: bad.clone(),
+    });
+    kpi.add_conditional_format(top);
+    let mut codes = ConditionalFormat::new(Range::new("kpi", 1, 8, 10, 8), conditional_format::Rule::Duplicate(bad.clone()));
+    codes.add_rule(conditional_format::Rule::Unique(good));
+    kpi.add_conditional_format(codes);
+    let mut book = Book::new();
+    book.add_sheet(kpi);
+    book
+}
+
+#[test]
+fn conditional_format_test() {
+    let book = make_conditional_format_book();
+    let formats = book.get_sheet(0).get_conditional_formats();
+    assert_eq!(8, formats.len());
+    assert!(formats[0].contains(5, 3));
+    assert!(!formats[0].contains(5, 2));
+    assert_eq!(Some("FFC7CE"), formats[0].get_rules()[0].get_style().and_then(|style| style.get_fill_color()).map(|color| color.as_str()));
+    assert_eq!(None, formats[3].get_rules()[0].get_style());
+    assert_eq!(3, IconStyle::Arrows3.get_icon_count());
+    assert_eq!(5, IconStyle::Quarters5.get_icon_count());
+
+    let mut sheet = book.get_sheet(0).clone();
+    sheet.insert_rows(0, 2);
+    assert_eq!(&vec![Range::new("kpi", 3, 1, 12, 1), Range::new("kpi", 3, 3, 12, 3)], sheet.get_conditional_formats()[0].get_ranges());
+    sheet.remove_rows(3, 10);
+    // the formats have no range left
+    assert!(sheet.get_conditional_formats().is_empty());
+}
+
+#[test]
+#[cfg(feature = "xlsx")]
+fn xlsx_conditional_format_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let book = make_conditional_format_book();
+    xlsx::write(&book, &dir.path().join("conditional_format.xlsx")).unwrap();
+    let res = xlsx::read(&dir.path().join("conditional_format.xlsx")).unwrap();
+    assert_eq!(book, res);
+
+    // the formats of an opened file are written again from the book, the dxfs of the same styles are used again
+    let mut book = xlsx::open(&dir.path().join("conditional_format.xlsx")).unwrap();
+    book.get_sheet_mut(0).remove_conditional_format(1);
+    let mut strike = DifferentialStyle::new();
+    let mut font = spsheet::rich_text::Font::new();
+    font.set_strike(true);
+    strike.set_font(font);
+    book.get_sheet_mut(0).add_conditional_format(ConditionalFormat::new(Range::cell("kpi", 0, 1),
+        conditional_format::Rule::Expression(String::from("ISBLANK(B2)"), strike)));
+    xlsx::write(&book, &dir.path().join("conditional_format_open.xlsx")).unwrap();
+    let res = xlsx::open(&dir.path().join("conditional_format_open.xlsx")).unwrap();
+    assert_eq!(book.get_sheet(0).get_conditional_formats(), res.get_sheet(0).get_conditional_formats());
+    let package = res.get_package().unwrap();
+    let sheet = String::from_utf8(package.get_part("xl/worksheets/sheet1.xml").unwrap().clone()).unwrap();
+    assert_eq!(8, sheet.matches("<conditionalFormatting").count());
+    assert!(sheet.contains("<conditionalFormatting sqref=\"B2:B11 D2:D11\"><cfRule type=\"cellIs\" dxfId=\"0\" priority=\"1\" operator=\"lessThan\"><formula>0</formula></cfRule>"));
+    assert!(sheet.contains("<cfRule type=\"iconSet\" priority=\"6\"><iconSet iconSet=\"3Arrows\"><cfvo type=\"percent\" val=\"0\"/>"));
+    let styles = String::from_utf8(package.get_part("xl/styles.xml").unwrap().clone()).unwrap();
+    assert!(styles.contains("<dxfs count=\"3\">"));
+    assert!(styles.contains("<dxf><font><strike/></font></dxf>"));
+}
+
+#[test]
+#[cfg(feature = "ods")]
+fn ods_conditional_format_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let book = make_conditional_format_book();
+    ods::write(&book, &dir.path().join("conditional_format.ods")).unwrap();
+    let res = ods::read(&dir.path().join("conditional_format.ods")).unwrap();
+    assert_eq!(book, res);
+
+    let mut book = ods::open(&dir.path().join("conditional_format.ods")).unwrap();
+    book.get_sheet_mut(0).remove_conditional_format(1);
+    let mut strike = DifferentialStyle::new();
+    let mut font = spsheet::rich_text::Font::new();
+    font.set_strike(true);
+    strike.set_font(font);
+    book.get_sheet_mut(0).add_conditional_format(ConditionalFormat::new(Range::cell("kpi", 0, 1),
+        conditional_format::Rule::Expression(String::from("ISBLANK(B2)"), strike)));
+    ods::write(&book, &dir.path().join("conditional_format_open.ods")).unwrap();
+    let res = ods::open(&dir.path().join("conditional_format_open.ods")).unwrap();
+    assert_eq!(book.get_sheet(0).get_conditional_formats(), res.get_sheet(0).get_conditional_formats());
+    let package = res.get_package().unwrap();
+    let content = String::from_utf8(package.get_part("content.xml").unwrap().clone()).unwrap();
+    assert_eq!(1, content.matches("<calcext:conditional-formats>").count());
+    assert!(content.contains("<calcext:conditional-format calcext:target-range-address=\"kpi.B2:kpi.B11 kpi.D2:kpi.D11\">"));
+    assert!(content.contains("calcext:apply-style-name=\"Conditional_20_2\" calcext:value=\"between(100,200)\" calcext:base-cell-address=\"kpi.B2\""));
+    let styles = String::from_utf8(package.get_part("styles.xml").unwrap().clone()).unwrap();
+    assert_eq!(1, styles.matches("style:name=\"Conditional_20_1\"").count());
+    assert!(styles.contains("style:name=\"Conditional_20_3\""));
+    assert!(!styles.contains("style:name=\"Conditional_20_4\""));
+}
+
+fn make_view_book() -> Book {
+    let mut book = Book::new();
+    let mut sheet = Sheet::new("list");
+    sheet.add_cell(Cell::str("name", ""), 0, 0);
+    let mut view = SheetView::new();
+    view.set_pane(Some(Pane::Frozen { rows: 1, columns: 2 }));
+    view.set_zoom(150);
+    view.set_active_cell(3, 1);
+    view.set_selection(vec![Range::new("list", 3, 1, 5, 2), Range::cell("list", 8, 0)]);
+    sheet.set_view(view);
+    book.add_sheet(sheet);
+
+    let mut sheet = Sheet::new("arabic");
+    let mut view = SheetView::new();
+    view.set_pane(Some(Pane::Split { x: 120.0, y: 45.0 }));
+    view.set_show_grid_lines(false);
+    view.set_show_headers(false);
+    view.set_right_to_left(true);
+    view.set_active_cell(10, 4);
+    sheet.set_view(view);
+    book.add_sheet(sheet);
+
+    book.add_sheet(Sheet::new("empty"));
+    book.set_active_sheet(1);
+    book
+}
+
+#[test]
+fn sheet_view_test() {
+    let mut view = SheetView::new();
+    assert_eq!(100, view.get_zoom());
+    view.set_zoom(1000);
+    assert_eq!(400, view.get_zoom());
+    assert_eq!((1, 2), Pane::Frozen { rows: 1, columns: 2 }.get_frozen());
+
+    // the active sheet follows the sheets inserted and removed before it
+    let mut book = make_view_book();
+    book.insert_sheet(0, Sheet::new("first"));
+    assert_eq!(2, book.get_active_sheet());
+    book.remove_sheet(1);
+    assert_eq!(1, book.get_active_sheet());
+    book.remove_sheet(1);
+    assert_eq!(1, book.get_active_sheet());
+    assert_eq!("empty", book.get_sheet(book.get_active_sheet()).get_name());
+    book.remove_sheet(1);
+    assert_eq!(0, book.get_active_sheet());
+}
+
+#[test]
+#[cfg(feature = "xlsx")]
+fn xlsx_sheet_view_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let book = make_view_book();
+    xlsx::write(&book, &dir.path().join("sheet_view.xlsx")).unwrap();
+    let res = xlsx::read(&dir.path().join("sheet_view.xlsx")).unwrap();
+    assert_eq!(book, res);
+
+    // the view of an opened sheet replaces the attributes of its sheetView
+    let mut book = xlsx::open(&dir.path().join("sheet_view.xlsx")).unwrap();
+    book.set_active_sheet(0);
+    book.get_sheet_mut(0).get_view_mut().set_pane(None);
+    book.get_sheet_mut(0).get_view_mut().set_selection(Vec::new());
+    xlsx::write(&book, &dir.path().join("sheet_view_open.xlsx")).unwrap();
+    let res = xlsx::open(&dir.path().join("sheet_view_open.xlsx")).unwrap();
+    assert_eq!(0, res.get_active_sheet());
+    assert_eq!(book.get_sheet(0).get_view(), res.get_sheet(0).get_view());
+    let package = res.get_package().unwrap();
+    let sheet = String::from_utf8(package.get_part("xl/worksheets/sheet1.xml").unwrap().clone()).unwrap();
+    assert!(sheet.contains("tabSelected=\"true\""));
+    assert!(sheet.contains("zoomScale=\"150\""));
+    assert!(sheet.contains("<selection pane=\"topLeft\" activeCell=\"B4\" activeCellId=\"0\" sqref=\"B4\"/>"));
+    assert!(!sheet.contains("<pane"));
+    let sheet = String::from_utf8(package.get_part("xl/worksheets/sheet2.xml").unwrap().clone()).unwrap();
+    assert!(sheet.contains("tabSelected=\"false\""));
+    assert!(sheet.contains("<pane xSplit=\"2400\" ySplit=\"900\" activePane=\"bottomRight\"/>"));
+    let workbook = String::from_utf8(package.get_part("xl/workbook.xml").unwrap().clone()).unwrap();
+    assert!(workbook.contains("activeTab=\"0\""));
+}
+
+#[test]
+#[cfg(feature = "ods")]
+fn ods_sheet_view_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    // ods keeps only the active cell and shows the headers of the active sheet on every sheet
+    let mut book = make_view_book();
+    ods::write(&book, &dir.path().join("sheet_view.ods")).unwrap();
+    let res = ods::read(&dir.path().join("sheet_view.ods")).unwrap();
+    book.get_sheet_mut(0).get_view_mut().set_selection(Vec::new());
+    for index in 0..book.get_sheet_size() {
+        book.get_sheet_mut(index).get_view_mut().set_show_headers(false);
+    }
+    assert_eq!(book, res);
+
+    let mut book = ods::open(&dir.path().join("sheet_view.ods")).unwrap();
+    book.set_active_sheet(0);
+    book.get_sheet_mut(1).get_view_mut().set_right_to_left(false);
+    book.get_sheet_mut(2).get_view_mut().set_right_to_left(true);
+    book.get_sheet_mut(2).get_view_mut().set_zoom(75);
+    ods::write(&book, &dir.path().join("sheet_view_open.ods")).unwrap();
+    let res = ods::open(&dir.path().join("sheet_view_open.ods")).unwrap();
+    assert_eq!(0, res.get_active_sheet());
+    for index in 0..book.get_sheet_size() {
+        assert_eq!(book.get_sheet(index).get_view(), res.get_sheet(index).get_view());
+    }
+    let package = res.get_package().unwrap();
+    let settings = String::from_utf8(package.get_part("settings.xml").unwrap().clone()).unwrap();
+    assert!(settings.contains("<config:config-item config:name=\"ActiveTable\" config:type=\"string\">list</config:config-item>"));
+    assert!(settings.contains("<config:config-item config:name=\"HorizontalSplitMode\" config:type=\"short\">2</config:config-item>"));
+    let content = String::from_utf8(package.get_part("content.xml").unwrap().clone()).unwrap();
+    assert!(content.contains("<table:table table:name=\"arabic\" table:style-name=\"spta1\""));
+    assert!(content.contains("<table:table table:name=\"empty\" table:style-name=\"spta2\""));
+
+    // a broken settings.xml is made again from the views of the book
+    let mut package = res.get_package().unwrap().clone();
+    package.set_part("settings.xml", b"<office:document-settings></office:settings>".to_vec());
+    let mut book = res.clone();
+    book.set_package(package);
+    ods::write(&book, &dir.path().join("sheet_view_broken.ods")).unwrap();
+    let res = ods::open(&dir.path().join("sheet_view_broken.ods")).unwrap();
+    assert_eq!(0, res.get_active_sheet());
+    assert_eq!(book.get_sheet(2).get_view(), res.get_sheet(2).get_view());
+}
+
+fn make_auto_filter_book() -> Book {
+    let mut book = Book::new();
+    let mut sheet = Sheet::new("orders");
+    for (column, header) in ["id", "city", "amount", "margin"].iter().enumerate() {
+        sheet.add_cell(Cell::str(*header, ""), 0, column);
+    }
+    for row in 1..10 {
+        sheet.add_cell(Cell::float(row as f64, ""), row, 0);
+        sheet.add_cell(Cell::str(if row % 2 == 0 { "Tokyo" } else { "Osaka" }, ""), row, 1);
+        sheet.add_cell(Cell::float(row as f64 * 100.0, ""), row, 2);
+        sheet.add_cell(Cell::float(row as f64 / 10.0, ""), row, 3);
+    }
+    let mut auto_filter = AutoFilter::new(Range::new("orders", 0, 0, 9, 3));
+    auto_filter.set_filter(1, Filter::Values { values: vec![String::from("Tokyo"), String::from("Osaka")], blank: true });
+    auto_filter.set_filter(2, Filter::Custom {
+        conditions: vec![(Comparison::GreaterThanOrEqual, String::from("200")), (Comparison::LessThan, String::from("800"))],
+        and: true,
+    });
+    auto_filter.set_filter(3, Filter::Top { count: 10.0, bottom: false, percent: true });
+    auto_filter.add_sort_key(SortKey::new(3, true));
+    auto_filter.add_sort_key(SortKey::new(0, false));
+    sheet.set_auto_filter(Some(auto_filter));
+    book.add_sheet(sheet);
+
+    let mut sheet = Sheet::new("names list");
+    sheet.add_cell(Cell::str("name", ""), 2, 1);
+    sheet.add_cell(Cell::str("Alice", ""), 3, 1);
+    sheet.add_cell(Cell::str("Bob", ""), 4, 1);
+    let mut auto_filter = AutoFilter::new(Range::new("names list", 2, 1, 4, 1));
+    auto_filter.set_filter(0, Filter::Custom {
+        conditions: vec![(Comparison::Equal, String::from("A*")), (Comparison::Equal, String::from("B*"))],
+        and: false,
+    });
+    sheet.set_auto_filter(Some(auto_filter));
+    book.add_sheet(sheet);
+
+    let mut sheet = Sheet::new("plain");
+    sheet.add_cell(Cell::str("header", ""), 0, 0);
+    sheet.set_auto_filter(Some(AutoFilter::new(Range::new("plain", 0, 0, 5, 0))));
+    book.add_sheet(sheet);
+    book
+}
+
+#[test]
+fn auto_filter_test() {
+    let mut book = make_auto_filter_book();
+    let sheet = book.get_sheet_mut(0);
+    sheet.insert_rows(0, 2);
+    assert_eq!(&Range::new("orders", 2, 0, 11, 3), sheet.get_auto_filter().unwrap().get_range());
+    sheet.remove_rows(5, 2);
+    assert_eq!(&Range::new("orders", 2, 0, 9, 3), sheet.get_auto_filter().unwrap().get_range());
+    assert!(sheet.get_auto_filter_mut().unwrap().remove_filter(3).is_some());
+    assert_eq!(2, sheet.get_auto_filter().unwrap().get_filters().len());
+    // the filter of the rows all removed is dropped
+    sheet.remove_rows(0, 20);
+    assert_eq!(None, sheet.get_auto_filter());
+}
+
+#[test]
+#[cfg(feature = "xlsx")]
+fn xlsx_auto_filter_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let book = make_auto_filter_book();
+    xlsx::write(&book, &dir.path().join("auto_filter.xlsx")).unwrap();
+    let res = xlsx::read(&dir.path().join("auto_filter.xlsx")).unwrap();
+    assert_eq!(book, res);
+
+    // the auto filter of an opened sheet replaces the original one with its sort state
+    let mut book = xlsx::open(&dir.path().join("auto_filter.xlsx")).unwrap();
+    book.get_sheet_mut(0).get_auto_filter_mut().unwrap().set_sort_keys(Vec::new());
+    book.get_sheet_mut(2).set_auto_filter(None);
+    xlsx::write(&book, &dir.path().join("auto_filter_open.xlsx")).unwrap();
+    let res = xlsx::open(&dir.path().join("auto_filter_open.xlsx")).unwrap();
+    for index in 0..book.get_sheet_size() {
+        assert_eq!(book.get_sheet(index).get_auto_filter(), res.get_sheet(index).get_auto_filter());
+    }
+    let package = res.get_package().unwrap();
+    let sheet = String::from_utf8(package.get_part("xl/worksheets/sheet1.xml").unwrap().clone()).unwrap();
+    assert!(sheet.contains("<autoFilter ref=\"A1:D10\"><filterColumn colId=\"1\"><filters blank=\"1\"><filter val=\"Tokyo\"/>"));
+    assert!(sheet.contains("<customFilters and=\"1\"><customFilter operator=\"greaterThanOrEqual\" val=\"200\"/>"));
+    assert!(!sheet.contains("<sortState"));
+    let sheet = String::from_utf8(package.get_part("xl/worksheets/sheet3.xml").unwrap().clone()).unwrap();
+    assert!(!sheet.contains("<autoFilter"));
+    let workbook = String::from_utf8(package.get_part("xl/workbook.xml").unwrap().clone()).unwrap();
+    assert!(workbook.contains("<definedName name=\"_xlnm._FilterDatabase\" localSheetId=\"0\" hidden=\"1\">orders!$A$1:$D$10</definedName>"));
+    assert!(workbook.contains("<definedName name=\"_xlnm._FilterDatabase\" localSheetId=\"1\" hidden=\"1\">&apos;names list&apos;!$B$3:$B$5</definedName>"));
+    assert_eq!(2, workbook.matches("_xlnm._FilterDatabase").count());
+}
+
+#[test]
+#[cfg(feature = "ods")]
+fn ods_auto_filter_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let book = make_auto_filter_book();
+    ods::write(&book, &dir.path().join("auto_filter.ods")).unwrap();
+    let res = ods::read(&dir.path().join("auto_filter.ods")).unwrap();
+    assert_eq!(book, res);
+
+    let mut book = ods::open(&dir.path().join("auto_filter.ods")).unwrap();
+    book.get_sheet_mut(0).get_auto_filter_mut().unwrap().remove_filter(1);
+    book.get_sheet_mut(2).set_auto_filter(None);
+    ods::write(&book, &dir.path().join("auto_filter_open.ods")).unwrap();
+    let res = ods::open(&dir.path().join("auto_filter_open.ods")).unwrap();
+    for index in 0..book.get_sheet_size() {
+        assert_eq!(book.get_sheet(index).get_auto_filter(), res.get_sheet(index).get_auto_filter());
+    }
+    let package = res.get_package().unwrap();
+    let content = String::from_utf8(package.get_part("content.xml").unwrap().clone()).unwrap();
+    assert!(content.contains("<table:database-range table:name=\"__Anonymous_Sheet_DB__0\" table:target-range-address=\"orders.A1:orders.D10\" table:display-filter-buttons=\"true\">"));
+    assert!(content.contains("<table:filter-or><table:filter-condition table:field-number=\"0\" table:value=\"A*\" table:operator=\"=\" table:data-type=\"text\"/>"));
+    assert!(content.contains("<table:sort-by table:field-number=\"3\" table:data-type=\"automatic\" table:order=\"descending\"/>"));
+    assert!(!content.contains("__Anonymous_Sheet_DB__2"));
+}
+
+fn make_table_book() -> Book {
+    let mut book = Book::new();
+    let mut sheet = Sheet::new("orders");
+    for (column, header) in ["Region", "Amount", "Price [USD]"].iter().enumerate() {
+        sheet.add_cell(Cell::str(*header, ""), 1, column + 1);
+    }
+    for row in 2..6 {
+        sheet.add_cell(Cell::str(if row % 2 == 0 { "East" } else { "West" }, ""), row, 1);
+        sheet.add_cell(Cell::float(row as f64 * 10.0, ""), row, 2);
+        sheet.add_cell(Cell::float(row as f64 * 1.5, ""), row, 3);
+    }
+    sheet.add_cell(Cell::str("Total", ""), 6, 1);
+    let mut region = TableColumn::new("Region");
+    region.set_totals(Some(Totals::Label(String::from("Total"))));
+    let mut amount = TableColumn::new("Amount");
+    amount.set_totals(Some(Totals::Function(TotalsFunction::Sum)));
+    let mut price = TableColumn::new("Price [USD]");
+    price.set_totals(Some(Totals::Function(TotalsFunction::Average)));
+    let mut table = Table::new("Sales", Range::new("orders", 1, 1, 6, 3));
+    table.set_columns(vec![region, amount, price]);
+    table.set_totals_row(true);
+    let mut style = TableStyle::new("TableStyleLight9");
+    style.set_show_first_column(true);
+    table.set_style(Some(style));
+    sheet.add_table(table);
+    let mut table = Table::new("Notes", Range::new("orders", 10, 0, 12, 0));
+    table.set_columns(vec![TableColumn::new("Column1")]);
+    table.set_header_row(false);
+    table.set_style(None);
+    sheet.add_table(table);
+    book.add_sheet(sheet);
+
+    let mut sheet = Sheet::new("stock list");
+    sheet.add_cell(Cell::str("item", ""), 0, 0);
+    sheet.add_cell(Cell::str("count", ""), 0, 1);
+    sheet.add_cell(Cell::str("pen", ""), 1, 0);
+    sheet.add_cell(Cell::float(3.0, ""), 1, 1);
+    let mut table = Table::new("Stock", Range::new("stock list", 0, 0, 1, 1));
+    table.set_columns(vec![TableColumn::new("item"), TableColumn::new("count")]);
+    sheet.add_table(table);
+    book.add_sheet(sheet);
+    book
+}
+
+#[test]
+fn table_test() {
+    let mut book = make_table_book();
+    let range = |reference: &str| book.get_named_range(reference, None).map(|range| range.to_string());
+    assert_eq!(Some(String::from("orders!$B$3:$D$6")), range("Sales"));
+    assert_eq!(Some(String::from("orders!$B$3:$D$6")), range("sales[]"));
+    assert_eq!(Some(String::from("orders!$C$3:$C$6")), range("Sales[Amount]"));
+    assert_eq!(Some(String::from("orders!$C$3:$C$6")), range("Sales[[amount]]"));
+    assert_eq!(Some(String::from("orders!$D$3:$D$6")), range("Sales[Price '[USD']]"));
+    assert_eq!(Some(String::from("orders!$B$2:$D$7")), range("Sales[#All]"));
+    assert_eq!(Some(String::from("orders!$B$2:$D$2")), range("Sales[#Headers]"));
+    assert_eq!(Some(String::from("orders!$B$7:$D$7")), range("Sales[#Totals]"));
+    assert_eq!(Some(String::from("orders!$C$2")), range("Sales[[#Headers],[Amount]]"));
+    assert_eq!(Some(String::from("orders!$B$2:$C$6")), range("Sales[[#Headers],[#Data],[Region]:[Amount]]"));
+    assert_eq!(Some(String::from("orders!$C$3:$D$7")), range("Sales[[#Data],[#Totals],[Amount]:[Price '[USD']]]"));
+    assert_eq!(Some(String::from("'stock list'!$A$2:$B$2")), range("Stock[#Data]"));
+    assert_eq!(None, range("Sales[Cost]"));
+    assert_eq!(None, range("Sales[[#Headers],[#Totals]]"));
+    assert_eq!(None, range("Sales[#This Row]"));
+    assert_eq!(None, range("Notes[#Headers]"));
+    assert_eq!(None, range("Returns[Amount]"));
+
+    // a defined name hides a table of the same name
+    book.add_defined_name(DefinedName::new("Stock", "orders!$A$1"));
+    assert_eq!(Some(Range::cell("orders", 0, 0)), book.get_named_range("Stock", None));
+    assert_eq!("Stock", book.get_table("stock").unwrap().get_name());
+
+    let sheet = book.get_sheet_mut(0);
+    sheet.insert_rows(0, 2);
+    assert_eq!(&Range::new("orders", 3, 1, 8, 3), sheet.get_table("Sales").unwrap().get_range());
+    sheet.remove_rows(12, 3);
+    assert!(sheet.get_table("Notes").is_none());
+    assert!(sheet.remove_table("SALES").is_some());
+    assert!(sheet.get_tables().is_empty());
+}
+
+#[test]
+#[cfg(feature = "xlsx")]
+fn xlsx_table_test() {
+    let dir = TempDir::new("spsheet").unwrap();
+    let book = make_table_book();
+    xlsx::write(&book, &dir.path().join("table.xlsx")).unwrap();
+    let res = xlsx::read(&dir.path().join("table.xlsx")).unwrap();
+    assert_eq!(book, res);
+    assert_eq!(Some(Range::new("orders", 2, 2, 5, 2)), res.get_named_range("Sales[Amount]", None));
+
+    // the tables of an opened sheet replace the original ones, a column without a name is named by its header cell
+    let mut book = xlsx::open(&dir.path().join("table.xlsx")).unwrap();
+    book.get_sheet_mut(0).remove_table("Notes");
+    let mut table = Table::new("Items", Range::new("stock list", 0, 0, 1, 1));
+    table.set_columns(vec![TableColumn::new("item")]);
+    book.get_sheet_mut(1).add_table(table);
+    xlsx::write(&book, &dir.path().join("table_open.xlsx")).unwrap();
+    let res = xlsx::open(&dir.path().join("table_open.xlsx")).unwrap();
+    assert_eq!(book.get_sheet(0).get_tables(), res.get_sheet(0).get_tables());
+    let names: Vec<&String> = res.get_sheet(1).get_table("Items").unwrap().get_columns().iter().map(|column| column.get_name()).collect();
+    assert_eq!(vec!["item", "count"], names);
+    let package = res.get_package().unwrap();
+    let sheet = String::from_utf8(package.get_part("xl/worksheets/sheet1.xml").unwrap().clone()).unwrap();
+    assert!(sheet.contains("<tableParts count=\"1\"><tablePart r:id=\""));
+    let table = String::from_utf8(package.get_part("xl/tables/table1.xml").unwrap().clone()).unwrap();
+    assert!(table.contains("id=\"1\" name=\"Sales\" displayName=\"Sales\" ref=\"B2:D7\" totalsRowCount=\"1\"><autoFilter ref=\"B2:D6\"/>"));
+    assert!(table.contains("<tableColumn id=\"1\" name=\"Region\" totalsRowLabel=\"Total\"/><tableColumn id=\"2\" name=\"Amount\" totalsRowFunction=\"sum\"/>"));
+    assert!(table.contains("<tableStyleInfo name=\"TableStyleLight9\" showFirstColumn=\"1\" showLastColumn=\"0\" showRowStripes=\"1\" showColumnStripes=\"0\"/>"));
+    assert!(package.get_part("xl/tables/table3.xml").is_some());
+    assert!(package.get_part("xl/tables/table4.xml").is_none());
+    let content_types = String::from_utf8(package.get_part("[Content_Types].xml").unwrap().clone()).unwrap();
+    assert_eq!(3, content_types.matches("spreadsheetml.table+xml").count());
+}
+
+#[test]
+#[cfg(any(feature = "ods", feature = "xlsx"))]
+fn template_table_test() {
+    let mut book = make_table_book();
+    let mut cell = Cell::float(0.0, "");
+    cell.set_formula(Some(String::from("SUM(Sales[Amount])+SALES[[#Totals],[Amount]]+\"Sales[Amount]\"")));
+    book.get_sheet_mut(0).add_cell(cell, 8, 2);
+    template::fill_sheets(&mut book, 0, vec![
+        (String::from("east"), Data::new()),
+        (String::from("west"), Data::new()),
+    ]).unwrap();
+
+    // the tables of the copied pages are renamed with the structured references to them, the ranges are on the pages
+    assert_eq!(Some(Range::new("east", 2, 2, 5, 2)), book.get_named_range("Sales[Amount]", None));
+    assert_eq!(Some(Range::new("west", 2, 2, 5, 2)), book.get_named_range("Sales_2[Amount]", None));
+    assert_eq!(Some(Range::new("west", 10, 0, 12, 0)), book.get_table("Notes_2").map(|table| table.get_range().clone()));
+    let formula = book.get_sheet(1).get_cell(8, 2).unwrap().get_formula().unwrap();
+    assert_eq!("SUM(Sales_2[Amount])+Sales_2[[#Totals],[Amount]]+\"Sales[Amount]\"", formula);
+}
+
+#[test]
+fn parse_date_test() {
+    assert!(Cell::parse_date("2017-12-02T13:30:00Z", "").is_ok());
+    assert!(Cell::parse_date("12/02/2017", "").is_err());
+    assert_eq!(Cell::date("2017-12-02", ""), Cell::parse_date(" 2017-12-02 ", "").unwrap());
+}
+
 #[test]
 fn format_test() {
 }
\ No newline at end of file